@@ -7,18 +7,28 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let server_url = "http://localhost:3000/mcp";
     let auth_token = "seu-token-de-api-aqui";
 
+    // Configurar o cliente com autenticação
+    let client = reqwest::Client::new();
+
+    // Criar uma conversa para manter o contexto entre as perguntas
+    let conversation = client
+        .post("http://localhost:3000/conversation")
+        .bearer_auth(auth_token)
+        .send()
+        .await?
+        .json::<serde_json::Value>()
+        .await?;
+
     // Criar uma mensagem para o agente OpenAI
     let message = create_mcp_message_for_agent(
         "openai",
         "chat",
         json!({
             "user_prompt": "Explique a linguagem Rust para iniciantes",
-            "conversation_id": "opcional-para-manter-contexto"
+            "conversation_id": conversation["conversation_id"]
         }),
     );
 
-    // Configurar o cliente com autenticação
-    let client = reqwest::Client::new();
     let response = client
         .post(server_url)
         .bearer_auth(auth_token)
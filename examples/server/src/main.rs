@@ -1,37 +1,43 @@
 use mcprs::agent::AgentRegistry;
+use mcprs::agent_anthropic::create_anthropic_agent;
+use mcprs::agent_azure_openai::create_azure_openai_agent;
+use mcprs::agent_cohere::create_cohere_agent;
 use mcprs::agent_deepseek::create_deepseek_agent;
+use mcprs::agent_gemini::create_gemini_agent;
+use mcprs::agent_mistral::create_mistral_agent;
 use mcprs::agent_openai::create_openai_agent;
 use mcprs::auth::AuthConfig;
 use mcprs::conversation::ConversationManager;
 use mcprs::server::run_http_server_with_auth; // Alterado para usar a nova função
 use std::net::SocketAddr;
+use std::time::Duration;
 
 #[tokio::main]
 async fn main() {
     // Criar o registro de agentes
     let mut registry = AgentRegistry::new();
 
-    // Registrar os agentes - OpenAI e DeepSeek
+    // Registrar os agentes - OpenAI, DeepSeek, Anthropic, Gemini, Mistral e Cohere
     registry.register_agent(Box::new(create_openai_agent(None)));
     registry.register_agent(Box::new(create_deepseek_agent(None)));
+    registry.register_agent(Box::new(create_anthropic_agent(None)));
+    registry.register_agent(Box::new(create_gemini_agent(None)));
+    registry.register_agent(Box::new(create_mistral_agent(None)));
+    registry.register_agent(Box::new(create_cohere_agent(None)));
+
+    // O Azure OpenAI só é registrado se o endpoint e os deployments estiverem configurados
+    if let Some(azure) = create_azure_openai_agent(None) {
+        registry.register_agent(Box::new(azure));
+    }
 
     // Configurar autenticação
     let auth_config = AuthConfig::new();
     auth_config.add_token("seu-token-de-api-aqui".to_string());
 
-    // Configurar gerenciador de conversas (manter histórico por 24 horas)
-    let conversation_manager = ConversationManager::new(24);
-
-    // Agendar limpeza periódica de conversas antigas
-    let conversation_manager_clone = conversation_manager.clone();
-    tokio::spawn(async move {
-        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(3600));
-        loop {
-            interval.tick().await;
-            let cleaned = conversation_manager_clone.cleanup_old_conversations();
-            println!("Limpeza de conversas: {} removidas", cleaned);
-        }
-    });
+    // Configurar gerenciador de conversas (manter histórico por 24 horas).
+    // O servidor remove as conversas expiradas automaticamente a cada hora.
+    let conversation_manager =
+        ConversationManager::new(24).with_cleanup_interval(Duration::from_secs(3600));
 
     // Iniciar o servidor na porta 3000
     let addr: SocketAddr = "0.0.0.0:3000".parse().unwrap();
@@ -9,7 +9,7 @@
 //! - [`AIAgent`]: Trait que define o comportamento de um agente de IA
 //! - [`AgentRegistry`]: Gerenciador central para múltiplos agentes
 //! - [`MCPMessage`]: Formato padrão de mensagem para o protocolo MCP
-//! - [`MCPError`]: Tipos de erros específicos do protocolo
+//! - [`MCPError`]: Tipos de erros específicos do protocolo, com códigos e status HTTP
 //!
 //! ## Exemplo de Uso
 //!
@@ -36,15 +36,27 @@
 //! ```
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use http::StatusCode;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
 use thiserror::Error;
 
+use crate::protocol::{CURRENT_VERSION, MAGIC};
+use crate::streaming::{token_stream_from_message, TokenStream};
+use crate::structured::{process_structured, ResponseFormat};
+use crate::usage::PriceTable;
+
 /// Erros que podem ocorrer durante o processamento de mensagens MCP.
 ///
 /// Usado para representar falhas específicas ao protocolo MCP que podem
 /// acontecer durante a comunicação com agentes ou o processamento de mensagens.
+///
+/// Cada variante tem um código estável ([`MCPError::code`]), um status HTTP
+/// ([`MCPError::status_code`]) e indica se a requisição pode ser repetida
+/// ([`MCPError::is_retryable`]). O servidor converte os erros no envelope
+/// JSON descrito por [`ErrorBody`].
 #[derive(Error, Debug)]
 pub enum MCPError {
     /// Retornado quando o formato de comando não segue o padrão "agente:acao".
@@ -58,6 +70,259 @@ pub enum MCPError {
     /// Retornado quando ocorre um erro interno em um agente específico.
     #[error("Erro interno do agente: {0}")]
     InternalAgentError(String),
+
+    /// Retornado quando a requisição referencia uma conversa inexistente.
+    #[error("Conversa '{0}' não foi encontrada")]
+    ConversationNotFound(String),
+
+    /// Retornado quando o campo `magic` da mensagem não identifica o protocolo MCP.
+    #[error("Magic inválido: '{0}' (esperado 'MCP0')")]
+    InvalidMagic(String),
+
+    /// Retornado quando a versão da mensagem não é suportada pelo servidor.
+    #[error("Versão do protocolo {requested} não suportada (suportadas: {supported:?})")]
+    UnsupportedVersion {
+        /// Versão informada na mensagem
+        requested: u8,
+
+        /// Versões aceitas pelo servidor
+        supported: Vec<u8>,
+    },
+
+    /// Retornado quando a requisição é malformada.
+    #[error("Requisição inválida: {0}")]
+    BadRequest(String),
+
+    /// Retornado quando o cliente não está autenticado ou autorizado.
+    #[error("Não autorizado: {0}")]
+    Unauthorized(String),
+
+    /// Retornado quando um recurso solicitado não existe.
+    #[error("Não encontrado: {0}")]
+    NotFound(String),
+
+    /// Retornado quando o payload não satisfaz o formato esperado pelo agente.
+    #[error("Payload inválido: {0}")]
+    PayloadValidation(String),
+
+    /// Retornado quando o provedor recusa a requisição por limite de taxa.
+    #[error("Limite de requisições excedido no provedor '{provider}'")]
+    RateLimited {
+        /// Nome do provedor (ex.: "openai")
+        provider: String,
+
+        /// Segundos sugeridos pelo provedor antes de tentar novamente
+        retry_after_secs: Option<u64>,
+    },
+
+    /// Retornado quando o provedor falha ou responde com erro.
+    #[error("Falha no provedor '{provider}': {message}")]
+    Upstream {
+        /// Nome do provedor (ex.: "openai")
+        provider: String,
+
+        /// Status HTTP retornado pelo provedor, se houver resposta
+        status: Option<u16>,
+
+        /// Descrição da falha
+        message: String,
+
+        /// Indica se a falha é temporária e repetir a requisição pode ter sucesso
+        retryable: bool,
+    },
+
+    /// Retornado quando o provedor não responde a tempo.
+    #[error("Tempo esgotado aguardando o provedor '{0}'")]
+    Timeout(String),
+}
+
+impl MCPError {
+    /// Cria o erro correspondente a uma resposta HTTP sem sucesso de um provedor.
+    ///
+    /// Respostas `429` viram [`MCPError::RateLimited`], `408` e `504` viram
+    /// [`MCPError::Timeout`] e as demais viram [`MCPError::Upstream`].
+    ///
+    /// # Argumentos
+    /// * `provider` - Nome do provedor
+    /// * `response` - A resposta recebida do provedor
+    pub fn from_provider_response(provider: &str, response: &reqwest::Response) -> Self {
+        let status = response.status();
+        match status.as_u16() {
+            429 => MCPError::RateLimited {
+                provider: provider.to_string(),
+                retry_after_secs: response
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.trim().parse().ok()),
+            },
+            408 | 504 => MCPError::Timeout(provider.to_string()),
+            code => MCPError::Upstream {
+                provider: provider.to_string(),
+                status: Some(code),
+                message: format!("API retornou status {}", status),
+                retryable: code >= 500,
+            },
+        }
+    }
+
+    /// Cria o erro correspondente a uma falha de transporte ao chamar um provedor.
+    pub fn from_transport_error(provider: &str, error: &reqwest::Error) -> Self {
+        if error.is_timeout() {
+            MCPError::Timeout(provider.to_string())
+        } else {
+            let status = error.status().map(|s| s.as_u16());
+            MCPError::Upstream {
+                provider: provider.to_string(),
+                status,
+                message: error.to_string(),
+                retryable: !matches!(status, Some(s) if s < 500),
+            }
+        }
+    }
+
+    /// Cria o erro para uma resposta do provedor que não pôde ser interpretada.
+    ///
+    /// O erro não é repetível: a mesma requisição produziria a mesma resposta.
+    ///
+    /// # Argumentos
+    /// * `provider` - Nome do provedor
+    /// * `error` - Descrição do problema encontrado na resposta
+    pub fn invalid_response(provider: &str, error: impl std::fmt::Display) -> Self {
+        MCPError::Upstream {
+            provider: provider.to_string(),
+            status: None,
+            message: format!("Resposta inválida: {}", error),
+            retryable: false,
+        }
+    }
+
+    /// Código estável do erro, para uso programático pelos clientes.
+    pub fn code(&self) -> &'static str {
+        match self {
+            MCPError::InvalidCommandFormat => "invalid_command",
+            MCPError::InvalidMagic(_) => "invalid_magic",
+            MCPError::UnsupportedVersion { .. } => "unsupported_version",
+            MCPError::AgentNotRegistered(_) => "agent_not_found",
+            MCPError::InternalAgentError(_) => "internal_error",
+            MCPError::ConversationNotFound(_) => "conversation_not_found",
+            MCPError::BadRequest(_) => "bad_request",
+            MCPError::Unauthorized(_) => "unauthorized",
+            MCPError::NotFound(_) => "not_found",
+            MCPError::PayloadValidation(_) => "invalid_payload",
+            MCPError::RateLimited { .. } => "rate_limited",
+            MCPError::Upstream { .. } => "upstream_error",
+            MCPError::Timeout(_) => "timeout",
+        }
+    }
+
+    /// Status HTTP correspondente ao erro.
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            MCPError::InvalidCommandFormat
+            | MCPError::InvalidMagic(_)
+            | MCPError::UnsupportedVersion { .. }
+            | MCPError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            MCPError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            MCPError::AgentNotRegistered(_)
+            | MCPError::ConversationNotFound(_)
+            | MCPError::NotFound(_) => StatusCode::NOT_FOUND,
+            MCPError::PayloadValidation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            MCPError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+            MCPError::Upstream { .. } => StatusCode::BAD_GATEWAY,
+            MCPError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            MCPError::InternalAgentError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Indica se repetir a mesma requisição pode ter sucesso.
+    ///
+    /// Limites de taxa, tempo esgotado e falhas do provedor marcadas como
+    /// temporárias (`5xx` ou sem resposta) podem ter sucesso; os demais erros,
+    /// incluindo respostas que não puderam ser interpretadas, se repetiriam.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            MCPError::RateLimited { .. } | MCPError::Timeout(_) => true,
+            MCPError::Upstream { retryable, .. } => *retryable,
+            _ => false,
+        }
+    }
+
+    /// Nome do provedor envolvido no erro, se houver.
+    pub fn provider(&self) -> Option<&str> {
+        match self {
+            MCPError::RateLimited { provider, .. }
+            | MCPError::Upstream { provider, .. }
+            | MCPError::Timeout(provider) => Some(provider),
+            _ => None,
+        }
+    }
+
+    /// Status HTTP retornado pelo provedor, se houver.
+    pub fn provider_status(&self) -> Option<u16> {
+        match self {
+            MCPError::Upstream { status, .. } => *status,
+            MCPError::RateLimited { .. } => Some(429),
+            _ => None,
+        }
+    }
+
+    /// Monta o corpo do envelope de erro JSON.
+    pub fn to_error_body(&self) -> ErrorBody {
+        ErrorBody {
+            code: self.code().to_string(),
+            message: self.to_string(),
+            retryable: self.is_retryable(),
+            provider: self.provider().map(str::to_string),
+            provider_status: self.provider_status(),
+            retry_after_secs: match self {
+                MCPError::RateLimited {
+                    retry_after_secs, ..
+                } => *retry_after_secs,
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Detalhes de um erro, enviados como `{ "error": ErrorBody }` pelo servidor.
+///
+/// # Exemplo
+///
+/// ```
+/// use mcprs::agent::MCPError;
+///
+/// let error = MCPError::RateLimited {
+///     provider: "openai".to_string(),
+///     retry_after_secs: Some(30),
+/// };
+/// let body = error.to_error_body();
+/// assert_eq!(body.code, "rate_limited");
+/// assert!(body.retryable);
+/// assert_eq!(body.retry_after_secs, Some(30));
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ErrorBody {
+    /// Código estável do erro (veja [`MCPError::code`])
+    pub code: String,
+
+    /// Mensagem legível
+    pub message: String,
+
+    /// Se a requisição pode ser repetida
+    pub retryable: bool,
+
+    /// Provedor envolvido no erro
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+
+    /// Status HTTP retornado pelo provedor
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider_status: Option<u16>,
+
+    /// Segundos sugeridos antes de tentar novamente
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after_secs: Option<u64>,
 }
 
 /// Estrutura central que representa uma mensagem no protocolo MCP.
@@ -72,7 +337,7 @@ pub struct MCPMessage {
     /// Identificador do protocolo, deve ser "MCP0"
     pub magic: String,
 
-    /// Versão do protocolo, atualmente 1
+    /// Versão do protocolo (veja [`SUPPORTED_VERSIONS`](crate::protocol::SUPPORTED_VERSIONS))
     pub version: u8,
 
     /// Comando no formato "agente:acao"
@@ -80,6 +345,19 @@ pub struct MCPMessage {
 
     /// Payload JSON com dados da requisição ou resposta
     pub payload: Value,
+
+    /// ID da requisição, repetido na resposta para correlação (opcional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+
+    /// Momento de criação da mensagem, em RFC 3339 (opcional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<DateTime<Utc>>,
+
+    /// Metadados de correlação, como `trace_id`, `conversation_id` e `user`
+    /// (veja as constantes `META_*` em [`crate::protocol`])
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub meta: HashMap<String, String>,
 }
 
 impl MCPMessage {
@@ -101,11 +379,116 @@ impl MCPMessage {
     /// ```
     pub fn new(command: &str, payload: Value) -> Self {
         MCPMessage {
-            magic: "MCP0".to_string(),
-            version: 1,
+            magic: MAGIC.to_string(),
+            version: CURRENT_VERSION,
             command: command.to_string(),
             payload,
+            id: None,
+            timestamp: None,
+            meta: HashMap::new(),
+        }
+    }
+
+    /// Define o ID da mensagem.
+    ///
+    /// # Exemplo
+    ///
+    /// ```
+    /// use mcprs::agent::MCPMessage;
+    /// use serde_json::json;
+    ///
+    /// let message = MCPMessage::new("openai:chat", json!({})).with_id("req-1");
+    /// assert_eq!(message.id.as_deref(), Some("req-1"));
+    /// ```
+    pub fn with_id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Retorna a ação do comando (a parte após `:`), ou `""` se não houver.
+    ///
+    /// # Exemplo
+    ///
+    /// ```
+    /// use mcprs::agent::MCPMessage;
+    /// use serde_json::json;
+    ///
+    /// let message = MCPMessage::new("openai:embeddings", json!({}));
+    /// assert_eq!(message.action(), "embeddings");
+    /// ```
+    pub fn action(&self) -> &str {
+        self.command
+            .split_once(':')
+            .map(|(_, action)| action)
+            .unwrap_or_default()
+    }
+
+    /// Retorna uma cópia apenas com os dados de correlação (ID e metadados).
+    fn correlation(&self) -> MCPMessage {
+        MCPMessage {
+            payload: Value::Null,
+            id: self.id.clone(),
+            meta: self.meta.clone(),
+            ..MCPMessage::new(&self.command, Value::Null)
+        }
+    }
+
+    /// Define um metadado de correlação da mensagem.
+    pub fn with_meta(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.meta.insert(key.into(), value.into());
+        self
+    }
+
+    /// Marca esta mensagem como resposta a `request`.
+    ///
+    /// Copia o ID da requisição (se a resposta ainda não tiver um), copia os
+    /// metadados de correlação ausentes na resposta e define o timestamp
+    /// para o momento atual.
+    ///
+    /// # Exemplo
+    ///
+    /// ```
+    /// use mcprs::agent::MCPMessage;
+    /// use serde_json::json;
+    ///
+    /// let request = MCPMessage::new("openai:chat", json!({}))
+    ///     .with_id("req-1")
+    ///     .with_meta("trace_id", "abc");
+    ///
+    /// let response = MCPMessage::new("openai_response", json!({})).in_reply_to(&request);
+    /// assert_eq!(response.id.as_deref(), Some("req-1"));
+    /// assert_eq!(response.meta["trace_id"], "abc");
+    /// assert!(response.timestamp.is_some());
+    /// ```
+    pub fn in_reply_to(mut self, request: &MCPMessage) -> Self {
+        if self.id.is_none() {
+            self.id = request.id.clone();
         }
+        for (key, value) in &request.meta {
+            self.meta
+                .entry(key.clone())
+                .or_insert_with(|| value.clone());
+        }
+        self.timestamp = Some(Utc::now());
+        self
+    }
+
+    /// Define a versão do protocolo da mensagem.
+    ///
+    /// Usado pelo servidor para responder na versão negociada com o cliente.
+    ///
+    /// # Exemplo
+    ///
+    /// ```
+    /// use mcprs::agent::MCPMessage;
+    /// use serde_json::json;
+    ///
+    /// let message = MCPMessage::new("openai:chat", json!({})).with_version(2);
+    /// assert_eq!(message.version, 2);
+    /// ```
+    pub fn with_version(mut self, version: u8) -> Self {
+        self.version = version;
+        self
     }
 }
 
@@ -114,6 +497,7 @@ impl MCPMessage {
 /// Qualquer agente deve ser capaz de:
 /// 1. Informar seu nome (chave de identificação).
 /// 2. Receber uma `MCPMessage` e processar, retornando outra `MCPMessage` ou um erro.
+/// 3. Opcionalmente, produzir a resposta como um stream de tokens.
 ///
 /// Para implementar um novo agente, é necessário implementar esta trait.
 #[async_trait]
@@ -132,6 +516,24 @@ pub trait AIAgent: Send + Sync {
     /// * `Ok(MCPMessage)` - A resposta processada com sucesso
     /// * `Err(MCPError)` - Um erro que ocorreu durante o processamento
     async fn process_request(&self, message: MCPMessage) -> Result<MCPMessage, MCPError>;
+
+    /// Processa uma requisição MCP retornando a resposta como stream de tokens.
+    ///
+    /// A implementação padrão chama [`AIAgent::process_request`] e emite a
+    /// resposta completa como um único token. Agentes com suporte nativo a
+    /// streaming devem sobrescrever este método para emitir os tokens à medida
+    /// que são gerados pelo provedor.
+    ///
+    /// # Argumentos
+    /// * `message` - A mensagem MCP recebida para processamento
+    ///
+    /// # Retorna
+    /// * `Ok(TokenStream)` - Stream de tokens, terminando com `is_finish = true`
+    /// * `Err(MCPError)` - Um erro ocorrido antes do início do streaming
+    async fn process_stream(&self, message: MCPMessage) -> Result<TokenStream, MCPError> {
+        let response = self.process_request(message).await?;
+        Ok(token_stream_from_message(response))
+    }
 }
 
 /// Estrutura para gerenciar múltiplos agentes de IA.
@@ -141,6 +543,9 @@ pub trait AIAgent: Send + Sync {
 pub struct AgentRegistry {
     /// Mapa de nome do agente para sua implementação
     agents: HashMap<String, Box<dyn AIAgent>>,
+
+    /// Tabela de preços usada para estimar o custo das respostas
+    price_table: Option<PriceTable>,
 }
 
 impl AgentRegistry {
@@ -156,6 +561,7 @@ impl AgentRegistry {
     pub fn new() -> Self {
         AgentRegistry {
             agents: HashMap::new(),
+            price_table: None,
         }
     }
 
@@ -178,6 +584,32 @@ impl AgentRegistry {
         self.agents.insert(agent.name().to_string(), agent);
     }
 
+    /// Define a tabela de preços usada para estimar o custo de cada chamada.
+    ///
+    /// Com a tabela definida, [`AgentRegistry::process`] acrescenta `cost` ao
+    /// payload das respostas que informam `model` e `usage` (veja
+    /// [`PriceTable::annotate`]).
+    ///
+    /// # Exemplo
+    ///
+    /// ```
+    /// use mcprs::agent::AgentRegistry;
+    /// use mcprs::usage::PriceTable;
+    ///
+    /// let mut registry = AgentRegistry::new();
+    /// registry.set_price_table(PriceTable::new().with_price("gpt-4o-mini", 0.15, 0.60));
+    /// ```
+    pub fn set_price_table(&mut self, price_table: PriceTable) {
+        self.price_table = Some(price_table);
+    }
+
+    /// Retorna os nomes dos agentes registrados, em ordem alfabética.
+    pub fn agent_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.agents.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
     /// Processa uma mensagem roteando-a para o agente correto.
     ///
     /// O comando deve estar no formato "nomeAgente:acao". A parte "nomeAgente"
@@ -193,17 +625,58 @@ impl AgentRegistry {
     /// # Erros
     /// * `MCPError::InvalidCommandFormat` - Se o comando não seguir o formato "agente:acao"
     /// * `MCPError::AgentNotRegistered` - Se o agente especificado não estiver registrado
+    ///
+    /// A resposta sempre carrega o `id` e os metadados da requisição, mesmo
+    /// que o agente não os tenha copiado. Se houver uma tabela de preços, o
+    /// payload também recebe o custo estimado em `cost`.
+    ///
+    /// Com um `response_format` JSON, a resposta é validada e devolvida em
+    /// `payload.data` (veja [`crate::structured`]).
     pub async fn process(&self, message: MCPMessage) -> Result<MCPMessage, MCPError> {
-        let parts: Vec<&str> = message.command.splitn(2, ':').collect();
-        if parts.len() != 2 {
-            return Err(MCPError::InvalidCommandFormat);
-        }
-        let agent_key = parts[0];
-        if let Some(agent) = self.agents.get(agent_key) {
-            agent.process_request(message).await
-        } else {
-            Err(MCPError::AgentNotRegistered(agent_key.to_string()))
+        let agent = self.resolve_agent(&message.command)?;
+        let correlation = message.correlation();
+        let mut response = match ResponseFormat::from_payload(&message.payload)? {
+            Some(format) if format.is_json() => process_structured(agent, message, &format).await?,
+            _ => agent.process_request(message).await?,
+        };
+
+        if let Some(price_table) = &self.price_table {
+            price_table.annotate(&mut response.payload);
         }
+        Ok(response.in_reply_to(&correlation))
+    }
+
+    /// Processa uma mensagem em modo streaming, roteando-a para o agente correto.
+    ///
+    /// O roteamento segue as mesmas regras de [`AgentRegistry::process`].
+    ///
+    /// # Argumentos
+    /// * `message` - A mensagem a ser processada
+    ///
+    /// # Retorna
+    /// * `Ok(TokenStream)` - Stream de tokens produzido pelo agente
+    /// * `Err(MCPError)` - Erro que ocorreu durante o roteamento ou antes do streaming
+    pub async fn process_stream(&self, message: MCPMessage) -> Result<TokenStream, MCPError> {
+        let agent = self.resolve_agent(&message.command)?;
+        agent.process_stream(message).await
+    }
+
+    /// Localiza o agente responsável por um comando no formato "agente:acao".
+    fn resolve_agent(&self, command: &str) -> Result<&dyn AIAgent, MCPError> {
+        let (agent_key, _action) = command
+            .split_once(':')
+            .ok_or(MCPError::InvalidCommandFormat)?;
+
+        self.agents
+            .get(agent_key)
+            .map(|agent| agent.as_ref())
+            .ok_or_else(|| MCPError::AgentNotRegistered(agent_key.to_string()))
+    }
+}
+
+impl Default for AgentRegistry {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -224,7 +697,8 @@ impl AIAgent for DummyAgent {
 
     async fn process_request(&self, message: MCPMessage) -> Result<MCPMessage, MCPError> {
         // Neste exemplo, apenas ecoamos o payload recebido, mas poderíamos chamar APIs externas.
-        Ok(MCPMessage::new("dummy_response", message.payload))
+        let response = MCPMessage::new("dummy_response", message.payload.clone());
+        Ok(response.in_reply_to(&message))
     }
 }
 
@@ -277,4 +751,159 @@ mod tests {
         let err = registry.process(msg3).await.unwrap_err();
         assert!(matches!(err, MCPError::InvalidCommandFormat));
     }
+
+    #[tokio::test]
+    async fn test_default_process_stream_wraps_response() {
+        use futures::StreamExt;
+
+        let mut registry = AgentRegistry::new();
+        registry.register_agent(Box::new(DummyAgent {
+            api_key: "test_key".to_string(),
+        }));
+
+        let msg = MCPMessage::new("dummy:action", json!({"answer": "eco"}));
+        let mut stream = registry.process_stream(msg).await.unwrap();
+
+        let token = stream.next().await.unwrap().unwrap();
+        assert_eq!(token.content, "eco");
+        assert!(!token.is_finish);
+        assert_eq!(token.metadata, Some(json!({"answer": "eco"})));
+
+        let last = stream.next().await.unwrap().unwrap();
+        assert!(last.is_finish);
+        assert!(stream.next().await.is_none());
+    }
+
+    #[test]
+    fn test_error_codes_and_status() {
+        let cases = [
+            (
+                MCPError::InvalidCommandFormat,
+                "invalid_command",
+                400,
+                false,
+            ),
+            (
+                MCPError::AgentNotRegistered("x".into()),
+                "agent_not_found",
+                404,
+                false,
+            ),
+            (
+                MCPError::PayloadValidation("x".into()),
+                "invalid_payload",
+                422,
+                false,
+            ),
+            (
+                MCPError::Unauthorized("x".into()),
+                "unauthorized",
+                401,
+                false,
+            ),
+            (
+                MCPError::RateLimited {
+                    provider: "openai".into(),
+                    retry_after_secs: None,
+                },
+                "rate_limited",
+                429,
+                true,
+            ),
+            (MCPError::Timeout("openai".into()), "timeout", 504, true),
+            (
+                MCPError::Upstream {
+                    provider: "openai".into(),
+                    status: Some(503),
+                    message: "x".into(),
+                    retryable: true,
+                },
+                "upstream_error",
+                502,
+                true,
+            ),
+            (
+                MCPError::Upstream {
+                    provider: "openai".into(),
+                    status: Some(401),
+                    message: "x".into(),
+                    retryable: false,
+                },
+                "upstream_error",
+                502,
+                false,
+            ),
+            (
+                MCPError::invalid_response("openai", "JSON truncado"),
+                "upstream_error",
+                502,
+                false,
+            ),
+            (
+                MCPError::InternalAgentError("x".into()),
+                "internal_error",
+                500,
+                false,
+            ),
+        ];
+
+        for (error, code, status, retryable) in cases {
+            assert_eq!(error.code(), code);
+            assert_eq!(error.status_code().as_u16(), status, "{}", code);
+            assert_eq!(error.is_retryable(), retryable, "{}", code);
+        }
+    }
+
+    #[test]
+    fn test_error_from_provider_response() {
+        let response = reqwest::Response::from(
+            http::Response::builder()
+                .status(429)
+                .header("Retry-After", "12")
+                .body("")
+                .unwrap(),
+        );
+        let error = MCPError::from_provider_response("openai", &response);
+        assert!(matches!(
+            error,
+            MCPError::RateLimited { ref provider, retry_after_secs: Some(12) } if provider == "openai"
+        ));
+
+        let response =
+            reqwest::Response::from(http::Response::builder().status(500).body("").unwrap());
+        let body = MCPError::from_provider_response("deepseek", &response).to_error_body();
+        assert_eq!(body.code, "upstream_error");
+        assert_eq!(body.provider.as_deref(), Some("deepseek"));
+        assert_eq!(body.provider_status, Some(500));
+        assert!(body.retryable);
+    }
+
+    #[tokio::test]
+    async fn test_registry_echoes_request_id() {
+        /// Agente que ignora os dados de correlação da requisição
+        struct ForgetfulAgent;
+
+        #[async_trait]
+        impl AIAgent for ForgetfulAgent {
+            fn name(&self) -> &str {
+                "forgetful"
+            }
+
+            async fn process_request(&self, _message: MCPMessage) -> Result<MCPMessage, MCPError> {
+                Ok(MCPMessage::new("forgetful_response", json!({})))
+            }
+        }
+
+        let mut registry = AgentRegistry::new();
+        registry.register_agent(Box::new(ForgetfulAgent));
+
+        let request = MCPMessage::new("forgetful:chat", json!({}))
+            .with_id("req-42")
+            .with_meta("trace_id", "trace-1");
+        let response = registry.process(request).await.unwrap();
+
+        assert_eq!(response.id.as_deref(), Some("req-42"));
+        assert_eq!(response.meta["trace_id"], "trace-1");
+        assert!(response.timestamp.is_some());
+    }
 }
@@ -0,0 +1,668 @@
+//! # Agente para integração com a API Anthropic
+//!
+//! Este módulo implementa um agente que se comunica com a Messages API da
+//! Anthropic, permitindo enviar prompts para os modelos Claude.
+//!
+//! ## Exemplo de Uso
+//!
+//! ```rust,no_run
+//! use mcprs::agent::{AgentRegistry, MCPMessage};
+//! use mcprs::agent_anthropic::create_anthropic_agent;
+//! use serde_json::json;
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! // Configurar variável de ambiente (ou diretamente na criação do agente)
+//! std::env::set_var("ANTHROPIC_API_KEY", "sua-chave-aqui");
+//!
+//! // Criar e registrar o agente Anthropic
+//! let mut registry = AgentRegistry::new();
+//! registry.register_agent(Box::new(create_anthropic_agent(None)));
+//!
+//! // Criar uma mensagem para o Claude
+//! let message = MCPMessage::new(
+//!     "anthropic:chat",
+//!     json!({
+//!         "system_prompt": "Responda em uma frase.",
+//!         "user_prompt": "Explique o que é Rust",
+//!         "max_tokens": 256
+//!     })
+//! );
+//!
+//! // Processar a mensagem
+//! let response = registry.process(message).await?;
+//! println!("Resposta: {}", response.payload["answer"]);
+//! println!("Tokens: {}", response.payload["usage"]);
+//! # Ok(())
+//! # }
+//! ```
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::env;
+
+use crate::agent::{AIAgent, MCPError, MCPMessage};
+use crate::chat::{parse_model_list, select_model, ChatMessage, ChatRequest, GenerationParams};
+use crate::streaming::{process_json_stream_with, StreamingToken, TokenStream};
+use crate::testing::HttpClient;
+use crate::usage::Usage;
+
+/// URL do endpoint da Messages API da Anthropic
+const ANTHROPIC_MESSAGES_URL: &str = "https://api.anthropic.com/v1/messages";
+
+/// Versão da API enviada no cabeçalho `anthropic-version`
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// Limite de tokens usado quando o payload não informa `max_tokens`.
+///
+/// A Messages API exige esse campo em toda requisição.
+pub const DEFAULT_MAX_TOKENS: u32 = 1024;
+
+/// Parâmetros de geração aceitos pela Messages API
+const SUPPORTED_PARAMS: &[&str] = &["temperature", "top_p", "max_tokens", "stop", "user"];
+
+/// Agente para comunicação com a API Anthropic.
+///
+/// Este agente implementa a trait `AIAgent` e se conecta à Messages API da
+/// Anthropic para enviar prompts e obter respostas dos modelos Claude.
+pub struct AnthropicAgent {
+    /// Chave de API da Anthropic
+    pub api_key: String,
+
+    /// Nome do modelo a ser usado (ex: "claude-3-5-sonnet-latest")
+    pub model: String,
+
+    /// Modelos adicionais que o payload pode escolher pelo campo `model`
+    pub allowed_models: Vec<String>,
+
+    /// Cliente HTTP para fazer as requisições
+    http_client: Box<dyn HttpClient>,
+}
+
+impl AnthropicAgent {
+    /// Cria uma nova instância do agente Anthropic.
+    ///
+    /// # Argumentos
+    /// * `api_key` - Chave de API da Anthropic
+    /// * `model` - Nome do modelo a ser usado
+    /// * `http_client` - Cliente HTTP para fazer as requisições
+    ///
+    /// # Exemplo
+    ///
+    /// ```
+    /// use mcprs::agent_anthropic::AnthropicAgent;
+    /// use mcprs::testing::ReqwestClient;
+    ///
+    /// let agent = AnthropicAgent::new(
+    ///     "sua-chave-api".to_string(),
+    ///     "claude-3-5-sonnet-latest".to_string(),
+    ///     Box::new(ReqwestClient::new())
+    /// );
+    /// ```
+    pub fn new(api_key: String, model: String, http_client: Box<dyn HttpClient>) -> Self {
+        Self {
+            api_key,
+            model,
+            allowed_models: Vec::new(),
+            http_client,
+        }
+    }
+
+    /// Permite que o payload escolha um dos modelos informados pelo campo `model`.
+    ///
+    /// O modelo padrão é sempre permitido; qualquer outro modelo é rejeitado
+    /// com `MCPError::PayloadValidation`.
+    pub fn with_allowed_models<I, S>(mut self, models: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allowed_models = models.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Monta o corpo da requisição da Messages API a partir do payload MCP.
+    ///
+    /// As mensagens de sistema são enviadas no campo `system`, separado das
+    /// demais mensagens, como exigido pela API. O parâmetro `stop` é enviado
+    /// como `stop_sequences` e `user` como `metadata.user_id`. Como a API não
+    /// tem modo JSON, um `response_format` JSON vira instruções de sistema.
+    fn build_request(
+        &self,
+        message: &MCPMessage,
+        stream: bool,
+    ) -> Result<AnthropicRequest, MCPError> {
+        // Interpretar as mensagens do payload
+        let mut chat_request = ChatRequest::from_payload(&message.payload)?;
+        chat_request.ensure_no_tools("anthropic")?;
+        if let Some(instructions) = chat_request
+            .response_format
+            .as_ref()
+            .and_then(|format| format.instructions())
+        {
+            chat_request.add_system_instruction(instructions);
+        }
+        let (system, messages) = chat_request.split_system();
+        let params = GenerationParams::from_payload(&message.payload)?;
+        params.ensure_supported("anthropic", SUPPORTED_PARAMS)?;
+        let model = select_model(&message.payload, Some(&self.model), &self.allowed_models)?
+            .unwrap_or(&self.model);
+
+        Ok(AnthropicRequest {
+            model: model.to_string(),
+            max_tokens: params.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+            system,
+            messages,
+            temperature: params.temperature,
+            top_p: params.top_p,
+            stop_sequences: stop_sequences(&message.payload)?.or(params.stop),
+            metadata: params.user.map(|user_id| AnthropicMetadata { user_id }),
+            stream: stream.then_some(true),
+        })
+    }
+
+    /// Envia a requisição para a API Anthropic e valida o status da resposta.
+    async fn send_request(
+        &self,
+        request_body: &AnthropicRequest,
+    ) -> Result<reqwest::Response, MCPError> {
+        // Preparar os headers
+        let headers = vec![
+            ("x-api-key".to_string(), self.api_key.clone()),
+            (
+                "anthropic-version".to_string(),
+                ANTHROPIC_VERSION.to_string(),
+            ),
+            ("Content-Type".to_string(), "application/json".to_string()),
+        ];
+
+        // Enviar a requisição para a API Anthropic
+        let response = self
+            .http_client
+            .post(
+                ANTHROPIC_MESSAGES_URL.to_string(),
+                serde_json::to_vec(request_body)
+                    .map_err(|e| MCPError::InternalAgentError(e.to_string()))?,
+                headers,
+            )
+            .await
+            .map_err(|e| MCPError::from_transport_error("anthropic", &e))?;
+
+        // Verificar o status da resposta
+        if !response.status().is_success() {
+            return Err(MCPError::from_provider_response("anthropic", &response));
+        }
+
+        Ok(response)
+    }
+}
+
+/// Lê o campo opcional `stop_sequences` do payload (uma string ou lista de strings).
+///
+/// É equivalente ao parâmetro comum `stop` e tem precedência sobre ele.
+fn stop_sequences(payload: &Value) -> Result<Option<Vec<String>>, MCPError> {
+    let invalid = || {
+        MCPError::PayloadValidation(
+            "Campo 'stop_sequences' deve ser uma string ou lista de strings".to_string(),
+        )
+    };
+
+    match payload.get("stop_sequences").filter(|v| !v.is_null()) {
+        None => Ok(None),
+        Some(Value::String(stop)) => Ok(Some(vec![stop.clone()])),
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|item| item.as_str().map(str::to_string).ok_or_else(invalid))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Some),
+        Some(_) => Err(invalid()),
+    }
+}
+
+/// Estrutura para o corpo da requisição à Messages API
+#[derive(Serialize)]
+struct AnthropicRequest {
+    model: String,
+    max_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<String>,
+    messages: Vec<ChatMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop_sequences: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<AnthropicMetadata>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream: Option<bool>,
+}
+
+/// Metadados da requisição; `user_id` identifica o usuário final
+#[derive(Serialize)]
+struct AnthropicMetadata {
+    user_id: String,
+}
+
+/// Estrutura para a resposta da Messages API
+#[derive(Deserialize)]
+struct AnthropicResponse {
+    id: String,
+    model: Option<String>,
+    content: Vec<AnthropicContentBlock>,
+    stop_reason: Option<String>,
+    #[serde(default)]
+    usage: AnthropicUsage,
+}
+
+/// Bloco de conteúdo da resposta; apenas blocos `text` têm texto
+#[derive(Deserialize)]
+struct AnthropicContentBlock {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    text: String,
+}
+
+/// Contagem de tokens informada pela API
+#[derive(Deserialize, Default, Clone, Copy)]
+struct AnthropicUsage {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+}
+
+/// Evento do stream SSE da Messages API, identificado pelo campo `type`
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum AnthropicStreamEvent {
+    MessageStart {
+        message: AnthropicStreamMessage,
+    },
+    ContentBlockDelta {
+        delta: AnthropicDelta,
+    },
+    MessageDelta {
+        delta: AnthropicMessageDelta,
+        #[serde(default)]
+        usage: AnthropicUsage,
+    },
+    Error {
+        error: AnthropicStreamError,
+    },
+    #[serde(other)]
+    Other,
+}
+
+/// Mensagem inicial do stream, usada para obter os tokens de entrada
+#[derive(Deserialize)]
+struct AnthropicStreamMessage {
+    #[serde(default)]
+    usage: AnthropicUsage,
+}
+
+/// Fragmento de um bloco de conteúdo; apenas `text_delta` tem texto
+#[derive(Deserialize)]
+struct AnthropicDelta {
+    #[serde(default)]
+    text: Option<String>,
+}
+
+/// Alterações finais da mensagem, enviadas antes de `message_stop`
+#[derive(Deserialize)]
+struct AnthropicMessageDelta {
+    stop_reason: Option<String>,
+}
+
+/// Erro enviado pela API no meio do stream
+#[derive(Deserialize)]
+struct AnthropicStreamError {
+    #[serde(rename = "type")]
+    kind: String,
+    message: String,
+}
+
+/// Processa o stream SSE da Messages API.
+///
+/// Cada evento `content_block_delta` com texto vira um token. O evento
+/// `message_delta` gera um token vazio com `stop_reason` e `usage` em
+/// `metadata`, e eventos `error` são propagados como `MCPError::Upstream`.
+async fn process_anthropic_stream<S>(stream: S) -> Result<TokenStream, MCPError>
+where
+    S: futures::Stream<Item = Result<bytes::Bytes, reqwest::Error>> + Send + 'static,
+{
+    let mut input_tokens = 0;
+
+    process_json_stream_with::<_, AnthropicStreamEvent, _>(stream, move |event| match event {
+        AnthropicStreamEvent::MessageStart { message } => {
+            input_tokens = message.usage.input_tokens;
+            Ok(None)
+        }
+        AnthropicStreamEvent::ContentBlockDelta { delta } => Ok(delta
+            .text
+            .filter(|text| !text.is_empty())
+            .map(|text| StreamingToken {
+                content: text,
+                is_finish: false,
+                metadata: None,
+            })),
+        AnthropicStreamEvent::MessageDelta { delta, usage } => Ok(Some(StreamingToken {
+            content: String::new(),
+            is_finish: false,
+            metadata: Some(json!({
+                "stop_reason": delta.stop_reason,
+                "usage": Usage::new(input_tokens, usage.output_tokens),
+            })),
+        })),
+        AnthropicStreamEvent::Error { error } => Err(MCPError::Upstream {
+            provider: "anthropic".to_string(),
+            status: None,
+            message: format!("{}: {}", error.kind, error.message),
+            retryable: true,
+        }),
+        AnthropicStreamEvent::Other => Ok(None),
+    })
+    .await
+}
+
+#[async_trait]
+impl AIAgent for AnthropicAgent {
+    /// Retorna o nome do agente: "anthropic"
+    fn name(&self) -> &str {
+        "anthropic"
+    }
+
+    /// Processa uma requisição enviando-a para a Messages API.
+    ///
+    /// # Parâmetros esperados no payload
+    /// As mensagens seguem o modelo de [`ChatRequest`]:
+    /// * `system_prompt` - Instrução de sistema (opcional)
+    /// * `messages` - Histórico de mensagens `{role, content}` (opcional)
+    /// * `user_prompt` - O prompt do usuário (opcional se `messages` for informado)
+    /// * `max_tokens` - Limite de tokens na resposta (opcional, padrão: 1024)
+    /// * `temperature` e `top_p` - Parâmetros de amostragem (opcionais)
+    /// * `stop_sequences` ou `stop` - Sequência ou lista de sequências de parada (opcional)
+    /// * `user` - Identificador do usuário final, enviado em `metadata.user_id` (opcional)
+    /// * `model` - Modelo a ser usado; deve ser o padrão ou estar em `allowed_models` (opcional)
+    /// * `response_format` - Formato JSON da resposta, pedido nas instruções de sistema (opcional)
+    ///
+    /// # Formato da resposta
+    /// A resposta terá o comando "anthropic_response" e o payload conterá:
+    /// * `answer` - O texto da resposta gerada pelo modelo
+    /// * `id` - O ID da mensagem gerada pela API
+    /// * `model` - O modelo que atendeu a requisição
+    /// * `stop_reason` - A razão de término da geração (end_turn, max_tokens, stop_sequence)
+    /// * `usage` - Tokens consumidos (`input_tokens`, `output_tokens` e `total_tokens`)
+    ///
+    /// # Erros
+    /// * `MCPError::PayloadValidation` - Se nem `user_prompt` nem `messages` forem informados,
+    ///   se algum parâmetro de geração for inválido ou não suportado
+    ///   (`seed`, `presence_penalty`, `frequency_penalty`, `n`, `logit_bias`),
+    ///   se o modelo não for permitido ou se o payload usar ferramentas
+    /// * `MCPError::RateLimited` ou `MCPError::Timeout` - Se a API limitar ou não responder a tempo
+    /// * `MCPError::Upstream` - Se a API retornar erro ou uma resposta que não possa ser processada
+    async fn process_request(&self, message: MCPMessage) -> Result<MCPMessage, MCPError> {
+        let request_body = self.build_request(&message, false)?;
+        let response = self.send_request(&request_body).await?;
+
+        // Deserializar a resposta
+        let resp_json = response
+            .json::<AnthropicResponse>()
+            .await
+            .map_err(|e| MCPError::invalid_response("anthropic", &e))?;
+
+        // Concatenar os blocos de texto da resposta
+        let answer_text: String = resp_json
+            .content
+            .iter()
+            .filter(|block| block.kind == "text")
+            .map(|block| block.text.as_str())
+            .collect();
+
+        // Retornar a resposta formatada como MCPMessage
+        Ok(MCPMessage::new(
+            "anthropic_response",
+            json!({
+                "answer": answer_text,
+                "id": resp_json.id,
+                "model": resp_json.model.unwrap_or(request_body.model),
+                "stop_reason": resp_json.stop_reason,
+                "usage": Usage::new(resp_json.usage.input_tokens, resp_json.usage.output_tokens),
+            }),
+        )
+        .in_reply_to(&message))
+    }
+
+    /// Processa uma requisição em modo streaming (`stream: true`).
+    ///
+    /// O texto de cada evento `content_block_delta` é emitido como um
+    /// `StreamingToken`; o evento `message_delta` traz `stop_reason` e
+    /// `usage` em `metadata`.
+    ///
+    /// # Erros
+    /// Os mesmos de [`AnthropicAgent::process_request`] para falhas antes do
+    /// início do streaming.
+    async fn process_stream(&self, message: MCPMessage) -> Result<TokenStream, MCPError> {
+        let request_body = self.build_request(&message, true)?;
+        let response = self.send_request(&request_body).await?;
+
+        process_anthropic_stream(response.bytes_stream()).await
+    }
+}
+
+/// Função auxiliar para criar um agente Anthropic com configurações do ambiente.
+///
+/// Esta função facilita a criação de uma instância do agente Anthropic, obtendo
+/// as configurações das variáveis de ambiente:
+/// - `ANTHROPIC_API_KEY` - Chave de API
+/// - `ANTHROPIC_MODEL` - Nome do modelo (padrão: claude-3-5-sonnet-latest)
+/// - `ANTHROPIC_ALLOWED_MODELS` - Modelos adicionais permitidos no payload, separados por vírgula
+///
+/// # Argumentos
+/// * `http_client` - Cliente HTTP opcional. Se None, será criado um novo.
+///
+/// # Retorno
+/// Uma nova instância de `AnthropicAgent` configurada.
+///
+/// # Exemplo
+///
+/// ```
+/// use mcprs::agent_anthropic::create_anthropic_agent;
+///
+/// // Configurar a variável de ambiente primeiro
+/// std::env::set_var("ANTHROPIC_API_KEY", "sua-chave-api");
+///
+/// // Criar o agente
+/// let agent = create_anthropic_agent(None);
+/// ```
+pub fn create_anthropic_agent(http_client: Option<Box<dyn HttpClient>>) -> AnthropicAgent {
+    let client = http_client.unwrap_or_else(|| Box::new(crate::testing::ReqwestClient::new()));
+
+    AnthropicAgent::new(
+        env::var("ANTHROPIC_API_KEY").unwrap_or_else(|_| "SUA_ANTHROPIC_KEY".to_string()),
+        env::var("ANTHROPIC_MODEL").unwrap_or_else(|_| "claude-3-5-sonnet-latest".to_string()),
+        client,
+    )
+    .with_allowed_models(
+        env::var("ANTHROPIC_ALLOWED_MODELS")
+            .map(|models| parse_model_list(&models))
+            .unwrap_or_default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockHttpClient;
+    use futures::StreamExt;
+
+    fn create_agent(mock_client: MockHttpClient) -> AnthropicAgent {
+        AnthropicAgent::new(
+            "test_key".to_string(),
+            "claude-test".to_string(),
+            Box::new(mock_client),
+        )
+    }
+
+    #[test]
+    fn test_build_request_splits_system_prompt() {
+        let agent = create_agent(MockHttpClient::new());
+        let message = MCPMessage::new(
+            "anthropic:chat",
+            json!({
+                "system_prompt": "Seja breve.",
+                "user_prompt": "Olá",
+                "max_tokens": 64,
+                "stop_sequences": "FIM"
+            }),
+        );
+
+        let request = serde_json::to_value(agent.build_request(&message, false).unwrap()).unwrap();
+        assert_eq!(
+            request,
+            json!({
+                "model": "claude-test",
+                "max_tokens": 64,
+                "system": "Seja breve.",
+                "messages": [{ "role": "user", "content": "Olá" }],
+                "stop_sequences": ["FIM"]
+            })
+        );
+    }
+
+    #[test]
+    fn test_build_request_validates_parameters() {
+        let agent = create_agent(MockHttpClient::new());
+
+        for payload in [
+            json!({ "user_prompt": "Olá", "max_tokens": 0 }),
+            json!({ "user_prompt": "Olá", "max_tokens": "muitos" }),
+            json!({ "user_prompt": "Olá", "stop_sequences": [1, 2] }),
+            json!({ "user_prompt": "Olá", "tools": [{ "name": "clima" }] }),
+        ] {
+            let message = MCPMessage::new("anthropic:chat", payload);
+            assert!(matches!(
+                agent.build_request(&message, false),
+                Err(MCPError::PayloadValidation(_))
+            ));
+        }
+
+        // Sem max_tokens, o padrão é usado
+        let message = MCPMessage::new("anthropic:chat", json!({ "user_prompt": "Olá" }));
+        let request = agent.build_request(&message, false).unwrap();
+        assert_eq!(request.max_tokens, DEFAULT_MAX_TOKENS);
+    }
+
+    #[test]
+    fn test_build_request_maps_generation_params() {
+        let agent = create_agent(MockHttpClient::new());
+        let message = MCPMessage::new(
+            "anthropic:chat",
+            json!({
+                "user_prompt": "Olá",
+                "temperature": 0.3,
+                "top_p": 0.8,
+                "stop": ["FIM"],
+                "user": "usuario-1"
+            }),
+        );
+
+        let request = serde_json::to_value(agent.build_request(&message, false).unwrap()).unwrap();
+        assert_eq!(request["temperature"], 0.3);
+        assert_eq!(request["top_p"], 0.8);
+        assert_eq!(request["stop_sequences"], json!(["FIM"]));
+        assert_eq!(request["metadata"], json!({ "user_id": "usuario-1" }));
+
+        // Parâmetros sem equivalente na Messages API são rejeitados
+        let message = MCPMessage::new(
+            "anthropic:chat",
+            json!({ "user_prompt": "Olá", "seed": 1, "presence_penalty": 0.5 }),
+        );
+        assert!(matches!(
+            agent.build_request(&message, false),
+            Err(MCPError::PayloadValidation(e)) if e.contains("seed, presence_penalty")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_anthropic_agent_process_stream() {
+        let mut mock_client = MockHttpClient::new();
+
+        mock_client
+            .expect_post()
+            .withf(|_, body, _| {
+                let parsed: Value = serde_json::from_slice(body).unwrap_or_default();
+                parsed["stream"] == true
+            })
+            .times(1)
+            .return_once(move |_, _, _| {
+                let body = concat!(
+                    "event: message_start\n",
+                    "data: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_1\",\"usage\":{\"input_tokens\":12,\"output_tokens\":1}}}\n\n",
+                    "event: content_block_start\n",
+                    "data: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\n\n",
+                    "event: ping\n",
+                    "data: {\"type\":\"ping\"}\n\n",
+                    "event: content_block_delta\n",
+                    "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Rust\"}}\n\n",
+                    "event: content_block_delta\n",
+                    "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\" é rápido\"}}\n\n",
+                    "event: content_block_stop\n",
+                    "data: {\"type\":\"content_block_stop\",\"index\":0}\n\n",
+                    "event: message_delta\n",
+                    "data: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"end_turn\"},\"usage\":{\"output_tokens\":5}}\n\n",
+                    "event: message_stop\n",
+                    "data: {\"type\":\"message_stop\"}\n\n"
+                );
+                Ok(reqwest::Response::from(
+                    http::Response::builder().status(200).body(body).unwrap(),
+                ))
+            });
+
+        let agent = create_agent(mock_client);
+        let message = MCPMessage::new("anthropic:chat", json!({ "user_prompt": "O que é Rust?" }));
+        let mut stream = agent.process_stream(message).await.unwrap();
+
+        let mut content = String::new();
+        let mut metadata = None;
+        while let Some(token) = stream.next().await {
+            let token = token.unwrap();
+            if token.is_finish {
+                break;
+            }
+            content.push_str(&token.content);
+            if token.metadata.is_some() {
+                metadata = token.metadata;
+            }
+        }
+
+        assert_eq!(content, "Rust é rápido");
+        assert_eq!(
+            metadata.unwrap(),
+            json!({
+                "stop_reason": "end_turn",
+                "usage": { "input_tokens": 12, "output_tokens": 5, "total_tokens": 17 }
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_anthropic_stream_error_event() {
+        let chunks = vec![Ok(bytes::Bytes::from(concat!(
+            "event: error\n",
+            "data: {\"type\":\"error\",\"error\":{\"type\":\"overloaded_error\",\"message\":\"Overloaded\"}}\n\n"
+        )))];
+
+        let mut stream = process_anthropic_stream(futures::stream::iter(chunks))
+            .await
+            .unwrap();
+
+        let error = stream.next().await.unwrap().unwrap_err();
+        assert!(matches!(
+            error,
+            MCPError::Upstream { ref message, .. } if message.contains("overloaded_error")
+        ));
+    }
+}
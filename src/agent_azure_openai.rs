@@ -0,0 +1,478 @@
+//! # Agente para integração com o Azure OpenAI
+//!
+//! O Azure OpenAI expõe os modelos da OpenAI por meio de *deployments*
+//! criados no recurso do Azure. Em relação à API da OpenAI, muda o formato da
+//! URL (`/openai/deployments/{deployment}/chat/completions?api-version=...`),
+//! a autenticação (cabeçalho `api-key` ou token Bearer do Azure AD) e o fato
+//! de o modelo ser escolhido pelo nome do deployment, e não pelo campo `model`.
+//!
+//! Um mesmo [`AzureOpenAIAgent`] pode atender vários deployments; o payload
+//! escolhe qual usar pelo campo `deployment`, e o primeiro deployment
+//! registrado é o padrão. A versão da API é fixada na criação do agente.
+//! Como o modelo é definido pelo deployment, o campo `model` do payload não é aceito.
+//!
+//! ## Exemplo de Uso
+//!
+//! ```rust,no_run
+//! use mcprs::agent::{AgentRegistry, MCPMessage};
+//! use mcprs::agent_azure_openai::{AzureCredential, AzureOpenAIAgent};
+//! use mcprs::testing::ReqwestClient;
+//! use serde_json::json;
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let agent = AzureOpenAIAgent::new(
+//!     "https://minha-empresa.openai.azure.com",
+//!     AzureCredential::ApiKey("sua-chave-azure".to_string()),
+//!     Box::new(ReqwestClient::new()),
+//! )
+//! .with_deployment("gpt-4o-prod")
+//! .with_deployment("gpt-4o-mini")
+//! .with_api_version("2024-06-01");
+//!
+//! let mut registry = AgentRegistry::new();
+//! registry.register_agent(Box::new(agent));
+//!
+//! // Usar um deployment específico
+//! let message = MCPMessage::new(
+//!     "azure:chat",
+//!     json!({ "user_prompt": "Olá", "deployment": "gpt-4o-mini" }),
+//! );
+//! let response = registry.process(message).await?;
+//! println!("Resposta: {}", response.payload["answer"]);
+//! # Ok(())
+//! # }
+//! ```
+
+use async_trait::async_trait;
+use serde_json::Value;
+use std::env;
+use std::future::Future;
+use std::sync::Arc;
+
+use crate::agent::{AIAgent, MCPError, MCPMessage};
+use crate::agent_openai_compatible::{
+    chat_completion, chat_completion_stream, AuthScheme, OpenAICompatibleConfig,
+};
+use crate::streaming::TokenStream;
+use crate::testing::HttpClient;
+
+/// Versão da API usada quando nenhuma outra é informada
+pub const DEFAULT_AZURE_API_VERSION: &str = "2024-06-01";
+
+/// Fornece tokens de acesso do Azure AD (Entra ID).
+///
+/// O provedor é chamado a cada requisição e é responsável por renovar e
+/// guardar em cache o token, se necessário. Qualquer closure assíncrona
+/// `Fn() -> Future<Output = Result<String, MCPError>>` implementa esta trait.
+#[async_trait]
+pub trait TokenProvider: Send + Sync {
+    /// Retorna um token de acesso válido para o escopo do Azure OpenAI.
+    async fn token(&self) -> Result<String, MCPError>;
+}
+
+#[async_trait]
+impl<F, Fut> TokenProvider for F
+where
+    F: Fn() -> Fut + Send + Sync,
+    Fut: Future<Output = Result<String, MCPError>> + Send,
+{
+    async fn token(&self) -> Result<String, MCPError> {
+        self().await
+    }
+}
+
+/// Credencial usada para autenticar no Azure OpenAI.
+#[derive(Clone)]
+pub enum AzureCredential {
+    /// Chave do recurso, enviada no cabeçalho `api-key`
+    ApiKey(String),
+
+    /// Token do Azure AD obtido a cada requisição, enviado como `Authorization: Bearer`
+    AzureAd(Arc<dyn TokenProvider>),
+}
+
+impl AzureCredential {
+    /// Cria uma credencial do Azure AD a partir de um provedor de tokens.
+    ///
+    /// # Exemplo
+    ///
+    /// ```
+    /// use mcprs::agent_azure_openai::AzureCredential;
+    ///
+    /// let credential = AzureCredential::azure_ad(|| async {
+    ///     // Obter o token com a biblioteca de identidade de sua preferência
+    ///     Ok("eyJ0eXAiOiJKV1Qi...".to_string())
+    /// });
+    /// ```
+    pub fn azure_ad(provider: impl TokenProvider + 'static) -> Self {
+        AzureCredential::AzureAd(Arc::new(provider))
+    }
+}
+
+/// Agente para comunicação com deployments do Azure OpenAI.
+///
+/// As requisições usam o formato de Chat Completions da OpenAI e a
+/// comunicação é feita pela implementação genérica de
+/// [`OpenAICompatibleAgent`](crate::agent_openai_compatible::OpenAICompatibleAgent).
+pub struct AzureOpenAIAgent {
+    /// URL do recurso (ex: "https://minha-empresa.openai.azure.com")
+    pub endpoint: String,
+
+    /// Versão da API enviada em `api-version`
+    pub api_version: String,
+
+    /// Deployments disponíveis; o primeiro é o padrão
+    pub deployments: Vec<String>,
+
+    /// Credencial de autenticação
+    credential: AzureCredential,
+
+    /// Cliente HTTP para fazer as requisições
+    http_client: Box<dyn HttpClient>,
+}
+
+impl AzureOpenAIAgent {
+    /// Cria um novo agente sem deployments, com a versão padrão da API.
+    ///
+    /// Ao menos um deployment deve ser registrado com
+    /// [`with_deployment`](Self::with_deployment) antes do uso.
+    ///
+    /// # Argumentos
+    /// * `endpoint` - URL do recurso do Azure OpenAI
+    /// * `credential` - Chave do recurso ou provedor de tokens do Azure AD
+    /// * `http_client` - Cliente HTTP para fazer as requisições
+    pub fn new(
+        endpoint: impl Into<String>,
+        credential: AzureCredential,
+        http_client: Box<dyn HttpClient>,
+    ) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            api_version: DEFAULT_AZURE_API_VERSION.to_string(),
+            deployments: Vec::new(),
+            credential,
+            http_client,
+        }
+    }
+
+    /// Registra um deployment. O primeiro registrado é o padrão.
+    pub fn with_deployment(mut self, deployment: impl Into<String>) -> Self {
+        self.deployments.push(deployment.into());
+        self
+    }
+
+    /// Fixa a versão da API enviada em `api-version`.
+    pub fn with_api_version(mut self, api_version: impl Into<String>) -> Self {
+        self.api_version = api_version.into();
+        self
+    }
+
+    /// Escolhe o deployment pelo campo `deployment` do payload.
+    fn resolve_deployment<'a>(&'a self, payload: &'a Value) -> Result<&'a str, MCPError> {
+        match payload.get("deployment").filter(|v| !v.is_null()) {
+            None => self.deployments.first().map(String::as_str).ok_or_else(|| {
+                MCPError::InternalAgentError("Nenhum deployment configurado".to_string())
+            }),
+            Some(Value::String(deployment)) => self
+                .deployments
+                .iter()
+                .find(|d| *d == deployment)
+                .map(String::as_str)
+                .ok_or_else(|| {
+                    MCPError::PayloadValidation(format!(
+                        "Deployment desconhecido: '{}'",
+                        deployment
+                    ))
+                }),
+            Some(_) => Err(MCPError::PayloadValidation(
+                "Campo 'deployment' deve ser uma string".to_string(),
+            )),
+        }
+    }
+
+    /// Configuração equivalente para a implementação genérica.
+    ///
+    /// O modelo não é enviado: no Azure ele é definido pelo deployment.
+    async fn config(&self, message: &MCPMessage) -> Result<OpenAICompatibleConfig, MCPError> {
+        let deployment = self.resolve_deployment(&message.payload)?;
+
+        let config =
+            OpenAICompatibleConfig::new("azure", self.endpoint.clone()).with_path(format!(
+                "/openai/deployments/{}/chat/completions?api-version={}",
+                deployment, self.api_version
+            ));
+
+        Ok(match &self.credential {
+            AzureCredential::ApiKey(key) => config
+                .with_auth(AuthScheme::Header("api-key".to_string()))
+                .with_api_key(key.clone()),
+            AzureCredential::AzureAd(provider) => config
+                .with_auth(AuthScheme::Bearer)
+                .with_api_key(provider.token().await?),
+        })
+    }
+}
+
+#[async_trait]
+impl AIAgent for AzureOpenAIAgent {
+    /// Retorna o nome do agente: "azure"
+    fn name(&self) -> &str {
+        "azure"
+    }
+
+    /// Processa uma requisição enviando-a para um deployment do Azure OpenAI.
+    ///
+    /// # Parâmetros esperados no payload
+    /// Os mesmos de [`OpenAIAgent`](crate::agent_openai::OpenAIAgent), além de:
+    /// * `deployment` - Nome do deployment a ser usado (opcional, padrão: o primeiro registrado)
+    ///
+    /// O campo `model` não é aceito: escolha o modelo pelo `deployment`.
+    ///
+    /// # Formato da resposta
+    /// A resposta terá o comando "azure_response" e o payload conterá:
+    /// * `answer` - O texto da resposta gerada pelo modelo
+    /// * `id`, `model`, `finish_reason` e `usage` - Quando informados pela API
+    /// * `deployment` - O deployment que atendeu a requisição
+    ///
+    /// # Erros
+    /// * `MCPError::PayloadValidation` - Se nem `user_prompt` nem `messages` forem informados,
+    ///   se o deployment não estiver registrado ou se o payload informar `model`
+    /// * `MCPError::InternalAgentError` - Se nenhum deployment estiver configurado
+    /// * `MCPError::RateLimited` ou `MCPError::Timeout` - Se a API limitar ou não responder a tempo
+    /// * `MCPError::Upstream` - Se a API retornar erro ou uma resposta que não possa ser processada
+    /// * Qualquer erro retornado pelo [`TokenProvider`]
+    async fn process_request(&self, message: MCPMessage) -> Result<MCPMessage, MCPError> {
+        let config = self.config(&message).await?;
+        let deployment = self.resolve_deployment(&message.payload)?.to_string();
+
+        let mut response = chat_completion(&config, self.http_client.as_ref(), message).await?;
+        response.payload["deployment"] = Value::String(deployment);
+        Ok(response)
+    }
+
+    /// Processa uma requisição em modo streaming (`stream: true`).
+    ///
+    /// O Azure OpenAI usa o mesmo formato SSE de Chat Completions da OpenAI;
+    /// cada fragmento de `delta.content` é emitido como um `StreamingToken`.
+    async fn process_stream(&self, message: MCPMessage) -> Result<TokenStream, MCPError> {
+        let config = self.config(&message).await?;
+        chat_completion_stream(&config, self.http_client.as_ref(), message).await
+    }
+}
+
+/// Função auxiliar para criar um agente Azure OpenAI com configurações do ambiente.
+///
+/// Esta função lê as variáveis de ambiente:
+/// - `AZURE_OPENAI_ENDPOINT` - URL do recurso (obrigatória)
+/// - `AZURE_OPENAI_DEPLOYMENTS` - Deployments separados por vírgula, o primeiro é o padrão (obrigatória)
+/// - `AZURE_OPENAI_API_KEY` - Chave do recurso
+/// - `AZURE_OPENAI_API_VERSION` - Versão da API (padrão: 2024-06-01)
+///
+/// Para autenticar com o Azure AD, crie o agente com [`AzureOpenAIAgent::new`]
+/// e [`AzureCredential::azure_ad`].
+///
+/// # Argumentos
+/// * `http_client` - Cliente HTTP opcional. Se None, será criado um novo.
+///
+/// # Retorno
+/// `None` se o endpoint ou os deployments não estiverem configurados
+///
+/// # Exemplo
+///
+/// ```
+/// use mcprs::agent_azure_openai::create_azure_openai_agent;
+///
+/// std::env::set_var("AZURE_OPENAI_ENDPOINT", "https://minha-empresa.openai.azure.com");
+/// std::env::set_var("AZURE_OPENAI_DEPLOYMENTS", "gpt-4o-prod,gpt-4o-mini");
+/// std::env::set_var("AZURE_OPENAI_API_KEY", "sua-chave-azure");
+///
+/// let agent = create_azure_openai_agent(None).unwrap();
+/// assert_eq!(agent.deployments, vec!["gpt-4o-prod", "gpt-4o-mini"]);
+/// ```
+pub fn create_azure_openai_agent(
+    http_client: Option<Box<dyn HttpClient>>,
+) -> Option<AzureOpenAIAgent> {
+    let endpoint = env::var("AZURE_OPENAI_ENDPOINT").ok()?;
+    let deployments: Vec<String> = env::var("AZURE_OPENAI_DEPLOYMENTS")
+        .ok()?
+        .split(',')
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+        .map(str::to_string)
+        .collect();
+    if deployments.is_empty() {
+        return None;
+    }
+
+    let client = http_client.unwrap_or_else(|| Box::new(crate::testing::ReqwestClient::new()));
+    let credential = AzureCredential::ApiKey(
+        env::var("AZURE_OPENAI_API_KEY").unwrap_or_else(|_| "SUA_AZURE_OPENAI_KEY".to_string()),
+    );
+
+    let mut agent = AzureOpenAIAgent::new(endpoint, credential, client);
+    agent.deployments = deployments;
+    if let Ok(api_version) = env::var("AZURE_OPENAI_API_VERSION") {
+        agent.api_version = api_version;
+    }
+    Some(agent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockHttpClient;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn create_mock_response() -> reqwest::Response {
+        reqwest::Response::from(
+            http::Response::builder()
+                .status(200)
+                .body(
+                    json!({
+                        "id": "chatcmpl-1",
+                        "choices": [{
+                            "message": { "role": "assistant", "content": "Olá!" },
+                            "finish_reason": "stop"
+                        }]
+                    })
+                    .to_string(),
+                )
+                .unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_azure_agent_selects_deployment() {
+        let mut mock_client = MockHttpClient::new();
+
+        mock_client
+            .expect_post()
+            .withf(|url, body, headers| {
+                let parsed: Value = serde_json::from_slice(body).unwrap_or_default();
+                url == "https://empresa.openai.azure.com/openai/deployments/gpt-4o-mini/chat/completions?api-version=2024-10-21"
+                    && parsed.get("model").is_none()
+                    && headers.iter().any(|(k, v)| k == "api-key" && v == "chave")
+                    && !headers.iter().any(|(k, _)| k == "Authorization")
+            })
+            .times(1)
+            .return_once(|_, _, _| Ok(create_mock_response()));
+
+        let agent = AzureOpenAIAgent::new(
+            "https://empresa.openai.azure.com",
+            AzureCredential::ApiKey("chave".to_string()),
+            Box::new(mock_client),
+        )
+        .with_deployment("gpt-4o-prod")
+        .with_deployment("gpt-4o-mini")
+        .with_api_version("2024-10-21");
+
+        let message = MCPMessage::new(
+            "azure:chat",
+            json!({ "user_prompt": "Olá", "deployment": "gpt-4o-mini" }),
+        );
+
+        let result = agent.process_request(message).await.unwrap();
+        assert_eq!(result.command, "azure_response");
+        assert_eq!(result.payload["answer"], "Olá!");
+        assert_eq!(result.payload["deployment"], "gpt-4o-mini");
+    }
+
+    #[tokio::test]
+    async fn test_azure_agent_uses_token_provider() {
+        let mut mock_client = MockHttpClient::new();
+
+        mock_client
+            .expect_post()
+            .withf(|url, _, headers| {
+                url.contains("/openai/deployments/gpt-4o-prod/")
+                    && headers
+                        .iter()
+                        .any(|(k, v)| k == "Authorization" && v == "Bearer token-2")
+                    && !headers.iter().any(|(k, _)| k == "api-key")
+            })
+            .times(1)
+            .return_once(|_, _, _| Ok(create_mock_response()));
+
+        // O provedor é chamado a cada requisição
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let credential = AzureCredential::azure_ad(move || {
+            let counter = counter.clone();
+            async move {
+                let n = counter.fetch_add(1, Ordering::SeqCst) + 2;
+                Ok(format!("token-{}", n))
+            }
+        });
+
+        let agent = AzureOpenAIAgent::new(
+            "https://empresa.openai.azure.com",
+            credential,
+            Box::new(mock_client),
+        )
+        .with_deployment("gpt-4o-prod");
+
+        let message = MCPMessage::new("azure:chat", json!({ "user_prompt": "Olá" }));
+        let result = agent.process_request(message).await.unwrap();
+
+        assert_eq!(result.payload["deployment"], "gpt-4o-prod");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_azure_agent_rejects_unknown_deployment() {
+        let agent = AzureOpenAIAgent::new(
+            "https://empresa.openai.azure.com",
+            AzureCredential::ApiKey("chave".to_string()),
+            Box::new(MockHttpClient::new()),
+        )
+        .with_deployment("gpt-4o-prod");
+
+        let message = MCPMessage::new(
+            "azure:chat",
+            json!({ "user_prompt": "Olá", "deployment": "gpt-5" }),
+        );
+
+        let result = agent.process_request(message).await;
+        assert!(matches!(result, Err(MCPError::PayloadValidation(e)) if e.contains("gpt-5")));
+    }
+
+    #[tokio::test]
+    async fn test_azure_agent_rejects_model_override() {
+        let agent = AzureOpenAIAgent::new(
+            "https://empresa.openai.azure.com",
+            AzureCredential::ApiKey("chave".to_string()),
+            Box::new(MockHttpClient::new()),
+        )
+        .with_deployment("gpt-4o-prod");
+
+        // O modelo é definido pelo deployment
+        let message = MCPMessage::new(
+            "azure:chat",
+            json!({ "user_prompt": "Olá", "model": "gpt-4o" }),
+        );
+
+        let result = agent.process_request(message).await;
+        assert!(matches!(result, Err(MCPError::PayloadValidation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_azure_agent_token_provider_error() {
+        let credential = AzureCredential::azure_ad(|| async {
+            Err(MCPError::InternalAgentError(
+                "Falha ao obter token".to_string(),
+            ))
+        });
+
+        let agent = AzureOpenAIAgent::new(
+            "https://empresa.openai.azure.com",
+            credential,
+            Box::new(MockHttpClient::new()),
+        )
+        .with_deployment("gpt-4o-prod");
+
+        let message = MCPMessage::new("azure:chat", json!({ "user_prompt": "Olá" }));
+        let result = agent.process_request(message).await;
+        assert!(matches!(result, Err(MCPError::InternalAgentError(_))));
+    }
+}
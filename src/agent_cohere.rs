@@ -0,0 +1,622 @@
+//! # Agente para integração com a API Cohere
+//!
+//! Este módulo implementa um agente que se comunica com a Chat API da
+//! Cohere, permitindo enviar prompts para os modelos Command. O histórico da
+//! conversa é enviado em `chat_history`, as instruções de sistema em
+//! `preamble` e a última mensagem do usuário em `message`.
+//!
+//! ## Exemplo de Uso
+//!
+//! ```rust,no_run
+//! use mcprs::agent::{AgentRegistry, MCPMessage};
+//! use mcprs::agent_cohere::create_cohere_agent;
+//! use serde_json::{json, Value};
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! // Configurar variável de ambiente (ou diretamente na criação do agente)
+//! std::env::set_var("COHERE_API_KEY", "sua-chave-aqui");
+//!
+//! // Criar e registrar o agente Cohere
+//! let mut registry = AgentRegistry::new();
+//! registry.register_agent(Box::new(create_cohere_agent(None)));
+//!
+//! // Criar uma mensagem para o Command
+//! let message = MCPMessage::new(
+//!     "cohere:chat",
+//!     json!({
+//!         "system_prompt": "Responda em uma frase.",
+//!         "user_prompt": "Explique o que é Rust"
+//!     })
+//! );
+//!
+//! // Processar a mensagem
+//! let response = registry.process(message).await?;
+//! println!("Resposta: {}", response.payload["answer"]);
+//! println!("Tokens: {}", response.payload["usage"]);
+//! # Ok(())
+//! # }
+//! ```
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::env;
+
+use crate::agent::{AIAgent, MCPError, MCPMessage};
+use crate::chat::{
+    parse_model_list, select_model, ChatMessage, ChatRequest, ChatRole, GenerationParams,
+};
+use crate::streaming::{process_json_stream_with, StreamingToken, TokenStream};
+use crate::testing::HttpClient;
+use crate::usage::Usage;
+
+/// URL do endpoint da Chat API da Cohere
+const COHERE_CHAT_URL: &str = "https://api.cohere.ai/v1/chat";
+
+/// Parâmetros de geração aceitos pela Chat API
+const SUPPORTED_PARAMS: &[&str] = &[
+    "temperature",
+    "top_p",
+    "max_tokens",
+    "stop",
+    "seed",
+    "presence_penalty",
+    "frequency_penalty",
+];
+
+/// Agente para comunicação com a API Cohere.
+///
+/// Este agente implementa a trait `AIAgent` e se conecta à Chat API da
+/// Cohere para enviar prompts e obter respostas dos modelos Command.
+pub struct CohereAgent {
+    /// Chave de API da Cohere
+    pub api_key: String,
+
+    /// Nome do modelo a ser usado (ex: "command-r", "command-r-plus")
+    pub model: String,
+
+    /// Modelos adicionais que o payload pode escolher pelo campo `model`
+    pub allowed_models: Vec<String>,
+
+    /// Cliente HTTP para fazer as requisições
+    http_client: Box<dyn HttpClient>,
+}
+
+impl CohereAgent {
+    /// Cria uma nova instância do agente Cohere.
+    ///
+    /// # Argumentos
+    /// * `api_key` - Chave de API da Cohere
+    /// * `model` - Nome do modelo a ser usado
+    /// * `http_client` - Cliente HTTP para fazer as requisições
+    ///
+    /// # Exemplo
+    ///
+    /// ```
+    /// use mcprs::agent_cohere::CohereAgent;
+    /// use mcprs::testing::ReqwestClient;
+    ///
+    /// let agent = CohereAgent::new(
+    ///     "sua-chave-api".to_string(),
+    ///     "command-r".to_string(),
+    ///     Box::new(ReqwestClient::new())
+    /// );
+    /// ```
+    pub fn new(api_key: String, model: String, http_client: Box<dyn HttpClient>) -> Self {
+        Self {
+            api_key,
+            model,
+            allowed_models: Vec::new(),
+            http_client,
+        }
+    }
+
+    /// Permite que o payload escolha um dos modelos informados pelo campo `model`.
+    ///
+    /// O modelo padrão é sempre permitido; qualquer outro modelo é rejeitado
+    /// com `MCPError::PayloadValidation`.
+    pub fn with_allowed_models<I, S>(mut self, models: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allowed_models = models.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Monta o corpo da requisição da Chat API a partir do payload MCP.
+    ///
+    /// A última mensagem deve ser do usuário e é enviada em `message`; as
+    /// anteriores vão para `chat_history` e as de sistema para `preamble`.
+    /// Os parâmetros `top_p` e `stop` são enviados como `p` e `stop_sequences`,
+    /// e o modo JSON como `response_format: {"type": "json_object", "schema"}`.
+    fn build_request(&self, message: &MCPMessage, stream: bool) -> Result<CohereRequest, MCPError> {
+        // Interpretar as mensagens do payload
+        let chat_request = ChatRequest::from_payload(&message.payload)?;
+        chat_request.ensure_no_tools("cohere")?;
+        let (preamble, mut messages) = chat_request.split_system();
+        let params = GenerationParams::from_payload(&message.payload)?;
+        params.ensure_supported("cohere", SUPPORTED_PARAMS)?;
+        let model = select_model(&message.payload, Some(&self.model), &self.allowed_models)?
+            .unwrap_or(&self.model);
+
+        let last = match messages.pop() {
+            Some(last) if last.role == ChatRole::User => last,
+            _ => {
+                return Err(MCPError::PayloadValidation(
+                    "A última mensagem deve ser do usuário".to_string(),
+                ))
+            }
+        };
+
+        Ok(CohereRequest {
+            model: model.to_string(),
+            message: last.content,
+            chat_history: messages.iter().map(CohereHistoryMessage::from).collect(),
+            preamble,
+            temperature: params.temperature,
+            p: params.top_p,
+            max_tokens: params.max_tokens,
+            stop_sequences: params.stop,
+            seed: params.seed,
+            presence_penalty: params.presence_penalty,
+            frequency_penalty: params.frequency_penalty,
+            response_format: chat_request
+                .response_format
+                .as_ref()
+                .filter(|format| format.is_json())
+                .map(|format| match format.schema() {
+                    Some(schema) => json!({ "type": "json_object", "schema": schema }),
+                    None => json!({ "type": "json_object" }),
+                }),
+            stream: stream.then_some(true),
+        })
+    }
+
+    /// Envia a requisição para a API Cohere e valida o status da resposta.
+    async fn send_request(
+        &self,
+        request_body: &CohereRequest,
+    ) -> Result<reqwest::Response, MCPError> {
+        // Preparar os headers
+        let headers = vec![
+            (
+                "Authorization".to_string(),
+                format!("Bearer {}", self.api_key),
+            ),
+            ("Content-Type".to_string(), "application/json".to_string()),
+        ];
+
+        // Enviar a requisição para a API Cohere
+        let response = self
+            .http_client
+            .post(
+                COHERE_CHAT_URL.to_string(),
+                serde_json::to_vec(request_body)
+                    .map_err(|e| MCPError::InternalAgentError(e.to_string()))?,
+                headers,
+            )
+            .await
+            .map_err(|e| MCPError::from_transport_error("cohere", &e))?;
+
+        // Verificar o status da resposta
+        if !response.status().is_success() {
+            return Err(MCPError::from_provider_response("cohere", &response));
+        }
+
+        Ok(response)
+    }
+}
+
+/// Estrutura para o corpo da requisição à Chat API
+#[derive(Serialize)]
+struct CohereRequest {
+    model: String,
+    message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    chat_history: Vec<CohereHistoryMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    preamble: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    p: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop_sequences: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    presence_penalty: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frequency_penalty: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream: Option<bool>,
+}
+
+/// Mensagem do histórico no formato da Cohere (`USER` ou `CHATBOT`)
+#[derive(Serialize)]
+struct CohereHistoryMessage {
+    role: &'static str,
+    message: String,
+}
+
+impl From<&ChatMessage> for CohereHistoryMessage {
+    fn from(message: &ChatMessage) -> Self {
+        let role = match message.role {
+            ChatRole::Assistant => "CHATBOT",
+            ChatRole::System => "SYSTEM",
+            ChatRole::User => "USER",
+            ChatRole::Tool => "TOOL",
+        };
+
+        Self {
+            role,
+            message: message.content.clone(),
+        }
+    }
+}
+
+/// Estrutura para a resposta da Chat API
+#[derive(Deserialize)]
+struct CohereResponse {
+    text: String,
+    generation_id: Option<String>,
+    finish_reason: Option<String>,
+    #[serde(default)]
+    meta: CohereMeta,
+}
+
+/// Metadados da resposta, incluindo a contagem de tokens cobrados
+#[derive(Deserialize, Default)]
+struct CohereMeta {
+    #[serde(default)]
+    billed_units: CohereUsage,
+}
+
+/// Contagem de tokens informada pela API
+#[derive(Deserialize, Default, Clone, Copy)]
+struct CohereUsage {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+}
+
+impl CohereUsage {
+    /// Contagem de tokens, no mesmo formato usado pelos demais agentes.
+    fn normalized(&self) -> Usage {
+        Usage::new(self.input_tokens, self.output_tokens)
+    }
+}
+
+/// Evento do stream da Chat API, identificado pelo campo `event_type`
+#[derive(Deserialize)]
+#[serde(tag = "event_type", rename_all = "kebab-case")]
+enum CohereStreamEvent {
+    TextGeneration {
+        text: String,
+    },
+    StreamEnd {
+        finish_reason: Option<String>,
+        response: Option<CohereStreamEndResponse>,
+    },
+    #[serde(other)]
+    Other,
+}
+
+/// Resposta completa enviada no evento `stream-end`
+#[derive(Deserialize)]
+struct CohereStreamEndResponse {
+    #[serde(default)]
+    meta: CohereMeta,
+}
+
+/// Processa o stream da Chat API (um objeto JSON por linha).
+///
+/// Cada evento `text-generation` vira um token. O evento `stream-end` gera
+/// um token vazio com `finish_reason` e `usage` em `metadata`; se a geração
+/// terminar com `ERROR`, o erro é propagado como `MCPError::Upstream`.
+async fn process_cohere_stream<S>(stream: S) -> Result<TokenStream, MCPError>
+where
+    S: futures::Stream<Item = Result<bytes::Bytes, reqwest::Error>> + Send + 'static,
+{
+    process_json_stream_with::<_, CohereStreamEvent, _>(stream, |event| match event {
+        CohereStreamEvent::TextGeneration { text } if !text.is_empty() => {
+            Ok(Some(StreamingToken {
+                content: text,
+                is_finish: false,
+                metadata: None,
+            }))
+        }
+        CohereStreamEvent::StreamEnd { finish_reason, .. }
+            if finish_reason.as_deref() == Some("ERROR") =>
+        {
+            Err(MCPError::Upstream {
+                provider: "cohere".to_string(),
+                status: None,
+                message: "A geração terminou com erro".to_string(),
+                retryable: true,
+            })
+        }
+        CohereStreamEvent::StreamEnd {
+            finish_reason,
+            response,
+        } => Ok(Some(StreamingToken {
+            content: String::new(),
+            is_finish: false,
+            metadata: Some(json!({
+                "finish_reason": finish_reason,
+                "usage": response.map(|r| r.meta.billed_units).unwrap_or_default().normalized(),
+            })),
+        })),
+        _ => Ok(None),
+    })
+    .await
+}
+
+#[async_trait]
+impl AIAgent for CohereAgent {
+    /// Retorna o nome do agente: "cohere"
+    fn name(&self) -> &str {
+        "cohere"
+    }
+
+    /// Processa uma requisição enviando-a para a Chat API.
+    ///
+    /// # Parâmetros esperados no payload
+    /// As mensagens seguem o modelo de [`ChatRequest`]:
+    /// * `system_prompt` - Instrução de sistema, enviada em `preamble` (opcional)
+    /// * `messages` - Histórico de mensagens `{role, content}`, enviado em `chat_history` (opcional)
+    /// * `user_prompt` - O prompt do usuário (opcional se `messages` terminar com uma mensagem do usuário)
+    /// * `temperature`, `top_p` (enviado como `p`), `max_tokens`, `stop` (enviado como
+    ///   `stop_sequences`), `seed`, `presence_penalty` e `frequency_penalty` -
+    ///   Parâmetros de geração (opcionais)
+    /// * `model` - Modelo a ser usado; deve ser o padrão ou estar em `allowed_models` (opcional)
+    /// * `response_format` - Formato JSON da resposta, com o schema se informado (opcional)
+    ///
+    /// # Formato da resposta
+    /// A resposta terá o comando "cohere_response" e o payload conterá:
+    /// * `answer` - O texto da resposta gerada pelo modelo
+    /// * `id` - O ID da geração informado pela API
+    /// * `model` - O modelo que atendeu a requisição
+    /// * `finish_reason` - A razão de término da geração (COMPLETE, MAX_TOKENS, etc.)
+    /// * `usage` - Tokens consumidos (`input_tokens`, `output_tokens` e `total_tokens`)
+    ///
+    /// # Erros
+    /// * `MCPError::PayloadValidation` - Se nem `user_prompt` nem `messages` forem informados,
+    ///   se a última mensagem não for do usuário, ou se algum parâmetro de geração
+    ///   for inválido ou não suportado (`n`, `logit_bias`, `user`), se o modelo
+    ///   não for permitido ou se o payload usar ferramentas
+    /// * `MCPError::RateLimited` ou `MCPError::Timeout` - Se a API limitar ou não responder a tempo
+    /// * `MCPError::Upstream` - Se a API retornar erro ou uma resposta que não possa ser processada
+    async fn process_request(&self, message: MCPMessage) -> Result<MCPMessage, MCPError> {
+        let request_body = self.build_request(&message, false)?;
+        let response = self.send_request(&request_body).await?;
+
+        // Deserializar a resposta
+        let resp_json = response
+            .json::<CohereResponse>()
+            .await
+            .map_err(|e| MCPError::invalid_response("cohere", &e))?;
+
+        // Retornar a resposta formatada como MCPMessage
+        Ok(MCPMessage::new(
+            "cohere_response",
+            json!({
+                "answer": resp_json.text,
+                "id": resp_json.generation_id,
+                "model": request_body.model,
+                "finish_reason": resp_json.finish_reason,
+                "usage": resp_json.meta.billed_units.normalized(),
+            }),
+        )
+        .in_reply_to(&message))
+    }
+
+    /// Processa uma requisição em modo streaming (`stream: true`).
+    ///
+    /// O texto de cada evento `text-generation` é emitido como um
+    /// `StreamingToken`; o evento `stream-end` traz `finish_reason` e `usage`
+    /// em `metadata`.
+    ///
+    /// # Erros
+    /// Os mesmos de [`CohereAgent::process_request`] para falhas antes do
+    /// início do streaming.
+    async fn process_stream(&self, message: MCPMessage) -> Result<TokenStream, MCPError> {
+        let request_body = self.build_request(&message, true)?;
+        let response = self.send_request(&request_body).await?;
+
+        process_cohere_stream(response.bytes_stream()).await
+    }
+}
+
+/// Função auxiliar para criar um agente Cohere com configurações do ambiente.
+///
+/// Esta função facilita a criação de uma instância do agente Cohere, obtendo
+/// as configurações das variáveis de ambiente:
+/// - `COHERE_API_KEY` - Chave de API
+/// - `COHERE_MODEL` - Nome do modelo (padrão: command-r)
+/// - `COHERE_ALLOWED_MODELS` - Modelos adicionais permitidos no payload, separados por vírgula
+///
+/// # Argumentos
+/// * `http_client` - Cliente HTTP opcional. Se None, será criado um novo.
+///
+/// # Retorno
+/// Uma nova instância de `CohereAgent` configurada.
+///
+/// # Exemplo
+///
+/// ```
+/// use mcprs::agent_cohere::create_cohere_agent;
+///
+/// // Configurar a variável de ambiente primeiro
+/// std::env::set_var("COHERE_API_KEY", "sua-chave-api");
+///
+/// // Criar o agente
+/// let agent = create_cohere_agent(None);
+/// ```
+pub fn create_cohere_agent(http_client: Option<Box<dyn HttpClient>>) -> CohereAgent {
+    let client = http_client.unwrap_or_else(|| Box::new(crate::testing::ReqwestClient::new()));
+
+    CohereAgent::new(
+        env::var("COHERE_API_KEY").unwrap_or_else(|_| "SUA_COHERE_KEY".to_string()),
+        env::var("COHERE_MODEL").unwrap_or_else(|_| "command-r".to_string()),
+        client,
+    )
+    .with_allowed_models(
+        env::var("COHERE_ALLOWED_MODELS")
+            .map(|models| parse_model_list(&models))
+            .unwrap_or_default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockHttpClient;
+    use futures::StreamExt;
+    use serde_json::Value;
+
+    fn create_agent(mock_client: MockHttpClient) -> CohereAgent {
+        CohereAgent::new(
+            "test_key".to_string(),
+            "command-test".to_string(),
+            Box::new(mock_client),
+        )
+    }
+
+    #[test]
+    fn test_build_request_maps_history_and_preamble() {
+        let agent = create_agent(MockHttpClient::new());
+        let message = MCPMessage::new(
+            "cohere:chat",
+            json!({
+                "system_prompt": "Seja breve.",
+                "messages": [
+                    { "role": "user", "content": "Qual a capital da França?" },
+                    { "role": "assistant", "content": "Paris." }
+                ],
+                "user_prompt": "E da Itália?"
+            }),
+        );
+
+        let request = serde_json::to_value(agent.build_request(&message, false).unwrap()).unwrap();
+        assert_eq!(
+            request,
+            json!({
+                "model": "command-test",
+                "message": "E da Itália?",
+                "chat_history": [
+                    { "role": "USER", "message": "Qual a capital da França?" },
+                    { "role": "CHATBOT", "message": "Paris." }
+                ],
+                "preamble": "Seja breve."
+            })
+        );
+    }
+
+    #[test]
+    fn test_build_request_maps_generation_params() {
+        let agent = create_agent(MockHttpClient::new());
+        let message = MCPMessage::new(
+            "cohere:chat",
+            json!({ "user_prompt": "Olá", "top_p": 0.75, "stop": "FIM", "max_tokens": 20 }),
+        );
+
+        let request = serde_json::to_value(agent.build_request(&message, false).unwrap()).unwrap();
+        assert_eq!(request["p"], 0.75);
+        assert_eq!(request["stop_sequences"], json!(["FIM"]));
+        assert_eq!(request["max_tokens"], 20);
+
+        let message = MCPMessage::new(
+            "cohere:chat",
+            json!({ "user_prompt": "Olá", "logit_bias": { "1": 1 } }),
+        );
+        assert!(matches!(
+            agent.build_request(&message, false),
+            Err(MCPError::PayloadValidation(e)) if e.contains("logit_bias")
+        ));
+    }
+
+    #[test]
+    fn test_build_request_requires_user_message_last() {
+        let agent = create_agent(MockHttpClient::new());
+        let message = MCPMessage::new(
+            "cohere:chat",
+            json!({ "messages": [{ "role": "assistant", "content": "Olá!" }] }),
+        );
+
+        assert!(matches!(
+            agent.build_request(&message, false),
+            Err(MCPError::PayloadValidation(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_cohere_agent_process_stream() {
+        let mut mock_client = MockHttpClient::new();
+
+        mock_client
+            .expect_post()
+            .withf(|_, body, _| {
+                let parsed: Value = serde_json::from_slice(body).unwrap_or_default();
+                parsed["stream"] == true
+            })
+            .times(1)
+            .return_once(move |_, _, _| {
+                let body = concat!(
+                    "{\"is_finished\":false,\"event_type\":\"stream-start\",\"generation_id\":\"g1\"}\n",
+                    "{\"is_finished\":false,\"event_type\":\"text-generation\",\"text\":\"Rust\"}\n",
+                    "{\"is_finished\":false,\"event_type\":\"text-generation\",\"text\":\" é seguro\"}\n",
+                    "{\"is_finished\":true,\"event_type\":\"stream-end\",\"finish_reason\":\"COMPLETE\",\"response\":{\"text\":\"Rust é seguro\",\"meta\":{\"billed_units\":{\"input_tokens\":9,\"output_tokens\":3}}}}\n"
+                );
+                Ok(reqwest::Response::from(
+                    http::Response::builder().status(200).body(body).unwrap(),
+                ))
+            });
+
+        let agent = create_agent(mock_client);
+        let message = MCPMessage::new("cohere:chat", json!({ "user_prompt": "O que é Rust?" }));
+        let mut stream = agent.process_stream(message).await.unwrap();
+
+        let mut content = String::new();
+        let mut metadata = None;
+        while let Some(token) = stream.next().await {
+            let token = token.unwrap();
+            if token.is_finish {
+                break;
+            }
+            content.push_str(&token.content);
+            if token.metadata.is_some() {
+                metadata = token.metadata;
+            }
+        }
+
+        assert_eq!(content, "Rust é seguro");
+        assert_eq!(
+            metadata.unwrap(),
+            json!({
+                "finish_reason": "COMPLETE",
+                "usage": { "input_tokens": 9, "output_tokens": 3, "total_tokens": 12 }
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cohere_stream_error_end() {
+        let chunks = vec![Ok(bytes::Bytes::from(
+            "{\"is_finished\":true,\"event_type\":\"stream-end\",\"finish_reason\":\"ERROR\"}\n",
+        ))];
+
+        let mut stream = process_cohere_stream(futures::stream::iter(chunks))
+            .await
+            .unwrap();
+
+        let error = stream.next().await.unwrap().unwrap_err();
+        assert!(matches!(error, MCPError::Upstream { .. }));
+    }
+}
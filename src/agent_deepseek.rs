@@ -34,17 +34,23 @@
 //! ```
 
 use async_trait::async_trait;
-use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
 use std::env;
 
 use crate::agent::{AIAgent, MCPError, MCPMessage};
+use crate::agent_openai_compatible::{
+    process_action, process_stream_action, OpenAICompatibleConfig,
+};
+use crate::chat::parse_model_list;
+use crate::streaming::TokenStream;
 use crate::testing::HttpClient;
 
 /// Agente para comunicação com a API DeepSeek.
 ///
 /// Este agente implementa a trait `AIAgent` e se conecta aos endpoints do
 /// DeepSeek para enviar prompts e obter respostas dos modelos disponíveis.
+/// A API DeepSeek é compatível com a da OpenAI, e a comunicação é feita pela
+/// implementação genérica de
+/// [`OpenAICompatibleAgent`](crate::agent_openai_compatible::OpenAICompatibleAgent).
 pub struct DeepSeekAgent {
     /// Chave de API do DeepSeek
     pub api_key: String,
@@ -55,6 +61,15 @@ pub struct DeepSeekAgent {
     /// Nome do modelo a ser usado
     pub model: String,
 
+    /// Modelos adicionais que o payload pode escolher pelo campo `model`
+    pub allowed_models: Vec<String>,
+
+    /// Modelo padrão do comando `deepseek:embeddings`, se o endpoint oferecer um
+    pub embedding_model: Option<String>,
+
+    /// Modelos de embeddings adicionais que o payload pode escolher pelo campo `model`
+    pub allowed_embedding_models: Vec<String>,
+
     /// Cliente HTTP para fazer as requisições
     http_client: Box<dyn HttpClient>,
 }
@@ -91,47 +106,58 @@ impl DeepSeekAgent {
             api_key,
             endpoint,
             model,
+            allowed_models: Vec::new(),
+            embedding_model: None,
+            allowed_embedding_models: Vec::new(),
             http_client,
         }
     }
-}
 
-/// Estrutura para o corpo da requisição à API DeepSeek
-#[derive(Serialize)]
-struct DeepSeekRequest {
-    model: String,
-    messages: Vec<DeepSeekMessage>,
-    temperature: Option<f32>,
-    max_tokens: Option<u32>,
-}
-
-/// Estrutura para uma mensagem na requisição à API DeepSeek
-#[derive(Serialize)]
-struct DeepSeekMessage {
-    role: String,
-    content: String,
-}
+    /// Permite que o payload escolha um dos modelos informados pelo campo `model`.
+    ///
+    /// O modelo padrão é sempre permitido; qualquer outro modelo é rejeitado
+    /// com `MCPError::PayloadValidation`.
+    pub fn with_allowed_models<I, S>(mut self, models: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allowed_models = models.into_iter().map(Into::into).collect();
+        self
+    }
 
-/// Estrutura para a resposta da API DeepSeek
-#[derive(Deserialize)]
-struct DeepSeekResponse {
-    id: String,
-    choices: Vec<DeepSeekChoice>,
-}
+    /// Define o modelo padrão do comando `deepseek:embeddings`.
+    ///
+    /// Sem ele, o payload deve informar um `model` presente em `allowed_embedding_models`.
+    pub fn with_embedding_model(mut self, model: impl Into<String>) -> Self {
+        self.embedding_model = Some(model.into());
+        self
+    }
 
-/// Estrutura para um item de escolha na resposta da API DeepSeek
-#[derive(Deserialize)]
-struct DeepSeekChoice {
-    message: DeepSeekMessageResponse,
-    finish_reason: String,
-}
+    /// Permite que o payload de `deepseek:embeddings` escolha um dos modelos informados.
+    ///
+    /// A lista é separada de `allowed_models`, usada apenas no chat.
+    pub fn with_allowed_embedding_models<I, S>(mut self, models: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allowed_embedding_models = models.into_iter().map(Into::into).collect();
+        self
+    }
 
-/// Estrutura para a mensagem dentro de um item de escolha na resposta
-#[derive(Deserialize)]
-struct DeepSeekMessageResponse {
-    #[allow(dead_code)]
-    role: String,
-    content: String,
+    /// Configuração equivalente para a implementação genérica.
+    fn config(&self) -> OpenAICompatibleConfig {
+        let config = OpenAICompatibleConfig::deepseek(self.api_key.clone())
+            .with_base_url(self.endpoint.clone())
+            .with_default_model(self.model.clone())
+            .with_allowed_models(self.allowed_models.clone())
+            .with_allowed_embedding_models(self.allowed_embedding_models.clone());
+        match &self.embedding_model {
+            Some(model) => config.with_embedding_model(model.clone()),
+            None => config,
+        }
+    }
 }
 
 #[async_trait]
@@ -143,100 +169,46 @@ impl AIAgent for DeepSeekAgent {
 
     /// Processa uma requisição enviando-a para a API DeepSeek.
     ///
+    /// O comando `deepseek:embeddings` é encaminhado ao endpoint de embeddings
+    /// (veja [`crate::embeddings`]); os demais comandos usam Chat Completions.
+    ///
     /// # Parâmetros esperados no payload
-    /// * `user_prompt` - O prompt do usuário (obrigatório)
-    /// * `temperature` - Temperatura para geração (opcional)
-    /// * `max_tokens` - Limite de tokens na resposta (opcional)
+    /// As mensagens seguem o modelo de [`ChatRequest`](crate::chat::ChatRequest):
+    /// * `system_prompt` - Instrução de sistema (opcional)
+    /// * `messages` - Histórico de mensagens `{role, content}` (opcional)
+    /// * `user_prompt` - O prompt do usuário (opcional se `messages` for informado)
+    /// * `temperature`, `top_p`, `max_tokens`, `stop`, `presence_penalty` e
+    ///   `frequency_penalty` - Parâmetros de geração (opcionais)
+    /// * `model` - Modelo a ser usado; deve ser o padrão ou estar em `allowed_models` (opcional)
+    /// * `tools` e `tool_choice` - Ferramentas que o modelo pode chamar (opcionais)
     ///
     /// # Formato da resposta
     /// A resposta terá o comando "deepseek_response" e o payload conterá:
     /// * `answer` - O texto da resposta gerada pelo modelo
+    /// * `model` - O modelo que atendeu a requisição, como informado pela API
     /// * `id` - O ID da resposta gerada pela API
     /// * `finish_reason` - A razão de término da geração (stop, length, etc.)
+    /// * `tool_calls` - Chamadas de ferramentas `{id, name, arguments}`, se houver
     ///
     /// # Erros
-    /// * Retorna `MCPError::InternalAgentError` se:
-    ///   - O campo `user_prompt` estiver ausente
-    ///   - Houver falha na comunicação com a API
-    ///   - A resposta da API não puder ser processada
+    /// * `MCPError::PayloadValidation` - Se nem `user_prompt` nem `messages` forem informados,
+    ///   se algum parâmetro de geração for inválido ou não suportado
+    ///   (`seed`, `n`, `logit_bias`, `user`), se o modelo não for permitido
+    ///   ou se `tools` ou `tool_choice` forem inválidos
+    /// * `MCPError::RateLimited` ou `MCPError::Timeout` - Se a API limitar ou não responder a tempo
+    /// * `MCPError::Upstream` - Se a API retornar erro ou uma resposta que não possa ser processada
     async fn process_request(&self, message: MCPMessage) -> Result<MCPMessage, MCPError> {
-        // Extrair o prompt de usuário do payload
-        let user_prompt = message
-            .payload
-            .get("user_prompt".to_owned())
-            .and_then(Value::as_str)
-            .ok_or_else(|| MCPError::InternalAgentError("Missing user_prompt".to_string()))?;
-
-        // Estruturar a requisição para DeepSeek
-        let request_body = DeepSeekRequest {
-            model: self.model.clone(),
-            messages: vec![DeepSeekMessage {
-                role: "user".to_string(),
-                content: user_prompt.to_string(),
-            }],
-            temperature: message
-                .payload
-                .get("temperature".to_owned())
-                .and_then(|v| v.as_f64())
-                .map(|v| v as f32),
-            max_tokens: message
-                .payload
-                .get("max_tokens".to_owned())
-                .and_then(|v| v.as_u64())
-                .map(|v| v as u32),
-        };
-
-        // Configurar headers
-        let headers = vec![
-            (
-                "Authorization".to_string(),
-                format!("Bearer {}", self.api_key),
-            ),
-            ("Content-Type".to_string(), "application/json".to_string()),
-        ];
-
-        // Enviar requisição para a API DeepSeek
-        let response = self
-            .http_client
-            .post(
-                format!("{}/v1/chat/completions", self.endpoint),
-                serde_json::to_vec(&request_body)
-                    .map_err(|e| MCPError::InternalAgentError(e.to_string()))?,
-                headers,
-            )
-            .await
-            .map_err(|e| MCPError::InternalAgentError(e.to_string()))?;
-
-        // Validar status da resposta
-        if !response.status().is_success() {
-            return Err(MCPError::InternalAgentError(format!(
-                "DeepSeek API retornou status {}",
-                response.status()
-            )));
-        }
+        process_action(&self.config(), self.http_client.as_ref(), message).await
+    }
 
-        // Desserializar e processar a resposta
-        let resp_json = response
-            .json::<DeepSeekResponse>()
-            .await
-            .map_err(|e| MCPError::InternalAgentError(e.to_string()))?;
-
-        // Extrair o texto da resposta
-        let answer_text = resp_json
-            .choices
-            .get(0)
-            .map(|choice| choice.message.content.clone())
-            .ok_or_else(|| MCPError::InternalAgentError("No response choices".to_string()))?;
-
-        // Retornar a resposta formatada como MCPMessage
-        Ok(MCPMessage::new(
-            "deepseek_response",
-            json!({
-                "answer": answer_text,
-                "id": resp_json.id,
-                "finish_reason": resp_json.choices.get(0).map(|c| &c.finish_reason).unwrap_or(&"unknown".to_string())
-            }),
-        ))
+    /// Processa uma requisição em modo streaming (`stream: true`).
+    ///
+    /// A API DeepSeek usa o mesmo formato SSE de Chat Completions da OpenAI;
+    /// cada fragmento de `delta.content` é emitido como um `StreamingToken`.
+    /// O comando `deepseek:embeddings` não tem streaming e é rejeitado com
+    /// `MCPError::PayloadValidation`.
+    async fn process_stream(&self, message: MCPMessage) -> Result<TokenStream, MCPError> {
+        process_stream_action(&self.config(), self.http_client.as_ref(), message).await
     }
 }
 
@@ -247,6 +219,9 @@ impl AIAgent for DeepSeekAgent {
 /// - `DEEPSEEK_API_KEY` - Chave de API
 /// - `DEEPSEEK_ENDPOINT` - URL base do endpoint (padrão: https://api.deepseek.ai)
 /// - `DEEPSEEK_MODEL` - Nome do modelo (padrão: deepseek-chat)
+/// - `DEEPSEEK_ALLOWED_MODELS` - Modelos adicionais permitidos no payload, separados por vírgula
+/// - `DEEPSEEK_EMBEDDING_MODEL` - Modelo de embeddings (opcional)
+/// - `DEEPSEEK_ALLOWED_EMBEDDING_MODELS` - Modelos de embeddings permitidos no payload (opcional)
 ///
 /// # Argumentos
 /// * `http_client` - Cliente HTTP opcional. Se None, será criado um novo.
@@ -268,12 +243,27 @@ impl AIAgent for DeepSeekAgent {
 pub fn create_deepseek_agent(http_client: Option<Box<dyn HttpClient>>) -> DeepSeekAgent {
     let client = http_client.unwrap_or_else(|| Box::new(crate::testing::ReqwestClient::new()));
 
-    DeepSeekAgent::new(
+    let agent = DeepSeekAgent::new(
         env::var("DEEPSEEK_API_KEY").unwrap_or_else(|_| "SUA_DEEPSEEK_KEY".to_string()),
         env::var("DEEPSEEK_ENDPOINT").unwrap_or_else(|_| "https://api.deepseek.ai".to_string()),
         env::var("DEEPSEEK_MODEL").unwrap_or_else(|_| "deepseek-chat".to_string()),
         client,
     )
+    .with_allowed_models(
+        env::var("DEEPSEEK_ALLOWED_MODELS")
+            .map(|models| parse_model_list(&models))
+            .unwrap_or_default(),
+    )
+    .with_allowed_embedding_models(
+        env::var("DEEPSEEK_ALLOWED_EMBEDDING_MODELS")
+            .map(|models| parse_model_list(&models))
+            .unwrap_or_default(),
+    );
+
+    match env::var("DEEPSEEK_EMBEDDING_MODEL") {
+        Ok(model) => agent.with_embedding_model(model),
+        Err(_) => agent,
+    }
 }
 
 #[cfg(test)]
@@ -281,7 +271,7 @@ mod tests {
     use super::*;
     use crate::testing::MockHttpClient;
     use mockall::predicate;
-    use serde_json::json;
+    use serde_json::{json, Value};
 
     fn create_mock_response(body: serde_json::Value) -> reqwest::Response {
         reqwest::Response::from(
@@ -307,7 +297,7 @@ mod tests {
         let result = agent.process_request(message).await;
 
         assert!(
-            matches!(result, Err(MCPError::InternalAgentError(e)) if e.contains("Missing user_prompt"))
+            matches!(result, Err(MCPError::PayloadValidation(e)) if e.contains("Missing user_prompt"))
         );
     }
 
@@ -405,4 +395,51 @@ mod tests {
         let result = agent.process_request(message).await.unwrap();
         assert_eq!(result.payload["answer"], "Resposta de teste com parâmetros");
     }
+
+    #[tokio::test]
+    async fn test_deepseek_agent_process_stream() {
+        use futures::StreamExt;
+
+        let mut mock_client = MockHttpClient::new();
+
+        mock_client
+            .expect_post()
+            .withf(|_, body, _| {
+                let parsed: Value = serde_json::from_slice(body).unwrap_or_default();
+                parsed["stream"] == true
+            })
+            .times(1)
+            .return_once(move |_, _, _| {
+                let body = concat!(
+                    "data: {\"choices\":[{\"delta\":{\"content\":\"Qubits\"},\"finish_reason\":null}]}\n\n",
+                    "data: {\"choices\":[{\"delta\":{\"content\":\" em superposição\"},\"finish_reason\":\"stop\"}]}\n\n",
+                    "data: [DONE]\n\n"
+                );
+                Ok(reqwest::Response::from(
+                    http::Response::builder().status(200).body(body).unwrap(),
+                ))
+            });
+
+        let agent = DeepSeekAgent::new(
+            "test_key".to_string(),
+            "https://api.test.deepseek.ai".to_string(),
+            "test-model".to_string(),
+            Box::new(mock_client),
+        );
+
+        let message = MCPMessage::new(
+            "deepseek:chat",
+            json!({ "user_prompt": "O que é computação quântica?" }),
+        );
+        let mut stream = agent.process_stream(message).await.unwrap();
+
+        let first = stream.next().await.unwrap().unwrap();
+        assert_eq!(first.content, "Qubits");
+
+        let second = stream.next().await.unwrap().unwrap();
+        assert_eq!(second.content, " em superposição");
+        assert_eq!(second.metadata.unwrap()["finish_reason"], "stop");
+
+        assert!(stream.next().await.unwrap().unwrap().is_finish);
+    }
 }
@@ -0,0 +1,733 @@
+//! # Agente para integração com a API Google Gemini
+//!
+//! Este módulo implementa um agente que se comunica com a API Gemini
+//! (`generateContent` e `streamGenerateContent`), permitindo enviar prompts
+//! para os modelos Gemini do Google.
+//!
+//! ## Exemplo de Uso
+//!
+//! ```rust,no_run
+//! use mcprs::agent::{AgentRegistry, MCPMessage};
+//! use mcprs::agent_gemini::create_gemini_agent;
+//! use serde_json::json;
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! // Configurar variável de ambiente (ou diretamente na criação do agente)
+//! std::env::set_var("GEMINI_API_KEY", "sua-chave-aqui");
+//!
+//! // Criar e registrar o agente Gemini
+//! let mut registry = AgentRegistry::new();
+//! registry.register_agent(Box::new(create_gemini_agent(None)));
+//!
+//! // Criar uma mensagem para o Gemini
+//! let message = MCPMessage::new(
+//!     "gemini:chat",
+//!     json!({
+//!         "system_prompt": "Responda em uma frase.",
+//!         "user_prompt": "Explique o que é Rust",
+//!         "safety_settings": [
+//!             { "category": "HARM_CATEGORY_HARASSMENT", "threshold": "BLOCK_ONLY_HIGH" }
+//!         ]
+//!     })
+//! );
+//!
+//! // Processar a mensagem
+//! let response = registry.process(message).await?;
+//! println!("Resposta: {}", response.payload["answer"]);
+//! # Ok(())
+//! # }
+//! ```
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::env;
+
+use crate::agent::{AIAgent, MCPError, MCPMessage};
+use crate::chat::{parse_model_list, select_model, ChatRequest, ChatRole, GenerationParams};
+use crate::streaming::{process_json_stream_with, StreamingToken, TokenStream};
+use crate::testing::HttpClient;
+use crate::usage::Usage;
+
+/// URL base da API Gemini
+const GEMINI_BASE_URL: &str = "https://generativelanguage.googleapis.com/v1beta";
+
+/// Parâmetros de geração aceitos pela API Gemini
+const SUPPORTED_PARAMS: &[&str] = &[
+    "temperature",
+    "top_p",
+    "max_tokens",
+    "stop",
+    "seed",
+    "presence_penalty",
+    "frequency_penalty",
+    "n",
+];
+
+/// Agente para comunicação com a API Gemini.
+///
+/// Este agente implementa a trait `AIAgent` e se conecta aos endpoints
+/// `generateContent` e `streamGenerateContent` do modelo configurado.
+pub struct GeminiAgent {
+    /// Chave de API do Google AI Studio
+    pub api_key: String,
+
+    /// Nome do modelo a ser usado (ex: "gemini-1.5-flash", "gemini-1.5-pro")
+    pub model: String,
+
+    /// Modelos adicionais que o payload pode escolher pelo campo `model`
+    pub allowed_models: Vec<String>,
+
+    /// Cliente HTTP para fazer as requisições
+    http_client: Box<dyn HttpClient>,
+}
+
+impl GeminiAgent {
+    /// Cria uma nova instância do agente Gemini.
+    ///
+    /// # Argumentos
+    /// * `api_key` - Chave de API do Google AI Studio
+    /// * `model` - Nome do modelo a ser usado
+    /// * `http_client` - Cliente HTTP para fazer as requisições
+    ///
+    /// # Exemplo
+    ///
+    /// ```
+    /// use mcprs::agent_gemini::GeminiAgent;
+    /// use mcprs::testing::ReqwestClient;
+    ///
+    /// let agent = GeminiAgent::new(
+    ///     "sua-chave-api".to_string(),
+    ///     "gemini-1.5-flash".to_string(),
+    ///     Box::new(ReqwestClient::new())
+    /// );
+    /// ```
+    pub fn new(api_key: String, model: String, http_client: Box<dyn HttpClient>) -> Self {
+        Self {
+            api_key,
+            model,
+            allowed_models: Vec::new(),
+            http_client,
+        }
+    }
+
+    /// Permite que o payload escolha um dos modelos informados pelo campo `model`.
+    ///
+    /// O modelo padrão é sempre permitido; qualquer outro modelo é rejeitado
+    /// com `MCPError::PayloadValidation`.
+    pub fn with_allowed_models<I, S>(mut self, models: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allowed_models = models.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// URL do método informado (`generateContent` ou `streamGenerateContent`) para o modelo.
+    fn url(model: &str, method: &str) -> String {
+        let url = format!("{}/models/{}:{}", GEMINI_BASE_URL, model, method);
+        if method == "streamGenerateContent" {
+            format!("{}?alt=sse", url)
+        } else {
+            url
+        }
+    }
+
+    /// Monta o corpo da requisição a partir do payload MCP.
+    ///
+    /// As mensagens do assistente usam o papel `model`, as instruções de
+    /// sistema são enviadas em `systemInstruction` e os parâmetros de geração
+    /// e o modo JSON em `generationConfig`. O modelo escolhido não vai no
+    /// corpo, e sim na URL.
+    fn build_request(&self, message: &MCPMessage) -> Result<GeminiRequest, MCPError> {
+        // Interpretar as mensagens do payload
+        let chat_request = ChatRequest::from_payload(&message.payload)?;
+        chat_request.ensure_no_tools("gemini")?;
+        let (system, messages) = chat_request.split_system();
+
+        let contents = messages
+            .into_iter()
+            .map(|m| GeminiContent {
+                role: Some(
+                    match m.role {
+                        ChatRole::Assistant => "model",
+                        _ => "user",
+                    }
+                    .to_string(),
+                ),
+                parts: vec![GeminiPart { text: m.content }],
+            })
+            .collect();
+
+        let safety_settings = match message.payload.get("safety_settings") {
+            None | Some(Value::Null) => None,
+            Some(Value::Array(settings)) => Some(settings.clone()),
+            Some(_) => {
+                return Err(MCPError::PayloadValidation(
+                    "Campo 'safety_settings' deve ser uma lista".to_string(),
+                ))
+            }
+        };
+
+        let params = GenerationParams::from_payload(&message.payload)?;
+        params.ensure_supported("gemini", SUPPORTED_PARAMS)?;
+        let json_mode = chat_request
+            .response_format
+            .as_ref()
+            .is_some_and(|format| format.is_json());
+        let has_params = !params.provided().is_empty() || json_mode;
+        let model = select_model(&message.payload, Some(&self.model), &self.allowed_models)?
+            .unwrap_or(&self.model);
+
+        let generation_config = GeminiGenerationConfig {
+            temperature: params.temperature,
+            top_p: params.top_p,
+            max_output_tokens: params.max_tokens,
+            stop_sequences: params.stop,
+            seed: params.seed,
+            presence_penalty: params.presence_penalty,
+            frequency_penalty: params.frequency_penalty,
+            candidate_count: params.n,
+            response_mime_type: json_mode.then(|| "application/json".to_string()),
+            response_json_schema: chat_request
+                .response_format
+                .as_ref()
+                .and_then(|format| format.schema())
+                .cloned(),
+        };
+
+        Ok(GeminiRequest {
+            model: model.to_string(),
+            contents,
+            system_instruction: system.map(|text| GeminiContent {
+                role: None,
+                parts: vec![GeminiPart { text }],
+            }),
+            safety_settings,
+            generation_config: has_params.then_some(generation_config),
+        })
+    }
+
+    /// Envia a requisição para a API Gemini e valida o status da resposta.
+    async fn send_request(
+        &self,
+        method: &str,
+        request_body: &GeminiRequest,
+    ) -> Result<reqwest::Response, MCPError> {
+        let headers = vec![
+            ("x-goog-api-key".to_string(), self.api_key.clone()),
+            ("Content-Type".to_string(), "application/json".to_string()),
+        ];
+
+        let response = self
+            .http_client
+            .post(
+                Self::url(&request_body.model, method),
+                serde_json::to_vec(request_body)
+                    .map_err(|e| MCPError::InternalAgentError(e.to_string()))?,
+                headers,
+            )
+            .await
+            .map_err(|e| MCPError::from_transport_error("gemini", &e))?;
+
+        // Verificar o status da resposta
+        if !response.status().is_success() {
+            return Err(MCPError::from_provider_response("gemini", &response));
+        }
+
+        Ok(response)
+    }
+}
+
+/// Estrutura para o corpo da requisição à API Gemini
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiRequest {
+    #[serde(skip)]
+    model: String,
+    contents: Vec<GeminiContent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system_instruction: Option<GeminiContent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    safety_settings: Option<Vec<Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    generation_config: Option<GeminiGenerationConfig>,
+}
+
+/// Conteúdo de uma mensagem, dividido em partes
+#[derive(Serialize, Deserialize)]
+struct GeminiContent {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    role: Option<String>,
+    #[serde(default)]
+    parts: Vec<GeminiPart>,
+}
+
+/// Parte textual de um conteúdo
+#[derive(Serialize, Deserialize)]
+struct GeminiPart {
+    #[serde(default)]
+    text: String,
+}
+
+/// Parâmetros de geração
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiGenerationConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_output_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop_sequences: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    presence_penalty: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frequency_penalty: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    candidate_count: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_mime_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_json_schema: Option<Value>,
+}
+
+/// Resposta de `generateContent`; cada evento de `streamGenerateContent` tem o mesmo formato
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiResponse {
+    #[serde(default)]
+    candidates: Vec<GeminiCandidate>,
+    model_version: Option<String>,
+    usage_metadata: Option<GeminiUsage>,
+    prompt_feedback: Option<GeminiPromptFeedback>,
+}
+
+impl GeminiResponse {
+    /// Texto do primeiro candidato.
+    fn text(&self) -> String {
+        self.candidates
+            .first()
+            .map(GeminiCandidate::text)
+            .unwrap_or_default()
+    }
+
+    /// Razão de término do primeiro candidato.
+    fn finish_reason(&self) -> Option<&str> {
+        self.candidates
+            .first()
+            .and_then(|candidate| candidate.finish_reason.as_deref())
+    }
+
+    /// Erro se o prompt foi bloqueado pelos filtros de segurança.
+    fn blocked_error(&self) -> Option<MCPError> {
+        let reason = self.prompt_feedback.as_ref()?.block_reason.as_ref()?;
+        Some(MCPError::Upstream {
+            provider: "gemini".to_string(),
+            status: None,
+            message: format!("Prompt bloqueado: {}", reason),
+            retryable: true,
+        })
+    }
+}
+
+/// Um candidato de resposta
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiCandidate {
+    #[serde(default)]
+    index: usize,
+    content: Option<GeminiContent>,
+    finish_reason: Option<String>,
+    #[serde(default)]
+    safety_ratings: Vec<Value>,
+}
+
+impl GeminiCandidate {
+    /// Texto do candidato, com as partes concatenadas.
+    fn text(&self) -> String {
+        self.content
+            .as_ref()
+            .map(|content| content.parts.iter().map(|p| p.text.as_str()).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Contagem de tokens informada pela API
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiUsage {
+    #[serde(default)]
+    prompt_token_count: u64,
+    #[serde(default)]
+    candidates_token_count: u64,
+    total_token_count: Option<u64>,
+}
+
+impl GeminiUsage {
+    /// Contagem de tokens, no mesmo formato usado pelos demais agentes.
+    fn normalized(&self) -> Usage {
+        Usage::new(self.prompt_token_count, self.candidates_token_count)
+            .with_total(self.total_token_count)
+    }
+}
+
+/// Avaliação do prompt pelos filtros de segurança
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiPromptFeedback {
+    block_reason: Option<String>,
+}
+
+#[async_trait]
+impl AIAgent for GeminiAgent {
+    /// Retorna o nome do agente: "gemini"
+    fn name(&self) -> &str {
+        "gemini"
+    }
+
+    /// Processa uma requisição enviando-a para `generateContent`.
+    ///
+    /// # Parâmetros esperados no payload
+    /// As mensagens seguem o modelo de [`ChatRequest`]:
+    /// * `system_prompt` - Instrução de sistema, enviada em `systemInstruction` (opcional)
+    /// * `messages` - Histórico de mensagens `{role, content}` (opcional)
+    /// * `user_prompt` - O prompt do usuário (opcional se `messages` for informado)
+    /// * `temperature`, `top_p`, `max_tokens`, `stop`, `seed`, `presence_penalty`,
+    ///   `frequency_penalty` e `n` - Parâmetros de geração, enviados em `generationConfig`
+    ///   como `topP`, `maxOutputTokens`, `stopSequences`, `candidateCount`, etc. (opcionais)
+    /// * `safety_settings` - Lista repassada sem alterações em `safetySettings` (opcional)
+    /// * `model` - Modelo a ser usado; deve ser o padrão ou estar em `allowed_models` (opcional)
+    /// * `response_format` - Formato JSON da resposta, enviado como `responseMimeType`
+    ///   e `responseJsonSchema` (opcional)
+    ///
+    /// # Formato da resposta
+    /// A resposta terá o comando "gemini_response" e o payload conterá:
+    /// * `answer` - O texto da resposta gerada pelo modelo
+    /// * `model` - O modelo que atendeu a requisição (`modelVersion`, se informado)
+    /// * `finish_reason` - A razão de término da geração (STOP, MAX_TOKENS, SAFETY, ...)
+    /// * `safety_ratings` - As avaliações de segurança da resposta
+    /// * `usage` - Tokens consumidos (`input_tokens`, `output_tokens` e `total_tokens`)
+    /// * `answers` - O texto de todos os candidatos, quando a API retorna mais de um
+    ///   (parâmetro `n`); `answer` e os demais campos vêm do primeiro
+    ///
+    /// # Erros
+    /// * `MCPError::PayloadValidation` - Se nem `user_prompt` nem `messages` forem informados,
+    ///   se algum parâmetro de geração for inválido ou não suportado (`logit_bias`, `user`),
+    ///   se o modelo não for permitido, se o payload usar ferramentas ou se
+    ///   `safety_settings` não for uma lista
+    /// * `MCPError::RateLimited` ou `MCPError::Timeout` - Se a API limitar ou não responder a tempo
+    /// * `MCPError::Upstream` - Se a API retornar erro, bloquear o prompt ou enviar uma
+    ///   resposta que não possa ser processada
+    async fn process_request(&self, message: MCPMessage) -> Result<MCPMessage, MCPError> {
+        let request_body = self.build_request(&message)?;
+        let response = self.send_request("generateContent", &request_body).await?;
+
+        // Deserializar a resposta
+        let mut resp_json = response
+            .json::<GeminiResponse>()
+            .await
+            .map_err(|e| MCPError::invalid_response("gemini", &e))?;
+
+        if let Some(error) = resp_json.blocked_error() {
+            return Err(error);
+        }
+
+        // Com `candidateCount` > 1 há um candidato por resposta
+        resp_json
+            .candidates
+            .sort_by_key(|candidate| candidate.index);
+        let candidate = resp_json
+            .candidates
+            .first()
+            .ok_or_else(|| MCPError::invalid_response("gemini", "No response candidates"))?;
+
+        let mut payload = json!({
+            "answer": candidate.text(),
+            "model": resp_json.model_version.as_deref().unwrap_or(&request_body.model),
+            "finish_reason": candidate.finish_reason,
+            "safety_ratings": candidate.safety_ratings,
+            "usage": resp_json.usage_metadata.as_ref().map(GeminiUsage::normalized),
+        });
+        if resp_json.candidates.len() > 1 {
+            let answers: Vec<String> = resp_json
+                .candidates
+                .iter()
+                .map(GeminiCandidate::text)
+                .collect();
+            payload["answers"] = json!(answers);
+        }
+
+        Ok(MCPMessage::new("gemini_response", payload).in_reply_to(&message))
+    }
+
+    /// Processa uma requisição em modo streaming via `streamGenerateContent`.
+    ///
+    /// Cada evento SSE traz um fragmento do texto, emitido como um
+    /// `StreamingToken`. Quando o evento informa `finishReason`, o token inclui
+    /// `finish_reason` e `usage` em `metadata`.
+    ///
+    /// # Erros
+    /// Os mesmos de [`GeminiAgent::process_request`] para falhas antes do
+    /// início do streaming. Requisições com `n` maior que 1 são rejeitadas com
+    /// `MCPError::PayloadValidation`, pois o stream emite apenas um candidato.
+    async fn process_stream(&self, message: MCPMessage) -> Result<TokenStream, MCPError> {
+        GenerationParams::from_payload(&message.payload)?.ensure_single_choice()?;
+        let request_body = self.build_request(&message)?;
+        let response = self
+            .send_request("streamGenerateContent", &request_body)
+            .await?;
+
+        process_json_stream_with::<_, GeminiResponse, _>(response.bytes_stream(), |chunk| {
+            if let Some(error) = chunk.blocked_error() {
+                return Err(error);
+            }
+
+            let content = chunk.text();
+            let metadata = chunk.finish_reason().map(|reason| {
+                json!({
+                    "finish_reason": reason,
+                    "usage": chunk.usage_metadata.as_ref().map(GeminiUsage::normalized),
+                })
+            });
+
+            if content.is_empty() && metadata.is_none() {
+                return Ok(None);
+            }
+
+            Ok(Some(StreamingToken {
+                content,
+                is_finish: false,
+                metadata,
+            }))
+        })
+        .await
+    }
+}
+
+/// Função auxiliar para criar um agente Gemini com configurações do ambiente.
+///
+/// Esta função facilita a criação de uma instância do agente Gemini, obtendo
+/// as configurações das variáveis de ambiente:
+/// - `GEMINI_API_KEY` - Chave de API
+/// - `GEMINI_MODEL` - Nome do modelo (padrão: gemini-1.5-flash)
+/// - `GEMINI_ALLOWED_MODELS` - Modelos adicionais permitidos no payload, separados por vírgula
+///
+/// # Argumentos
+/// * `http_client` - Cliente HTTP opcional. Se None, será criado um novo.
+///
+/// # Retorno
+/// Uma nova instância de `GeminiAgent` configurada.
+///
+/// # Exemplo
+///
+/// ```
+/// use mcprs::agent_gemini::create_gemini_agent;
+///
+/// // Configurar a variável de ambiente primeiro
+/// std::env::set_var("GEMINI_API_KEY", "sua-chave-api");
+///
+/// // Criar o agente
+/// let agent = create_gemini_agent(None);
+/// ```
+pub fn create_gemini_agent(http_client: Option<Box<dyn HttpClient>>) -> GeminiAgent {
+    let client = http_client.unwrap_or_else(|| Box::new(crate::testing::ReqwestClient::new()));
+
+    GeminiAgent::new(
+        env::var("GEMINI_API_KEY").unwrap_or_else(|_| "SUA_GEMINI_KEY".to_string()),
+        env::var("GEMINI_MODEL").unwrap_or_else(|_| "gemini-1.5-flash".to_string()),
+        client,
+    )
+    .with_allowed_models(
+        env::var("GEMINI_ALLOWED_MODELS")
+            .map(|models| parse_model_list(&models))
+            .unwrap_or_default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockHttpClient;
+    use futures::StreamExt;
+
+    fn create_agent(mock_client: MockHttpClient) -> GeminiAgent {
+        GeminiAgent::new(
+            "test_key".to_string(),
+            "gemini-test".to_string(),
+            Box::new(mock_client),
+        )
+    }
+
+    #[test]
+    fn test_build_request_maps_roles_and_system_instruction() {
+        let agent = create_agent(MockHttpClient::new());
+        let message = MCPMessage::new(
+            "gemini:chat",
+            json!({
+                "system_prompt": "Seja breve.",
+                "messages": [
+                    { "role": "user", "content": "Oi" },
+                    { "role": "assistant", "content": "Olá!" }
+                ],
+                "user_prompt": "Tudo bem?",
+                "max_tokens": 32,
+                "safety_settings": [
+                    { "category": "HARM_CATEGORY_HATE_SPEECH", "threshold": "BLOCK_NONE" }
+                ]
+            }),
+        );
+
+        let request = serde_json::to_value(agent.build_request(&message).unwrap()).unwrap();
+        assert_eq!(
+            request,
+            json!({
+                "contents": [
+                    { "role": "user", "parts": [{ "text": "Oi" }] },
+                    { "role": "model", "parts": [{ "text": "Olá!" }] },
+                    { "role": "user", "parts": [{ "text": "Tudo bem?" }] }
+                ],
+                "systemInstruction": { "parts": [{ "text": "Seja breve." }] },
+                "safetySettings": [
+                    { "category": "HARM_CATEGORY_HATE_SPEECH", "threshold": "BLOCK_NONE" }
+                ],
+                "generationConfig": { "maxOutputTokens": 32 }
+            })
+        );
+
+        let invalid = MCPMessage::new(
+            "gemini:chat",
+            json!({ "user_prompt": "Oi", "safety_settings": "nenhum" }),
+        );
+        assert!(matches!(
+            agent.build_request(&invalid),
+            Err(MCPError::PayloadValidation(_))
+        ));
+    }
+
+    #[test]
+    fn test_build_request_maps_generation_params() {
+        let agent = create_agent(MockHttpClient::new());
+        let message = MCPMessage::new(
+            "gemini:chat",
+            json!({
+                "user_prompt": "Olá",
+                "temperature": 0.4,
+                "top_p": 0.95,
+                "stop": "FIM",
+                "seed": 9,
+                "n": 2
+            }),
+        );
+
+        let request = serde_json::to_value(agent.build_request(&message).unwrap()).unwrap();
+        assert_eq!(
+            request["generationConfig"],
+            json!({
+                "temperature": 0.4,
+                "topP": 0.95,
+                "stopSequences": ["FIM"],
+                "seed": 9,
+                "candidateCount": 2
+            })
+        );
+
+        // Parâmetros sem equivalente na API Gemini são rejeitados
+        let message = MCPMessage::new(
+            "gemini:chat",
+            json!({ "user_prompt": "Olá", "user": "usuario-1" }),
+        );
+        assert!(matches!(
+            agent.build_request(&message),
+            Err(MCPError::PayloadValidation(e)) if e.contains("user")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_gemini_agent_process_stream() {
+        let mut mock_client = MockHttpClient::new();
+
+        mock_client
+            .expect_post()
+            .withf(|url, _, _| url.ends_with(":streamGenerateContent?alt=sse"))
+            .times(1)
+            .return_once(move |_, _, _| {
+                let body = concat!(
+                    "data: {\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"Rust\"}],\"role\":\"model\"}}]}\r\n\r\n",
+                    "data: {\"candidates\":[{\"content\":{\"parts\":[{\"text\":\" é rápido\"}],\"role\":\"model\"},\"finishReason\":\"STOP\"}],",
+                    "\"usageMetadata\":{\"promptTokenCount\":4,\"candidatesTokenCount\":3,\"totalTokenCount\":7}}\r\n\r\n"
+                );
+                Ok(reqwest::Response::from(
+                    http::Response::builder().status(200).body(body).unwrap(),
+                ))
+            });
+
+        let agent = create_agent(mock_client);
+        let message = MCPMessage::new("gemini:chat", json!({ "user_prompt": "O que é Rust?" }));
+        let mut stream = agent.process_stream(message).await.unwrap();
+
+        let mut content = String::new();
+        let mut metadata = None;
+        while let Some(token) = stream.next().await {
+            let token = token.unwrap();
+            if token.is_finish {
+                break;
+            }
+            content.push_str(&token.content);
+            if token.metadata.is_some() {
+                metadata = token.metadata;
+            }
+        }
+
+        assert_eq!(content, "Rust é rápido");
+        assert_eq!(
+            metadata.unwrap(),
+            json!({
+                "finish_reason": "STOP",
+                "usage": { "input_tokens": 4, "output_tokens": 3, "total_tokens": 7 }
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_gemini_agent_returns_every_candidate() {
+        let mut mock_client = MockHttpClient::new();
+
+        mock_client
+            .expect_post()
+            .times(1)
+            .return_once(move |_, _, _| {
+                let body = json!({
+                    "candidates": [
+                        { "index": 0, "content": { "parts": [{ "text": "Olá!" }] }, "finishReason": "STOP" },
+                        { "index": 1, "content": { "parts": [{ "text": "Oi!" }] }, "finishReason": "STOP" }
+                    ]
+                });
+                Ok(reqwest::Response::from(
+                    http::Response::builder()
+                        .status(200)
+                        .body(body.to_string())
+                        .unwrap(),
+                ))
+            });
+
+        let agent = create_agent(mock_client);
+        let message = MCPMessage::new("gemini:chat", json!({ "user_prompt": "Olá", "n": 2 }));
+        let result = agent.process_request(message).await.unwrap();
+
+        assert_eq!(result.payload["answer"], "Olá!");
+        assert_eq!(result.payload["answers"], json!(["Olá!", "Oi!"]));
+
+        // O stream emite um único candidato
+        let message = MCPMessage::new("gemini:chat", json!({ "user_prompt": "Olá", "n": 2 }));
+        assert!(matches!(
+            agent.process_stream(message).await,
+            Err(MCPError::PayloadValidation(_))
+        ));
+    }
+}
@@ -0,0 +1,410 @@
+//! # Agente para integração com a API Mistral
+//!
+//! Este módulo implementa um agente que se comunica com a API de Chat
+//! Completions da Mistral AI, permitindo enviar prompts para modelos como
+//! Mistral Small, Mistral Large e Codestral. O modo JSON é ativado com
+//! `response_format: {"type": "json_object"}` no payload.
+//!
+//! ## Exemplo de Uso
+//!
+//! ```rust,no_run
+//! use mcprs::agent::{AgentRegistry, MCPMessage};
+//! use mcprs::agent_mistral::create_mistral_agent;
+//! use serde_json::json;
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! // Configurar variável de ambiente (ou diretamente na criação do agente)
+//! std::env::set_var("MISTRAL_API_KEY", "sua-chave-aqui");
+//!
+//! // Criar e registrar o agente Mistral
+//! let mut registry = AgentRegistry::new();
+//! registry.register_agent(Box::new(create_mistral_agent(None)));
+//!
+//! // Pedir uma resposta em JSON
+//! let message = MCPMessage::new(
+//!     "mistral:chat",
+//!     json!({
+//!         "system_prompt": "Responda com um objeto JSON {\"linguagem\", \"ano\"}.",
+//!         "user_prompt": "Quando o Rust 1.0 foi lançado?",
+//!         "response_format": { "type": "json_object" }
+//!     })
+//! );
+//!
+//! // Processar a mensagem
+//! let response = registry.process(message).await?;
+//! println!("Resposta: {}", response.payload["answer"]);
+//! println!("Tokens: {}", response.payload["usage"]);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::agent::{AIAgent, MCPError, MCPMessage};
+use crate::agent_openai_compatible::{
+    chat_completion, chat_completion_stream, OpenAICompatibleConfig,
+};
+use crate::chat::parse_model_list;
+use crate::streaming::TokenStream;
+use crate::testing::HttpClient;
+use async_trait::async_trait;
+use std::env;
+
+/// URL base da API Mistral
+const MISTRAL_BASE_URL: &str = "https://api.mistral.ai";
+
+/// Agente para comunicação com a API Mistral.
+///
+/// A API da Mistral segue o formato de Chat Completions da OpenAI, então a
+/// comunicação é feita pela implementação genérica de
+/// [`OpenAICompatibleAgent`](crate::agent_openai_compatible::OpenAICompatibleAgent).
+pub struct MistralAgent {
+    /// Chave de API da Mistral
+    pub api_key: String,
+
+    /// Nome do modelo a ser usado (ex: "mistral-small-latest", "mistral-large-latest")
+    pub model: String,
+
+    /// Modelos adicionais que o payload pode escolher pelo campo `model`
+    pub allowed_models: Vec<String>,
+
+    /// Cliente HTTP para fazer as requisições
+    http_client: Box<dyn HttpClient>,
+}
+
+impl MistralAgent {
+    /// Cria uma nova instância do agente Mistral.
+    ///
+    /// # Argumentos
+    /// * `api_key` - Chave de API da Mistral
+    /// * `model` - Nome do modelo a ser usado
+    /// * `http_client` - Cliente HTTP para fazer as requisições
+    ///
+    /// # Exemplo
+    ///
+    /// ```
+    /// use mcprs::agent_mistral::MistralAgent;
+    /// use mcprs::testing::ReqwestClient;
+    ///
+    /// let agent = MistralAgent::new(
+    ///     "sua-chave-api".to_string(),
+    ///     "mistral-small-latest".to_string(),
+    ///     Box::new(ReqwestClient::new())
+    /// );
+    /// ```
+    pub fn new(api_key: String, model: String, http_client: Box<dyn HttpClient>) -> Self {
+        Self {
+            api_key,
+            model,
+            allowed_models: Vec::new(),
+            http_client,
+        }
+    }
+
+    /// Permite que o payload escolha um dos modelos informados pelo campo `model`.
+    ///
+    /// O modelo padrão é sempre permitido; qualquer outro modelo é rejeitado
+    /// com `MCPError::PayloadValidation`.
+    pub fn with_allowed_models<I, S>(mut self, models: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allowed_models = models.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Configuração equivalente para a implementação genérica.
+    ///
+    /// A Mistral não aceita `logit_bias` nem `user` e recebe `seed` como `random_seed`.
+    fn config(&self) -> OpenAICompatibleConfig {
+        OpenAICompatibleConfig::new("mistral", MISTRAL_BASE_URL)
+            .with_api_key(self.api_key.clone())
+            .with_default_model(self.model.clone())
+            .with_allowed_models(self.allowed_models.clone())
+            // A Mistral envia `usage` no último chunk sem `stream_options`
+            .with_stream_usage(false)
+            .with_supported_params(&[
+                "temperature",
+                "top_p",
+                "max_tokens",
+                "stop",
+                "seed",
+                "presence_penalty",
+                "frequency_penalty",
+                "n",
+            ])
+            .with_renamed_param("seed", "random_seed")
+    }
+}
+
+#[async_trait]
+impl AIAgent for MistralAgent {
+    /// Retorna o nome do agente: "mistral"
+    fn name(&self) -> &str {
+        "mistral"
+    }
+
+    /// Processa uma requisição enviando-a para a API Mistral.
+    ///
+    /// # Parâmetros esperados no payload
+    /// O payload segue o modelo de [`ChatRequest`](crate::chat::ChatRequest):
+    /// * `system_prompt` - Instrução de sistema (opcional)
+    /// * `messages` - Histórico de mensagens `{role, content}` (opcional)
+    /// * `user_prompt` - O prompt do usuário (opcional se `messages` for informado)
+    /// * `temperature`, `top_p`, `max_tokens`, `stop`, `seed` (enviado como `random_seed`),
+    ///   `presence_penalty`, `frequency_penalty` e `n` - Parâmetros de geração (opcionais)
+    /// * `response_format` - `{"type": "json_object"}` ou `{"type": "json_schema", "schema": ...}`
+    ///   para o modo JSON (opcional)
+    /// * `model` - Modelo a ser usado; deve ser o padrão ou estar em `allowed_models` (opcional)
+    /// * `tools` e `tool_choice` - Ferramentas que o modelo pode chamar (opcionais)
+    ///
+    /// # Formato da resposta
+    /// A resposta terá o comando "mistral_response" e o payload conterá:
+    /// * `answer` - O texto da resposta gerada pelo modelo
+    /// * `model` - O modelo que atendeu a requisição, como informado pela API
+    /// * `id` e `finish_reason` - Quando informados pela API
+    /// * `usage` - Tokens consumidos (`input_tokens`, `output_tokens` e `total_tokens`)
+    /// * `tool_calls` - Chamadas de ferramentas `{id, name, arguments}`, se houver
+    ///
+    /// # Erros
+    /// * `MCPError::PayloadValidation` - Se nem `user_prompt` nem `messages` forem informados,
+    ///   se algum parâmetro de geração for inválido ou não suportado (`logit_bias`, `user`),
+    ///   se o modelo não for permitido ou se `tools` ou `tool_choice` forem inválidos
+    /// * `MCPError::RateLimited` ou `MCPError::Timeout` - Se a API limitar ou não responder a tempo
+    /// * `MCPError::Upstream` - Se a API retornar erro ou uma resposta que não possa ser processada
+    async fn process_request(&self, message: MCPMessage) -> Result<MCPMessage, MCPError> {
+        chat_completion(&self.config(), self.http_client.as_ref(), message).await
+    }
+
+    /// Processa uma requisição em modo streaming (`stream: true`).
+    ///
+    /// Cada fragmento de `delta.content` é emitido como um `StreamingToken`;
+    /// o último chunk traz `finish_reason` e `usage` em `metadata`.
+    ///
+    /// # Erros
+    /// Os mesmos de [`MistralAgent::process_request`] para falhas antes do
+    /// início do streaming.
+    async fn process_stream(&self, message: MCPMessage) -> Result<TokenStream, MCPError> {
+        chat_completion_stream(&self.config(), self.http_client.as_ref(), message).await
+    }
+}
+
+/// Função auxiliar para criar um agente Mistral com configurações do ambiente.
+///
+/// Esta função facilita a criação de uma instância do agente Mistral, obtendo
+/// as configurações das variáveis de ambiente:
+/// - `MISTRAL_API_KEY` - Chave de API
+/// - `MISTRAL_MODEL` - Nome do modelo (padrão: mistral-small-latest)
+/// - `MISTRAL_ALLOWED_MODELS` - Modelos adicionais permitidos no payload, separados por vírgula
+///
+/// # Argumentos
+/// * `http_client` - Cliente HTTP opcional. Se None, será criado um novo.
+///
+/// # Retorno
+/// Uma nova instância de `MistralAgent` configurada.
+///
+/// # Exemplo
+///
+/// ```
+/// use mcprs::agent_mistral::create_mistral_agent;
+///
+/// // Configurar a variável de ambiente primeiro
+/// std::env::set_var("MISTRAL_API_KEY", "sua-chave-api");
+///
+/// // Criar o agente
+/// let agent = create_mistral_agent(None);
+/// ```
+pub fn create_mistral_agent(http_client: Option<Box<dyn HttpClient>>) -> MistralAgent {
+    let client = http_client.unwrap_or_else(|| Box::new(crate::testing::ReqwestClient::new()));
+
+    MistralAgent::new(
+        env::var("MISTRAL_API_KEY").unwrap_or_else(|_| "SUA_MISTRAL_KEY".to_string()),
+        env::var("MISTRAL_MODEL").unwrap_or_else(|_| "mistral-small-latest".to_string()),
+        client,
+    )
+    .with_allowed_models(
+        env::var("MISTRAL_ALLOWED_MODELS")
+            .map(|models| parse_model_list(&models))
+            .unwrap_or_default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockHttpClient;
+    use futures::StreamExt;
+    use serde_json::{json, Value};
+
+    fn create_agent(mock_client: MockHttpClient) -> MistralAgent {
+        MistralAgent::new(
+            "test_key".to_string(),
+            "mistral-test".to_string(),
+            Box::new(mock_client),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_mistral_agent_json_mode() {
+        let mut mock_client = MockHttpClient::new();
+
+        mock_client
+            .expect_post()
+            .withf(|url, body, headers| {
+                let parsed: Value = serde_json::from_slice(body).unwrap_or_default();
+                url == "https://api.mistral.ai/v1/chat/completions"
+                    && parsed["model"] == "mistral-test"
+                    && parsed["response_format"] == json!({ "type": "json_object" })
+                    && headers
+                        .iter()
+                        .any(|(k, v)| k == "Authorization" && v == "Bearer test_key")
+            })
+            .times(1)
+            .return_once(|_, _, _| {
+                Ok(reqwest::Response::from(
+                    http::Response::builder()
+                        .status(200)
+                        .body(
+                            json!({
+                                "id": "cmpl-1",
+                                "choices": [{
+                                    "message": { "role": "assistant", "content": "{\"ano\": 2015}" },
+                                    "finish_reason": "stop"
+                                }],
+                                "usage": { "prompt_tokens": 20, "completion_tokens": 7, "total_tokens": 27 }
+                            })
+                            .to_string(),
+                        )
+                        .unwrap(),
+                ))
+            });
+
+        let agent = create_agent(mock_client);
+        let message = MCPMessage::new(
+            "mistral:chat",
+            json!({
+                "user_prompt": "Quando o Rust 1.0 foi lançado?",
+                "response_format": { "type": "json_object" }
+            }),
+        );
+
+        let result = agent.process_request(message).await.unwrap();
+        assert_eq!(result.command, "mistral_response");
+        assert_eq!(
+            result.payload,
+            json!({
+                "answer": "{\"ano\": 2015}",
+                "id": "cmpl-1",
+                "model": "mistral-test",
+                "finish_reason": "stop",
+                "usage": { "input_tokens": 20, "output_tokens": 7, "total_tokens": 27 }
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mistral_agent_rejects_invalid_response_format() {
+        let agent = create_agent(MockHttpClient::new());
+        let message = MCPMessage::new(
+            "mistral:chat",
+            json!({ "user_prompt": "Olá", "response_format": "json" }),
+        );
+
+        let result = agent.process_request(message).await;
+        assert!(matches!(result, Err(MCPError::PayloadValidation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_mistral_agent_generation_params() {
+        let mut mock_client = MockHttpClient::new();
+
+        mock_client
+            .expect_post()
+            .withf(|_, body, _| {
+                let parsed: Value = serde_json::from_slice(body).unwrap_or_default();
+                parsed["random_seed"] == 7
+                    && parsed.get("seed").is_none()
+                    && parsed["top_p"] == 0.5
+                    && parsed["stop"] == json!(["FIM"])
+            })
+            .times(1)
+            .return_once(|_, _, _| {
+                Ok(reqwest::Response::from(
+                    http::Response::builder()
+                        .status(200)
+                        .body(
+                            json!({ "choices": [{ "message": { "content": "ok" } }] }).to_string(),
+                        )
+                        .unwrap(),
+                ))
+            });
+
+        let agent = create_agent(mock_client);
+        let message = MCPMessage::new(
+            "mistral:chat",
+            json!({ "user_prompt": "Olá", "seed": 7, "top_p": 0.5, "stop": "FIM" }),
+        );
+        assert_eq!(
+            agent.process_request(message).await.unwrap().payload["answer"],
+            "ok"
+        );
+
+        // Parâmetros sem equivalente na Mistral são rejeitados
+        let agent = create_agent(MockHttpClient::new());
+        let message = MCPMessage::new(
+            "mistral:chat",
+            json!({ "user_prompt": "Olá", "logit_bias": { "1": 5 } }),
+        );
+        let result = agent.process_request(message).await;
+        assert!(matches!(result, Err(MCPError::PayloadValidation(e)) if e.contains("logit_bias")));
+    }
+
+    #[tokio::test]
+    async fn test_mistral_agent_process_stream() {
+        let mut mock_client = MockHttpClient::new();
+
+        mock_client
+            .expect_post()
+            .withf(|_, body, _| {
+                let parsed: Value = serde_json::from_slice(body).unwrap_or_default();
+                parsed["stream"] == true && parsed.get("stream_options").is_none()
+            })
+            .times(1)
+            .return_once(move |_, _, _| {
+                let body = concat!(
+                    "data: {\"id\":\"c1\",\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":\"\"},\"finish_reason\":null}]}\n\n",
+                    "data: {\"id\":\"c1\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Bonjour\"},\"finish_reason\":null}]}\n\n",
+                    "data: {\"id\":\"c1\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"!\"},\"finish_reason\":\"stop\"}],\"usage\":{\"prompt_tokens\":5,\"completion_tokens\":2,\"total_tokens\":7}}\n\n",
+                    "data: [DONE]\n\n"
+                );
+                Ok(reqwest::Response::from(
+                    http::Response::builder().status(200).body(body).unwrap(),
+                ))
+            });
+
+        let agent = create_agent(mock_client);
+        let message = MCPMessage::new("mistral:chat", json!({ "user_prompt": "Salut" }));
+        let mut stream = agent.process_stream(message).await.unwrap();
+
+        let mut content = String::new();
+        let mut metadata = None;
+        while let Some(token) = stream.next().await {
+            let token = token.unwrap();
+            if token.is_finish {
+                break;
+            }
+            content.push_str(&token.content);
+            if token.metadata.is_some() {
+                metadata = token.metadata;
+            }
+        }
+
+        assert_eq!(content, "Bonjour!");
+        assert_eq!(
+            metadata.unwrap(),
+            json!({
+                "finish_reason": "stop",
+                "usage": { "input_tokens": 5, "output_tokens": 2, "total_tokens": 7 }
+            })
+        );
+    }
+}
@@ -0,0 +1,741 @@
+//! # Agente para modelos locais via Ollama
+//!
+//! Este módulo implementa um agente que se comunica com um servidor compatível
+//! com a API do [Ollama](https://ollama.com), permitindo usar modelos locais
+//! sem acesso à internet.
+//!
+//! O comando define o endpoint usado:
+//! - `ollama:chat` (ou qualquer outra ação) usa `/api/chat`, com histórico de mensagens
+//! - `ollama:generate` usa `/api/generate`, com um único prompt
+//! - `ollama:models` lista os modelos instalados via `/api/tags`
+//! - `ollama:embeddings` gera embeddings em lote via `/api/embed` (veja [`crate::embeddings`])
+//!
+//! ## Exemplo de Uso
+//!
+//! ```rust,no_run
+//! use mcprs::agent::{AgentRegistry, MCPMessage};
+//! use mcprs::agent_ollama::create_ollama_agent;
+//! use serde_json::json;
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! // Configurar o endereço do servidor (padrão: http://localhost:11434)
+//! std::env::set_var("OLLAMA_ENDPOINT", "http://localhost:11434");
+//!
+//! // Criar e registrar o agente Ollama
+//! let mut registry = AgentRegistry::new();
+//! registry.register_agent(Box::new(create_ollama_agent(None)));
+//!
+//! // Criar uma mensagem para o modelo local
+//! let message = MCPMessage::new(
+//!     "ollama:chat",
+//!     json!({
+//!         "user_prompt": "Explique o que é Rust em poucas palavras"
+//!     })
+//! );
+//!
+//! // Processar a mensagem
+//! let response = registry.process(message).await?;
+//! println!("Resposta: {}", response.payload["answer"]);
+//! # Ok(())
+//! # }
+//! ```
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::env;
+
+use crate::agent::{AIAgent, MCPError, MCPMessage};
+use crate::chat::{parse_model_list, select_model, ChatMessage, ChatRequest, GenerationParams};
+use crate::embeddings::{EmbeddingRequest, Embeddings};
+use crate::streaming::{process_json_stream_with, StreamingToken, TokenStream};
+use crate::structured::ResponseFormat;
+use crate::testing::HttpClient;
+use crate::usage::Usage;
+
+/// Endereço padrão de um servidor Ollama local
+pub const DEFAULT_OLLAMA_ENDPOINT: &str = "http://localhost:11434";
+
+/// Modelo de embeddings padrão
+const DEFAULT_EMBEDDING_MODEL: &str = "nomic-embed-text";
+
+/// Parâmetros de geração aceitos em `options`
+const SUPPORTED_PARAMS: &[&str] = &[
+    "temperature",
+    "top_p",
+    "max_tokens",
+    "stop",
+    "seed",
+    "presence_penalty",
+    "frequency_penalty",
+];
+
+/// Agente para comunicação com um servidor compatível com o Ollama.
+///
+/// Este agente implementa a trait `AIAgent` e se conecta aos endpoints
+/// `/api/chat`, `/api/generate`, `/api/embed` e `/api/tags` do servidor configurado.
+pub struct OllamaAgent {
+    /// URL base do servidor (exemplo: http://localhost:11434)
+    pub endpoint: String,
+
+    /// Nome do modelo a ser usado (ex: "llama3.2", "mistral")
+    pub model: String,
+
+    /// Modelos adicionais que o payload pode escolher pelo campo `model`
+    pub allowed_models: Vec<String>,
+
+    /// Modelo padrão do comando `ollama:embeddings` (ex: "nomic-embed-text")
+    pub embedding_model: String,
+
+    /// Modelos de embeddings adicionais que o payload pode escolher pelo campo `model`
+    pub allowed_embedding_models: Vec<String>,
+
+    /// Cliente HTTP para fazer as requisições
+    http_client: Box<dyn HttpClient>,
+}
+
+/// Um modelo instalado no servidor, como retornado por `/api/tags`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OllamaModel {
+    /// Nome do modelo, incluindo a tag (ex: "llama3.2:latest")
+    pub name: String,
+
+    /// Tamanho do modelo em bytes
+    #[serde(default)]
+    pub size: u64,
+
+    /// Data da última modificação, como informada pelo servidor
+    #[serde(default)]
+    pub modified_at: String,
+}
+
+impl OllamaAgent {
+    /// Cria uma nova instância do agente Ollama.
+    ///
+    /// # Argumentos
+    /// * `endpoint` - URL base do servidor
+    /// * `model` - Nome do modelo a ser usado
+    /// * `http_client` - Cliente HTTP para fazer as requisições
+    ///
+    /// # Exemplo
+    ///
+    /// ```
+    /// use mcprs::agent_ollama::OllamaAgent;
+    /// use mcprs::testing::ReqwestClient;
+    ///
+    /// let agent = OllamaAgent::new(
+    ///     "http://localhost:11434".to_string(),
+    ///     "llama3.2".to_string(),
+    ///     Box::new(ReqwestClient::new())
+    /// );
+    /// ```
+    pub fn new(endpoint: String, model: String, http_client: Box<dyn HttpClient>) -> Self {
+        Self {
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            model,
+            allowed_models: Vec::new(),
+            embedding_model: DEFAULT_EMBEDDING_MODEL.to_string(),
+            allowed_embedding_models: Vec::new(),
+            http_client,
+        }
+    }
+
+    /// Permite que o payload escolha um dos modelos informados pelo campo `model`.
+    ///
+    /// O modelo padrão é sempre permitido; qualquer outro modelo é rejeitado
+    /// com `MCPError::PayloadValidation`.
+    pub fn with_allowed_models<I, S>(mut self, models: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allowed_models = models.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Define o modelo padrão do comando `ollama:embeddings`.
+    pub fn with_embedding_model(mut self, model: impl Into<String>) -> Self {
+        self.embedding_model = model.into();
+        self
+    }
+
+    /// Permite que o payload de `ollama:embeddings` escolha um dos modelos informados.
+    ///
+    /// A lista é separada de `allowed_models`, usada apenas no chat e no generate.
+    pub fn with_allowed_embedding_models<I, S>(mut self, models: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allowed_embedding_models = models.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Lista os modelos instalados no servidor (`GET /api/tags`).
+    ///
+    /// # Erros
+    /// * `MCPError::Upstream` - Se o servidor retornar erro ou uma resposta inválida
+    /// * `MCPError::Timeout` - Se o servidor não responder a tempo
+    pub async fn list_models(&self) -> Result<Vec<OllamaModel>, MCPError> {
+        let response = self
+            .http_client
+            .get(format!("{}/api/tags", self.endpoint), Vec::new())
+            .await
+            .map_err(|e| MCPError::from_transport_error("ollama", &e))?;
+
+        if !response.status().is_success() {
+            return Err(MCPError::from_provider_response("ollama", &response));
+        }
+
+        let tags = response
+            .json::<OllamaTagsResponse>()
+            .await
+            .map_err(|e| MCPError::invalid_response("ollama", &e))?;
+
+        Ok(tags.models)
+    }
+
+    /// Gera embeddings para os textos do payload (`POST /api/embed`).
+    ///
+    /// # Erros
+    /// * `MCPError::PayloadValidation` - Se `input` ou `dimensions` forem inválidos
+    ///   ou se o modelo não for permitido
+    /// * `MCPError::Upstream` - Se o servidor retornar erro ou uma resposta inválida
+    /// * `MCPError::Timeout` - Se o servidor não responder a tempo
+    async fn create_embeddings(&self, message: &MCPMessage) -> Result<Embeddings, MCPError> {
+        let request = EmbeddingRequest::from_payload(&message.payload)?;
+        let model = select_model(
+            &message.payload,
+            Some(&self.embedding_model),
+            &self.allowed_embedding_models,
+        )?
+        .unwrap_or(&self.embedding_model)
+        .to_string();
+
+        let request_body = OllamaEmbedRequest {
+            model,
+            input: &request.input,
+            dimensions: request.dimensions,
+        };
+        let response = self.send_request("/api/embed", &request_body).await?;
+
+        let resp_json = response
+            .json::<OllamaEmbedResponse>()
+            .await
+            .map_err(|e| MCPError::invalid_response("ollama", &e))?;
+
+        if let Some(error) = resp_json.error {
+            return Err(MCPError::invalid_response("ollama", &error));
+        }
+
+        let embeddings = Embeddings {
+            model: resp_json.model.unwrap_or(request_body.model),
+            vectors: resp_json.embeddings,
+            usage: resp_json
+                .prompt_eval_count
+                .map(|tokens| Usage::new(tokens, 0)),
+        };
+        embeddings
+            .check(&request)
+            .map_err(|e| MCPError::invalid_response("ollama", &e))?;
+
+        Ok(embeddings)
+    }
+
+    /// Monta a requisição para o endpoint correspondente ao comando.
+    ///
+    /// # Retorna
+    /// Uma tupla com o caminho do endpoint e o corpo da requisição
+    fn build_request(
+        &self,
+        message: &MCPMessage,
+        stream: bool,
+    ) -> Result<(&'static str, OllamaRequest), MCPError> {
+        let params = GenerationParams::from_payload(&message.payload)?;
+        params.ensure_supported("ollama", SUPPORTED_PARAMS)?;
+        let has_params = !params.provided().is_empty();
+
+        let options = OllamaOptions {
+            temperature: params.temperature,
+            top_p: params.top_p,
+            num_predict: params.max_tokens,
+            stop: params.stop,
+            seed: params.seed,
+            presence_penalty: params.presence_penalty,
+            frequency_penalty: params.frequency_penalty,
+        };
+        let options = has_params.then_some(options);
+        let model = select_model(&message.payload, Some(&self.model), &self.allowed_models)?
+            .unwrap_or(&self.model)
+            .to_string();
+
+        // O campo `format` aceita "json" ou um JSON Schema
+        let format = match ResponseFormat::from_payload(&message.payload)? {
+            Some(ResponseFormat::JsonObject { .. }) => Some(json!("json")),
+            Some(ResponseFormat::JsonSchema { schema, .. }) => Some(schema),
+            Some(ResponseFormat::Text) | None => None,
+        };
+
+        if message.action() == "generate" {
+            let prompt = message
+                .payload
+                .get("user_prompt")
+                .and_then(Value::as_str)
+                .ok_or_else(|| MCPError::PayloadValidation("Missing user_prompt".to_string()))?;
+
+            return Ok((
+                "/api/generate",
+                OllamaRequest {
+                    model,
+                    messages: None,
+                    prompt: Some(prompt.to_string()),
+                    system: message
+                        .payload
+                        .get("system_prompt")
+                        .and_then(Value::as_str)
+                        .map(str::to_string),
+                    format,
+                    options,
+                    stream,
+                },
+            ));
+        }
+
+        // Interpretar as mensagens do payload
+        let chat_request = ChatRequest::from_payload(&message.payload)?;
+        chat_request.ensure_no_tools("ollama")?;
+
+        Ok((
+            "/api/chat",
+            OllamaRequest {
+                model,
+                messages: Some(chat_request.messages),
+                prompt: None,
+                system: None,
+                format,
+                options,
+                stream,
+            },
+        ))
+    }
+
+    /// Envia a requisição para o servidor e valida o status da resposta.
+    async fn send_request(
+        &self,
+        path: &str,
+        request_body: &(impl Serialize + Sync),
+    ) -> Result<reqwest::Response, MCPError> {
+        let headers = vec![("Content-Type".to_string(), "application/json".to_string())];
+
+        let response = self
+            .http_client
+            .post(
+                format!("{}{}", self.endpoint, path),
+                serde_json::to_vec(request_body)
+                    .map_err(|e| MCPError::InternalAgentError(e.to_string()))?,
+                headers,
+            )
+            .await
+            .map_err(|e| MCPError::from_transport_error("ollama", &e))?;
+
+        // Validar status da resposta
+        if !response.status().is_success() {
+            return Err(MCPError::from_provider_response("ollama", &response));
+        }
+
+        Ok(response)
+    }
+}
+
+/// Estrutura para o corpo das requisições a `/api/chat` e `/api/generate`
+#[derive(Serialize)]
+struct OllamaRequest {
+    model: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    messages: Option<Vec<ChatMessage>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    prompt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<OllamaOptions>,
+    stream: bool,
+}
+
+/// Parâmetros de geração do Ollama
+#[derive(Serialize)]
+struct OllamaOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_predict: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    presence_penalty: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frequency_penalty: Option<f64>,
+}
+
+/// Resposta de `/api/chat` ou `/api/generate`.
+///
+/// No modo streaming, cada linha do corpo é um objeto neste formato, e a
+/// última tem `done: true` com as estatísticas da geração.
+#[derive(Deserialize)]
+struct OllamaResponse {
+    /// Modelo que gerou a resposta
+    model: Option<String>,
+
+    /// Mensagem gerada (apenas em `/api/chat`)
+    message: Option<OllamaMessage>,
+
+    /// Texto gerado (apenas em `/api/generate`)
+    response: Option<String>,
+
+    #[serde(default)]
+    done: bool,
+    done_reason: Option<String>,
+    prompt_eval_count: Option<u64>,
+    eval_count: Option<u64>,
+
+    /// Erro informado pelo servidor no meio do stream
+    error: Option<String>,
+}
+
+impl OllamaResponse {
+    /// Texto gerado, independentemente do endpoint.
+    fn text(&self) -> &str {
+        self.message
+            .as_ref()
+            .map(|message| message.content.as_str())
+            .or(self.response.as_deref())
+            .unwrap_or_default()
+    }
+
+    /// Contagem de tokens, no mesmo formato usado pelos demais agentes.
+    fn usage(&self) -> Usage {
+        Usage::new(
+            self.prompt_eval_count.unwrap_or(0),
+            self.eval_count.unwrap_or(0),
+        )
+    }
+}
+
+/// Estrutura para o corpo das requisições a `/api/embed`
+#[derive(Serialize)]
+struct OllamaEmbedRequest<'a> {
+    model: String,
+    input: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dimensions: Option<u32>,
+}
+
+/// Resposta de `/api/embed`
+#[derive(Deserialize)]
+struct OllamaEmbedResponse {
+    model: Option<String>,
+    #[serde(default)]
+    embeddings: Vec<Vec<f64>>,
+    prompt_eval_count: Option<u64>,
+    error: Option<String>,
+}
+
+/// Mensagem dentro de uma resposta de `/api/chat`
+#[derive(Deserialize)]
+struct OllamaMessage {
+    content: String,
+}
+
+/// Resposta de `/api/tags`
+#[derive(Deserialize)]
+struct OllamaTagsResponse {
+    #[serde(default)]
+    models: Vec<OllamaModel>,
+}
+
+#[async_trait]
+impl AIAgent for OllamaAgent {
+    /// Retorna o nome do agente: "ollama"
+    fn name(&self) -> &str {
+        "ollama"
+    }
+
+    /// Processa uma requisição enviando-a para o servidor Ollama.
+    ///
+    /// # Parâmetros esperados no payload
+    /// Para `ollama:chat`, as mensagens seguem o modelo de [`ChatRequest`].
+    /// Para `ollama:generate`, apenas `user_prompt` (obrigatório) e
+    /// `system_prompt` (opcional) são usados. Ambos aceitam:
+    /// * `temperature`, `top_p`, `stop`, `seed`, `presence_penalty` e `frequency_penalty` -
+    ///   Parâmetros de geração, enviados em `options` (opcionais)
+    /// * `max_tokens` - Limite de tokens na resposta, enviado como `num_predict` (opcional)
+    /// * `model` - Modelo a ser usado; deve ser o padrão ou estar em `allowed_models` (opcional)
+    /// * `response_format` - Formato JSON da resposta, enviado em `format` como `"json"`
+    ///   ou como o JSON Schema (opcional)
+    ///
+    /// # Formato da resposta
+    /// A resposta terá o comando "ollama_response" e o payload conterá:
+    /// * `answer` - O texto da resposta gerada pelo modelo
+    /// * `model` - O modelo que atendeu a requisição
+    /// * `done_reason` - A razão de término da geração (stop, length, etc.)
+    /// * `usage` - Tokens consumidos (`input_tokens`, `output_tokens` e `total_tokens`)
+    ///
+    /// Para `ollama:models`, o payload conterá `models`, a lista de modelos
+    /// instalados (veja [`OllamaAgent::list_models`]).
+    ///
+    /// Para `ollama:embeddings`, o payload segue o formato de [`crate::embeddings`]:
+    /// `input` (texto ou lista de textos), `dimensions` e `model` (opcionais) na
+    /// requisição; `embeddings`, `dimensions`, `model` e `usage` na resposta.
+    ///
+    /// # Erros
+    /// * `MCPError::PayloadValidation` - Se o prompt ou as mensagens não forem informados,
+    ///   se algum parâmetro de geração for inválido ou não suportado (`n`, `logit_bias`, `user`),
+    ///   se o modelo não for permitido ou se o payload usar ferramentas
+    /// * `MCPError::Timeout` - Se o servidor não responder a tempo
+    /// * `MCPError::Upstream` - Se o servidor retornar erro ou uma resposta que não possa ser processada
+    async fn process_request(&self, message: MCPMessage) -> Result<MCPMessage, MCPError> {
+        if message.action() == "models" {
+            let models = self.list_models().await?;
+            return Ok(
+                MCPMessage::new("ollama_response", json!({ "models": models }))
+                    .in_reply_to(&message),
+            );
+        }
+
+        if message.action() == "embeddings" {
+            let embeddings = self.create_embeddings(&message).await?;
+            return Ok(
+                MCPMessage::new("ollama_response", embeddings.to_payload()).in_reply_to(&message)
+            );
+        }
+
+        let (path, request_body) = self.build_request(&message, false)?;
+        let response = self.send_request(path, &request_body).await?;
+
+        // Desserializar a resposta
+        let resp_json = response
+            .json::<OllamaResponse>()
+            .await
+            .map_err(|e| MCPError::invalid_response("ollama", &e))?;
+
+        if let Some(error) = resp_json.error {
+            return Err(MCPError::invalid_response("ollama", &error));
+        }
+
+        Ok(MCPMessage::new(
+            "ollama_response",
+            json!({
+                "answer": resp_json.text(),
+                "model": resp_json.model.as_ref().unwrap_or(&request_body.model),
+                "done_reason": resp_json.done_reason,
+                "usage": resp_json.usage(),
+            }),
+        )
+        .in_reply_to(&message))
+    }
+
+    /// Processa uma requisição em modo streaming.
+    ///
+    /// O Ollama responde com JSON delimitado por linha; o texto de cada linha
+    /// é emitido como um `StreamingToken`. A última linha (`done: true`) gera
+    /// um token vazio com `done_reason` e `usage` em `metadata`.
+    ///
+    /// # Erros
+    /// Os mesmos de [`OllamaAgent::process_request`] para falhas antes do
+    /// início do streaming. Os comandos `ollama:models` e `ollama:embeddings`
+    /// não têm streaming e são rejeitados com `MCPError::PayloadValidation`.
+    async fn process_stream(&self, message: MCPMessage) -> Result<TokenStream, MCPError> {
+        if matches!(message.action(), "models" | "embeddings") {
+            return Err(MCPError::PayloadValidation(format!(
+                "A ação '{}' do agente 'ollama' não suporta streaming",
+                message.action()
+            )));
+        }
+
+        let (path, request_body) = self.build_request(&message, true)?;
+        let response = self.send_request(path, &request_body).await?;
+
+        process_json_stream_with::<_, OllamaResponse, _>(response.bytes_stream(), |chunk| {
+            if let Some(error) = chunk.error {
+                return Err(MCPError::invalid_response("ollama", &error));
+            }
+
+            if chunk.done {
+                return Ok(Some(StreamingToken {
+                    content: chunk.text().to_string(),
+                    is_finish: false,
+                    metadata: Some(json!({
+                        "done_reason": chunk.done_reason,
+                        "usage": chunk.usage(),
+                    })),
+                }));
+            }
+
+            let text = chunk.text();
+            Ok((!text.is_empty()).then(|| StreamingToken {
+                content: text.to_string(),
+                is_finish: false,
+                metadata: None,
+            }))
+        })
+        .await
+    }
+}
+
+/// Função auxiliar para criar um agente Ollama com configurações do ambiente.
+///
+/// Esta função facilita a criação de uma instância do agente Ollama, obtendo
+/// as configurações das variáveis de ambiente:
+/// - `OLLAMA_ENDPOINT` - URL base do servidor (padrão: http://localhost:11434)
+/// - `OLLAMA_MODEL` - Nome do modelo (padrão: llama3.2)
+/// - `OLLAMA_ALLOWED_MODELS` - Modelos adicionais permitidos no payload, separados por vírgula
+/// - `OLLAMA_EMBEDDING_MODEL` - Modelo de embeddings (padrão: nomic-embed-text)
+/// - `OLLAMA_ALLOWED_EMBEDDING_MODELS` - Modelos de embeddings adicionais permitidos no payload
+///
+/// # Argumentos
+/// * `http_client` - Cliente HTTP opcional. Se None, será criado um novo.
+///
+/// # Retorno
+/// Uma nova instância de `OllamaAgent` configurada.
+///
+/// # Exemplo
+///
+/// ```
+/// use mcprs::agent_ollama::create_ollama_agent;
+///
+/// let agent = create_ollama_agent(None);
+/// ```
+pub fn create_ollama_agent(http_client: Option<Box<dyn HttpClient>>) -> OllamaAgent {
+    let client = http_client.unwrap_or_else(|| Box::new(crate::testing::ReqwestClient::new()));
+
+    OllamaAgent::new(
+        env::var("OLLAMA_ENDPOINT").unwrap_or_else(|_| DEFAULT_OLLAMA_ENDPOINT.to_string()),
+        env::var("OLLAMA_MODEL").unwrap_or_else(|_| "llama3.2".to_string()),
+        client,
+    )
+    .with_allowed_models(
+        env::var("OLLAMA_ALLOWED_MODELS")
+            .map(|models| parse_model_list(&models))
+            .unwrap_or_default(),
+    )
+    .with_embedding_model(
+        env::var("OLLAMA_EMBEDDING_MODEL").unwrap_or_else(|_| DEFAULT_EMBEDDING_MODEL.to_string()),
+    )
+    .with_allowed_embedding_models(
+        env::var("OLLAMA_ALLOWED_EMBEDDING_MODELS")
+            .map(|models| parse_model_list(&models))
+            .unwrap_or_default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockHttpClient;
+
+    fn create_agent() -> OllamaAgent {
+        OllamaAgent::new(
+            "http://localhost:11434/".to_string(),
+            "llama3.2".to_string(),
+            Box::new(MockHttpClient::new()),
+        )
+    }
+
+    #[test]
+    fn test_build_request_by_action() {
+        let agent = create_agent();
+        assert_eq!(agent.endpoint, "http://localhost:11434");
+
+        let chat = MCPMessage::new(
+            "ollama:chat",
+            json!({ "system_prompt": "Seja breve.", "user_prompt": "Olá", "max_tokens": 10 }),
+        );
+        let (path, request) = agent.build_request(&chat, false).unwrap();
+        assert_eq!(path, "/api/chat");
+        assert_eq!(
+            serde_json::to_value(request).unwrap(),
+            json!({
+                "model": "llama3.2",
+                "messages": [
+                    { "role": "system", "content": "Seja breve." },
+                    { "role": "user", "content": "Olá" }
+                ],
+                "options": { "num_predict": 10 },
+                "stream": false
+            })
+        );
+
+        let generate = MCPMessage::new(
+            "ollama:generate",
+            json!({ "system_prompt": "Seja breve.", "user_prompt": "Olá" }),
+        );
+        let (path, request) = agent.build_request(&generate, true).unwrap();
+        assert_eq!(path, "/api/generate");
+        assert_eq!(
+            serde_json::to_value(request).unwrap(),
+            json!({
+                "model": "llama3.2",
+                "prompt": "Olá",
+                "system": "Seja breve.",
+                "stream": true
+            })
+        );
+    }
+
+    #[test]
+    fn test_build_request_maps_generation_params() {
+        let agent = create_agent();
+        let message = MCPMessage::new(
+            "ollama:chat",
+            json!({ "user_prompt": "Olá", "top_p": 0.9, "stop": ["FIM"], "seed": 3 }),
+        );
+
+        let (_, request) = agent.build_request(&message, false).unwrap();
+        assert_eq!(
+            serde_json::to_value(request).unwrap()["options"],
+            json!({ "top_p": 0.9, "stop": ["FIM"], "seed": 3 })
+        );
+
+        // O modo JSON vai em `format`, como "json" ou como o schema
+        let message = MCPMessage::new(
+            "ollama:chat",
+            json!({ "user_prompt": "Olá", "response_format": { "type": "json_object" } }),
+        );
+        let (_, request) = agent.build_request(&message, false).unwrap();
+        assert_eq!(serde_json::to_value(request).unwrap()["format"], "json");
+
+        let schema = json!({ "type": "object", "properties": { "ano": { "type": "integer" } } });
+        let message = MCPMessage::new(
+            "ollama:generate",
+            json!({
+                "user_prompt": "Olá",
+                "response_format": { "type": "json_schema", "schema": schema }
+            }),
+        );
+        let (_, request) = agent.build_request(&message, false).unwrap();
+        assert_eq!(serde_json::to_value(request).unwrap()["format"], schema);
+
+        let message = MCPMessage::new("ollama:chat", json!({ "user_prompt": "Olá", "n": 2 }));
+        assert!(matches!(
+            agent.build_request(&message, false),
+            Err(MCPError::PayloadValidation(e)) if e.contains("'ollama': n")
+        ));
+    }
+
+    #[test]
+    fn test_build_generate_request_requires_prompt() {
+        let agent = create_agent();
+        let message = MCPMessage::new("ollama:generate", json!({ "system_prompt": "x" }));
+
+        assert!(matches!(
+            agent.build_request(&message, false),
+            Err(MCPError::PayloadValidation(e)) if e.contains("Missing user_prompt")
+        ));
+    }
+}
@@ -32,17 +32,28 @@
 //! # Ok(())
 //! # }
 //! ```
+//!
+//! O comando `openai:embeddings` gera embeddings em lote (veja [`crate::embeddings`]).
 
 use crate::agent::{AIAgent, MCPError, MCPMessage};
+use crate::agent_openai_compatible::{
+    process_action, process_stream_action, OpenAICompatibleConfig,
+};
+use crate::chat::parse_model_list;
+use crate::streaming::TokenStream;
 use crate::testing::HttpClient;
 use async_trait::async_trait;
-use serde_json::{json, Value};
 use std::env;
 
+/// Modelo de embeddings padrão da OpenAI
+const DEFAULT_EMBEDDING_MODEL: &str = "text-embedding-3-small";
+
 /// Agente para comunicação com a API OpenAI.
 ///
 /// Este agente implementa a trait `AIAgent` e se conecta aos endpoints da
 /// OpenAI para enviar prompts e obter respostas de modelos como GPT-3.5 e GPT-4.
+/// A comunicação é feita pela implementação genérica de
+/// [`OpenAICompatibleAgent`](crate::agent_openai_compatible::OpenAICompatibleAgent).
 pub struct OpenAIAgent {
     /// Chave de API do OpenAI
     pub api_key: String,
@@ -50,6 +61,15 @@ pub struct OpenAIAgent {
     /// Nome do modelo a ser usado (ex: "gpt-3.5-turbo", "gpt-4")
     pub model: String,
 
+    /// Modelos adicionais que o payload pode escolher pelo campo `model`
+    pub allowed_models: Vec<String>,
+
+    /// Modelo padrão do comando `openai:embeddings`
+    pub embedding_model: String,
+
+    /// Modelos de embeddings adicionais que o payload pode escolher pelo campo `model`
+    pub allowed_embedding_models: Vec<String>,
+
     /// Cliente HTTP para fazer as requisições
     http_client: Box<dyn HttpClient>,
 }
@@ -78,43 +98,52 @@ impl OpenAIAgent {
         Self {
             api_key,
             model,
+            allowed_models: Vec::new(),
+            embedding_model: DEFAULT_EMBEDDING_MODEL.to_string(),
+            allowed_embedding_models: Vec::new(),
             http_client,
         }
     }
-}
 
-/// Estrutura para o corpo da requisição à API OpenAI Chat
-#[derive(serde::Serialize)]
-struct OpenAIChatRequest {
-    model: String,
-    messages: Vec<OpenAIChatMessage>,
-}
-
-/// Estrutura para uma mensagem na requisição à API OpenAI Chat
-#[derive(serde::Serialize)]
-struct OpenAIChatMessage {
-    role: String,
-    content: String,
-}
+    /// Permite que o payload escolha um dos modelos informados pelo campo `model`.
+    ///
+    /// O modelo padrão é sempre permitido; qualquer outro modelo é rejeitado
+    /// com `MCPError::PayloadValidation`.
+    pub fn with_allowed_models<I, S>(mut self, models: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allowed_models = models.into_iter().map(Into::into).collect();
+        self
+    }
 
-/// Estrutura para a resposta da API OpenAI Chat
-#[derive(serde::Deserialize)]
-struct OpenAIChatResponse {
-    choices: Vec<OpenAIChatChoice>,
-}
+    /// Define o modelo padrão do comando `openai:embeddings`.
+    pub fn with_embedding_model(mut self, model: impl Into<String>) -> Self {
+        self.embedding_model = model.into();
+        self
+    }
 
-/// Estrutura para um item de escolha na resposta da API OpenAI
-#[derive(serde::Deserialize)]
-struct OpenAIChatChoice {
-    message: OpenAIChatMessageResponse,
-}
+    /// Permite que o payload de `openai:embeddings` escolha um dos modelos informados.
+    ///
+    /// A lista é separada de `allowed_models`, usada apenas no chat.
+    pub fn with_allowed_embedding_models<I, S>(mut self, models: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allowed_embedding_models = models.into_iter().map(Into::into).collect();
+        self
+    }
 
-/// Estrutura para a mensagem dentro de um item de escolha na resposta
-#[derive(serde::Deserialize)]
-struct OpenAIChatMessageResponse {
-    #[allow(dead_code)]
-    role: String,
-    content: String,
+    /// Configuração equivalente para a implementação genérica.
+    fn config(&self) -> OpenAICompatibleConfig {
+        OpenAICompatibleConfig::openai(self.api_key.clone())
+            .with_default_model(self.model.clone())
+            .with_allowed_models(self.allowed_models.clone())
+            .with_embedding_model(self.embedding_model.clone())
+            .with_allowed_embedding_models(self.allowed_embedding_models.clone())
+    }
 }
 
 #[async_trait]
@@ -126,89 +155,59 @@ impl AIAgent for OpenAIAgent {
 
     /// Processa uma requisição enviando-a para a API OpenAI.
     ///
+    /// O comando `openai:embeddings` é encaminhado ao endpoint de embeddings
+    /// (veja [`crate::embeddings`]); os demais comandos usam Chat Completions.
+    ///
     /// # Parâmetros esperados no payload
-    /// * `user_prompt` - O prompt do usuário (obrigatório)
+    /// O payload segue o modelo de [`ChatRequest`](crate::chat::ChatRequest):
+    /// * `system_prompt` - Instrução de sistema (opcional)
+    /// * `messages` - Histórico de mensagens `{role, content}` (opcional)
+    /// * `user_prompt` - O prompt do usuário (opcional se `messages` for informado)
+    /// * Todos os parâmetros de geração de [`GenerationParams`](crate::chat::GenerationParams):
+    ///   `temperature`, `top_p`, `max_tokens`, `stop`, `seed`, `presence_penalty`,
+    ///   `frequency_penalty`, `n`, `logit_bias` e `user` (opcionais)
+    /// * `model` - Modelo a ser usado; deve ser o padrão ou estar em `allowed_models` (opcional)
+    /// * `tools` e `tool_choice` - Ferramentas que o modelo pode chamar (opcionais)
     ///
     /// # Formato da resposta
     /// A resposta terá o comando "openai_response" e o payload conterá:
     /// * `answer` - O texto da resposta gerada pelo modelo
+    /// * `model` - O modelo que atendeu a requisição, como informado pela API
+    /// * `id` e `finish_reason` - Quando informados pela API
+    /// * `tool_calls` - Chamadas de ferramentas `{id, name, arguments}`, se houver
     ///
     /// # Erros
-    /// * Retorna `MCPError::InternalAgentError` se:
-    ///   - O campo `user_prompt` estiver ausente
-    ///   - Houver falha na comunicação com a API
-    ///   - A resposta da API não puder ser processada
+    /// * `MCPError::PayloadValidation` - Se nem `user_prompt` nem `messages` forem informados,
+    ///   se algum parâmetro de geração for inválido, se o modelo não for permitido
+    ///   ou se `tools` ou `tool_choice` forem inválidos
+    /// * `MCPError::RateLimited` ou `MCPError::Timeout` - Se a API limitar ou não responder a tempo
+    /// * `MCPError::Upstream` - Se a API retornar erro ou uma resposta que não possa ser processada
     async fn process_request(&self, message: MCPMessage) -> Result<MCPMessage, MCPError> {
-        // Extrair o prompt do usuário do payload
-        let user_prompt = message
-            .payload
-            .get("user_prompt")
-            .and_then(Value::as_str)
-            .ok_or_else(|| MCPError::InternalAgentError("Missing user_prompt".to_string()))?;
-
-        // Construir o corpo da requisição
-        let request_body = OpenAIChatRequest {
-            model: self.model.clone(),
-            messages: vec![OpenAIChatMessage {
-                role: "user".to_string(),
-                content: user_prompt.to_string(),
-            }],
-        };
-
-        // Preparar os headers
-        let headers = vec![
-            (
-                "Authorization".to_string(),
-                format!("Bearer {}", self.api_key),
-            ),
-            ("Content-Type".to_string(), "application/json".to_string()),
-        ];
-
-        // Enviar a requisição para a API OpenAI
-        let response = self
-            .http_client
-            .post(
-                "https://api.openai.com/v1/chat/completions".to_string(),
-                serde_json::to_vec(&request_body)
-                    .map_err(|e| MCPError::InternalAgentError(e.to_string()))?,
-                headers,
-            )
-            .await
-            .map_err(|e| MCPError::InternalAgentError(e.to_string()))?;
-
-        // Verificar o status da resposta
-        if !response.status().is_success() {
-            return Err(MCPError::InternalAgentError(format!(
-                "OpenAI API retornou status {}",
-                response.status()
-            )));
-        }
+        process_action(&self.config(), self.http_client.as_ref(), message).await
+    }
 
-        // Deserializar a resposta
-        let resp_json = response
-            .json::<OpenAIChatResponse>()
-            .await
-            .map_err(|e| MCPError::InternalAgentError(e.to_string()))?;
-
-        // Extrair o texto da resposta
-        let answer_text = resp_json
-            .choices
-            .get(0)
-            .map(|choice| choice.message.content.clone())
-            .ok_or_else(|| MCPError::InternalAgentError("No response choices".to_string()))?;
-
-        // Retornar a resposta formatada como MCPMessage
-        Ok(MCPMessage::new(
-            "openai_response",
-            json!({ "answer": answer_text }),
-        ))
+    /// Processa uma requisição em modo streaming (`stream: true`).
+    ///
+    /// Os eventos SSE da API OpenAI são decodificados incrementalmente e cada
+    /// fragmento de `delta.content` é emitido como um `StreamingToken`.
+    ///
+    /// # Erros
+    /// Os mesmos de [`OpenAIAgent::process_request`] para falhas antes do
+    /// início do streaming. O comando `openai:embeddings` não tem streaming e
+    /// é rejeitado com `MCPError::PayloadValidation`.
+    async fn process_stream(&self, message: MCPMessage) -> Result<TokenStream, MCPError> {
+        process_stream_action(&self.config(), self.http_client.as_ref(), message).await
     }
 }
 
 /// Função auxiliar para criar um agente OpenAI com configurações do ambiente.
 ///
 /// Esta função facilita a criação de uma instância do agente OpenAI, obtendo
-/// a chave de API da variável de ambiente `OPENAI_API_KEY`.
+/// as configurações das variáveis de ambiente:
+/// - `OPENAI_API_KEY` - Chave de API
+/// - `OPENAI_ALLOWED_MODELS` - Modelos adicionais permitidos no payload, separados por vírgula
+/// - `OPENAI_EMBEDDING_MODEL` - Modelo de embeddings (padrão: text-embedding-3-small)
+/// - `OPENAI_ALLOWED_EMBEDDING_MODELS` - Modelos de embeddings adicionais permitidos no payload
 ///
 /// # Argumentos
 /// * `http_client` - Cliente HTTP opcional. Se None, será criado um novo.
@@ -235,6 +234,19 @@ pub fn create_openai_agent(http_client: Option<Box<dyn HttpClient>>) -> OpenAIAg
         "gpt-3.5-turbo".to_string(),
         client,
     )
+    .with_allowed_models(
+        env::var("OPENAI_ALLOWED_MODELS")
+            .map(|models| parse_model_list(&models))
+            .unwrap_or_default(),
+    )
+    .with_embedding_model(
+        env::var("OPENAI_EMBEDDING_MODEL").unwrap_or_else(|_| DEFAULT_EMBEDDING_MODEL.to_string()),
+    )
+    .with_allowed_embedding_models(
+        env::var("OPENAI_ALLOWED_EMBEDDING_MODELS")
+            .map(|models| parse_model_list(&models))
+            .unwrap_or_default(),
+    )
 }
 
 #[cfg(test)]
@@ -242,7 +254,7 @@ mod tests {
     use super::*;
     use crate::testing::MockHttpClient;
     use mockall::predicate;
-    use serde_json::json;
+    use serde_json::{json, Value};
 
     fn create_mock_response(body: serde_json::Value) -> reqwest::Response {
         reqwest::Response::from(
@@ -267,7 +279,7 @@ mod tests {
         let result = agent.process_request(message).await;
 
         assert!(
-            matches!(result, Err(MCPError::InternalAgentError(e)) if e.contains("Missing user_prompt"))
+            matches!(result, Err(MCPError::PayloadValidation(e)) if e.contains("Missing user_prompt"))
         );
     }
 
@@ -309,4 +321,50 @@ mod tests {
             "Rust é uma linguagem de programação focada em segurança, desempenho e concorrência."
         );
     }
+
+    #[tokio::test]
+    async fn test_openai_agent_process_stream() {
+        use futures::StreamExt;
+
+        let mut mock_client = MockHttpClient::new();
+
+        mock_client
+            .expect_post()
+            .withf(|_, body, _| {
+                let parsed: Value = serde_json::from_slice(body).unwrap_or_default();
+                parsed["stream"] == true
+            })
+            .times(1)
+            .return_once(move |_, _, _| {
+                let body = concat!(
+                    "data: {\"choices\":[{\"delta\":{\"content\":\"Rust\"},\"finish_reason\":null}]}\n\n",
+                    "data: {\"choices\":[{\"delta\":{\"content\":\" é rápido\"},\"finish_reason\":null}]}\n\n",
+                    "data: {\"choices\":[{\"delta\":{},\"finish_reason\":\"stop\"}]}\n\n",
+                    "data: [DONE]\n\n"
+                );
+                Ok(reqwest::Response::from(
+                    http::Response::builder().status(200).body(body).unwrap(),
+                ))
+            });
+
+        let agent = OpenAIAgent::new(
+            "test_key".to_string(),
+            "gpt-3.5-turbo".to_string(),
+            Box::new(mock_client),
+        );
+
+        let message = MCPMessage::new("openai:chat", json!({ "user_prompt": "O que é Rust?" }));
+        let mut stream = agent.process_stream(message).await.unwrap();
+
+        let mut content = String::new();
+        while let Some(token) = stream.next().await {
+            let token = token.unwrap();
+            if token.is_finish {
+                break;
+            }
+            content.push_str(&token.content);
+        }
+
+        assert_eq!(content, "Rust é rápido");
+    }
 }
@@ -0,0 +1,1243 @@
+//! # Agente genérico para APIs compatíveis com a OpenAI
+//!
+//! Muitos provedores e servidores locais expõem o mesmo formato de Chat
+//! Completions da OpenAI (vLLM, LM Studio, Groq, Together, DeepSeek, ...).
+//! Este módulo implementa um único agente, [`OpenAICompatibleAgent`],
+//! configurado por [`OpenAICompatibleConfig`] com nome, URL base, caminho,
+//! esquema de autenticação, cabeçalhos extras e modelo padrão. Assim, cada
+//! provedor pode ser registrado como uma entrada própria do registro sem
+//! código novo.
+//!
+//! [`OpenAIAgent`](crate::agent_openai::OpenAIAgent) e
+//! [`DeepSeekAgent`](crate::agent_deepseek::DeepSeekAgent) usam esta mesma
+//! implementação.
+//!
+//! ## Exemplo de Uso
+//!
+//! ```rust,no_run
+//! use mcprs::agent::{AgentRegistry, MCPMessage};
+//! use mcprs::agent_openai_compatible::{AuthScheme, OpenAICompatibleAgent, OpenAICompatibleConfig};
+//! use mcprs::testing::ReqwestClient;
+//! use serde_json::json;
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut registry = AgentRegistry::new();
+//!
+//! // Groq, com autenticação Bearer
+//! let groq = OpenAICompatibleConfig::new("groq", "https://api.groq.com/openai")
+//!     .with_api_key("sua-chave-groq")
+//!     .with_default_model("llama-3.1-8b-instant");
+//! registry.register_agent(Box::new(OpenAICompatibleAgent::new(
+//!     groq,
+//!     Box::new(ReqwestClient::new()),
+//! )));
+//!
+//! // LM Studio local, sem autenticação
+//! let lmstudio = OpenAICompatibleConfig::new("lmstudio", "http://localhost:1234")
+//!     .with_auth(AuthScheme::None)
+//!     .with_default_model("qwen2.5-7b-instruct");
+//! registry.register_agent(Box::new(OpenAICompatibleAgent::new(
+//!     lmstudio,
+//!     Box::new(ReqwestClient::new()),
+//! )));
+//!
+//! let message = MCPMessage::new("groq:chat", json!({ "user_prompt": "Olá" }));
+//! let response = registry.process(message).await?;
+//! println!("Resposta: {}", response.payload["answer"]);
+//! # Ok(())
+//! # }
+//! ```
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::env;
+
+use crate::agent::{AIAgent, MCPError, MCPMessage};
+use crate::chat::{
+    parse_model_list, select_model, ChatMessage, ChatRequest, GenerationParams, ToolCall,
+    ToolChoice, ToolDefinition,
+};
+use crate::embeddings::{EmbeddingRequest, Embeddings};
+use crate::streaming::{process_chat_completion_stream, TokenStream};
+use crate::testing::HttpClient;
+use crate::usage::ChatCompletionUsage;
+
+/// Caminho padrão do endpoint de Chat Completions
+pub const DEFAULT_CHAT_PATH: &str = "/v1/chat/completions";
+
+/// Caminho padrão do endpoint de embeddings
+pub const DEFAULT_EMBEDDINGS_PATH: &str = "/v1/embeddings";
+
+/// Como a chave de API é enviada ao provedor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthScheme {
+    /// Cabeçalho `Authorization: Bearer <chave>` (OpenAI, DeepSeek, Groq, Together)
+    Bearer,
+
+    /// Chave enviada em um cabeçalho próprio, como `api-key` no Azure
+    Header(String),
+
+    /// Sem autenticação (servidores locais como LM Studio e vLLM)
+    None,
+}
+
+/// Configuração de um provedor compatível com a OpenAI.
+#[derive(Clone)]
+pub struct OpenAICompatibleConfig {
+    /// Nome do agente no registro (usado em `nome:ação`)
+    pub name: String,
+
+    /// URL base do provedor (ex: "https://api.groq.com/openai")
+    pub base_url: String,
+
+    /// Caminho do endpoint de chat, que pode incluir query string (padrão: `/v1/chat/completions`)
+    pub path: String,
+
+    /// Esquema de autenticação (padrão: [`AuthScheme::Bearer`])
+    pub auth: AuthScheme,
+
+    /// Chave de API, se o provedor exigir
+    pub api_key: Option<String>,
+
+    /// Cabeçalhos adicionais enviados em toda requisição
+    pub headers: Vec<(String, String)>,
+
+    /// Modelo enviado no campo `model`; omitido se `None`
+    pub default_model: Option<String>,
+
+    /// Modelos que o payload pode escolher pelo campo `model`, além do padrão
+    pub allowed_models: Vec<String>,
+
+    /// Parâmetros de geração aceitos (padrão: todos de [`GenerationParams::ALL`])
+    pub supported_params: Vec<&'static str>,
+
+    /// Parâmetros enviados com outro nome, como `seed` → `random_seed` na Mistral
+    pub renamed_params: Vec<(&'static str, &'static str)>,
+
+    /// Se o provedor aceita `response_format` do tipo `json_schema` (padrão: `true`)
+    pub native_json_schema: bool,
+
+    /// Se o stream pede o uso de tokens com `stream_options.include_usage` (padrão: `true`)
+    pub stream_usage: bool,
+
+    /// Caminho do endpoint de embeddings (padrão: `/v1/embeddings`)
+    pub embeddings_path: String,
+
+    /// Modelo padrão da ação `embeddings`; sem ele, o payload deve informar `model`
+    pub embedding_model: Option<String>,
+
+    /// Modelos que o payload da ação `embeddings` pode escolher, além do padrão
+    pub allowed_embedding_models: Vec<String>,
+}
+
+impl OpenAICompatibleConfig {
+    /// Cria uma configuração com o caminho padrão e autenticação Bearer.
+    ///
+    /// # Argumentos
+    /// * `name` - Nome do agente no registro
+    /// * `base_url` - URL base do provedor
+    pub fn new(name: impl Into<String>, base_url: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            base_url: base_url.into(),
+            path: DEFAULT_CHAT_PATH.to_string(),
+            auth: AuthScheme::Bearer,
+            api_key: None,
+            headers: Vec::new(),
+            default_model: None,
+            allowed_models: Vec::new(),
+            supported_params: GenerationParams::ALL.to_vec(),
+            renamed_params: Vec::new(),
+            native_json_schema: true,
+            stream_usage: true,
+            embeddings_path: DEFAULT_EMBEDDINGS_PATH.to_string(),
+            embedding_model: None,
+            allowed_embedding_models: Vec::new(),
+        }
+    }
+
+    /// Configuração da API oficial da OpenAI.
+    pub fn openai(api_key: impl Into<String>) -> Self {
+        Self::new("openai", "https://api.openai.com").with_api_key(api_key)
+    }
+
+    /// Configuração da API do DeepSeek.
+    ///
+    /// A API não aceita `seed`, `n`, `logit_bias` nem `user`, e tem apenas o
+    /// modo `json_object`.
+    pub fn deepseek(api_key: impl Into<String>) -> Self {
+        Self::new("deepseek", "https://api.deepseek.ai")
+            .with_api_key(api_key)
+            .with_native_json_schema(false)
+            .with_supported_params(&[
+                "temperature",
+                "top_p",
+                "max_tokens",
+                "stop",
+                "presence_penalty",
+                "frequency_penalty",
+            ])
+    }
+
+    /// Cria uma configuração a partir de variáveis de ambiente.
+    ///
+    /// O prefixo das variáveis é o nome em maiúsculas, com `-` trocado por `_`:
+    /// - `<PREFIXO>_BASE_URL` - URL base (obrigatória)
+    /// - `<PREFIXO>_API_KEY` - Chave de API (opcional)
+    /// - `<PREFIXO>_MODEL` - Modelo padrão (opcional)
+    /// - `<PREFIXO>_CHAT_PATH` - Caminho do endpoint de chat (opcional)
+    /// - `<PREFIXO>_ALLOWED_MODELS` - Modelos permitidos no payload, separados por vírgula (opcional)
+    /// - `<PREFIXO>_EMBEDDING_MODEL` - Modelo padrão de embeddings (opcional)
+    /// - `<PREFIXO>_ALLOWED_EMBEDDING_MODELS` - Modelos de embeddings permitidos no payload,
+    ///   separados por vírgula (opcional)
+    /// - `<PREFIXO>_EMBEDDINGS_PATH` - Caminho do endpoint de embeddings (opcional)
+    ///
+    /// # Retorna
+    /// `None` se `<PREFIXO>_BASE_URL` não estiver definida
+    ///
+    /// # Exemplo
+    ///
+    /// ```
+    /// use mcprs::agent_openai_compatible::OpenAICompatibleConfig;
+    ///
+    /// std::env::set_var("TOGETHER_BASE_URL", "https://api.together.xyz");
+    /// std::env::set_var("TOGETHER_MODEL", "meta-llama/Llama-3-8b-chat-hf");
+    ///
+    /// let config = OpenAICompatibleConfig::from_env("together").unwrap();
+    /// assert_eq!(config.url(), "https://api.together.xyz/v1/chat/completions");
+    /// ```
+    pub fn from_env(name: &str) -> Option<Self> {
+        let prefix = name.to_uppercase().replace('-', "_");
+        let var = |suffix: &str| env::var(format!("{}_{}", prefix, suffix)).ok();
+
+        let mut config = Self::new(name, var("BASE_URL")?);
+        config.api_key = var("API_KEY");
+        config.default_model = var("MODEL");
+        if let Some(path) = var("CHAT_PATH") {
+            config.path = path;
+        }
+        if let Some(models) = var("ALLOWED_MODELS") {
+            config.allowed_models = parse_model_list(&models);
+        }
+        config.embedding_model = var("EMBEDDING_MODEL");
+        if let Some(models) = var("ALLOWED_EMBEDDING_MODELS") {
+            config.allowed_embedding_models = parse_model_list(&models);
+        }
+        if let Some(path) = var("EMBEDDINGS_PATH") {
+            config.embeddings_path = path;
+        }
+        Some(config)
+    }
+
+    /// Define a URL base do provedor.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Define o caminho do endpoint de chat.
+    pub fn with_path(mut self, path: impl Into<String>) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    /// Define o esquema de autenticação.
+    pub fn with_auth(mut self, auth: AuthScheme) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// Define a chave de API.
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Adiciona um cabeçalho enviado em toda requisição.
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Define o modelo padrão.
+    pub fn with_default_model(mut self, model: impl Into<String>) -> Self {
+        self.default_model = Some(model.into());
+        self
+    }
+
+    /// Define os modelos que o payload pode escolher pelo campo `model`.
+    ///
+    /// O modelo padrão é sempre permitido; qualquer outro modelo é rejeitado
+    /// com `MCPError::PayloadValidation` antes de chegar ao provedor.
+    pub fn with_allowed_models<I, S>(mut self, models: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allowed_models = models.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Restringe os parâmetros de geração aceitos pelo provedor.
+    ///
+    /// Requisições com outros parâmetros são rejeitadas com
+    /// `MCPError::PayloadValidation` antes de chegar ao provedor.
+    pub fn with_supported_params(mut self, params: &[&'static str]) -> Self {
+        self.supported_params = params.to_vec();
+        self
+    }
+
+    /// Envia um parâmetro de geração com outro nome de campo.
+    pub fn with_renamed_param(mut self, param: &'static str, field: &'static str) -> Self {
+        self.renamed_params.push((param, field));
+        self
+    }
+
+    /// Define se o provedor aceita `response_format` do tipo `json_schema`.
+    ///
+    /// Sem suporte, o schema é pedido com `{"type": "json_object"}` e com as
+    /// instruções de [`ResponseFormat::instructions`] no prompt de sistema.
+    ///
+    /// [`ResponseFormat::instructions`]: crate::structured::ResponseFormat::instructions
+    pub fn with_native_json_schema(mut self, supported: bool) -> Self {
+        self.native_json_schema = supported;
+        self
+    }
+
+    /// Define se o stream pede o uso de tokens com `stream_options.include_usage`.
+    ///
+    /// Desative para provedores que rejeitam o campo; o uso então só é
+    /// informado se o provedor o enviar por conta própria.
+    pub fn with_stream_usage(mut self, enabled: bool) -> Self {
+        self.stream_usage = enabled;
+        self
+    }
+
+    /// Define o caminho do endpoint de embeddings.
+    pub fn with_embeddings_path(mut self, path: impl Into<String>) -> Self {
+        self.embeddings_path = path.into();
+        self
+    }
+
+    /// Define o modelo padrão da ação `embeddings`.
+    pub fn with_embedding_model(mut self, model: impl Into<String>) -> Self {
+        self.embedding_model = Some(model.into());
+        self
+    }
+
+    /// Define os modelos que o payload da ação `embeddings` pode escolher.
+    ///
+    /// A lista é separada de `allowed_models`: um modelo de embeddings
+    /// liberado aqui não pode ser usado no chat, e vice-versa.
+    pub fn with_allowed_embedding_models<I, S>(mut self, models: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allowed_embedding_models = models.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// URL completa do endpoint de chat.
+    pub fn url(&self) -> String {
+        format!("{}{}", self.base_url.trim_end_matches('/'), self.path)
+    }
+
+    /// URL completa do endpoint de embeddings.
+    pub fn embeddings_url(&self) -> String {
+        format!(
+            "{}{}",
+            self.base_url.trim_end_matches('/'),
+            self.embeddings_path
+        )
+    }
+
+    /// Cabeçalhos da requisição, incluindo a autenticação.
+    fn request_headers(&self) -> Vec<(String, String)> {
+        let mut headers = vec![("Content-Type".to_string(), "application/json".to_string())];
+
+        if let Some(api_key) = &self.api_key {
+            match &self.auth {
+                AuthScheme::Bearer => {
+                    headers.push(("Authorization".to_string(), format!("Bearer {}", api_key)))
+                }
+                AuthScheme::Header(name) => headers.push((name.clone(), api_key.clone())),
+                AuthScheme::None => {}
+            }
+        }
+
+        headers.extend(self.headers.iter().cloned());
+        headers
+    }
+}
+
+/// Agente para qualquer API compatível com o Chat Completions da OpenAI.
+pub struct OpenAICompatibleAgent {
+    /// Configuração do provedor
+    config: OpenAICompatibleConfig,
+
+    /// Cliente HTTP para fazer as requisições
+    http_client: Box<dyn HttpClient>,
+}
+
+impl OpenAICompatibleAgent {
+    /// Cria um novo agente com a configuração informada.
+    ///
+    /// # Exemplo
+    ///
+    /// ```
+    /// use mcprs::agent::AIAgent;
+    /// use mcprs::agent_openai_compatible::{OpenAICompatibleAgent, OpenAICompatibleConfig};
+    /// use mcprs::testing::ReqwestClient;
+    ///
+    /// let config = OpenAICompatibleConfig::new("vllm", "http://localhost:8000")
+    ///     .with_default_model("meta-llama/Llama-3.1-8B-Instruct");
+    /// let agent = OpenAICompatibleAgent::new(config, Box::new(ReqwestClient::new()));
+    /// assert_eq!(agent.name(), "vllm");
+    /// ```
+    pub fn new(config: OpenAICompatibleConfig, http_client: Box<dyn HttpClient>) -> Self {
+        Self {
+            config,
+            http_client,
+        }
+    }
+
+    /// Retorna a configuração do agente.
+    pub fn config(&self) -> &OpenAICompatibleConfig {
+        &self.config
+    }
+}
+
+#[async_trait]
+impl AIAgent for OpenAICompatibleAgent {
+    /// Retorna o nome configurado
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    /// Processa uma requisição de acordo com a ação do comando (veja [`process_action`]).
+    async fn process_request(&self, message: MCPMessage) -> Result<MCPMessage, MCPError> {
+        process_action(&self.config, self.http_client.as_ref(), message).await
+    }
+
+    /// Processa uma requisição em modo streaming (veja [`process_stream_action`]).
+    async fn process_stream(&self, message: MCPMessage) -> Result<TokenStream, MCPError> {
+        process_stream_action(&self.config, self.http_client.as_ref(), message).await
+    }
+}
+
+/// Função auxiliar para criar um agente compatível com a OpenAI a partir do ambiente.
+///
+/// Veja [`OpenAICompatibleConfig::from_env`] para as variáveis lidas.
+///
+/// # Retorno
+/// `None` se a URL base do provedor não estiver configurada
+pub fn create_openai_compatible_agent(
+    name: &str,
+    http_client: Option<Box<dyn HttpClient>>,
+) -> Option<OpenAICompatibleAgent> {
+    let config = OpenAICompatibleConfig::from_env(name)?;
+    let client = http_client.unwrap_or_else(|| Box::new(crate::testing::ReqwestClient::new()));
+
+    Some(OpenAICompatibleAgent::new(config, client))
+}
+
+/// Encaminha a requisição de acordo com a ação do comando.
+///
+/// A ação `chat` usa [`chat_completion`] e a ação `embeddings` usa
+/// [`create_embeddings`]; as demais são rejeitadas com `MCPError::PayloadValidation`.
+pub(crate) async fn process_action(
+    config: &OpenAICompatibleConfig,
+    http_client: &dyn HttpClient,
+    message: MCPMessage,
+) -> Result<MCPMessage, MCPError> {
+    match message.action() {
+        "chat" => chat_completion(config, http_client, message).await,
+        "embeddings" => create_embeddings(config, http_client, message).await,
+        action => Err(unknown_action(config, action)),
+    }
+}
+
+/// Encaminha a requisição em modo streaming de acordo com a ação do comando.
+///
+/// Apenas a ação `chat` tem streaming, via [`chat_completion_stream`]; a ação
+/// `embeddings` e ações desconhecidas são rejeitadas com `MCPError::PayloadValidation`.
+pub(crate) async fn process_stream_action(
+    config: &OpenAICompatibleConfig,
+    http_client: &dyn HttpClient,
+    message: MCPMessage,
+) -> Result<TokenStream, MCPError> {
+    match message.action() {
+        "chat" => chat_completion_stream(config, http_client, message).await,
+        "embeddings" => Err(MCPError::PayloadValidation(format!(
+            "A ação 'embeddings' do agente '{}' não suporta streaming",
+            config.name
+        ))),
+        action => Err(unknown_action(config, action)),
+    }
+}
+
+/// Cria o erro para uma ação que o agente não implementa.
+fn unknown_action(config: &OpenAICompatibleConfig, action: &str) -> MCPError {
+    MCPError::PayloadValidation(format!(
+        "Ação '{}' não suportada pelo agente '{}' (use 'chat' ou 'embeddings')",
+        action, config.name
+    ))
+}
+
+/// Gera embeddings para um ou mais textos e converte a resposta.
+///
+/// # Parâmetros esperados no payload
+/// Os campos de [`EmbeddingRequest`]: `input` (texto ou lista de textos) e
+/// `dimensions` (opcional), além de `model`, que deve ser o modelo de
+/// embeddings padrão ou estar em `allowed_embedding_models` (opcional).
+///
+/// # Formato da resposta
+/// A resposta terá o comando `<nome>_response` e o payload de
+/// [`Embeddings::to_payload`]: `embeddings`, `dimensions`, `model` e `usage`.
+///
+/// # Erros
+/// * `MCPError::PayloadValidation` - Se `input` ou `dimensions` forem inválidos,
+///   se o modelo não for permitido ou se nenhum modelo de embeddings estiver configurado
+/// * `MCPError::RateLimited` ou `MCPError::Timeout` - Se o provedor limitar ou não responder a tempo
+/// * `MCPError::Upstream` - Se o provedor retornar erro ou uma resposta que não possa ser processada
+pub(crate) async fn create_embeddings(
+    config: &OpenAICompatibleConfig,
+    http_client: &dyn HttpClient,
+    message: MCPMessage,
+) -> Result<MCPMessage, MCPError> {
+    let request = EmbeddingRequest::from_payload(&message.payload)?;
+    let model = select_model(
+        &message.payload,
+        config.embedding_model.as_deref(),
+        &config.allowed_embedding_models,
+    )?
+    .ok_or_else(|| {
+        MCPError::PayloadValidation(format!(
+            "O agente '{}' não tem modelo de embeddings configurado; informe 'model'",
+            config.name
+        ))
+    })?;
+
+    let body = json!({
+        "model": model,
+        "input": request.input,
+        "dimensions": request.dimensions,
+        "encoding_format": "float",
+    });
+    let response = post_json(config, http_client, config.embeddings_url(), body).await?;
+
+    let resp_json = response
+        .json::<EmbeddingsResponse>()
+        .await
+        .map_err(|e| MCPError::invalid_response(&config.name, &e))?;
+
+    // Os vetores podem vir fora de ordem; `index` indica o texto de origem
+    let mut data = resp_json.data;
+    data.sort_by_key(|item| item.index);
+
+    let embeddings = Embeddings {
+        model: resp_json.model.unwrap_or_else(|| model.to_string()),
+        vectors: data.into_iter().map(|item| item.embedding).collect(),
+        usage: resp_json.usage.map(|usage| usage.normalized()),
+    };
+    embeddings
+        .check(&request)
+        .map_err(|e| MCPError::invalid_response(&config.name, &e))?;
+
+    Ok(MCPMessage::new(
+        &format!("{}_response", config.name),
+        embeddings.to_payload(),
+    )
+    .in_reply_to(&message))
+}
+
+/// Envia uma requisição de Chat Completions e converte a resposta.
+///
+/// # Parâmetros esperados no payload
+/// As mensagens seguem o modelo de [`ChatRequest`]:
+/// * `system_prompt` - Instrução de sistema (opcional)
+/// * `messages` - Histórico de mensagens `{role, content}` (opcional)
+/// * `user_prompt` - O prompt do usuário (opcional se `messages` for informado)
+/// * `model` - Modelo a ser usado; deve ser o padrão ou estar em `allowed_models` (opcional)
+/// * Parâmetros de geração de [`GenerationParams`] aceitos pelo provedor (opcionais)
+/// * `response_format` - Formato da resposta (veja [`crate::structured`]), enviado como
+///   `json_object` ou `json_schema` (opcional)
+/// * `tools` e `tool_choice` - Ferramentas que o modelo pode chamar, enviadas como funções (opcionais)
+///
+/// Mensagens do assistente com `tool_calls` e mensagens `tool` com `tool_call_id`
+/// no histórico são convertidas para o formato de Chat Completions.
+///
+/// # Formato da resposta
+/// A resposta terá o comando `<nome>_response` e o payload conterá:
+/// * `answer` - O texto da resposta gerada pelo modelo
+/// * `id` - O ID da resposta gerada pela API, se informado
+/// * `model` - O modelo que atendeu a requisição, se conhecido
+/// * `finish_reason` - A razão de término da geração (stop, length, tool_calls, etc.), se informada
+/// * `usage` - Tokens consumidos (`input_tokens`, `output_tokens` e `total_tokens`), se informados
+/// * `tool_calls` - Chamadas de ferramentas `{id, name, arguments}` pedidas pelo modelo, se houver
+/// * `answers` - O texto de todas as respostas, na ordem de `index`, quando o provedor
+///   retorna mais de uma (parâmetro `n`); `answer` e os demais campos vêm da primeira
+///
+/// # Erros
+/// * `MCPError::PayloadValidation` - Se nem `user_prompt` nem `messages` forem informados,
+///   se algum parâmetro de geração for inválido ou não suportado pelo provedor,
+///   se o modelo não for permitido, se `response_format` for inválido
+///   ou se `tools` ou `tool_choice` forem inválidos
+/// * `MCPError::RateLimited` ou `MCPError::Timeout` - Se a API limitar ou não responder a tempo
+/// * `MCPError::Upstream` - Se a API retornar erro ou uma resposta que não possa ser processada
+pub(crate) async fn chat_completion(
+    config: &OpenAICompatibleConfig,
+    http_client: &dyn HttpClient,
+    message: MCPMessage,
+) -> Result<MCPMessage, MCPError> {
+    let request_body = build_request(config, &message, false)?;
+    let response = send_request(config, http_client, &request_body).await?;
+
+    // Deserializar a resposta
+    let resp_json = response
+        .json::<ChatCompletionResponse>()
+        .await
+        .map_err(|e| MCPError::invalid_response(&config.name, &e))?;
+
+    // Extrair o texto da resposta; com `n` > 1 há uma escolha por resposta
+    let mut choices = resp_json.choices;
+    choices.sort_by_key(|choice| choice.index);
+    let answers: Vec<String> = choices
+        .iter()
+        .map(|choice| choice.message.content.clone().unwrap_or_default())
+        .collect();
+    let choice = choices
+        .into_iter()
+        .next()
+        .ok_or_else(|| MCPError::invalid_response(&config.name, "No response choices"))?;
+
+    let tool_calls: Vec<ToolCall> = choice
+        .message
+        .tool_calls
+        .unwrap_or_default()
+        .into_iter()
+        .map(ToolCall::from)
+        .collect();
+
+    let mut payload = json!({ "answer": choice.message.content.unwrap_or_default() });
+    if let Some(id) = resp_json.id {
+        payload["id"] = json!(id);
+    }
+    // O modelo informado pela API pode trazer a versão exata usada
+    if let Some(model) = resp_json.model.or(request_body.model) {
+        payload["model"] = json!(model);
+    }
+    if let Some(finish_reason) = choice.finish_reason {
+        payload["finish_reason"] = json!(finish_reason);
+    }
+    if let Some(usage) = resp_json.usage {
+        payload["usage"] = usage.normalized().to_json();
+    }
+    if !tool_calls.is_empty() {
+        payload["tool_calls"] = json!(tool_calls);
+    }
+    if answers.len() > 1 {
+        payload["answers"] = json!(answers);
+    }
+
+    Ok(MCPMessage::new(&format!("{}_response", config.name), payload).in_reply_to(&message))
+}
+
+/// Envia uma requisição de Chat Completions com `stream: true`.
+///
+/// Cada fragmento de `delta.content` é emitido como um `StreamingToken`.
+/// Com `stream_usage`, o provedor envia o uso de tokens no último chunk, que
+/// chega em `metadata.usage`.
+///
+/// # Erros
+/// Os mesmos de [`chat_completion`] para falhas antes do início do streaming.
+/// Requisições com `tools` ou com `n` maior que 1 são rejeitadas com
+/// `MCPError::PayloadValidation`, pois o stream emite apenas o texto de uma resposta.
+pub(crate) async fn chat_completion_stream(
+    config: &OpenAICompatibleConfig,
+    http_client: &dyn HttpClient,
+    message: MCPMessage,
+) -> Result<TokenStream, MCPError> {
+    let request_body = build_request(config, &message, true)?;
+    let response = send_request(config, http_client, &request_body).await?;
+
+    process_chat_completion_stream(response.bytes_stream()).await
+}
+
+/// Monta o corpo da requisição de chat a partir do payload MCP.
+fn build_request(
+    config: &OpenAICompatibleConfig,
+    message: &MCPMessage,
+    stream: bool,
+) -> Result<ChatCompletionRequest, MCPError> {
+    // Interpretar as mensagens do payload
+    let mut chat_request = ChatRequest::from_payload(&message.payload)?;
+    let params = GenerationParams::from_payload(&message.payload)?;
+    params.ensure_supported(&config.name, &config.supported_params)?;
+
+    if stream {
+        if !chat_request.tools.is_empty() {
+            return Err(MCPError::PayloadValidation(
+                "Chamadas de ferramentas não são suportadas em modo streaming".to_string(),
+            ));
+        }
+        params.ensure_single_choice()?;
+    }
+
+    // Sem `json_schema` nativo, o schema vai nas instruções de sistema
+    if let Some(format) = &chat_request.response_format {
+        if format.is_json() && !config.native_json_schema {
+            if let Some(instructions) = format.instructions() {
+                chat_request.add_system_instruction(instructions);
+            }
+        }
+    }
+    let response_format = chat_request
+        .response_format
+        .as_ref()
+        .map(|format| format.openai_value(config.native_json_schema));
+
+    let model = select_model(
+        &message.payload,
+        config.default_model.as_deref(),
+        &config.allowed_models,
+    )?;
+
+    Ok(ChatCompletionRequest {
+        model: model.map(str::to_string),
+        messages: chat_request
+            .messages
+            .into_iter()
+            .map(ChatCompletionRequestMessage::from)
+            .collect(),
+        tools: chat_request
+            .tools
+            .into_iter()
+            .map(|function| ChatCompletionTool {
+                kind: "function",
+                function,
+            })
+            .collect(),
+        tool_choice: chat_request.tool_choice.as_ref().map(tool_choice_value),
+        params,
+        response_format,
+        stream: stream.then_some(true),
+        // Sem a opção, a OpenAI não envia o chunk final com `usage`
+        stream_options: (stream && config.stream_usage).then(|| json!({ "include_usage": true })),
+    })
+}
+
+/// Envia a requisição para o provedor e valida o status da resposta.
+async fn send_request(
+    config: &OpenAICompatibleConfig,
+    http_client: &dyn HttpClient,
+    request_body: &ChatCompletionRequest,
+) -> Result<reqwest::Response, MCPError> {
+    let mut body = serde_json::to_value(request_body)
+        .map_err(|e| MCPError::InternalAgentError(e.to_string()))?;
+    for (param, field) in &config.renamed_params {
+        if let Some(value) = body.as_object_mut().and_then(|b| b.remove(*param)) {
+            body[*field] = value;
+        }
+    }
+
+    post_json(config, http_client, config.url(), body).await
+}
+
+/// Envia um corpo JSON ao provedor e valida o status da resposta.
+///
+/// Campos `null` no primeiro nível do corpo não são enviados.
+async fn post_json(
+    config: &OpenAICompatibleConfig,
+    http_client: &dyn HttpClient,
+    url: String,
+    mut body: Value,
+) -> Result<reqwest::Response, MCPError> {
+    if let Some(fields) = body.as_object_mut() {
+        fields.retain(|_, value| !value.is_null());
+    }
+
+    let response = http_client
+        .post(
+            url,
+            serde_json::to_vec(&body).map_err(|e| MCPError::InternalAgentError(e.to_string()))?,
+            config.request_headers(),
+        )
+        .await
+        .map_err(|e| MCPError::from_transport_error(&config.name, &e))?;
+
+    // Verificar o status da resposta
+    if !response.status().is_success() {
+        return Err(MCPError::from_provider_response(&config.name, &response));
+    }
+
+    Ok(response)
+}
+
+/// Converte `tool_choice` para o formato de Chat Completions.
+fn tool_choice_value(choice: &ToolChoice) -> Value {
+    match choice {
+        ToolChoice::Auto => json!("auto"),
+        ToolChoice::None => json!("none"),
+        ToolChoice::Required => json!("required"),
+        ToolChoice::Tool(name) => json!({ "type": "function", "function": { "name": name } }),
+    }
+}
+
+/// Estrutura para o corpo da requisição de Chat Completions
+#[derive(Serialize)]
+struct ChatCompletionRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    model: Option<String>,
+    messages: Vec<ChatCompletionRequestMessage>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<ChatCompletionTool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<Value>,
+    #[serde(flatten)]
+    params: GenerationParams,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<Value>,
+}
+
+/// Mensagem no formato de Chat Completions
+#[derive(Serialize)]
+struct ChatCompletionRequestMessage {
+    role: &'static str,
+    /// `null` quando o assistente apenas pede chamadas de ferramentas
+    content: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tool_calls: Vec<ChatCompletionToolCall>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_call_id: Option<String>,
+}
+
+impl From<ChatMessage> for ChatCompletionRequestMessage {
+    fn from(message: ChatMessage) -> Self {
+        let content = if message.content.is_empty() && !message.tool_calls.is_empty() {
+            None
+        } else {
+            Some(message.content)
+        };
+
+        Self {
+            role: message.role.as_str(),
+            content,
+            tool_calls: message
+                .tool_calls
+                .into_iter()
+                .map(ChatCompletionToolCall::from)
+                .collect(),
+            tool_call_id: message.tool_call_id,
+        }
+    }
+}
+
+/// Definição de ferramenta no formato de Chat Completions
+#[derive(Serialize)]
+struct ChatCompletionTool {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    function: ToolDefinition,
+}
+
+/// Chamada de ferramenta no formato de Chat Completions
+#[derive(Serialize, Deserialize)]
+struct ChatCompletionToolCall {
+    id: String,
+    #[serde(rename = "type", default = "function_kind")]
+    kind: String,
+    function: ChatCompletionFunctionCall,
+}
+
+/// Nome e argumentos (em texto JSON) de uma chamada de função
+#[derive(Serialize, Deserialize)]
+struct ChatCompletionFunctionCall {
+    name: String,
+    #[serde(default)]
+    arguments: String,
+}
+
+/// Tipo padrão das chamadas de ferramentas.
+fn function_kind() -> String {
+    "function".to_string()
+}
+
+impl From<ToolCall> for ChatCompletionToolCall {
+    fn from(call: ToolCall) -> Self {
+        let arguments = match call.arguments {
+            Value::String(text) => text,
+            other => other.to_string(),
+        };
+
+        Self {
+            id: call.id,
+            kind: function_kind(),
+            function: ChatCompletionFunctionCall {
+                name: call.name,
+                arguments,
+            },
+        }
+    }
+}
+
+impl From<ChatCompletionToolCall> for ToolCall {
+    /// Os argumentos são convertidos para JSON; se o modelo gerar um texto
+    /// inválido, ele é mantido como string.
+    fn from(call: ChatCompletionToolCall) -> Self {
+        let text = call.function.arguments;
+        let arguments = if text.trim().is_empty() {
+            json!({})
+        } else {
+            serde_json::from_str(&text).unwrap_or(Value::String(text))
+        };
+
+        ToolCall {
+            id: call.id,
+            name: call.function.name,
+            arguments,
+        }
+    }
+}
+
+/// Estrutura para a resposta de Chat Completions
+#[derive(Deserialize)]
+struct ChatCompletionResponse {
+    id: Option<String>,
+    model: Option<String>,
+    choices: Vec<ChatCompletionChoice>,
+    usage: Option<ChatCompletionUsage>,
+}
+
+/// Resposta do endpoint de embeddings
+#[derive(Deserialize)]
+struct EmbeddingsResponse {
+    data: Vec<EmbeddingsData>,
+    model: Option<String>,
+    usage: Option<ChatCompletionUsage>,
+}
+
+/// Vetor de um texto na resposta de embeddings
+#[derive(Deserialize)]
+struct EmbeddingsData {
+    #[serde(default)]
+    index: usize,
+    embedding: Vec<f64>,
+}
+
+/// Estrutura para um item de escolha na resposta
+#[derive(Deserialize)]
+struct ChatCompletionChoice {
+    #[serde(default)]
+    index: usize,
+    message: ChatCompletionMessage,
+    finish_reason: Option<String>,
+}
+
+/// Estrutura para a mensagem dentro de um item de escolha na resposta
+#[derive(Deserialize)]
+struct ChatCompletionMessage {
+    content: Option<String>,
+    tool_calls: Option<Vec<ChatCompletionToolCall>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockHttpClient;
+
+    fn create_mock_response(body: Value) -> reqwest::Response {
+        reqwest::Response::from(
+            http::Response::builder()
+                .status(200)
+                .body(body.to_string())
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_request_headers_by_auth_scheme() {
+        let bearer = OpenAICompatibleConfig::new("groq", "https://api.groq.com/openai")
+            .with_api_key("chave")
+            .with_header("X-Extra", "1");
+        assert_eq!(
+            bearer.request_headers(),
+            vec![
+                ("Content-Type".to_string(), "application/json".to_string()),
+                ("Authorization".to_string(), "Bearer chave".to_string()),
+                ("X-Extra".to_string(), "1".to_string()),
+            ]
+        );
+
+        let header = bearer
+            .clone()
+            .with_auth(AuthScheme::Header("api-key".to_string()));
+        assert!(header
+            .request_headers()
+            .contains(&("api-key".to_string(), "chave".to_string())));
+
+        let none = bearer.with_auth(AuthScheme::None);
+        assert!(!none
+            .request_headers()
+            .iter()
+            .any(|(k, _)| k == "Authorization"));
+    }
+
+    #[tokio::test]
+    async fn test_compatible_agent_uses_configuration() {
+        let mut mock_client = MockHttpClient::new();
+
+        mock_client
+            .expect_post()
+            .withf(|url, body, headers| {
+                let parsed: Value = serde_json::from_slice(body).unwrap_or_default();
+                url == "http://localhost:8000/v1/chat/completions?api-version=1"
+                    && parsed["model"] == "llama"
+                    && parsed["temperature"] == 0.5
+                    && !headers.iter().any(|(k, _)| k == "Authorization")
+            })
+            .times(1)
+            .return_once(|_, _, _| {
+                Ok(create_mock_response(json!({
+                    "id": "cmpl-1",
+                    "choices": [{
+                        "message": { "role": "assistant", "content": "Olá!" },
+                        "finish_reason": "stop"
+                    }]
+                })))
+            });
+
+        let config = OpenAICompatibleConfig::new("vllm", "http://localhost:8000/")
+            .with_path("/v1/chat/completions?api-version=1")
+            .with_auth(AuthScheme::None)
+            .with_default_model("llama");
+        let agent = OpenAICompatibleAgent::new(config, Box::new(mock_client));
+
+        let message = MCPMessage::new(
+            "vllm:chat",
+            json!({ "user_prompt": "Olá", "temperature": 0.5 }),
+        );
+        let result = agent.process_request(message).await.unwrap();
+
+        assert_eq!(result.command, "vllm_response");
+        assert_eq!(
+            result.payload,
+            json!({ "answer": "Olá!", "id": "cmpl-1", "model": "llama", "finish_reason": "stop" })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compatible_agent_errors_use_configured_name() {
+        let mut mock_client = MockHttpClient::new();
+        mock_client.expect_post().return_once(|_, _, _| {
+            Ok(reqwest::Response::from(
+                http::Response::builder()
+                    .status(503)
+                    .body("Unavailable")
+                    .unwrap(),
+            ))
+        });
+
+        let agent = OpenAICompatibleAgent::new(
+            OpenAICompatibleConfig::new("together", "https://api.together.xyz"),
+            Box::new(mock_client),
+        );
+
+        let message = MCPMessage::new("together:chat", json!({ "user_prompt": "Olá" }));
+        let err = agent.process_request(message).await.unwrap_err();
+        assert_eq!(err.provider(), Some("together"));
+    }
+
+    #[tokio::test]
+    async fn test_compatible_agent_model_override() {
+        let mut mock_client = MockHttpClient::new();
+
+        mock_client
+            .expect_post()
+            .withf(|_, body, _| {
+                let parsed: Value = serde_json::from_slice(body).unwrap_or_default();
+                parsed["model"] == "llama-70b"
+            })
+            .times(1)
+            .return_once(|_, _, _| {
+                Ok(create_mock_response(json!({
+                    "model": "llama-70b-instruct",
+                    "choices": [{ "message": { "role": "assistant", "content": "Olá!" } }]
+                })))
+            });
+
+        let config = OpenAICompatibleConfig::new("vllm", "http://localhost:8000")
+            .with_default_model("llama-8b")
+            .with_allowed_models(["llama-70b"]);
+        let agent = OpenAICompatibleAgent::new(config, Box::new(mock_client));
+
+        let message = MCPMessage::new(
+            "vllm:chat",
+            json!({ "user_prompt": "Olá", "model": "llama-70b" }),
+        );
+        let result = agent.process_request(message).await.unwrap();
+
+        // O modelo informado pela API tem precedência sobre o pedido
+        assert_eq!(result.payload["model"], "llama-70b-instruct");
+    }
+
+    #[tokio::test]
+    async fn test_compatible_agent_rejects_model_outside_allow_list() {
+        let config = OpenAICompatibleConfig::new("vllm", "http://localhost:8000")
+            .with_default_model("llama-8b")
+            .with_allowed_models(["llama-70b"]);
+        let agent = OpenAICompatibleAgent::new(config, Box::new(MockHttpClient::new()));
+
+        let message = MCPMessage::new(
+            "vllm:chat",
+            json!({ "user_prompt": "Olá", "model": "gpt-4o" }),
+        );
+        let result = agent.process_request(message).await;
+        assert!(matches!(result, Err(MCPError::PayloadValidation(e)) if e.contains("gpt-4o")));
+    }
+
+    #[tokio::test]
+    async fn test_compatible_agent_returns_every_choice() {
+        let mut mock_client = MockHttpClient::new();
+
+        mock_client
+            .expect_post()
+            .withf(|_, body, _| {
+                let parsed: Value = serde_json::from_slice(body).unwrap_or_default();
+                parsed["n"] == 2
+            })
+            .times(1)
+            .return_once(|_, _, _| {
+                Ok(create_mock_response(json!({
+                    "choices": [
+                        { "index": 1, "message": { "content": "Oi!" }, "finish_reason": "stop" },
+                        { "index": 0, "message": { "content": "Olá!" }, "finish_reason": "stop" }
+                    ]
+                })))
+            });
+
+        let config = OpenAICompatibleConfig::new("vllm", "http://localhost:8000")
+            .with_default_model("llama");
+        let agent = OpenAICompatibleAgent::new(config, Box::new(mock_client));
+
+        let message = MCPMessage::new("vllm:chat", json!({ "user_prompt": "Olá", "n": 2 }));
+        let result = agent.process_request(message).await.unwrap();
+
+        assert_eq!(result.payload["answer"], "Olá!");
+        assert_eq!(result.payload["answers"], json!(["Olá!", "Oi!"]));
+    }
+
+    #[tokio::test]
+    async fn test_compatible_agent_stream_rejects_multiple_choices() {
+        let config = OpenAICompatibleConfig::new("vllm", "http://localhost:8000");
+        let agent = OpenAICompatibleAgent::new(config, Box::new(MockHttpClient::new()));
+
+        let message = MCPMessage::new("vllm:chat", json!({ "user_prompt": "Olá", "n": 3 }));
+        let result = agent.process_stream(message).await;
+        assert!(matches!(result, Err(MCPError::PayloadValidation(e)) if e.contains("'n'")));
+    }
+
+    #[tokio::test]
+    async fn test_compatible_agent_stream_rejects_embeddings() {
+        let config = OpenAICompatibleConfig::new("vllm", "http://localhost:8000")
+            .with_embedding_model("bge");
+        let agent = OpenAICompatibleAgent::new(config, Box::new(MockHttpClient::new()));
+
+        let message = MCPMessage::new("vllm:embeddings", json!({ "input": "Olá" }));
+        let result = agent.process_stream(message).await;
+        assert!(matches!(result, Err(MCPError::PayloadValidation(e)) if e.contains("streaming")));
+    }
+
+    #[tokio::test]
+    async fn test_compatible_agent_rejects_unknown_action() {
+        let config = OpenAICompatibleConfig::new("vllm", "http://localhost:8000");
+        let agent = OpenAICompatibleAgent::new(config, Box::new(MockHttpClient::new()));
+
+        let message = MCPMessage::new("vllm:embedings", json!({ "input": "Olá" }));
+        let result = agent.process_request(message.clone()).await;
+        assert!(matches!(result, Err(MCPError::PayloadValidation(e)) if e.contains("'embedings'")));
+
+        let result = agent.process_stream(message).await;
+        assert!(matches!(result, Err(MCPError::PayloadValidation(e)) if e.contains("'embedings'")));
+    }
+
+    #[tokio::test]
+    async fn test_compatible_agent_embedding_models_are_separate() {
+        let config = OpenAICompatibleConfig::new("vllm", "http://localhost:8000")
+            .with_default_model("llama-8b")
+            .with_allowed_models(["llama-70b"])
+            .with_embedding_model("bge-small")
+            .with_allowed_embedding_models(["bge-large"]);
+        let agent = OpenAICompatibleAgent::new(config, Box::new(MockHttpClient::new()));
+
+        // Um modelo de chat não pode ser usado em embeddings, e vice-versa
+        let message = MCPMessage::new(
+            "vllm:embeddings",
+            json!({ "input": "Olá", "model": "llama-70b" }),
+        );
+        let result = agent.process_request(message).await;
+        assert!(matches!(result, Err(MCPError::PayloadValidation(e)) if e.contains("llama-70b")));
+
+        let message = MCPMessage::new(
+            "vllm:chat",
+            json!({ "user_prompt": "Olá", "model": "bge-large" }),
+        );
+        let result = agent.process_request(message).await;
+        assert!(matches!(result, Err(MCPError::PayloadValidation(e)) if e.contains("bge-large")));
+    }
+
+    #[tokio::test]
+    async fn test_compatible_agent_stream_requests_usage() {
+        use futures::StreamExt;
+
+        let mut mock_client = MockHttpClient::new();
+
+        mock_client
+            .expect_post()
+            .withf(|_, body, _| {
+                let parsed: Value = serde_json::from_slice(body).unwrap_or_default();
+                parsed["stream"] == true
+                    && parsed["stream_options"] == json!({ "include_usage": true })
+            })
+            .times(1)
+            .return_once(|_, _, _| {
+                // O uso chega em um último chunk sem escolhas
+                let body = concat!(
+                    "data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Olá\"},\"finish_reason\":\"stop\"}]}\n\n",
+                    "data: {\"choices\":[],\"usage\":{\"prompt_tokens\":3,\"completion_tokens\":1,\"total_tokens\":4}}\n\n",
+                    "data: [DONE]\n\n"
+                );
+                Ok(reqwest::Response::from(
+                    http::Response::builder().status(200).body(body).unwrap(),
+                ))
+            });
+
+        let config = OpenAICompatibleConfig::new("vllm", "http://localhost:8000");
+        let agent = OpenAICompatibleAgent::new(config, Box::new(mock_client));
+
+        let message = MCPMessage::new("vllm:chat", json!({ "user_prompt": "Olá" }));
+        let mut stream = agent.process_stream(message).await.unwrap();
+
+        let mut usage = None;
+        while let Some(token) = stream.next().await {
+            let token = token.unwrap();
+            if token.is_finish {
+                break;
+            }
+            if let Some(metadata) = token.metadata {
+                usage = metadata.get("usage").cloned().or(usage);
+            }
+        }
+
+        assert_eq!(
+            usage.unwrap(),
+            json!({ "input_tokens": 3, "output_tokens": 1, "total_tokens": 4 })
+        );
+    }
+}
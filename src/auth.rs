@@ -19,18 +19,26 @@
 //! let is_valid = auth_config.is_valid_token("seu-token-secreto");
 //! assert!(is_valid);
 //! ```
+//!
+//! ## Integração com Axum
+//!
+//! O servidor avançado ([`crate::server::run_http_server_with_auth`]) instala um
+//! middleware que valida o token Bearer contra a `AuthConfig` configurada e
+//! rejeita a requisição com `401 Unauthorized` quando o token é desconhecido.
+//! O [`AuthUser`] resolvido fica disponível para os handlers como extrator.
 
 use axum::{
-    extract::{FromRequestParts, TypedHeader},
-    headers::{authorization::Bearer, Authorization},
-    http::{request::Parts, StatusCode},
+    extract::FromRequestParts,
+    http::{header::AUTHORIZATION, request::Parts, HeaderMap},
     response::{IntoResponse, Response},
-    Json,
 };
 use serde::Serialize;
+use sha2::{Digest, Sha256};
 use std::collections::HashSet;
 use std::sync::{Arc, RwLock};
 
+use crate::agent::MCPError;
+
 /// Representa um usuário autenticado após validação do token.
 ///
 /// Esta estrutura é utilizada como extrator em rotas protegidas do Axum.
@@ -40,6 +48,26 @@ pub struct AuthUser {
     pub token: String,
 }
 
+impl AuthUser {
+    /// Retorna um identificador estável do usuário derivado do token.
+    ///
+    /// O identificador é o hash SHA-256 do token em hexadecimal, permitindo
+    /// associar recursos (como conversas) ao usuário sem armazenar o token.
+    ///
+    /// # Exemplo
+    ///
+    /// ```
+    /// use mcprs::auth::AuthUser;
+    ///
+    /// let user = AuthUser { token: "token-secreto".to_string() };
+    /// assert_eq!(user.subject().len(), 64);
+    /// assert!(!user.subject().contains("token-secreto"));
+    /// ```
+    pub fn subject(&self) -> String {
+        format!("{:x}", Sha256::digest(self.token.as_bytes()))
+    }
+}
+
 /// Configuração de autenticação para o servidor MCP.
 ///
 /// Mantém um conjunto de tokens válidos e fornece métodos para
@@ -111,6 +139,37 @@ impl AuthConfig {
             false
         }
     }
+
+    /// Valida um token e retorna o usuário autenticado correspondente.
+    ///
+    /// # Argumentos
+    /// * `token` - O token Bearer recebido na requisição
+    ///
+    /// # Retorna
+    /// * `Ok(AuthUser)` - Se o token estiver registrado
+    /// * `Err(AuthError)` - Se o token for desconhecido
+    ///
+    /// # Exemplo
+    ///
+    /// ```
+    /// use mcprs::auth::AuthConfig;
+    ///
+    /// let config = AuthConfig::new();
+    /// config.add_token("token123".to_string());
+    ///
+    /// let user = config.authenticate("token123").unwrap();
+    /// assert_eq!(user.token, "token123");
+    /// assert!(config.authenticate("outro-token").is_err());
+    /// ```
+    pub fn authenticate(&self, token: &str) -> Result<AuthUser, AuthError> {
+        if self.is_valid_token(token) {
+            Ok(AuthUser {
+                token: token.to_string(),
+            })
+        } else {
+            Err(AuthError::new("Token de autorização inválido"))
+        }
+    }
 }
 
 impl Default for AuthConfig {
@@ -123,22 +182,64 @@ impl Default for AuthConfig {
 ///
 /// Esta estrutura é usada para retornar respostas de erro
 /// quando a autenticação falha.
-#[derive(Serialize)]
+#[derive(Serialize, Debug)]
 pub struct AuthError {
     /// Mensagem de erro para o cliente
     message: String,
 }
 
+impl AuthError {
+    /// Cria um novo erro de autenticação com a mensagem informada.
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+
+    /// Retorna a mensagem de erro.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl From<AuthError> for MCPError {
+    fn from(error: AuthError) -> Self {
+        MCPError::Unauthorized(error.message)
+    }
+}
+
+/// Responde com `401 Unauthorized` e o mesmo envelope de erro JSON dos
+/// demais erros do servidor (veja [`MCPError::Unauthorized`]).
 impl IntoResponse for AuthError {
     fn into_response(self) -> Response {
-        (StatusCode::UNAUTHORIZED, Json(self)).into_response()
+        MCPError::from(self).into_response()
     }
 }
 
+/// Extrai o token Bearer do cabeçalho `Authorization` da requisição.
+///
+/// Retorna `None` se o cabeçalho estiver ausente ou não usar o esquema Bearer.
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| {
+            let (scheme, token) = value.split_once(' ')?;
+            if scheme.eq_ignore_ascii_case("bearer") {
+                Some(token.trim())
+            } else {
+                None
+            }
+        })
+        .filter(|token| !token.is_empty())
+}
+
 /// Implementação do extrator `AuthUser` para Axum.
 ///
-/// Este extrator pode ser usado em handlers Axum para exigir
-/// autenticação via token Bearer.
+/// Se o middleware de autenticação do servidor já resolveu o usuário, ele é
+/// reutilizado. Caso contrário, o token Bearer é validado contra a
+/// `AuthConfig` registrada via `Extension`; sem configuração disponível,
+/// a requisição é rejeitada.
 #[async_trait::async_trait]
 impl<S> FromRequestParts<S> for AuthUser
 where
@@ -146,27 +247,23 @@ where
 {
     type Rejection = AuthError;
 
-    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
-        // Tentativa de obter header de autorização
-        if let Ok(TypedHeader(Authorization(bearer))) =
-            TypedHeader::<Authorization<Bearer>>::from_request_parts(parts, _state).await
-        {
-            // Na versão simplificada, aceitamos qualquer token
-            Ok(AuthUser {
-                token: bearer.token().to_string(),
-            })
-        } else {
-            Err(AuthError {
-                message: "Token de autorização ausente ou inválido".into(),
-            })
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        // Usuário já resolvido pelo middleware de autenticação
+        if let Some(user) = parts.extensions.get::<AuthUser>() {
+            return Ok(user.clone());
         }
+
+        let config = AuthConfig::from_request_parts(parts, state).await?;
+        let token = bearer_token(&parts.headers)
+            .ok_or_else(|| AuthError::new("Token de autorização ausente ou inválido"))?;
+
+        config.authenticate(token)
     }
 }
 
 /// Implementação do extrator `AuthConfig` para Axum.
 ///
-/// Este extrator é usado internamente para obter a configuração
-/// de autenticação a partir do estado do aplicativo.
+/// Obtém a configuração registrada no roteador via `Extension(auth_config)`.
 #[async_trait::async_trait]
 impl<S> FromRequestParts<S> for AuthConfig
 where
@@ -174,9 +271,12 @@ where
 {
     type Rejection = AuthError;
 
-    async fn from_request_parts(_parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
-        // Versão simplificada - lógica real seria implementada com Extension
-        Ok(AuthConfig::new())
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<AuthConfig>()
+            .cloned()
+            .ok_or_else(|| AuthError::new("Autenticação não configurada no servidor"))
     }
 }
 
@@ -184,6 +284,22 @@ where
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_auth_user_subject_is_stable_per_token() {
+        let user = AuthUser {
+            token: "token-a".to_string(),
+        };
+        let same = AuthUser {
+            token: "token-a".to_string(),
+        };
+        let other = AuthUser {
+            token: "token-b".to_string(),
+        };
+
+        assert_eq!(user.subject(), same.subject());
+        assert_ne!(user.subject(), other.subject());
+    }
+
     #[test]
     fn test_auth_config_add_and_validate_token() {
         let config = AuthConfig::new();
@@ -219,9 +335,36 @@ mod tests {
         };
 
         let response = error.into_response();
-        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.status(), axum::http::StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn test_bearer_token_parsing() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(bearer_token(&headers), None);
+
+        headers.insert(AUTHORIZATION, "Bearer abc123".parse().unwrap());
+        assert_eq!(bearer_token(&headers), Some("abc123"));
+
+        headers.insert(AUTHORIZATION, "bearer abc123".parse().unwrap());
+        assert_eq!(bearer_token(&headers), Some("abc123"));
+
+        headers.insert(AUTHORIZATION, "Basic dXNlcjpwYXNz".parse().unwrap());
+        assert_eq!(bearer_token(&headers), None);
+
+        headers.insert(AUTHORIZATION, "Bearer ".parse().unwrap());
+        assert_eq!(bearer_token(&headers), None);
+    }
+
+    #[test]
+    fn test_authenticate_unknown_token() {
+        let config = AuthConfig::new();
+        config.add_token("valid".to_string());
+
+        assert_eq!(config.authenticate("valid").unwrap().token, "valid");
+        assert!(config.authenticate("unknown").is_err());
     }
 
-    // Testes mais avançados envolvendo os extractors necessitariam de um ambiente
-    // de teste Axum, o que está fora do escopo destes testes unitários simples.
+    // Os testes end-to-end dos extratores, passando pelo roteador Axum,
+    // ficam no módulo `server`.
 }
@@ -0,0 +1,1112 @@
+//! # Modelo de Requisição de Chat
+//!
+//! Este módulo define o modelo tipado compartilhado por todos os agentes para
+//! interpretar payloads MCP de chat. Assim, cada provedor recebe exatamente a
+//! mesma sequência de mensagens, independentemente do formato usado pelo cliente.
+//!
+//! ## Formato do Payload
+//!
+//! O payload de um comando de chat aceita os seguintes campos:
+//!
+//! - `system_prompt` (opcional): instrução de sistema, inserida no início da conversa
+//! - `messages` (opcional): lista de mensagens `{ "role": ..., "content": ... }`
+//!   com papéis `system`, `user`, `assistant` ou `tool`
+//! - `user_prompt` (opcional): atalho para uma mensagem final com papel `user`
+//!
+//! Pelo menos uma mensagem que não seja de sistema deve estar presente.
+//!
+//! ## Parâmetros de Geração
+//!
+//! Os parâmetros de amostragem são lidos por [`GenerationParams::from_payload`]
+//! com os mesmos nomes em todos os provedores: `temperature`, `top_p`,
+//! `max_tokens`, `stop`, `seed`, `presence_penalty`, `frequency_penalty`, `n`,
+//! `logit_bias` e `user`. Cada agente converte esses campos para os nomes do
+//! seu provedor e rejeita com `MCPError::PayloadValidation` os que o provedor
+//! não suporta, em vez de ignorá-los.
+//!
+//! ## Ferramentas
+//!
+//! O campo `tools` lista as ferramentas que o modelo pode chamar, cada uma com
+//! `name`, `description` e um JSON Schema em `parameters` (veja
+//! [`ToolDefinition`]), e `tool_choice` controla a escolha (veja [`ToolChoice`]).
+//! As chamadas pedidas pelo modelo voltam na resposta em `tool_calls`, no
+//! formato `{id, name, arguments}` de [`ToolCall`]. Para continuar a conversa,
+//! o cliente envia a mensagem do assistente com os `tool_calls` recebidos e uma
+//! mensagem `{"role": "tool", "tool_call_id": ..., "content": ...}` por resultado.
+//!
+//! O campo `model` permite escolher outro modelo por requisição, desde que
+//! ele esteja na lista de modelos permitidos do agente (veja [`select_model`]).
+//!
+//! ## Exemplo de Uso
+//!
+//! ```rust
+//! use mcprs::chat::{ChatRequest, ChatRole};
+//! use serde_json::json;
+//!
+//! let payload = json!({
+//!     "system_prompt": "Você é um assistente conciso.",
+//!     "messages": [
+//!         { "role": "user", "content": "Olá!" },
+//!         { "role": "assistant", "content": "Olá! Como posso ajudar?" }
+//!     ],
+//!     "user_prompt": "O que é Rust?"
+//! });
+//!
+//! let request = ChatRequest::from_payload(&payload).unwrap();
+//! assert_eq!(request.messages.len(), 4);
+//! assert_eq!(request.messages[0].role, ChatRole::System);
+//! assert_eq!(request.messages[3].content, "O que é Rust?");
+//! ```
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use crate::agent::MCPError;
+use crate::structured::ResponseFormat;
+
+/// Papel do autor de uma mensagem de chat.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatRole {
+    /// Instruções de sistema que orientam o comportamento do modelo
+    System,
+
+    /// Mensagem enviada pelo usuário
+    User,
+
+    /// Resposta gerada pelo modelo
+    Assistant,
+
+    /// Resultado de uma chamada de ferramenta pedida pelo modelo
+    Tool,
+}
+
+impl ChatRole {
+    /// Retorna o nome do papel como usado nas APIs ("system", "user", "assistant", "tool").
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChatRole::System => "system",
+            ChatRole::User => "user",
+            ChatRole::Assistant => "assistant",
+            ChatRole::Tool => "tool",
+        }
+    }
+}
+
+impl std::str::FromStr for ChatRole {
+    type Err = MCPError;
+
+    fn from_str(role: &str) -> Result<Self, Self::Err> {
+        match role {
+            "system" => Ok(ChatRole::System),
+            "user" => Ok(ChatRole::User),
+            "assistant" => Ok(ChatRole::Assistant),
+            "tool" => Ok(ChatRole::Tool),
+            other => Err(MCPError::PayloadValidation(format!(
+                "Papel de mensagem inválido: '{}'",
+                other
+            ))),
+        }
+    }
+}
+
+/// Uma mensagem individual de uma conversa de chat.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ChatMessage {
+    /// Papel do autor da mensagem
+    pub role: ChatRole,
+
+    /// Conteúdo textual da mensagem (`null` é tratado como vazio)
+    #[serde(default, deserialize_with = "null_as_empty")]
+    pub content: String,
+
+    /// Ferramentas que o assistente pediu para chamar
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+
+    /// ID da chamada respondida por uma mensagem com papel `tool`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+impl ChatMessage {
+    /// Cria uma nova mensagem com o papel e conteúdo informados.
+    pub fn new(role: ChatRole, content: impl Into<String>) -> Self {
+        Self {
+            role,
+            content: content.into(),
+            tool_calls: Vec::new(),
+            tool_call_id: None,
+        }
+    }
+
+    /// Cria uma mensagem de sistema.
+    pub fn system(content: impl Into<String>) -> Self {
+        Self::new(ChatRole::System, content)
+    }
+
+    /// Cria uma mensagem do usuário.
+    pub fn user(content: impl Into<String>) -> Self {
+        Self::new(ChatRole::User, content)
+    }
+
+    /// Cria uma mensagem do assistente.
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self::new(ChatRole::Assistant, content)
+    }
+
+    /// Cria uma mensagem do assistente que pede chamadas de ferramentas.
+    pub fn assistant_tool_calls(content: impl Into<String>, tool_calls: Vec<ToolCall>) -> Self {
+        Self {
+            tool_calls,
+            ..Self::assistant(content)
+        }
+    }
+
+    /// Cria uma mensagem com o resultado de uma chamada de ferramenta.
+    ///
+    /// # Argumentos
+    /// * `tool_call_id` - O ID da chamada, como informado em [`ToolCall::id`]
+    /// * `content` - O resultado da ferramenta, normalmente em JSON
+    pub fn tool(tool_call_id: impl Into<String>, content: impl Into<String>) -> Self {
+        Self {
+            tool_call_id: Some(tool_call_id.into()),
+            ..Self::new(ChatRole::Tool, content)
+        }
+    }
+
+    /// Verifica a consistência entre o papel e os campos de ferramentas.
+    ///
+    /// # Erros
+    /// * Retorna `MCPError::PayloadValidation` se uma mensagem `tool` não tiver
+    ///   `tool_call_id`, ou se uma mensagem que não seja do assistente tiver `tool_calls`
+    pub fn validate(&self) -> Result<(), MCPError> {
+        if self.role == ChatRole::Tool && self.tool_call_id.is_none() {
+            return Err(MCPError::PayloadValidation(
+                "Mensagens com papel 'tool' devem informar 'tool_call_id'".to_string(),
+            ));
+        }
+        if self.role != ChatRole::Assistant && !self.tool_calls.is_empty() {
+            return Err(MCPError::PayloadValidation(
+                "Apenas mensagens do assistente podem ter 'tool_calls'".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Chamada de ferramenta pedida pelo modelo, no formato comum a todos os provedores.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ToolCall {
+    /// ID da chamada, usado em `tool_call_id` na mensagem de resultado
+    pub id: String,
+
+    /// Nome da ferramenta
+    pub name: String,
+
+    /// Argumentos gerados pelo modelo; se não forem JSON válido, o texto original
+    #[serde(default)]
+    pub arguments: Value,
+}
+
+/// Definição de uma ferramenta que o modelo pode chamar.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ToolDefinition {
+    /// Nome da ferramenta (letras, números, `_` e `-`, até 64 caracteres)
+    pub name: String,
+
+    /// Descrição usada pelo modelo para decidir quando chamar a ferramenta
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// JSON Schema dos argumentos (padrão: objeto sem propriedades)
+    #[serde(default = "empty_object_schema")]
+    pub parameters: Value,
+}
+
+/// Como o modelo deve escolher as ferramentas.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToolChoice {
+    /// O modelo decide se chama alguma ferramenta (`"auto"`)
+    Auto,
+
+    /// O modelo não deve chamar ferramentas (`"none"`)
+    None,
+
+    /// O modelo deve chamar ao menos uma ferramenta (`"required"`)
+    Required,
+
+    /// O modelo deve chamar a ferramenta informada (`{"name": "..."}`)
+    Tool(String),
+}
+
+impl ToolChoice {
+    /// Interpreta o campo `tool_choice` do payload.
+    fn from_value(value: &Value) -> Result<Self, MCPError> {
+        match value {
+            Value::String(choice) => match choice.as_str() {
+                "auto" => Ok(ToolChoice::Auto),
+                "none" => Ok(ToolChoice::None),
+                "required" => Ok(ToolChoice::Required),
+                other => Err(MCPError::PayloadValidation(format!(
+                    "Valor inválido para 'tool_choice': '{}'",
+                    other
+                ))),
+            },
+            Value::Object(choice) => choice
+                .get("name")
+                .and_then(Value::as_str)
+                .map(|name| ToolChoice::Tool(name.to_string()))
+                .ok_or_else(|| {
+                    MCPError::PayloadValidation(
+                        "Campo 'tool_choice' deve ter o nome da ferramenta em 'name'".to_string(),
+                    )
+                }),
+            _ => Err(MCPError::PayloadValidation(
+                "Campo 'tool_choice' deve ser \"auto\", \"none\", \"required\" ou {\"name\": ...}"
+                    .to_string(),
+            )),
+        }
+    }
+}
+
+/// Requisição de chat normalizada a partir de um payload MCP.
+///
+/// Todos os agentes devem usar [`ChatRequest::from_payload`] para interpretar
+/// o payload, garantindo que `system_prompt`, `messages` e `user_prompt`
+/// sejam combinados da mesma forma em todos os provedores.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatRequest {
+    /// Mensagens na ordem em que devem ser enviadas ao modelo
+    pub messages: Vec<ChatMessage>,
+
+    /// Ferramentas que o modelo pode chamar
+    pub tools: Vec<ToolDefinition>,
+
+    /// Como o modelo deve escolher as ferramentas, se informado
+    pub tool_choice: Option<ToolChoice>,
+
+    /// Formato pedido para a resposta, se informado
+    pub response_format: Option<ResponseFormat>,
+}
+
+impl ChatRequest {
+    /// Interpreta um payload MCP de chat.
+    ///
+    /// A ordem resultante é: `system_prompt` (se houver), seguido das
+    /// mensagens de `messages` e, por fim, `user_prompt` como mensagem do usuário.
+    ///
+    /// # Argumentos
+    /// * `payload` - O payload JSON da mensagem MCP
+    ///
+    /// As ferramentas são lidas de `tools` (lista de [`ToolDefinition`]) e
+    /// `tool_choice` (veja [`ToolChoice`]), e o formato da resposta de
+    /// `response_format` (veja [`ResponseFormat`]).
+    ///
+    /// # Erros
+    /// * Retorna `MCPError::PayloadValidation` se:
+    ///   - Nenhuma mensagem de usuário ou assistente for informada
+    ///   - `messages` não for uma lista de objetos `{role, content}` válidos
+    ///   - Uma mensagem `tool` não tiver `tool_call_id`, ou uma mensagem que não
+    ///     seja do assistente tiver `tool_calls`
+    ///   - `system_prompt` ou `user_prompt` não forem strings
+    ///   - `tools`, `tool_choice` ou `response_format` forem inválidos
+    pub fn from_payload(payload: &Value) -> Result<Self, MCPError> {
+        let mut messages = Vec::new();
+
+        if let Some(system_prompt) = optional_str(payload, "system_prompt")? {
+            messages.push(ChatMessage::system(system_prompt));
+        }
+
+        if let Some(raw_messages) = payload.get("messages").filter(|v| !v.is_null()) {
+            let parsed: Vec<ChatMessage> =
+                serde_json::from_value(raw_messages.clone()).map_err(|e| {
+                    MCPError::PayloadValidation(format!("Campo 'messages' inválido: {}", e))
+                })?;
+            messages.extend(parsed);
+        }
+
+        if let Some(user_prompt) = optional_str(payload, "user_prompt")? {
+            messages.push(ChatMessage::user(user_prompt));
+        }
+
+        if messages.iter().all(|m| m.role == ChatRole::System) {
+            return Err(MCPError::PayloadValidation(
+                "Missing user_prompt or messages".to_string(),
+            ));
+        }
+
+        for message in &messages {
+            message.validate()?;
+        }
+
+        let tools = parse_tools(payload)?;
+        let tool_choice = field(payload, "tool_choice")
+            .map(ToolChoice::from_value)
+            .transpose()?;
+
+        match &tool_choice {
+            Some(_) if tools.is_empty() => {
+                return Err(MCPError::PayloadValidation(
+                    "Campo 'tool_choice' exige 'tools'".to_string(),
+                ))
+            }
+            Some(ToolChoice::Tool(name)) if !tools.iter().any(|t| &t.name == name) => {
+                return Err(MCPError::PayloadValidation(format!(
+                    "Ferramenta '{}' de 'tool_choice' não está em 'tools'",
+                    name
+                )))
+            }
+            _ => {}
+        }
+
+        Ok(Self {
+            messages,
+            tools,
+            tool_choice,
+            response_format: ResponseFormat::from_payload(payload)?,
+        })
+    }
+
+    /// Acrescenta uma instrução de sistema depois das já existentes.
+    ///
+    /// Usado pelos agentes para pedir JSON a provedores sem modo JSON nativo
+    /// (veja [`ResponseFormat::instructions`]).
+    pub fn add_system_instruction(&mut self, instruction: impl Into<String>) {
+        let position = self
+            .messages
+            .iter()
+            .position(|m| m.role != ChatRole::System)
+            .unwrap_or(self.messages.len());
+        self.messages
+            .insert(position, ChatMessage::system(instruction));
+    }
+
+    /// Indica se a requisição usa ferramentas, seja por definições em
+    /// `tools` ou por chamadas e resultados no histórico.
+    pub fn uses_tools(&self) -> bool {
+        !self.tools.is_empty()
+            || self
+                .messages
+                .iter()
+                .any(|m| m.role == ChatRole::Tool || !m.tool_calls.is_empty())
+    }
+
+    /// Rejeita requisições com ferramentas em provedores que não as suportam.
+    ///
+    /// # Erros
+    /// * Retorna `MCPError::PayloadValidation` se [`uses_tools`](Self::uses_tools) for verdadeiro
+    pub fn ensure_no_tools(&self, provider: &str) -> Result<(), MCPError> {
+        if self.uses_tools() {
+            return Err(MCPError::PayloadValidation(format!(
+                "O provedor '{}' não suporta chamadas de ferramentas",
+                provider
+            )));
+        }
+        Ok(())
+    }
+
+    /// Separa as instruções de sistema das demais mensagens.
+    ///
+    /// Útil para provedores que recebem o prompt de sistema em um campo
+    /// próprio. Múltiplas mensagens de sistema são concatenadas com uma linha
+    /// em branco entre elas.
+    ///
+    /// # Retorna
+    /// Uma tupla com o prompt de sistema combinado (se houver) e as mensagens restantes
+    ///
+    /// # Exemplo
+    ///
+    /// ```
+    /// use mcprs::chat::ChatRequest;
+    /// use serde_json::json;
+    ///
+    /// let request = ChatRequest::from_payload(&json!({
+    ///     "system_prompt": "Seja breve.",
+    ///     "user_prompt": "Olá"
+    /// }))
+    /// .unwrap();
+    ///
+    /// let (system, messages) = request.split_system();
+    /// assert_eq!(system.as_deref(), Some("Seja breve."));
+    /// assert_eq!(messages.len(), 1);
+    /// ```
+    pub fn split_system(&self) -> (Option<String>, Vec<ChatMessage>) {
+        let (system, rest): (Vec<&ChatMessage>, Vec<&ChatMessage>) = self
+            .messages
+            .iter()
+            .partition(|m| m.role == ChatRole::System);
+
+        let system_prompt = if system.is_empty() {
+            None
+        } else {
+            Some(
+                system
+                    .iter()
+                    .map(|m| m.content.as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n\n"),
+            )
+        };
+
+        (system_prompt, rest.into_iter().cloned().collect())
+    }
+}
+
+/// Parâmetros de geração comuns a todos os provedores.
+///
+/// Os campos são opcionais e validados em [`GenerationParams::from_payload`].
+/// A serialização usa os nomes da API de Chat Completions da OpenAI e omite
+/// os campos não informados.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct GenerationParams {
+    /// Temperatura de amostragem, entre 0 e 2
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+
+    /// Amostragem por núcleo (nucleus sampling), entre 0 e 1
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f64>,
+
+    /// Limite de tokens na resposta (inteiro positivo)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+
+    /// Sequências de parada; no payload aceita uma string ou lista de strings
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop: Option<Vec<String>>,
+
+    /// Semente para amostragem reprodutível
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+
+    /// Penalidade de presença, entre -2 e 2
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f64>,
+
+    /// Penalidade de frequência, entre -2 e 2
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f64>,
+
+    /// Número de respostas alternativas a gerar (inteiro positivo)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub n: Option<u32>,
+
+    /// Viés por ID de token, com valores entre -100 e 100
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logit_bias: Option<BTreeMap<String, f64>>,
+
+    /// Identificador do usuário final, repassado ao provedor para monitoramento de abuso
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+}
+
+impl GenerationParams {
+    /// Nomes de todos os parâmetros, na ordem em que são declarados.
+    pub const ALL: [&'static str; 10] = [
+        "temperature",
+        "top_p",
+        "max_tokens",
+        "stop",
+        "seed",
+        "presence_penalty",
+        "frequency_penalty",
+        "n",
+        "logit_bias",
+        "user",
+    ];
+
+    /// Lê e valida os parâmetros de geração do payload.
+    ///
+    /// Campos ausentes ou `null` são tratados como não informados.
+    ///
+    /// # Erros
+    /// * Retorna `MCPError::PayloadValidation` se algum campo tiver o tipo
+    ///   errado ou estiver fora do intervalo permitido
+    ///
+    /// # Exemplo
+    ///
+    /// ```
+    /// use mcprs::chat::GenerationParams;
+    /// use serde_json::json;
+    ///
+    /// let params = GenerationParams::from_payload(&json!({
+    ///     "user_prompt": "Olá",
+    ///     "temperature": 0.2,
+    ///     "stop": "FIM"
+    /// }))
+    /// .unwrap();
+    ///
+    /// assert_eq!(params.temperature, Some(0.2));
+    /// assert_eq!(params.stop, Some(vec!["FIM".to_string()]));
+    /// assert_eq!(params.provided(), vec!["temperature", "stop"]);
+    ///
+    /// assert!(GenerationParams::from_payload(&json!({ "top_p": 1.5 })).is_err());
+    /// ```
+    pub fn from_payload(payload: &Value) -> Result<Self, MCPError> {
+        let logit_bias = match field(payload, "logit_bias") {
+            None => None,
+            Some(Value::Object(entries)) => Some(
+                entries
+                    .iter()
+                    .map(|(token, bias)| {
+                        bias.as_f64()
+                            .filter(|b| (-100.0..=100.0).contains(b))
+                            .map(|b| (token.clone(), b))
+                            .ok_or_else(|| invalid_param("logit_bias", "valores entre -100 e 100"))
+                    })
+                    .collect::<Result<BTreeMap<_, _>, _>>()?,
+            ),
+            Some(_) => return Err(invalid_param("logit_bias", "um objeto")),
+        };
+
+        let stop = match field(payload, "stop") {
+            None => None,
+            Some(Value::String(stop)) => Some(vec![stop.clone()]),
+            Some(Value::Array(items)) => Some(
+                items
+                    .iter()
+                    .map(|item| item.as_str().map(str::to_string))
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| invalid_param("stop", "uma string ou lista de strings"))?,
+            ),
+            Some(_) => return Err(invalid_param("stop", "uma string ou lista de strings")),
+        };
+
+        Ok(Self {
+            temperature: number_in_range(payload, "temperature", 0.0, 2.0)?,
+            top_p: number_in_range(payload, "top_p", 0.0, 1.0)?,
+            max_tokens: positive_integer(payload, "max_tokens")?,
+            stop,
+            seed: match field(payload, "seed") {
+                None => None,
+                Some(seed) => Some(
+                    seed.as_i64()
+                        .ok_or_else(|| invalid_param("seed", "um inteiro"))?,
+                ),
+            },
+            presence_penalty: number_in_range(payload, "presence_penalty", -2.0, 2.0)?,
+            frequency_penalty: number_in_range(payload, "frequency_penalty", -2.0, 2.0)?,
+            n: positive_integer(payload, "n")?,
+            logit_bias,
+            user: optional_str(payload, "user")?.map(str::to_string),
+        })
+    }
+
+    /// Nomes dos parâmetros informados, na ordem de [`GenerationParams::ALL`].
+    pub fn provided(&self) -> Vec<&'static str> {
+        let present = [
+            self.temperature.is_some(),
+            self.top_p.is_some(),
+            self.max_tokens.is_some(),
+            self.stop.is_some(),
+            self.seed.is_some(),
+            self.presence_penalty.is_some(),
+            self.frequency_penalty.is_some(),
+            self.n.is_some(),
+            self.logit_bias.is_some(),
+            self.user.is_some(),
+        ];
+
+        Self::ALL
+            .iter()
+            .zip(present)
+            .filter(|(_, present)| *present)
+            .map(|(name, _)| *name)
+            .collect()
+    }
+
+    /// Rejeita os parâmetros informados que o provedor não suporta.
+    ///
+    /// # Argumentos
+    /// * `provider` - Nome do provedor, usado na mensagem de erro
+    /// * `supported` - Parâmetros que o provedor aceita
+    ///
+    /// # Erros
+    /// * Retorna `MCPError::PayloadValidation` listando os parâmetros não suportados
+    pub fn ensure_supported(&self, provider: &str, supported: &[&str]) -> Result<(), MCPError> {
+        let unsupported: Vec<&str> = self
+            .provided()
+            .into_iter()
+            .filter(|name| !supported.contains(name))
+            .collect();
+
+        if unsupported.is_empty() {
+            Ok(())
+        } else {
+            Err(MCPError::PayloadValidation(format!(
+                "Parâmetros não suportados pelo provedor '{}': {}",
+                provider,
+                unsupported.join(", ")
+            )))
+        }
+    }
+
+    /// Rejeita `n` maior que 1 em requisições de streaming.
+    ///
+    /// O stream emite o texto de uma única resposta, então pedir mais
+    /// respostas ao provedor apenas geraria custo sem retorno ao cliente.
+    ///
+    /// # Erros
+    /// * Retorna `MCPError::PayloadValidation` se `n` for maior que 1
+    ///
+    /// # Exemplo
+    ///
+    /// ```
+    /// use mcprs::chat::GenerationParams;
+    /// use serde_json::json;
+    ///
+    /// let params = GenerationParams::from_payload(&json!({ "n": 2 })).unwrap();
+    /// assert!(params.ensure_single_choice().is_err());
+    /// assert!(GenerationParams::default().ensure_single_choice().is_ok());
+    /// ```
+    pub fn ensure_single_choice(&self) -> Result<(), MCPError> {
+        match self.n {
+            Some(n) if n > 1 => Err(MCPError::PayloadValidation(
+                "O parâmetro 'n' maior que 1 não é suportado em modo streaming".to_string(),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Escolhe o modelo da requisição a partir do campo `model` do payload.
+///
+/// Sem `model` no payload, o modelo padrão do agente é usado. Um modelo
+/// informado só é aceito se for o padrão ou estiver na lista de modelos
+/// permitidos configurada no agente.
+///
+/// # Argumentos
+/// * `payload` - O payload JSON da mensagem MCP
+/// * `default` - Modelo padrão do agente, se houver
+/// * `allowed` - Modelos adicionais que podem ser pedidos no payload
+///
+/// # Retorna
+/// O modelo a ser usado, ou `None` se não houver padrão nem `model` no payload
+///
+/// # Erros
+/// * Retorna `MCPError::PayloadValidation` se `model` não for uma string ou
+///   não estiver entre os modelos permitidos
+///
+/// # Exemplo
+///
+/// ```
+/// use mcprs::chat::select_model;
+/// use serde_json::json;
+///
+/// let allowed = vec!["gpt-4o".to_string()];
+///
+/// let payload = json!({ "user_prompt": "Olá" });
+/// let model = select_model(&payload, Some("gpt-4o-mini"), &allowed).unwrap();
+/// assert_eq!(model, Some("gpt-4o-mini"));
+///
+/// let payload = json!({ "user_prompt": "Olá", "model": "gpt-4o" });
+/// let model = select_model(&payload, Some("gpt-4o-mini"), &allowed).unwrap();
+/// assert_eq!(model, Some("gpt-4o"));
+///
+/// let payload = json!({ "user_prompt": "Olá", "model": "o1" });
+/// assert!(select_model(&payload, Some("gpt-4o-mini"), &allowed).is_err());
+/// ```
+pub fn select_model<'a>(
+    payload: &'a Value,
+    default: Option<&'a str>,
+    allowed: &'a [String],
+) -> Result<Option<&'a str>, MCPError> {
+    match field(payload, "model") {
+        None => Ok(default),
+        Some(Value::String(model)) => {
+            if default == Some(model.as_str()) || allowed.contains(model) {
+                Ok(Some(model))
+            } else {
+                Err(MCPError::PayloadValidation(format!(
+                    "Modelo '{}' não está entre os modelos permitidos",
+                    model
+                )))
+            }
+        }
+        Some(_) => Err(MCPError::PayloadValidation(
+            "Campo 'model' deve ser uma string".to_string(),
+        )),
+    }
+}
+
+/// Lê uma lista de modelos separados por vírgula, como em `OPENAI_ALLOWED_MODELS`.
+pub(crate) fn parse_model_list(models: &str) -> Vec<String> {
+    models
+        .split(',')
+        .map(str::trim)
+        .filter(|m| !m.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Lê e valida as definições de ferramentas do campo `tools`.
+fn parse_tools(payload: &Value) -> Result<Vec<ToolDefinition>, MCPError> {
+    let Some(raw_tools) = field(payload, "tools") else {
+        return Ok(Vec::new());
+    };
+
+    let tools: Vec<ToolDefinition> = serde_json::from_value(raw_tools.clone())
+        .map_err(|e| MCPError::PayloadValidation(format!("Campo 'tools' inválido: {}", e)))?;
+
+    for (i, tool) in tools.iter().enumerate() {
+        let valid_name = !tool.name.is_empty()
+            && tool.name.len() <= 64
+            && tool
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if !valid_name {
+            return Err(MCPError::PayloadValidation(format!(
+                "Nome de ferramenta inválido: '{}'",
+                tool.name
+            )));
+        }
+        if !tool.parameters.is_object() {
+            return Err(MCPError::PayloadValidation(format!(
+                "Campo 'parameters' da ferramenta '{}' deve ser um JSON Schema",
+                tool.name
+            )));
+        }
+        if tools[..i].iter().any(|t| t.name == tool.name) {
+            return Err(MCPError::PayloadValidation(format!(
+                "Ferramenta duplicada: '{}'",
+                tool.name
+            )));
+        }
+    }
+
+    Ok(tools)
+}
+
+/// Schema padrão de uma ferramenta sem argumentos.
+fn empty_object_schema() -> Value {
+    serde_json::json!({ "type": "object", "properties": {} })
+}
+
+/// Desserializa `null` como texto vazio.
+fn null_as_empty<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer).map(Option::unwrap_or_default)
+}
+
+/// Lê um campo do payload, tratando `null` como ausente.
+fn field<'a>(payload: &'a Value, name: &str) -> Option<&'a Value> {
+    payload.get(name).filter(|v| !v.is_null())
+}
+
+/// Erro de validação para um parâmetro de geração.
+fn invalid_param(name: &str, expected: &str) -> MCPError {
+    MCPError::PayloadValidation(format!("Campo '{}' deve ser {}", name, expected))
+}
+
+/// Lê um número opcional dentro do intervalo fechado `[min, max]`.
+fn number_in_range(
+    payload: &Value,
+    name: &str,
+    min: f64,
+    max: f64,
+) -> Result<Option<f64>, MCPError> {
+    match field(payload, name) {
+        None => Ok(None),
+        Some(value) => value
+            .as_f64()
+            .filter(|v| (min..=max).contains(v))
+            .map(Some)
+            .ok_or_else(|| invalid_param(name, &format!("um número entre {} e {}", min, max))),
+    }
+}
+
+/// Lê um inteiro positivo opcional.
+fn positive_integer(payload: &Value, name: &str) -> Result<Option<u32>, MCPError> {
+    match field(payload, name) {
+        None => Ok(None),
+        Some(value) => value
+            .as_u64()
+            .filter(|&v| v > 0)
+            .and_then(|v| u32::try_from(v).ok())
+            .map(Some)
+            .ok_or_else(|| invalid_param(name, "um inteiro positivo")),
+    }
+}
+
+/// Lê um campo opcional de texto do payload, rejeitando tipos diferentes de string.
+fn optional_str<'a>(payload: &'a Value, field: &str) -> Result<Option<&'a str>, MCPError> {
+    match payload.get(field) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(s)) => Ok(Some(s)),
+        Some(_) => Err(MCPError::PayloadValidation(format!(
+            "Campo '{}' deve ser uma string",
+            field
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_user_prompt_shorthand() {
+        let request = ChatRequest::from_payload(&json!({ "user_prompt": "Olá" })).unwrap();
+        assert_eq!(request.messages, vec![ChatMessage::user("Olá")]);
+    }
+
+    #[test]
+    fn test_full_payload_ordering() {
+        let request = ChatRequest::from_payload(&json!({
+            "system_prompt": "Sistema",
+            "messages": [
+                { "role": "user", "content": "Pergunta 1" },
+                { "role": "assistant", "content": "Resposta 1" }
+            ],
+            "user_prompt": "Pergunta 2"
+        }))
+        .unwrap();
+
+        assert_eq!(
+            request.messages,
+            vec![
+                ChatMessage::system("Sistema"),
+                ChatMessage::user("Pergunta 1"),
+                ChatMessage::assistant("Resposta 1"),
+                ChatMessage::user("Pergunta 2"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_missing_messages() {
+        let err = ChatRequest::from_payload(&json!({})).unwrap_err();
+        assert!(matches!(err, MCPError::PayloadValidation(e) if e.contains("Missing user_prompt")));
+
+        // Apenas o prompt de sistema não é suficiente
+        let err = ChatRequest::from_payload(&json!({ "system_prompt": "Sistema" })).unwrap_err();
+        assert!(matches!(err, MCPError::PayloadValidation(e) if e.contains("Missing user_prompt")));
+    }
+
+    #[test]
+    fn test_invalid_role() {
+        let err = ChatRequest::from_payload(&json!({
+            "messages": [{ "role": "robot", "content": "beep" }]
+        }))
+        .unwrap_err();
+        assert!(matches!(err, MCPError::PayloadValidation(e) if e.contains("messages")));
+    }
+
+    #[test]
+    fn test_non_string_prompt() {
+        let err = ChatRequest::from_payload(&json!({ "user_prompt": 42 })).unwrap_err();
+        assert!(matches!(err, MCPError::PayloadValidation(e) if e.contains("user_prompt")));
+    }
+
+    #[test]
+    fn test_split_system() {
+        let request = ChatRequest::from_payload(&json!({
+            "system_prompt": "Regra 1",
+            "messages": [
+                { "role": "system", "content": "Regra 2" },
+                { "role": "user", "content": "Olá" }
+            ]
+        }))
+        .unwrap();
+
+        let (system, messages) = request.split_system();
+        assert_eq!(system.as_deref(), Some("Regra 1\n\nRegra 2"));
+        assert_eq!(messages, vec![ChatMessage::user("Olá")]);
+    }
+
+    #[test]
+    fn test_generation_params_parsing() {
+        let params = GenerationParams::from_payload(&json!({
+            "user_prompt": "Olá",
+            "temperature": 0.7,
+            "top_p": 0.9,
+            "max_tokens": 100,
+            "stop": ["FIM", "###"],
+            "seed": 42,
+            "presence_penalty": -0.5,
+            "frequency_penalty": 1.0,
+            "n": 2,
+            "logit_bias": { "50256": -100 },
+            "user": "usuario-1",
+            "top_k": null
+        }))
+        .unwrap();
+
+        assert_eq!(params.provided(), GenerationParams::ALL.to_vec());
+        assert_eq!(
+            serde_json::to_value(&params).unwrap(),
+            json!({
+                "temperature": 0.7,
+                "top_p": 0.9,
+                "max_tokens": 100,
+                "stop": ["FIM", "###"],
+                "seed": 42,
+                "presence_penalty": -0.5,
+                "frequency_penalty": 1.0,
+                "n": 2,
+                "logit_bias": { "50256": -100.0 },
+                "user": "usuario-1"
+            })
+        );
+
+        // Sem parâmetros, nada é serializado
+        let empty = GenerationParams::from_payload(&json!({ "temperature": null })).unwrap();
+        assert_eq!(empty, GenerationParams::default());
+        assert_eq!(serde_json::to_value(&empty).unwrap(), json!({}));
+    }
+
+    #[test]
+    fn test_generation_params_validation() {
+        for (payload, field) in [
+            (json!({ "temperature": 2.5 }), "temperature"),
+            (json!({ "temperature": "quente" }), "temperature"),
+            (json!({ "top_p": -0.1 }), "top_p"),
+            (json!({ "max_tokens": 0 }), "max_tokens"),
+            (json!({ "stop": [1] }), "stop"),
+            (json!({ "seed": 1.5 }), "seed"),
+            (json!({ "presence_penalty": 3 }), "presence_penalty"),
+            (json!({ "n": -1 }), "n"),
+            (json!({ "logit_bias": { "1": 200 } }), "logit_bias"),
+            (json!({ "logit_bias": [1] }), "logit_bias"),
+            (json!({ "user": 7 }), "user"),
+        ] {
+            let err = GenerationParams::from_payload(&payload).unwrap_err();
+            assert!(
+                matches!(err, MCPError::PayloadValidation(ref e) if e.contains(field)),
+                "{:?}",
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn test_select_model() {
+        let allowed = vec!["grande".to_string()];
+
+        assert_eq!(
+            select_model(&json!({ "model": null }), Some("pequeno"), &allowed).unwrap(),
+            Some("pequeno")
+        );
+        assert_eq!(
+            select_model(&json!({ "model": "pequeno" }), Some("pequeno"), &[]).unwrap(),
+            Some("pequeno")
+        );
+        assert_eq!(
+            select_model(&json!({ "model": "grande" }), None, &allowed).unwrap(),
+            Some("grande")
+        );
+        assert_eq!(select_model(&json!({}), None, &allowed).unwrap(), None);
+
+        for payload in [json!({ "model": "outro" }), json!({ "model": 1 })] {
+            let err = select_model(&payload, Some("pequeno"), &allowed).unwrap_err();
+            assert!(matches!(err, MCPError::PayloadValidation(_)));
+        }
+    }
+
+    #[test]
+    fn test_generation_params_ensure_supported() {
+        let params = GenerationParams::from_payload(&json!({
+            "temperature": 0.5,
+            "seed": 1,
+            "logit_bias": { "1": 1 }
+        }))
+        .unwrap();
+
+        assert!(params
+            .ensure_supported("openai", &GenerationParams::ALL)
+            .is_ok());
+
+        let err = params
+            .ensure_supported("anthropic", &["temperature", "max_tokens"])
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            MCPError::PayloadValidation(e) if e == "Parâmetros não suportados pelo provedor 'anthropic': seed, logit_bias"
+        ));
+    }
+
+    #[test]
+    fn test_tools_parsing() {
+        let request = ChatRequest::from_payload(&json!({
+            "messages": [
+                { "role": "user", "content": "Qual o clima em Lisboa?" },
+                {
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [{ "id": "call_1", "name": "clima", "arguments": { "cidade": "Lisboa" } }]
+                },
+                { "role": "tool", "tool_call_id": "call_1", "content": "{\"temp\": 21}" }
+            ],
+            "tools": [{
+                "name": "clima",
+                "description": "Consulta o clima de uma cidade",
+                "parameters": { "type": "object", "properties": { "cidade": { "type": "string" } } }
+            }, { "name": "hora" }],
+            "tool_choice": { "name": "clima" }
+        }))
+        .unwrap();
+
+        assert_eq!(request.tools.len(), 2);
+        assert_eq!(
+            request.tools[1].parameters,
+            json!({ "type": "object", "properties": {} })
+        );
+        assert_eq!(
+            request.tool_choice,
+            Some(ToolChoice::Tool("clima".to_string()))
+        );
+        assert_eq!(
+            request.messages[1],
+            ChatMessage::assistant_tool_calls(
+                "",
+                vec![ToolCall {
+                    id: "call_1".to_string(),
+                    name: "clima".to_string(),
+                    arguments: json!({ "cidade": "Lisboa" }),
+                }]
+            )
+        );
+        assert_eq!(
+            request.messages[2],
+            ChatMessage::tool("call_1", "{\"temp\": 21}")
+        );
+        assert!(request.uses_tools());
+        assert!(request.ensure_no_tools("cohere").is_err());
+    }
+
+    #[test]
+    fn test_tools_validation() {
+        let invalid_payloads = [
+            json!({ "user_prompt": "Olá", "tools": [{ "name": "com espaço" }] }),
+            json!({ "user_prompt": "Olá", "tools": [{ "name": "a" }, { "name": "a" }] }),
+            json!({ "user_prompt": "Olá", "tools": [{ "name": "a", "parameters": "x" }] }),
+            json!({ "user_prompt": "Olá", "tool_choice": "auto" }),
+            json!({ "user_prompt": "Olá", "tools": [{ "name": "a" }], "tool_choice": "sempre" }),
+            json!({ "user_prompt": "Olá", "tools": [{ "name": "a" }], "tool_choice": { "name": "b" } }),
+            json!({ "messages": [{ "role": "tool", "content": "42" }] }),
+            json!({ "messages": [{ "role": "user", "content": "Oi", "tool_calls": [{ "id": "1", "name": "a" }] }] }),
+        ];
+
+        for payload in invalid_payloads {
+            assert!(
+                matches!(
+                    ChatRequest::from_payload(&payload),
+                    Err(MCPError::PayloadValidation(_))
+                ),
+                "payload deveria ser rejeitado: {}",
+                payload
+            );
+        }
+
+        // Sem ferramentas, nada muda para os provedores
+        let request = ChatRequest::from_payload(&json!({ "user_prompt": "Olá" })).unwrap();
+        assert!(!request.uses_tools());
+        assert!(request.ensure_no_tools("cohere").is_ok());
+    }
+}
@@ -29,34 +29,148 @@
 //! let removed = manager.cleanup_old_conversations();
 //! println!("{} conversas antigas foram removidas", removed);
 //! ```
+//!
+//! ## Janela de Contexto
+//!
+//! Conversas longas podem ultrapassar o limite de contexto dos modelos. O
+//! [`ContextWindowBuilder`] seleciona as mensagens mais recentes que cabem em um
+//! orçamento de tokens, sempre preservando as mensagens de sistema:
+//!
+//! ```rust
+//! use mcprs::conversation::{Conversation, ContextWindowBuilder};
+//!
+//! let mut conversation = Conversation::new();
+//! conversation.add_message("system", "Seja breve.");
+//! for i in 0..100 {
+//!     conversation.add_message("user", &format!("Mensagem número {}", i));
+//! }
+//!
+//! let window = ContextWindowBuilder::new(200).build(&conversation.messages);
+//! assert_eq!(window.messages[0].role, "system");
+//! assert!(window.dropped > 0);
+//! ```
 
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+use std::str::FromStr;
+use std::sync::Arc;
 use std::time::{Duration, SystemTime};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info};
 use uuid::Uuid;
 
+use crate::agent::MCPError;
+use crate::chat::{ChatMessage, ChatRole, ToolCall};
+use crate::conversation_store::{ConversationStore, InMemoryConversationStore};
+
 /// Representa uma mensagem individual em uma conversa.
 ///
 /// Cada mensagem tem um papel (role) que identifica se é do usuário,
 /// do assistente ou do sistema, além do conteúdo e timestamp.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ConversationMessage {
+    /// ID único da mensagem (UUID). Mensagens armazenadas antes da
+    /// introdução do campo recebem, ao serem carregadas, um ID estável
+    /// derivado da conversa, da posição e do timestamp da mensagem.
+    #[serde(default)]
+    pub id: String,
+
     /// Papel do remetente (user, assistant, system)
     pub role: String,
 
     /// Conteúdo da mensagem
     pub content: String,
 
+    /// Ferramentas que o assistente pediu para chamar
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+
+    /// ID da chamada respondida por uma mensagem com papel `tool`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+
     /// Momento em que a mensagem foi criada
     pub timestamp: SystemTime,
 }
 
+impl ConversationMessage {
+    /// Cria uma nova mensagem com ID único e o momento atual como timestamp.
+    ///
+    /// # Exemplo
+    ///
+    /// ```
+    /// use mcprs::conversation::ConversationMessage;
+    ///
+    /// let message = ConversationMessage::new("user", "Olá");
+    /// assert_eq!(message.role, "user");
+    /// assert!(!message.id.is_empty());
+    /// ```
+    pub fn new(role: &str, content: &str) -> Self {
+        Self {
+            id: new_message_id(),
+            role: role.to_string(),
+            content: content.to_string(),
+            tool_calls: Vec::new(),
+            tool_call_id: None,
+            timestamp: SystemTime::now(),
+        }
+    }
+
+    /// Cria uma mensagem a partir de uma mensagem de chat, preservando as
+    /// chamadas de ferramentas e o `tool_call_id`.
+    pub fn from_chat(message: &ChatMessage) -> Self {
+        Self {
+            tool_calls: message.tool_calls.clone(),
+            tool_call_id: message.tool_call_id.clone(),
+            ..Self::new(message.role.as_str(), &message.content)
+        }
+    }
+
+    /// Converte a mensagem armazenada em uma mensagem de chat.
+    ///
+    /// # Retorna
+    /// * `Ok(ChatMessage)` - A mensagem convertida
+    /// * `Err(MCPError)` - Se a mensagem tiver um papel desconhecido
+    pub fn to_chat(&self) -> Result<ChatMessage, MCPError> {
+        Ok(ChatMessage {
+            tool_calls: self.tool_calls.clone(),
+            tool_call_id: self.tool_call_id.clone(),
+            ..ChatMessage::new(ChatRole::from_str(&self.role)?, &self.content)
+        })
+    }
+}
+
+/// Gera um novo ID de mensagem.
+fn new_message_id() -> String {
+    Uuid::new_v4().to_string()
+}
+
+/// Deriva o ID de uma mensagem armazenada sem o campo `id`.
+///
+/// O mesmo trio (conversa, posição, timestamp) gera sempre o mesmo ID, então
+/// a mensagem mantém o ID entre leituras mesmo antes de ser regravada.
+fn legacy_message_id(conversation_id: &str, index: usize, timestamp: SystemTime) -> String {
+    let nanos = timestamp
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let digest = Sha256::digest(format!("{}:{}:{}", conversation_id, index, nanos).as_bytes());
+
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    uuid::Builder::from_custom_bytes(bytes)
+        .into_uuid()
+        .to_string()
+}
+
 /// Representa uma conversa completa entre usuário e assistente.
 ///
 /// Uma conversa contém um ID único, uma sequência de mensagens,
 /// metadados opcionais e timestamps de criação e atualização.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(from = "StoredConversation")]
 pub struct Conversation {
     /// ID único da conversa (UUID)
     pub id: String,
@@ -67,6 +181,12 @@ pub struct Conversation {
     /// Metadados opcionais para contexto adicional
     pub metadata: HashMap<String, String>,
 
+    /// Identificador do dono da conversa (veja `AuthUser::subject`).
+    /// Conversas sem dono só são acessíveis sem identidade (veja
+    /// [`Conversation::is_accessible_by`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+
     /// Momento de criação da conversa
     pub created_at: SystemTime,
 
@@ -74,6 +194,39 @@ pub struct Conversation {
     pub updated_at: SystemTime,
 }
 
+/// Formato armazenado de uma conversa, usado na desserialização de [`Conversation`].
+#[derive(Deserialize)]
+struct StoredConversation {
+    id: String,
+    messages: Vec<ConversationMessage>,
+    metadata: HashMap<String, String>,
+    #[serde(default)]
+    owner: Option<String>,
+    created_at: SystemTime,
+    updated_at: SystemTime,
+}
+
+impl From<StoredConversation> for Conversation {
+    /// Atribui IDs estáveis às mensagens gravadas antes do campo `id`.
+    fn from(stored: StoredConversation) -> Self {
+        let mut messages = stored.messages;
+        for (index, message) in messages.iter_mut().enumerate() {
+            if message.id.is_empty() {
+                message.id = legacy_message_id(&stored.id, index, message.timestamp);
+            }
+        }
+
+        Self {
+            id: stored.id,
+            messages,
+            metadata: stored.metadata,
+            owner: stored.owner,
+            created_at: stored.created_at,
+            updated_at: stored.updated_at,
+        }
+    }
+}
+
 impl Conversation {
     /// Cria uma nova conversa vazia com ID único.
     ///
@@ -91,6 +244,7 @@ impl Conversation {
             id: Uuid::new_v4().to_string(),
             messages: Vec::new(),
             metadata: HashMap::new(),
+            owner: None,
             created_at: now,
             updated_at: now,
         }
@@ -114,11 +268,27 @@ impl Conversation {
     /// assert_eq!(conversation.messages.len(), 2);
     /// ```
     pub fn add_message(&mut self, role: &str, content: &str) {
-        self.messages.push(ConversationMessage {
-            role: role.to_string(),
-            content: content.to_string(),
-            timestamp: SystemTime::now(),
-        });
+        self.messages.push(ConversationMessage::new(role, content));
+        self.updated_at = SystemTime::now();
+    }
+
+    /// Adiciona uma mensagem de chat à conversa, incluindo chamadas de
+    /// ferramentas e resultados (`tool_call_id`).
+    ///
+    /// # Exemplo
+    ///
+    /// ```
+    /// use mcprs::chat::ChatMessage;
+    /// use mcprs::conversation::Conversation;
+    ///
+    /// let mut conversation = Conversation::new();
+    /// conversation.add_chat_message(&ChatMessage::tool("call_1", "{\"temp\": 21}"));
+    ///
+    /// assert_eq!(conversation.messages[0].role, "tool");
+    /// assert_eq!(conversation.messages[0].tool_call_id.as_deref(), Some("call_1"));
+    /// ```
+    pub fn add_chat_message(&mut self, message: &ChatMessage) {
+        self.messages.push(ConversationMessage::from_chat(message));
         self.updated_at = SystemTime::now();
     }
 
@@ -143,6 +313,30 @@ impl Conversation {
         &self.messages
     }
 
+    /// Converte o histórico da conversa em mensagens de chat.
+    ///
+    /// Usado para reenviar o contexto da conversa aos agentes.
+    ///
+    /// # Retorna
+    /// * `Ok(Vec<ChatMessage>)` - As mensagens na ordem cronológica
+    /// * `Err(MCPError)` - Se alguma mensagem tiver um papel desconhecido
+    ///
+    /// # Exemplo
+    ///
+    /// ```
+    /// use mcprs::chat::ChatMessage;
+    /// use mcprs::conversation::Conversation;
+    ///
+    /// let mut conversation = Conversation::new();
+    /// conversation.add_message("user", "Olá!");
+    ///
+    /// let history = conversation.chat_history().unwrap();
+    /// assert_eq!(history, vec![ChatMessage::user("Olá!")]);
+    /// ```
+    pub fn chat_history(&self) -> Result<Vec<ChatMessage>, MCPError> {
+        to_chat_messages(&self.messages)
+    }
+
     /// Define um valor de metadado para a conversa.
     ///
     /// # Argumentos
@@ -163,6 +357,70 @@ impl Conversation {
     pub fn set_metadata(&mut self, key: &str, value: &str) {
         self.metadata.insert(key.to_string(), value.to_string());
     }
+
+    /// Verifica se a conversa pode ser acessada pelo dono informado.
+    ///
+    /// A conversa é acessível apenas quando o dono informado é exatamente o
+    /// dono registrado. Conversas sem dono (criadas sem autenticação ou antes
+    /// dela) só são acessíveis sem identidade; para liberá-las a um usuário,
+    /// atribua o dono com [`ConversationManager::assign_owner`].
+    ///
+    /// # Exemplo
+    ///
+    /// ```
+    /// use mcprs::conversation::Conversation;
+    ///
+    /// let mut conversation = Conversation::new();
+    /// assert!(conversation.is_accessible_by(None));
+    /// assert!(!conversation.is_accessible_by(Some("alice")));
+    ///
+    /// conversation.owner = Some("alice".to_string());
+    /// assert!(conversation.is_accessible_by(Some("alice")));
+    /// assert!(!conversation.is_accessible_by(Some("bob")));
+    /// assert!(!conversation.is_accessible_by(None));
+    /// ```
+    pub fn is_accessible_by(&self, owner: Option<&str>) -> bool {
+        self.owner.as_deref() == owner
+    }
+
+    /// Retorna o tempo de retenção específico desta conversa, se definido.
+    ///
+    /// O valor é lido do metadado [`TTL_METADATA_KEY`], em segundos. Valores
+    /// que não sejam um inteiro não negativo são ignorados.
+    ///
+    /// # Exemplo
+    ///
+    /// ```
+    /// use mcprs::conversation::{Conversation, TTL_METADATA_KEY};
+    /// use std::time::Duration;
+    ///
+    /// let mut conversation = Conversation::new();
+    /// conversation.set_metadata(TTL_METADATA_KEY, "600");
+    /// assert_eq!(conversation.ttl(), Some(Duration::from_secs(600)));
+    /// ```
+    pub fn ttl(&self) -> Option<Duration> {
+        self.metadata
+            .get(TTL_METADATA_KEY)
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .map(Duration::from_secs)
+    }
+
+    /// Verifica se a conversa expirou no instante `now`.
+    ///
+    /// Usa o TTL da própria conversa (veja [`Conversation::ttl`]) quando
+    /// definido, limitado a `max_age`, ou `max_age` caso contrário. Como o
+    /// metadado pode ser alterado pelos clientes, ele só encurta a retenção.
+    pub fn is_expired(&self, now: SystemTime, max_age: Duration) -> bool {
+        let max_age = self.ttl().map_or(max_age, |ttl| ttl.min(max_age));
+        now.duration_since(self.updated_at)
+            .map(|duration| duration > max_age)
+            .unwrap_or(false)
+    }
+}
+
+/// Converte mensagens armazenadas em mensagens de chat.
+fn to_chat_messages(messages: &[ConversationMessage]) -> Result<Vec<ChatMessage>, MCPError> {
+    messages.iter().map(ConversationMessage::to_chat).collect()
 }
 
 impl Default for Conversation {
@@ -171,16 +429,36 @@ impl Default for Conversation {
     }
 }
 
+/// Chave de metadado que define o tempo de retenção de uma conversa, em segundos.
+///
+/// Reduz o tempo máximo de retenção do [`ConversationManager`] apenas para a
+/// conversa em que está definida; valores maiores que o máximo do gerenciador
+/// não estendem a retenção.
+pub const TTL_METADATA_KEY: &str = "ttl_secs";
+
+/// Intervalo padrão entre execuções da limpeza automática.
+pub const DEFAULT_CLEANUP_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Menor intervalo aceito entre execuções da limpeza automática.
+pub const MIN_CLEANUP_INTERVAL: Duration = Duration::from_millis(1);
+
 /// Gerenciador de conversas que mantém histórico e limpa conversas antigas.
 ///
 /// O `ConversationManager` é responsável por criar, armazenar, recuperar e
 /// limpar conversas, com base em um tempo máximo de retenção configurável.
+/// As conversas são guardadas em um [`ConversationStore`], em memória por padrão.
 pub struct ConversationManager {
-    /// Mapa de ID para objeto Conversation, compartilhado entre threads
-    conversations: Arc<RwLock<HashMap<String, Conversation>>>,
+    /// Backend de armazenamento, compartilhado entre threads
+    store: Arc<dyn ConversationStore>,
 
     /// Tempo máximo que uma conversa será mantida após sua última atualização
     max_age: Duration,
+
+    /// Orçamento de tokens para o histórico reenviado aos agentes (opcional)
+    context_window: Option<ContextWindowBuilder>,
+
+    /// Intervalo entre execuções da limpeza automática
+    cleanup_interval: Duration,
 }
 
 impl ConversationManager {
@@ -198,13 +476,85 @@ impl ConversationManager {
     /// let manager = ConversationManager::new(24);
     /// ```
     pub fn new(max_age_hours: u64) -> Self {
-        let max_age = Duration::from_secs(max_age_hours * 3600);
+        Self::with_store(max_age_hours, InMemoryConversationStore::new())
+    }
+
+    /// Cria um gerenciador de conversas que usa o backend de armazenamento informado.
+    ///
+    /// # Argumentos
+    /// * `max_age_hours` - Tempo máximo de retenção em horas
+    /// * `store` - O backend onde as conversas serão armazenadas
+    ///
+    /// # Exemplo
+    ///
+    /// ```no_run
+    /// use mcprs::conversation::ConversationManager;
+    /// use mcprs::conversation_store::JsonLinesConversationStore;
+    ///
+    /// // Conversas persistidas em disco sobrevivem a reinicializações
+    /// let store = JsonLinesConversationStore::open("conversas.jsonl").unwrap();
+    /// let manager = ConversationManager::with_store(24, store);
+    /// ```
+    pub fn with_store(max_age_hours: u64, store: impl ConversationStore + 'static) -> Self {
         Self {
-            conversations: Arc::new(RwLock::new(HashMap::new())),
-            max_age,
+            store: Arc::new(store),
+            max_age: Duration::from_secs(max_age_hours * 3600),
+            context_window: None,
+            cleanup_interval: DEFAULT_CLEANUP_INTERVAL,
         }
     }
 
+    /// Define a janela de contexto usada ao reenviar o histórico aos agentes.
+    ///
+    /// Sem uma janela configurada, o histórico completo é reenviado.
+    ///
+    /// # Argumentos
+    /// * `builder` - O construtor da janela de contexto com o orçamento de tokens
+    ///
+    /// # Exemplo
+    ///
+    /// ```
+    /// use mcprs::conversation::{ContextWindowBuilder, ConversationManager};
+    ///
+    /// let manager = ConversationManager::new(24)
+    ///     .with_context_window(ContextWindowBuilder::new(4096));
+    /// assert!(manager.context_window().is_some());
+    /// ```
+    pub fn with_context_window(mut self, builder: ContextWindowBuilder) -> Self {
+        self.context_window = Some(builder);
+        self
+    }
+
+    /// Retorna a janela de contexto configurada, se houver.
+    pub fn context_window(&self) -> Option<&ContextWindowBuilder> {
+        self.context_window.as_ref()
+    }
+
+    /// Define o intervalo entre execuções da limpeza automática.
+    ///
+    /// O padrão é [`DEFAULT_CLEANUP_INTERVAL`] (5 minutos). Valores abaixo de
+    /// [`MIN_CLEANUP_INTERVAL`] (incluindo zero) são elevados a ele.
+    ///
+    /// # Exemplo
+    ///
+    /// ```
+    /// use mcprs::conversation::ConversationManager;
+    /// use std::time::Duration;
+    ///
+    /// let manager = ConversationManager::new(24)
+    ///     .with_cleanup_interval(Duration::from_secs(60));
+    /// assert_eq!(manager.cleanup_interval(), Duration::from_secs(60));
+    /// ```
+    pub fn with_cleanup_interval(mut self, interval: Duration) -> Self {
+        self.cleanup_interval = interval.max(MIN_CLEANUP_INTERVAL);
+        self
+    }
+
+    /// Retorna o intervalo entre execuções da limpeza automática.
+    pub fn cleanup_interval(&self) -> Duration {
+        self.cleanup_interval
+    }
+
     /// Cria uma nova conversa e a registra no gerenciador.
     ///
     /// # Retorna
@@ -222,14 +572,8 @@ impl ConversationManager {
     /// ```
     pub fn create_conversation(&self) -> Result<Conversation, String> {
         let conversation = Conversation::new();
-        let id = conversation.id.clone();
-
-        if let Ok(mut conversations) = self.conversations.write() {
-            conversations.insert(id, conversation.clone());
-            Ok(conversation)
-        } else {
-            Err("Falha ao adquirir lock".to_string())
-        }
+        self.store.save(&conversation)?;
+        Ok(conversation)
     }
 
     /// Recupera uma conversa existente pelo ID.
@@ -256,10 +600,12 @@ impl ConversationManager {
     /// }
     /// ```
     pub fn get_conversation(&self, id: &str) -> Option<Conversation> {
-        if let Ok(conversations) = self.conversations.read() {
-            conversations.get(id).cloned()
-        } else {
-            None
+        match self.store.load(id) {
+            Ok(conversation) => conversation,
+            Err(e) => {
+                error!("Falha ao carregar conversa {}: {}", id, e);
+                None
+            }
         }
     }
 
@@ -288,12 +634,7 @@ impl ConversationManager {
     /// manager.update_conversation(conversation).unwrap();
     /// ```
     pub fn update_conversation(&self, conversation: Conversation) -> Result<(), String> {
-        if let Ok(mut conversations) = self.conversations.write() {
-            conversations.insert(conversation.id.clone(), conversation);
-            Ok(())
-        } else {
-            Err("Falha ao adquirir lock".to_string())
-        }
+        self.store.save(&conversation)
     }
 
     /// Adiciona uma mensagem a uma conversa existente.
@@ -326,22 +667,207 @@ impl ConversationManager {
         role: &str,
         content: &str,
     ) -> Result<(), String> {
-        if let Ok(mut conversations) = self.conversations.write() {
-            if let Some(conversation) = conversations.get_mut(conversation_id) {
-                conversation.add_message(role, content);
-                conversation.updated_at = SystemTime::now();
-                Ok(())
-            } else {
-                Err(format!("Conversa {} não encontrada", conversation_id))
-            }
+        self.append_message(conversation_id, ConversationMessage::new(role, content))
+    }
+
+    /// Adiciona uma mensagem de chat a uma conversa existente, preservando as
+    /// chamadas de ferramentas e o `tool_call_id`.
+    ///
+    /// # Retorna
+    /// * `Ok(())` - Se a adição for bem-sucedida
+    /// * `Err(String)` - Mensagem de erro se a conversa não existir ou a operação falhar
+    ///
+    /// # Exemplo
+    ///
+    /// ```
+    /// use mcprs::chat::ChatMessage;
+    /// use mcprs::conversation::ConversationManager;
+    ///
+    /// let manager = ConversationManager::new(24);
+    /// let id = manager.create_conversation().unwrap().id;
+    ///
+    /// manager
+    ///     .add_chat_message_to_conversation(&id, &ChatMessage::tool("call_1", "21 graus"))
+    ///     .unwrap();
+    ///
+    /// let conversation = manager.get_conversation(&id).unwrap();
+    /// assert_eq!(conversation.messages[0].tool_call_id.as_deref(), Some("call_1"));
+    /// ```
+    pub fn add_chat_message_to_conversation(
+        &self,
+        conversation_id: &str,
+        message: &ChatMessage,
+    ) -> Result<(), String> {
+        self.append_message(conversation_id, ConversationMessage::from_chat(message))
+    }
+
+    /// Acrescenta uma mensagem ao armazenamento, falhando se a conversa não existir.
+    fn append_message(
+        &self,
+        conversation_id: &str,
+        message: ConversationMessage,
+    ) -> Result<(), String> {
+        if self.store.append_message(conversation_id, message)? {
+            Ok(())
         } else {
-            Err("Falha ao adquirir lock".to_string())
+            Err(format!("Conversa {} não encontrada", conversation_id))
         }
     }
 
+    /// Remove uma conversa.
+    ///
+    /// # Retorna
+    /// * `Ok(true)` - Se a conversa existia e foi removida
+    /// * `Ok(false)` - Se a conversa não existe
+    /// * `Err(String)` - Mensagem de erro se a operação falhar
+    pub fn delete_conversation(&self, id: &str) -> Result<bool, String> {
+        self.store.delete(id)
+    }
+
+    /// Aplica alterações aos metadados de uma conversa.
+    ///
+    /// Chaves com valor `Some` são inseridas ou substituídas; chaves com
+    /// valor `None` são removidas.
+    ///
+    /// # Retorna
+    /// * `Ok(Conversation)` - A conversa atualizada
+    /// * `Err(String)` - Se a conversa não existir ou a operação falhar
+    ///
+    /// # Exemplo
+    ///
+    /// ```
+    /// use mcprs::conversation::ConversationManager;
+    /// use std::collections::HashMap;
+    ///
+    /// let manager = ConversationManager::new(24);
+    /// let conversation = manager.create_conversation().unwrap();
+    ///
+    /// let changes = HashMap::from([("language".to_string(), Some("pt-br".to_string()))]);
+    /// let updated = manager.update_metadata(&conversation.id, changes).unwrap();
+    /// assert_eq!(updated.metadata["language"], "pt-br");
+    /// ```
+    pub fn update_metadata(
+        &self,
+        id: &str,
+        changes: HashMap<String, Option<String>>,
+    ) -> Result<Conversation, String> {
+        let mut conversation = self
+            .store
+            .load(id)?
+            .ok_or_else(|| format!("Conversa {} não encontrada", id))?;
+
+        for (key, value) in changes {
+            match value {
+                Some(value) => conversation.metadata.insert(key, value),
+                None => conversation.metadata.remove(&key),
+            };
+        }
+        conversation.updated_at = SystemTime::now();
+
+        self.store.save(&conversation)?;
+        Ok(conversation)
+    }
+
+    /// Atribui um dono a uma conversa sem dono.
+    ///
+    /// Conversas sem dono não são acessíveis por usuários autenticados (veja
+    /// [`Conversation::is_accessible_by`]); use este método para migrar
+    /// explicitamente conversas antigas para o usuário correto. Conversas que
+    /// já pertencem a outro dono não são alteradas.
+    ///
+    /// # Retorna
+    /// * `Ok(Conversation)` - A conversa, agora pertencente a `owner`
+    /// * `Err(String)` - Se a conversa não existir, já pertencer a outro dono
+    ///   ou a operação falhar
+    ///
+    /// # Exemplo
+    ///
+    /// ```
+    /// use mcprs::conversation::ConversationManager;
+    ///
+    /// let manager = ConversationManager::new(24);
+    /// let conversation = manager.create_conversation().unwrap();
+    ///
+    /// let claimed = manager.assign_owner(&conversation.id, "alice").unwrap();
+    /// assert!(claimed.is_accessible_by(Some("alice")));
+    /// assert!(manager.assign_owner(&conversation.id, "bob").is_err());
+    /// ```
+    pub fn assign_owner(&self, id: &str, owner: &str) -> Result<Conversation, String> {
+        let mut conversation = self
+            .store
+            .load(id)?
+            .ok_or_else(|| format!("Conversa {} não encontrada", id))?;
+
+        match conversation.owner.as_deref() {
+            Some(current) if current == owner => return Ok(conversation),
+            Some(_) => return Err(format!("Conversa {} já possui dono", id)),
+            None => conversation.owner = Some(owner.to_string()),
+        }
+
+        self.store.save(&conversation)?;
+        Ok(conversation)
+    }
+
+    /// Lista conversas, da atualizada mais recentemente para a mais antiga.
+    ///
+    /// # Argumentos
+    /// * `scope` - Quais conversas listar (veja [`ConversationScope`])
+    /// * `offset` - Número de conversas a pular
+    /// * `limit` - Número máximo de conversas retornadas
+    ///
+    /// # Exemplo
+    ///
+    /// ```
+    /// use mcprs::conversation::{ConversationManager, ConversationScope};
+    ///
+    /// let manager = ConversationManager::new(24);
+    /// for _ in 0..3 {
+    ///     manager.create_conversation().unwrap();
+    /// }
+    ///
+    /// let page = manager.list_conversations(ConversationScope::All, 0, 2).unwrap();
+    /// assert_eq!(page.total, 3);
+    /// assert_eq!(page.conversations.len(), 2);
+    ///
+    /// let page = manager
+    ///     .list_conversations(ConversationScope::AccessibleBy(Some("alice")), 0, 2)
+    ///     .unwrap();
+    /// assert_eq!(page.total, 0);
+    /// ```
+    pub fn list_conversations(
+        &self,
+        scope: ConversationScope<'_>,
+        offset: usize,
+        limit: usize,
+    ) -> Result<ConversationPage, String> {
+        let mut conversations: Vec<Conversation> = self
+            .store
+            .list()?
+            .into_iter()
+            .filter(|conv| scope.includes(conv))
+            .collect();
+        conversations.sort_by(|a, b| {
+            b.updated_at
+                .cmp(&a.updated_at)
+                .then_with(|| a.id.cmp(&b.id))
+        });
+
+        let total = conversations.len();
+        let conversations = conversations.into_iter().skip(offset).take(limit).collect();
+
+        Ok(ConversationPage {
+            conversations,
+            total,
+        })
+    }
+
     /// Remove conversas mais antigas que o tempo máximo de retenção.
     ///
-    /// Esta função deve ser chamada periodicamente para limpar conversas antigas.
+    /// Conversas com o metadado [`TTL_METADATA_KEY`] usam o próprio tempo de
+    /// retenção, se menor que o do gerenciador. Esta função deve ser chamada
+    /// periodicamente para limpar conversas antigas, diretamente ou por meio de
+    /// [`ConversationManager::spawn_janitor`]. Como acessa o armazenamento de
+    /// forma síncrona, em código assíncrono chame-a via `spawn_blocking`.
     ///
     /// # Retorna
     /// O número de conversas removidas
@@ -364,48 +890,387 @@ impl ConversationManager {
     /// ```
     pub fn cleanup_old_conversations(&self) -> usize {
         let now = SystemTime::now();
-        let mut count = 0;
 
-        if let Ok(mut conversations) = self.conversations.write() {
-            let ids_to_remove: Vec<String> = conversations
-                .iter()
-                .filter(|(_, conv)| {
-                    now.duration_since(conv.updated_at)
-                        .map(|duration| duration > self.max_age)
-                        .unwrap_or(false)
-                })
-                .map(|(id, _)| id.clone())
-                .collect();
-
-            for id in ids_to_remove {
-                conversations.remove(&id);
-                count += 1;
+        match self
+            .store
+            .retain(&|conv| !conv.is_expired(now, self.max_age))
+        {
+            Ok(count) => count,
+            Err(e) => {
+                error!("Falha ao limpar conversas antigas: {}", e);
+                0
             }
         }
-
-        count
     }
 
-    /// Obtém um clone do Arc<RwLock> interno contendo as conversas.
+    /// Retorna o backend de armazenamento usado pelo gerenciador.
     ///
     /// Útil quando precisa compartilhar o acesso às conversas com outra parte do código.
+    pub fn store(&self) -> Arc<dyn ConversationStore> {
+        Arc::clone(&self.store)
+    }
+
+    /// Inicia uma tarefa em segundo plano que remove conversas expiradas.
+    ///
+    /// A cada [`ConversationManager::cleanup_interval`], a tarefa chama
+    /// [`ConversationManager::cleanup_old_conversations`] via `spawn_blocking`
+    /// e emite um evento de tracing com o número de conversas removidas. Deve
+    /// ser chamada dentro de um runtime Tokio.
     ///
     /// # Retorna
-    /// Um clone do Arc<RwLock> contendo o mapa de conversas
-    pub fn get_arc_clone(&self) -> Arc<RwLock<HashMap<String, Conversation>>> {
-        Arc::clone(&self.conversations)
+    /// Um [`JanitorHandle`] que encerra a tarefa ao chamar `shutdown` ou ao ser descartado
+    ///
+    /// # Exemplo
+    ///
+    /// ```
+    /// use mcprs::conversation::ConversationManager;
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let manager = ConversationManager::new(24)
+    ///     .with_cleanup_interval(Duration::from_secs(60));
+    ///
+    /// let janitor = manager.spawn_janitor();
+    /// // ... servidor em execução ...
+    /// janitor.shutdown().await;
+    /// # }
+    /// ```
+    pub fn spawn_janitor(&self) -> JanitorHandle {
+        let manager = self.clone();
+        let (shutdown_tx, mut shutdown_rx) = oneshot::channel::<()>();
+
+        let task = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(manager.cleanup_interval);
+            // O primeiro tick é imediato; a primeira limpeza ocorre após um intervalo
+            interval.tick().await;
+
+            loop {
+                tokio::select! {
+                    _ = &mut shutdown_rx => break,
+                    _ = interval.tick() => {
+                        // A limpeza acessa o armazenamento de forma síncrona
+                        let cleanup = manager.clone();
+                        let removed = match tokio::task::spawn_blocking(move || {
+                            cleanup.cleanup_old_conversations()
+                        })
+                        .await
+                        {
+                            Ok(removed) => removed,
+                            Err(e) => {
+                                error!("Falha na tarefa de limpeza de conversas: {}", e);
+                                0
+                            }
+                        };
+                        if removed > 0 {
+                            info!(removed, "Conversas expiradas removidas");
+                        } else {
+                            debug!(removed, "Nenhuma conversa expirada");
+                        }
+                    }
+                }
+            }
+        });
+
+        JanitorHandle {
+            shutdown_tx: Some(shutdown_tx),
+            task: Some(task),
+        }
+    }
+}
+
+/// Conjunto de conversas listado por [`ConversationManager::list_conversations`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConversationScope<'a> {
+    /// Todas as conversas, de qualquer dono (uso administrativo)
+    All,
+
+    /// Apenas as conversas acessíveis pelo dono informado, com a mesma regra de
+    /// [`Conversation::is_accessible_by`]: `None` é o acesso anônimo e vê
+    /// apenas conversas sem dono
+    AccessibleBy(Option<&'a str>),
+}
+
+impl ConversationScope<'_> {
+    /// Indica se a conversa pertence a este conjunto.
+    pub fn includes(&self, conversation: &Conversation) -> bool {
+        match self {
+            ConversationScope::All => true,
+            ConversationScope::AccessibleBy(owner) => conversation.is_accessible_by(*owner),
+        }
+    }
+}
+
+/// Uma página de conversas retornada por [`ConversationManager::list_conversations`].
+#[derive(Debug, Clone)]
+pub struct ConversationPage {
+    /// Conversas da página atual
+    pub conversations: Vec<Conversation>,
+
+    /// Número total de conversas que satisfazem o filtro
+    pub total: usize,
+}
+
+/// Controle da tarefa de limpeza iniciada por [`ConversationManager::spawn_janitor`].
+///
+/// Descartar o handle também encerra a tarefa.
+pub struct JanitorHandle {
+    /// Canal usado para sinalizar o encerramento
+    shutdown_tx: Option<oneshot::Sender<()>>,
+
+    /// Tarefa em execução
+    task: Option<JoinHandle<()>>,
+}
+
+impl JanitorHandle {
+    /// Sinaliza o encerramento e aguarda a tarefa terminar.
+    pub async fn shutdown(mut self) {
+        if let Some(tx) = self.shutdown_tx.take() {
+            let _ = tx.send(());
+        }
+        if let Some(task) = self.task.take() {
+            let _ = task.await;
+        }
+    }
+}
+
+impl Drop for JanitorHandle {
+    fn drop(&mut self) {
+        if let Some(tx) = self.shutdown_tx.take() {
+            let _ = tx.send(());
+        }
     }
 }
 
 impl Clone for ConversationManager {
     fn clone(&self) -> Self {
         Self {
-            conversations: Arc::clone(&self.conversations),
+            store: Arc::clone(&self.store),
             max_age: self.max_age,
+            context_window: self.context_window.clone(),
+            cleanup_interval: self.cleanup_interval,
         }
     }
 }
 
+/// Contador de tokens usado para dimensionar a janela de contexto.
+///
+/// Implemente esta trait para usar o tokenizador exato de um modelo.
+pub trait TokenCounter: Send + Sync {
+    /// Conta os tokens de um texto.
+    fn count_tokens(&self, text: &str) -> usize;
+
+    /// Conta os tokens de uma mensagem, incluindo o custo fixo de formatação.
+    ///
+    /// A implementação padrão soma 4 tokens por mensagem ao conteúdo e ao papel,
+    /// aproximando o overhead dos formatos de chat das APIs.
+    fn count_message_tokens(&self, role: &str, content: &str) -> usize {
+        self.count_tokens(role) + self.count_tokens(content) + 4
+    }
+}
+
+/// Contador heurístico de tokens baseado no número de caracteres.
+///
+/// Estima um token a cada `chars_per_token` caracteres (4 por padrão, a média
+/// aproximada para textos em inglês e português nos tokenizadores BPE).
+#[derive(Debug, Clone, Copy)]
+pub struct HeuristicTokenCounter {
+    /// Número médio de caracteres por token
+    pub chars_per_token: usize,
+}
+
+impl Default for HeuristicTokenCounter {
+    fn default() -> Self {
+        Self { chars_per_token: 4 }
+    }
+}
+
+impl TokenCounter for HeuristicTokenCounter {
+    fn count_tokens(&self, text: &str) -> usize {
+        text.chars().count().div_ceil(self.chars_per_token.max(1))
+    }
+}
+
+/// Resultado da seleção de mensagens para a janela de contexto.
+#[derive(Debug, Clone)]
+pub struct ContextWindow {
+    /// Mensagens selecionadas, na ordem cronológica original
+    pub messages: Vec<ConversationMessage>,
+
+    /// Número de mensagens descartadas por não caberem no orçamento
+    pub dropped: usize,
+
+    /// Total estimado de tokens das mensagens selecionadas
+    pub token_count: usize,
+}
+
+impl ContextWindow {
+    /// Converte as mensagens selecionadas em mensagens de chat.
+    ///
+    /// # Retorna
+    /// * `Ok(Vec<ChatMessage>)` - As mensagens na ordem cronológica
+    /// * `Err(MCPError)` - Se alguma mensagem tiver um papel desconhecido
+    pub fn chat_messages(&self) -> Result<Vec<ChatMessage>, MCPError> {
+        to_chat_messages(&self.messages)
+    }
+}
+
+/// Construtor de janelas de contexto limitadas por um orçamento de tokens.
+///
+/// As mensagens de sistema são sempre mantidas. As demais são selecionadas da
+/// mais recente para a mais antiga enquanto couberem no orçamento; a primeira
+/// mensagem que não couber encerra a seleção, para que o histórico enviado
+/// seja sempre contíguo. Uma mensagem do assistente com `tool_calls` e os
+/// resultados de ferramenta que a seguem são mantidos ou descartados juntos.
+#[derive(Clone)]
+pub struct ContextWindowBuilder {
+    /// Orçamento máximo de tokens
+    max_tokens: usize,
+
+    /// Contador de tokens usado na estimativa
+    counter: Arc<dyn TokenCounter>,
+}
+
+impl ContextWindowBuilder {
+    /// Cria um construtor com o orçamento informado e o contador heurístico padrão.
+    ///
+    /// # Argumentos
+    /// * `max_tokens` - Número máximo de tokens da janela
+    pub fn new(max_tokens: usize) -> Self {
+        Self {
+            max_tokens,
+            counter: Arc::new(HeuristicTokenCounter::default()),
+        }
+    }
+
+    /// Substitui o contador de tokens.
+    ///
+    /// # Exemplo
+    ///
+    /// ```
+    /// use mcprs::conversation::{ContextWindowBuilder, TokenCounter};
+    ///
+    /// /// Conta um token por palavra
+    /// struct WordCounter;
+    ///
+    /// impl TokenCounter for WordCounter {
+    ///     fn count_tokens(&self, text: &str) -> usize {
+    ///         text.split_whitespace().count()
+    ///     }
+    /// }
+    ///
+    /// let builder = ContextWindowBuilder::new(1000).with_counter(WordCounter);
+    /// assert_eq!(builder.counter().count_tokens("uma frase curta"), 3);
+    /// ```
+    pub fn with_counter(mut self, counter: impl TokenCounter + 'static) -> Self {
+        self.counter = Arc::new(counter);
+        self
+    }
+
+    /// Retorna o orçamento máximo de tokens.
+    pub fn max_tokens(&self) -> usize {
+        self.max_tokens
+    }
+
+    /// Retorna o contador de tokens configurado.
+    pub fn counter(&self) -> &dyn TokenCounter {
+        self.counter.as_ref()
+    }
+
+    /// Seleciona as mensagens que cabem no orçamento completo.
+    ///
+    /// # Argumentos
+    /// * `messages` - O histórico da conversa em ordem cronológica
+    pub fn build(&self, messages: &[ConversationMessage]) -> ContextWindow {
+        self.build_with_reserved(messages, 0)
+    }
+
+    /// Seleciona as mensagens reservando parte do orçamento.
+    ///
+    /// Útil para descontar as mensagens novas da requisição, que serão
+    /// enviadas junto com o histórico.
+    ///
+    /// # Argumentos
+    /// * `messages` - O histórico da conversa em ordem cronológica
+    /// * `reserved_tokens` - Tokens do orçamento já comprometidos
+    pub fn build_with_reserved(
+        &self,
+        messages: &[ConversationMessage],
+        reserved_tokens: usize,
+    ) -> ContextWindow {
+        let cost =
+            |msg: &ConversationMessage| self.counter.count_message_tokens(&msg.role, &msg.content);
+
+        // Mensagens de sistema são sempre mantidas
+        let mut token_count = reserved_tokens
+            + messages
+                .iter()
+                .filter(|msg| msg.role == "system")
+                .map(cost)
+                .sum::<usize>();
+
+        // Seleciona os grupos mais recentes enquanto couberem
+        let mut keep = vec![false; messages.len()];
+        for group in tool_call_groups(messages).iter().rev() {
+            let tokens: usize = group.iter().map(|&index| cost(&messages[index])).sum();
+            if token_count + tokens > self.max_tokens {
+                break;
+            }
+            token_count += tokens;
+            for &index in group {
+                keep[index] = true;
+            }
+        }
+
+        // Mensagens de sistema são mantidas independentemente do corte
+        for (index, msg) in messages.iter().enumerate() {
+            if msg.role == "system" {
+                keep[index] = true;
+            }
+        }
+
+        let selected: Vec<ConversationMessage> = messages
+            .iter()
+            .zip(&keep)
+            .filter(|(_, kept)| **kept)
+            .map(|(msg, _)| msg.clone())
+            .collect();
+
+        ContextWindow {
+            dropped: messages.len() - selected.len(),
+            messages: selected,
+            token_count: token_count - reserved_tokens,
+        }
+    }
+}
+
+/// Agrupa os índices das mensagens que não são de sistema em unidades
+/// indivisíveis para a janela de contexto.
+///
+/// Uma mensagem do assistente com `tool_calls` forma um grupo com as
+/// mensagens `tool` que a seguem: os provedores rejeitam um resultado de
+/// ferramenta sem a chamada correspondente. As demais mensagens formam
+/// grupos de um elemento.
+fn tool_call_groups(messages: &[ConversationMessage]) -> Vec<Vec<usize>> {
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    for (index, msg) in messages.iter().enumerate() {
+        if msg.role == "system" {
+            continue;
+        }
+
+        let continues_group = msg.role == "tool"
+            && groups.last().is_some_and(|group| {
+                let first = &messages[group[0]];
+                first.role == "assistant" && !first.tool_calls.is_empty()
+            });
+        match groups.last_mut() {
+            Some(group) if continues_group => group.push(index),
+            _ => groups.push(vec![index]),
+        }
+    }
+    groups
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -496,4 +1361,318 @@ mod tests {
         assert_eq!(removed, 3);
         assert!(manager.get_conversation(&conv1.id).is_none());
     }
+
+    #[test]
+    fn test_manager_with_persistent_store() {
+        use crate::conversation_store::JsonLinesConversationStore;
+
+        let path = std::env::temp_dir().join(format!("mcprs-manager-{}.jsonl", Uuid::new_v4()));
+
+        let id = {
+            let store = JsonLinesConversationStore::open(&path).unwrap();
+            let manager = ConversationManager::with_store(24, store);
+            let conversation = manager.create_conversation().unwrap();
+            manager
+                .add_message_to_conversation(&conversation.id, "user", "Olá")
+                .unwrap();
+            conversation.id
+        };
+
+        // Um novo gerenciador sobre o mesmo arquivo recupera o histórico
+        let store = JsonLinesConversationStore::open(&path).unwrap();
+        let manager = ConversationManager::with_store(24, store);
+        let retrieved = manager.get_conversation(&id).unwrap();
+        assert_eq!(retrieved.messages.len(), 1);
+        assert_eq!(retrieved.messages[0].content, "Olá");
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_cleanup_respects_ttl_metadata() {
+        let manager = ConversationManager::new(24);
+
+        let mut short = manager.create_conversation().unwrap();
+        short.set_metadata(TTL_METADATA_KEY, "0");
+        manager.update_conversation(short.clone()).unwrap();
+        let regular = manager.create_conversation().unwrap();
+
+        thread::sleep(Duration::from_millis(10));
+        assert_eq!(manager.cleanup_old_conversations(), 1);
+        assert!(manager.get_conversation(&short.id).is_none());
+        assert!(manager.get_conversation(&regular.id).is_some());
+
+        // O TTL não estende a retenção além do máximo do gerenciador
+        let manager = ConversationManager::new(0);
+        let mut long = manager.create_conversation().unwrap();
+        long.set_metadata(TTL_METADATA_KEY, &u64::MAX.to_string());
+        manager.update_conversation(long.clone()).unwrap();
+
+        thread::sleep(Duration::from_millis(10));
+        assert_eq!(manager.cleanup_old_conversations(), 1);
+        assert!(manager.get_conversation(&long.id).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_zero_cleanup_interval_is_raised_to_minimum() {
+        let manager = ConversationManager::new(0).with_cleanup_interval(Duration::ZERO);
+        assert_eq!(manager.cleanup_interval(), MIN_CLEANUP_INTERVAL);
+        let conversation = manager.create_conversation().unwrap();
+
+        // O janitor roda em vez de entrar em pânico
+        let janitor = manager.spawn_janitor();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(manager.get_conversation(&conversation.id).is_none());
+        tokio::time::timeout(Duration::from_secs(1), janitor.shutdown())
+            .await
+            .expect("janitor deveria encerrar");
+    }
+
+    #[tokio::test]
+    async fn test_janitor_evicts_and_shuts_down() {
+        let manager = ConversationManager::new(0).with_cleanup_interval(Duration::from_millis(10));
+        let conversation = manager.create_conversation().unwrap();
+
+        let janitor = manager.spawn_janitor();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(manager.get_conversation(&conversation.id).is_none());
+
+        tokio::time::timeout(Duration::from_secs(1), janitor.shutdown())
+            .await
+            .expect("janitor deveria encerrar");
+
+        // Sem a tarefa, conversas novas não são mais removidas
+        let conversation = manager.create_conversation().unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(manager.get_conversation(&conversation.id).is_some());
+    }
+
+    #[test]
+    fn test_list_conversations_filters_and_paginates() {
+        let manager = ConversationManager::new(24);
+
+        let mut owned = Vec::new();
+        for _ in 0..3 {
+            let mut conversation = manager.create_conversation().unwrap();
+            conversation.owner = Some("alice".to_string());
+            manager.update_conversation(conversation.clone()).unwrap();
+            owned.push(conversation.id);
+            thread::sleep(Duration::from_millis(2));
+        }
+        let mut other = manager.create_conversation().unwrap();
+        other.owner = Some("bob".to_string());
+        manager.update_conversation(other).unwrap();
+
+        let alice = ConversationScope::AccessibleBy(Some("alice"));
+        let page = manager.list_conversations(alice, 0, 2).unwrap();
+        assert_eq!(page.total, 3);
+        // Mais recentes primeiro
+        let ids: Vec<&str> = page.conversations.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec![owned[2].as_str(), owned[1].as_str()]);
+
+        let page = manager.list_conversations(alice, 2, 2).unwrap();
+        assert_eq!(page.conversations.len(), 1);
+        assert_eq!(page.conversations[0].id, owned[0]);
+
+        let all = manager.list_conversations(ConversationScope::All, 0, 10);
+        assert_eq!(all.unwrap().total, 4);
+
+        // O acesso anônimo vê apenas conversas sem dono, nunca as de outros usuários
+        let anonymous = ConversationScope::AccessibleBy(None);
+        assert_eq!(
+            manager.list_conversations(anonymous, 0, 10).unwrap().total,
+            0
+        );
+        manager.create_conversation().unwrap();
+        assert_eq!(
+            manager.list_conversations(anonymous, 0, 10).unwrap().total,
+            1
+        );
+    }
+
+    #[test]
+    fn test_update_metadata_and_delete() {
+        let manager = ConversationManager::new(24);
+        let mut conversation = manager.create_conversation().unwrap();
+        conversation.set_metadata("remover", "sim");
+        manager.update_conversation(conversation.clone()).unwrap();
+
+        let changes = HashMap::from([
+            ("idioma".to_string(), Some("pt-br".to_string())),
+            ("remover".to_string(), None),
+        ]);
+        let updated = manager.update_metadata(&conversation.id, changes).unwrap();
+        assert_eq!(updated.metadata.get("idioma").unwrap(), "pt-br");
+        assert!(!updated.metadata.contains_key("remover"));
+
+        assert!(manager
+            .update_metadata("inexistente", HashMap::new())
+            .is_err());
+
+        assert!(manager.delete_conversation(&conversation.id).unwrap());
+        assert!(!manager.delete_conversation(&conversation.id).unwrap());
+        assert!(manager.get_conversation(&conversation.id).is_none());
+    }
+
+    #[test]
+    fn test_stored_messages_without_id_get_stable_ids() {
+        let timestamp =
+            serde_json::json!({ "secs_since_epoch": 1_700_000_000, "nanos_since_epoch": 0 });
+        let stored = serde_json::json!({
+            "id": "conversa-antiga",
+            "messages": [
+                { "role": "user", "content": "Olá", "timestamp": timestamp },
+                { "role": "assistant", "content": "Oi!", "timestamp": timestamp },
+                { "id": "msg-1", "role": "user", "content": "Tudo bem?", "timestamp": timestamp }
+            ],
+            "metadata": {},
+            "created_at": timestamp,
+            "updated_at": timestamp
+        });
+
+        let first: Conversation = serde_json::from_value(stored.clone()).unwrap();
+        let second: Conversation = serde_json::from_value(stored).unwrap();
+
+        let ids: Vec<&str> = first.messages.iter().map(|m| m.id.as_str()).collect();
+        assert!(!ids[0].is_empty());
+        assert_ne!(ids[0], ids[1]);
+        assert_eq!(ids[2], "msg-1");
+        // O mesmo registro gera os mesmos IDs a cada leitura
+        let again: Vec<&str> = second.messages.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, again);
+
+        // Após regravada, a conversa mantém os IDs derivados
+        let reloaded: Conversation =
+            serde_json::from_str(&serde_json::to_string(&first).unwrap()).unwrap();
+        assert_eq!(reloaded.messages[0].id, ids[0]);
+    }
+
+    fn message(role: &str, content: &str) -> ConversationMessage {
+        ConversationMessage::new(role, content)
+    }
+
+    /// Contador determinístico: um token por caractere, sem overhead
+    struct CharCounter;
+
+    impl TokenCounter for CharCounter {
+        fn count_tokens(&self, text: &str) -> usize {
+            text.len()
+        }
+
+        fn count_message_tokens(&self, _role: &str, content: &str) -> usize {
+            self.count_tokens(content)
+        }
+    }
+
+    #[test]
+    fn test_heuristic_token_counter() {
+        let counter = HeuristicTokenCounter::default();
+        assert_eq!(counter.count_tokens(""), 0);
+        assert_eq!(counter.count_tokens("abcd"), 1);
+        assert_eq!(counter.count_tokens("abcde"), 2);
+        assert_eq!(counter.count_message_tokens("user", "abcd"), 6);
+    }
+
+    #[test]
+    fn test_context_window_keeps_recent_messages() {
+        let messages = vec![
+            message("user", "aaaa"),
+            message("assistant", "bbbb"),
+            message("user", "cccc"),
+            message("assistant", "dddd"),
+        ];
+
+        let window = ContextWindowBuilder::new(9)
+            .with_counter(CharCounter)
+            .build(&messages);
+
+        assert_eq!(window.dropped, 2);
+        assert_eq!(window.token_count, 8);
+        let contents: Vec<&str> = window.messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, vec!["cccc", "dddd"]);
+    }
+
+    #[test]
+    fn test_context_window_always_keeps_system_messages() {
+        let messages = vec![
+            message("system", "ssssss"),
+            message("user", "aaaa"),
+            message("assistant", "bbbb"),
+            message("user", "cccc"),
+        ];
+
+        // Orçamento menor que o prompt de sistema: apenas ele é mantido
+        let window = ContextWindowBuilder::new(4)
+            .with_counter(CharCounter)
+            .build(&messages);
+        assert_eq!(window.messages.len(), 1);
+        assert_eq!(window.messages[0].role, "system");
+        assert_eq!(window.dropped, 3);
+
+        // Orçamento para o sistema e uma mensagem
+        let window = ContextWindowBuilder::new(10)
+            .with_counter(CharCounter)
+            .build(&messages);
+        let contents: Vec<&str> = window.messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, vec!["ssssss", "cccc"]);
+        assert_eq!(window.dropped, 2);
+    }
+
+    #[test]
+    fn test_context_window_is_contiguous_and_reserves_budget() {
+        let messages = vec![
+            message("user", "a"),
+            message("assistant", "bbbbbbbbbb"),
+            message("user", "cc"),
+        ];
+
+        // A mensagem longa não cabe; a mais antiga, embora curta, também é descartada
+        let window = ContextWindowBuilder::new(5)
+            .with_counter(CharCounter)
+            .build(&messages);
+        let contents: Vec<&str> = window.messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, vec!["cc"]);
+
+        // Tokens reservados reduzem o orçamento disponível
+        let window = ContextWindowBuilder::new(5)
+            .with_counter(CharCounter)
+            .build_with_reserved(&messages, 4);
+        assert!(window.messages.is_empty());
+        assert_eq!(window.dropped, 3);
+    }
+
+    #[test]
+    fn test_context_window_keeps_tool_call_groups_together() {
+        let call = |id: &str| crate::chat::ToolCall {
+            id: id.to_string(),
+            name: "clima".to_string(),
+            arguments: serde_json::json!({}),
+        };
+        let messages = vec![
+            message("user", "aa"),
+            ConversationMessage::from_chat(&ChatMessage::assistant_tool_calls(
+                "b",
+                vec![call("call_1"), call("call_2")],
+            )),
+            ConversationMessage::from_chat(&ChatMessage::tool("call_1", "ccc")),
+            ConversationMessage::from_chat(&ChatMessage::tool("call_2", "ddd")),
+            message("assistant", "ee"),
+        ];
+
+        // O limite cai dentro do grupo: os resultados caberiam, mas sem a chamada
+        let window = ContextWindowBuilder::new(8)
+            .with_counter(CharCounter)
+            .build(&messages);
+        let contents: Vec<&str> = window.messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, vec!["ee"]);
+        assert_eq!(window.dropped, 4);
+
+        // Com orçamento para o grupo inteiro, chamada e resultados são mantidos
+        let window = ContextWindowBuilder::new(9)
+            .with_counter(CharCounter)
+            .build(&messages);
+        let roles: Vec<&str> = window.messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, vec!["assistant", "tool", "tool", "assistant"]);
+        assert!(window.chat_messages().is_ok());
+    }
 }
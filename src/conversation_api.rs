@@ -0,0 +1,234 @@
+//! # Modelo de Resposta da API de Conversas
+//!
+//! Este módulo define os tipos serializados pelos endpoints `/conversation`
+//! do servidor avançado. Eles podem ser usados pelos clientes para
+//! desserializar as respostas.
+//!
+//! - Timestamps são absolutos, no formato RFC 3339 (UTC)
+//! - Mensagens têm IDs estáveis e são ordenadas cronologicamente
+//! - Metadados são serializados com as chaves em ordem alfabética
+//! - Respostas de nível superior incluem `schema_version`, incrementado a cada
+//!   mudança incompatível do formato
+//!
+//! ## Exemplo de Uso
+//!
+//! ```rust
+//! use mcprs::conversation::Conversation;
+//! use mcprs::conversation_api::{ConversationResponse, CONVERSATION_SCHEMA_VERSION};
+//!
+//! let mut conversation = Conversation::new();
+//! conversation.add_message("user", "Olá");
+//!
+//! let response = ConversationResponse::from(&conversation);
+//! assert_eq!(response.schema_version, CONVERSATION_SCHEMA_VERSION);
+//! assert_eq!(response.messages[0].content, "Olá");
+//!
+//! let json = serde_json::to_value(&response).unwrap();
+//! assert!(json["created_at"].as_str().unwrap().ends_with('Z'));
+//! ```
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::time::SystemTime;
+
+use crate::conversation::{Conversation, ConversationMessage, ConversationPage};
+
+/// Versão atual do formato das respostas de conversa.
+pub const CONVERSATION_SCHEMA_VERSION: u32 = 1;
+
+/// Uma mensagem de conversa como exposta pela API.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MessageResponse {
+    /// ID único da mensagem
+    pub id: String,
+
+    /// Papel do remetente (user, assistant, system)
+    pub role: String,
+
+    /// Conteúdo da mensagem
+    pub content: String,
+
+    /// Momento em que a mensagem foi criada
+    #[serde(with = "rfc3339")]
+    pub timestamp: DateTime<Utc>,
+}
+
+impl From<&ConversationMessage> for MessageResponse {
+    fn from(message: &ConversationMessage) -> Self {
+        Self {
+            id: message.id.clone(),
+            role: message.role.clone(),
+            content: message.content.clone(),
+            timestamp: to_datetime(message.timestamp),
+        }
+    }
+}
+
+/// Resumo de uma conversa, sem as mensagens, usado na listagem.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ConversationSummary {
+    /// ID da conversa
+    pub conversation_id: String,
+
+    /// Número de mensagens na conversa
+    pub message_count: usize,
+
+    /// Metadados da conversa
+    pub metadata: BTreeMap<String, String>,
+
+    /// Momento de criação da conversa
+    #[serde(with = "rfc3339")]
+    pub created_at: DateTime<Utc>,
+
+    /// Momento da última atualização da conversa
+    #[serde(with = "rfc3339")]
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<&Conversation> for ConversationSummary {
+    fn from(conversation: &Conversation) -> Self {
+        Self {
+            conversation_id: conversation.id.clone(),
+            message_count: conversation.messages.len(),
+            metadata: conversation
+                .metadata
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+            created_at: to_datetime(conversation.created_at),
+            updated_at: to_datetime(conversation.updated_at),
+        }
+    }
+}
+
+/// Uma conversa completa, com suas mensagens.
+///
+/// Retornada pelos endpoints que criam, consultam ou alteram uma conversa.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ConversationResponse {
+    /// Versão do formato da resposta
+    pub schema_version: u32,
+
+    /// Dados da conversa
+    #[serde(flatten)]
+    pub summary: ConversationSummary,
+
+    /// Mensagens em ordem cronológica
+    pub messages: Vec<MessageResponse>,
+}
+
+impl From<&Conversation> for ConversationResponse {
+    fn from(conversation: &Conversation) -> Self {
+        Self {
+            schema_version: CONVERSATION_SCHEMA_VERSION,
+            summary: ConversationSummary::from(conversation),
+            messages: conversation
+                .messages
+                .iter()
+                .map(MessageResponse::from)
+                .collect(),
+        }
+    }
+}
+
+/// Uma página da listagem de conversas.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ConversationListResponse {
+    /// Versão do formato da resposta
+    pub schema_version: u32,
+
+    /// Conversas da página, da atualizada mais recentemente para a mais antiga
+    pub conversations: Vec<ConversationSummary>,
+
+    /// Número total de conversas do usuário
+    pub total: usize,
+
+    /// Número de conversas puladas
+    pub offset: usize,
+
+    /// Tamanho máximo da página
+    pub limit: usize,
+}
+
+impl ConversationListResponse {
+    /// Cria a resposta a partir de uma página de conversas.
+    pub fn new(page: &ConversationPage, offset: usize, limit: usize) -> Self {
+        Self {
+            schema_version: CONVERSATION_SCHEMA_VERSION,
+            conversations: page
+                .conversations
+                .iter()
+                .map(ConversationSummary::from)
+                .collect(),
+            total: page.total,
+            offset,
+            limit,
+        }
+    }
+}
+
+/// Converte um `SystemTime` em data UTC.
+fn to_datetime(time: SystemTime) -> DateTime<Utc> {
+    DateTime::<Utc>::from(time)
+}
+
+/// Serialização RFC 3339 com precisão de milissegundos e sufixo `Z`.
+mod rfc3339 {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        time: &DateTime<Utc>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&time.to_rfc3339_opts(SecondsFormat::Millis, true))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<DateTime<Utc>, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        DateTime::parse_from_rfc3339(&value)
+            .map(|time| time.with_timezone(&Utc))
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn test_timestamps_are_rfc3339() {
+        let mut conversation = Conversation::new();
+        conversation.created_at = UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+        conversation.add_message("user", "Olá");
+
+        let json = serde_json::to_value(ConversationResponse::from(&conversation)).unwrap();
+        assert_eq!(json["created_at"], "2023-11-14T22:13:20.123Z");
+        assert_eq!(json["schema_version"], CONVERSATION_SCHEMA_VERSION);
+        assert_eq!(json["messages"][0]["id"], conversation.messages[0].id);
+
+        // O formato é reversível para os clientes
+        let parsed: ConversationResponse = serde_json::from_value(json).unwrap();
+        assert_eq!(
+            parsed.summary.created_at.timestamp_millis(),
+            1_700_000_000_123
+        );
+    }
+
+    #[test]
+    fn test_metadata_keys_are_sorted() {
+        let mut conversation = Conversation::new();
+        for key in ["zeta", "alfa", "meio"] {
+            conversation.set_metadata(key, "x");
+        }
+
+        let json = serde_json::to_string(&ConversationSummary::from(&conversation)).unwrap();
+        let alfa = json.find("alfa").unwrap();
+        let meio = json.find("meio").unwrap();
+        let zeta = json.find("zeta").unwrap();
+        assert!(alfa < meio && meio < zeta);
+    }
+}
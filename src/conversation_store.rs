@@ -0,0 +1,689 @@
+//! # Armazenamento de Conversas
+//!
+//! Este módulo define a trait [`ConversationStore`], que abstrai onde o
+//! [`ConversationManager`](crate::conversation::ConversationManager) guarda as
+//! conversas, e fornece três implementações:
+//!
+//! - [`InMemoryConversationStore`]: mapa em memória (padrão, perdido ao reiniciar)
+//! - [`JsonLinesConversationStore`]: arquivo JSON-lines com log de operações
+//! - [`SqliteConversationStore`]: banco SQLite embarcado (feature `sqlite`)
+//!
+//! ## Exemplo de Uso
+//!
+//! ```rust,no_run
+//! use mcprs::conversation::ConversationManager;
+//! use mcprs::conversation_store::JsonLinesConversationStore;
+//!
+//! // As conversas sobrevivem a reinicializações do servidor
+//! let store = JsonLinesConversationStore::open("conversas.jsonl").unwrap();
+//! let manager = ConversationManager::with_store(24, store);
+//!
+//! let conversation = manager.create_conversation().unwrap();
+//! manager
+//!     .add_message_to_conversation(&conversation.id, "user", "Olá!")
+//!     .unwrap();
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+
+use crate::conversation::{Conversation, ConversationMessage};
+
+/// Backend de armazenamento de conversas.
+///
+/// As operações retornam `Err(String)` com uma mensagem descritiva em caso de
+/// falha, seguindo a convenção do `ConversationManager`.
+pub trait ConversationStore: Send + Sync {
+    /// Insere ou substitui uma conversa.
+    fn save(&self, conversation: &Conversation) -> Result<(), String>;
+
+    /// Recupera uma conversa pelo ID.
+    fn load(&self, id: &str) -> Result<Option<Conversation>, String>;
+
+    /// Remove uma conversa, retornando `true` se ela existia.
+    fn delete(&self, id: &str) -> Result<bool, String>;
+
+    /// Lista todas as conversas armazenadas.
+    fn list(&self) -> Result<Vec<Conversation>, String>;
+
+    /// Adiciona uma mensagem ao final de uma conversa existente.
+    ///
+    /// A implementação padrão carrega, modifica e salva a conversa; backends
+    /// que suportam atualizações atômicas devem sobrescrevê-la.
+    ///
+    /// # Retorna
+    /// * `Ok(true)` - Se a conversa existia e foi atualizada
+    /// * `Ok(false)` - Se a conversa não existe
+    fn append_message(&self, id: &str, message: ConversationMessage) -> Result<bool, String> {
+        match self.load(id)? {
+            Some(mut conversation) => {
+                conversation.updated_at = message.timestamp;
+                conversation.messages.push(message);
+                self.save(&conversation)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Remove as conversas para as quais `keep` retorna `false`.
+    ///
+    /// # Retorna
+    /// O número de conversas removidas
+    fn retain(&self, keep: &dyn Fn(&Conversation) -> bool) -> Result<usize, String> {
+        let mut removed = 0;
+        for conversation in self.list()? {
+            if !keep(&conversation) && self.delete(&conversation.id)? {
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+}
+
+/// Armazenamento em memória, baseado em um `HashMap` protegido por `RwLock`.
+///
+/// É o backend padrão do `ConversationManager`. Os dados são perdidos quando
+/// o processo termina.
+#[derive(Default)]
+pub struct InMemoryConversationStore {
+    /// Mapa de ID para objeto Conversation
+    conversations: RwLock<HashMap<String, Conversation>>,
+}
+
+impl InMemoryConversationStore {
+    /// Cria um armazenamento em memória vazio.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ConversationStore for InMemoryConversationStore {
+    fn save(&self, conversation: &Conversation) -> Result<(), String> {
+        let mut conversations = self.conversations.write().map_err(lock_error)?;
+        conversations.insert(conversation.id.clone(), conversation.clone());
+        Ok(())
+    }
+
+    fn load(&self, id: &str) -> Result<Option<Conversation>, String> {
+        let conversations = self.conversations.read().map_err(lock_error)?;
+        Ok(conversations.get(id).cloned())
+    }
+
+    fn delete(&self, id: &str) -> Result<bool, String> {
+        let mut conversations = self.conversations.write().map_err(lock_error)?;
+        Ok(conversations.remove(id).is_some())
+    }
+
+    fn list(&self) -> Result<Vec<Conversation>, String> {
+        let conversations = self.conversations.read().map_err(lock_error)?;
+        Ok(conversations.values().cloned().collect())
+    }
+
+    fn append_message(&self, id: &str, message: ConversationMessage) -> Result<bool, String> {
+        let mut conversations = self.conversations.write().map_err(lock_error)?;
+        match conversations.get_mut(id) {
+            Some(conversation) => {
+                conversation.updated_at = message.timestamp;
+                conversation.messages.push(message);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn retain(&self, keep: &dyn Fn(&Conversation) -> bool) -> Result<usize, String> {
+        let mut conversations = self.conversations.write().map_err(lock_error)?;
+        let before = conversations.len();
+        conversations.retain(|_, conversation| keep(conversation));
+        Ok(before - conversations.len())
+    }
+}
+
+/// Registro de uma operação no arquivo JSON-lines.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum JsonLinesRecord {
+    /// Estado completo de uma conversa após uma alteração
+    Put { conversation: Conversation },
+
+    /// Mensagem adicionada ao final de uma conversa
+    Append {
+        id: String,
+        message: ConversationMessage,
+    },
+
+    /// Remoção de uma conversa
+    Delete { id: String },
+}
+
+/// Estado interno do armazenamento JSON-lines.
+struct JsonLinesState {
+    /// Cópia em memória do estado atual
+    conversations: HashMap<String, Conversation>,
+
+    /// Arquivo aberto em modo append
+    writer: BufWriter<File>,
+}
+
+/// Armazenamento em arquivo no formato JSON-lines.
+///
+/// Cada alteração é anexada ao arquivo como uma linha JSON (`put` com o estado
+/// completo da conversa, `append` com uma nova mensagem ou `delete`), e o
+/// arquivo é reprocessado ao abrir. Adicionar uma mensagem grava apenas a
+/// mensagem, então o arquivo cresce com o volume de mensagens. Use
+/// [`JsonLinesConversationStore::compact`] para reescrever o arquivo apenas
+/// com o estado atual, descartando conversas removidas e estados antigos.
+pub struct JsonLinesConversationStore {
+    /// Caminho do arquivo
+    path: PathBuf,
+
+    /// Estado protegido por mutex (leitura e escrita serializadas)
+    state: Mutex<JsonLinesState>,
+}
+
+impl JsonLinesConversationStore {
+    /// Abre (ou cria) um armazenamento no caminho informado.
+    ///
+    /// Uma última linha sem o `\n` final (de uma escrita interrompida) é
+    /// descartada, e o arquivo é truncado no fim da última linha completa
+    /// para que os próximos registros comecem em uma linha nova.
+    ///
+    /// # Erros
+    /// Retorna `Err(String)` se o arquivo não puder ser lido, truncado ou
+    /// criado, ou se uma linha completa for inválida (arquivo corrompido).
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref().to_path_buf();
+        let mut conversations = HashMap::new();
+
+        if path.exists() {
+            let contents = fs::read(&path).map_err(|e| io_error(&path, e))?;
+
+            // Apenas linhas terminadas em `\n` foram gravadas por completo
+            let complete_len = contents
+                .iter()
+                .rposition(|byte| *byte == b'\n')
+                .map_or(0, |pos| pos + 1);
+            for (number, line) in contents[..complete_len]
+                .split(|byte| *byte == b'\n')
+                .enumerate()
+            {
+                if line.iter().all(u8::is_ascii_whitespace) {
+                    continue;
+                }
+                let record = serde_json::from_slice::<JsonLinesRecord>(line).map_err(|e| {
+                    format!("Linha {} inválida em {}: {}", number + 1, path.display(), e)
+                })?;
+                apply_record(&mut conversations, record);
+            }
+
+            if complete_len < contents.len() {
+                tracing::warn!("Linha incompleta descartada no final de {}", path.display());
+                OpenOptions::new()
+                    .write(true)
+                    .open(&path)
+                    .and_then(|file| file.set_len(complete_len as u64))
+                    .map_err(|e| io_error(&path, e))?;
+            }
+        }
+
+        let writer = BufWriter::new(Self::open_append(&path)?);
+
+        Ok(Self {
+            path,
+            state: Mutex::new(JsonLinesState {
+                conversations,
+                writer,
+            }),
+        })
+    }
+
+    /// Reescreve o arquivo contendo apenas o estado atual das conversas.
+    ///
+    /// A escrita é feita em um arquivo temporário que substitui o original
+    /// ao final, para que uma falha não corrompa os dados existentes.
+    pub fn compact(&self) -> Result<(), String> {
+        let mut state = self.state.lock().map_err(lock_error)?;
+
+        let tmp_path = self.path.with_extension("jsonl.tmp");
+        {
+            let file = File::create(&tmp_path).map_err(|e| io_error(&tmp_path, e))?;
+            let mut writer = BufWriter::new(file);
+            for conversation in state.conversations.values() {
+                write_record(
+                    &mut writer,
+                    &JsonLinesRecord::Put {
+                        conversation: conversation.clone(),
+                    },
+                )?;
+            }
+            writer.flush().map_err(|e| io_error(&tmp_path, e))?;
+        }
+        fs::rename(&tmp_path, &self.path).map_err(|e| io_error(&self.path, e))?;
+
+        state.writer = BufWriter::new(Self::open_append(&self.path)?);
+        Ok(())
+    }
+
+    fn open_append(path: &Path) -> Result<File, String> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| io_error(path, e))
+    }
+}
+
+impl JsonLinesState {
+    /// Anexa um registro ao arquivo e o aplica ao estado em memória.
+    fn apply(&mut self, record: JsonLinesRecord) -> Result<(), String> {
+        write_record(&mut self.writer, &record)?;
+        self.writer
+            .flush()
+            .map_err(|e| format!("Falha ao gravar conversa: {}", e))?;
+
+        apply_record(&mut self.conversations, record);
+        Ok(())
+    }
+}
+
+/// Aplica um registro do arquivo JSON-lines ao estado em memória.
+fn apply_record(conversations: &mut HashMap<String, Conversation>, record: JsonLinesRecord) {
+    match record {
+        JsonLinesRecord::Put { conversation } => {
+            conversations.insert(conversation.id.clone(), conversation);
+        }
+        JsonLinesRecord::Append { id, message } => {
+            if let Some(conversation) = conversations.get_mut(&id) {
+                conversation.updated_at = message.timestamp;
+                conversation.messages.push(message);
+            }
+        }
+        JsonLinesRecord::Delete { id } => {
+            conversations.remove(&id);
+        }
+    }
+}
+
+impl ConversationStore for JsonLinesConversationStore {
+    fn save(&self, conversation: &Conversation) -> Result<(), String> {
+        let mut state = self.state.lock().map_err(lock_error)?;
+        state.apply(JsonLinesRecord::Put {
+            conversation: conversation.clone(),
+        })
+    }
+
+    fn load(&self, id: &str) -> Result<Option<Conversation>, String> {
+        let state = self.state.lock().map_err(lock_error)?;
+        Ok(state.conversations.get(id).cloned())
+    }
+
+    fn delete(&self, id: &str) -> Result<bool, String> {
+        let mut state = self.state.lock().map_err(lock_error)?;
+        if !state.conversations.contains_key(id) {
+            return Ok(false);
+        }
+        state.apply(JsonLinesRecord::Delete { id: id.to_string() })?;
+        Ok(true)
+    }
+
+    fn list(&self) -> Result<Vec<Conversation>, String> {
+        let state = self.state.lock().map_err(lock_error)?;
+        Ok(state.conversations.values().cloned().collect())
+    }
+
+    fn append_message(&self, id: &str, message: ConversationMessage) -> Result<bool, String> {
+        let mut state = self.state.lock().map_err(lock_error)?;
+        if !state.conversations.contains_key(id) {
+            return Ok(false);
+        }
+        state.apply(JsonLinesRecord::Append {
+            id: id.to_string(),
+            message,
+        })?;
+        Ok(true)
+    }
+}
+
+/// Serializa um registro como uma linha JSON.
+fn write_record(writer: &mut impl Write, record: &JsonLinesRecord) -> Result<(), String> {
+    serde_json::to_writer(&mut *writer, record)
+        .map_err(|e| format!("Falha ao serializar conversa: {}", e))?;
+    writer
+        .write_all(b"\n")
+        .map_err(|e| format!("Falha ao gravar conversa: {}", e))
+}
+
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteConversationStore;
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::*;
+    use rusqlite::{params, Connection, OptionalExtension};
+    use std::time::UNIX_EPOCH;
+
+    /// Armazenamento em um banco SQLite embarcado.
+    ///
+    /// Cada conversa é armazenada como um documento JSON na tabela
+    /// `conversations`, indexada pelo ID e pela data de atualização.
+    /// Disponível com a feature opcional `sqlite`.
+    pub struct SqliteConversationStore {
+        /// Conexão com o banco, serializada por mutex
+        connection: Mutex<Connection>,
+    }
+
+    impl SqliteConversationStore {
+        /// Abre (ou cria) um banco SQLite no caminho informado.
+        ///
+        /// # Exemplo
+        ///
+        /// ```rust,no_run
+        /// use mcprs::conversation::ConversationManager;
+        /// use mcprs::conversation_store::SqliteConversationStore;
+        ///
+        /// let store = SqliteConversationStore::open("conversas.db").unwrap();
+        /// let manager = ConversationManager::with_store(24, store);
+        /// ```
+        pub fn open(path: impl AsRef<Path>) -> Result<Self, String> {
+            let connection = Connection::open(path.as_ref())
+                .map_err(|e| format!("Falha ao abrir banco SQLite: {}", e))?;
+            Self::initialize(connection)
+        }
+
+        /// Cria um banco SQLite em memória, útil para testes.
+        pub fn open_in_memory() -> Result<Self, String> {
+            let connection = Connection::open_in_memory()
+                .map_err(|e| format!("Falha ao abrir banco SQLite: {}", e))?;
+            Self::initialize(connection)
+        }
+
+        fn initialize(connection: Connection) -> Result<Self, String> {
+            connection
+                .execute_batch(
+                    "CREATE TABLE IF NOT EXISTS conversations (
+                        id TEXT PRIMARY KEY,
+                        data TEXT NOT NULL,
+                        updated_at INTEGER NOT NULL
+                    );
+                    CREATE INDEX IF NOT EXISTS conversations_updated_at
+                        ON conversations (updated_at);",
+                )
+                .map_err(sqlite_error)?;
+
+            Ok(Self {
+                connection: Mutex::new(connection),
+            })
+        }
+
+        fn write(connection: &Connection, conversation: &Conversation) -> Result<(), String> {
+            let data = serde_json::to_string(conversation)
+                .map_err(|e| format!("Falha ao serializar conversa: {}", e))?;
+            let updated_at = conversation
+                .updated_at
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs() as i64)
+                .unwrap_or_default();
+
+            connection
+                .execute(
+                    "INSERT INTO conversations (id, data, updated_at) VALUES (?1, ?2, ?3)
+                     ON CONFLICT(id) DO UPDATE SET data = excluded.data, updated_at = excluded.updated_at",
+                    params![conversation.id, data, updated_at],
+                )
+                .map_err(sqlite_error)?;
+            Ok(())
+        }
+
+        fn read(connection: &Connection, id: &str) -> Result<Option<Conversation>, String> {
+            let data: Option<String> = connection
+                .query_row(
+                    "SELECT data FROM conversations WHERE id = ?1",
+                    params![id],
+                    |row| row.get(0),
+                )
+                .optional()
+                .map_err(sqlite_error)?;
+
+            data.map(|data| deserialize(&data)).transpose()
+        }
+    }
+
+    impl ConversationStore for SqliteConversationStore {
+        fn save(&self, conversation: &Conversation) -> Result<(), String> {
+            let connection = self.connection.lock().map_err(lock_error)?;
+            Self::write(&connection, conversation)
+        }
+
+        fn load(&self, id: &str) -> Result<Option<Conversation>, String> {
+            let connection = self.connection.lock().map_err(lock_error)?;
+            Self::read(&connection, id)
+        }
+
+        fn delete(&self, id: &str) -> Result<bool, String> {
+            let connection = self.connection.lock().map_err(lock_error)?;
+            let affected = connection
+                .execute("DELETE FROM conversations WHERE id = ?1", params![id])
+                .map_err(sqlite_error)?;
+            Ok(affected > 0)
+        }
+
+        fn list(&self) -> Result<Vec<Conversation>, String> {
+            let connection = self.connection.lock().map_err(lock_error)?;
+            let mut statement = connection
+                .prepare("SELECT data FROM conversations")
+                .map_err(sqlite_error)?;
+            let rows = statement
+                .query_map([], |row| row.get::<_, String>(0))
+                .map_err(sqlite_error)?;
+
+            rows.map(|data| deserialize(&data.map_err(sqlite_error)?))
+                .collect()
+        }
+
+        fn append_message(&self, id: &str, message: ConversationMessage) -> Result<bool, String> {
+            let mut connection = self.connection.lock().map_err(lock_error)?;
+            let transaction = connection.transaction().map_err(sqlite_error)?;
+
+            let Some(mut conversation) = Self::read(&transaction, id)? else {
+                return Ok(false);
+            };
+            conversation.updated_at = message.timestamp;
+            conversation.messages.push(message);
+            Self::write(&transaction, &conversation)?;
+
+            transaction.commit().map_err(sqlite_error)?;
+            Ok(true)
+        }
+    }
+
+    fn deserialize(data: &str) -> Result<Conversation, String> {
+        serde_json::from_str(data).map_err(|e| format!("Conversa armazenada inválida: {}", e))
+    }
+
+    fn sqlite_error(e: rusqlite::Error) -> String {
+        format!("Erro no SQLite: {}", e)
+    }
+}
+
+fn lock_error<T>(_: T) -> String {
+    "Falha ao adquirir lock".to_string()
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> String {
+    format!("Erro de E/S em {}: {}", path.display(), e)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("mcprs-{}-{}.jsonl", name, uuid::Uuid::new_v4()))
+    }
+
+    fn message(role: &str, content: &str) -> ConversationMessage {
+        ConversationMessage::new(role, content)
+    }
+
+    /// Exercita o contrato básico da trait em qualquer backend
+    fn exercise_store(store: &dyn ConversationStore) {
+        let conversation = Conversation::new();
+        store.save(&conversation).unwrap();
+
+        assert!(store
+            .append_message(&conversation.id, message("user", "Olá"))
+            .unwrap());
+        assert!(!store
+            .append_message("inexistente", message("user", "Olá"))
+            .unwrap());
+
+        let loaded = store.load(&conversation.id).unwrap().unwrap();
+        assert_eq!(loaded.messages.len(), 1);
+        assert_eq!(loaded.messages[0].content, "Olá");
+        assert_eq!(store.list().unwrap().len(), 1);
+
+        let other = Conversation::new();
+        store.save(&other).unwrap();
+        let removed = store.retain(&|c| c.id != other.id).unwrap();
+        assert_eq!(removed, 1);
+        assert!(store.load(&other.id).unwrap().is_none());
+
+        assert!(store.delete(&conversation.id).unwrap());
+        assert!(!store.delete(&conversation.id).unwrap());
+        assert!(store.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_in_memory_store() {
+        exercise_store(&InMemoryConversationStore::new());
+    }
+
+    #[test]
+    fn test_json_lines_store() {
+        let path = temp_path("contract");
+        exercise_store(&JsonLinesConversationStore::open(&path).unwrap());
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_json_lines_store_survives_reopen_and_compaction() {
+        let path = temp_path("reopen");
+
+        let mut conversation = Conversation::new();
+        conversation.set_metadata("idioma", "pt-br");
+        let removed = Conversation::new();
+        {
+            let store = JsonLinesConversationStore::open(&path).unwrap();
+            store.save(&conversation).unwrap();
+            store.save(&removed).unwrap();
+            store
+                .append_message(&conversation.id, message("user", "Olá"))
+                .unwrap();
+            store.delete(&removed.id).unwrap();
+        }
+
+        // Cada mensagem é gravada sozinha, sem repetir a conversa inteira
+        let contents = fs::read_to_string(&path).unwrap();
+        let append = contents.lines().nth(2).unwrap();
+        assert!(append.contains("\"op\":\"append\""));
+        assert!(!append.contains("idioma"));
+
+        let store = JsonLinesConversationStore::open(&path).unwrap();
+        let loaded = store.load(&conversation.id).unwrap().unwrap();
+        assert_eq!(loaded.messages.len(), 1);
+        assert_eq!(loaded.metadata.get("idioma").unwrap(), "pt-br");
+        assert!(store.load(&removed.id).unwrap().is_none());
+
+        // Após compactar, o arquivo contém apenas o estado atual
+        store.compact().unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        assert_eq!(contents.lines().count(), 1);
+
+        store
+            .append_message(&conversation.id, message("assistant", "Oi!"))
+            .unwrap();
+        drop(store);
+
+        let store = JsonLinesConversationStore::open(&path).unwrap();
+        assert_eq!(
+            store
+                .load(&conversation.id)
+                .unwrap()
+                .unwrap()
+                .messages
+                .len(),
+            2
+        );
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_json_lines_store_recovers_from_partial_line() {
+        let path = temp_path("partial");
+
+        let conversation = Conversation::new();
+        {
+            let store = JsonLinesConversationStore::open(&path).unwrap();
+            store.save(&conversation).unwrap();
+        }
+
+        // Simula uma escrita interrompida no meio de um registro
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"op\":\"append\",\"id\":").unwrap();
+        drop(file);
+
+        {
+            let store = JsonLinesConversationStore::open(&path).unwrap();
+            store
+                .append_message(&conversation.id, message("user", "Olá"))
+                .unwrap();
+        }
+
+        // O registro novo não é gravado na mesma linha do registro incompleto
+        let store = JsonLinesConversationStore::open(&path).unwrap();
+        let loaded = store.load(&conversation.id).unwrap().unwrap();
+        assert_eq!(loaded.messages.len(), 1);
+        assert_eq!(loaded.messages[0].content, "Olá");
+        drop(store);
+
+        // Linhas completas inválidas indicam corrupção e não são ignoradas
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"lixo\n").unwrap();
+        drop(file);
+        let error = JsonLinesConversationStore::open(&path).err().unwrap();
+        assert!(error.contains("Linha 3"));
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_store() {
+        exercise_store(&SqliteConversationStore::open_in_memory().unwrap());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_store_survives_reopen() {
+        let path = std::env::temp_dir().join(format!("mcprs-{}.db", uuid::Uuid::new_v4()));
+
+        let conversation = Conversation::new();
+        {
+            let store = SqliteConversationStore::open(&path).unwrap();
+            store.save(&conversation).unwrap();
+            store
+                .append_message(&conversation.id, message("user", "Persistente"))
+                .unwrap();
+        }
+
+        let store = SqliteConversationStore::open(&path).unwrap();
+        let loaded = store.load(&conversation.id).unwrap().unwrap();
+        assert_eq!(loaded.messages[0].content, "Persistente");
+        let _ = fs::remove_file(&path);
+    }
+}
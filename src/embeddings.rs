@@ -0,0 +1,244 @@
+//! # Embeddings
+//!
+//! Este módulo define o formato comum das requisições e respostas da ação
+//! `embeddings` (ex: `openai:embeddings`, `ollama:embeddings`), que gera
+//! vetores de embeddings para um ou mais textos em uma única chamada.
+//!
+//! ## Payload da Requisição
+//!
+//! - `input` - Um texto ou uma lista de textos (até [`MAX_EMBEDDING_INPUTS`])
+//! - `model` - Modelo de embeddings; deve ser o padrão do agente ou estar em
+//!   `allowed_models` (opcional)
+//! - `dimensions` - Número de dimensões dos vetores, para modelos que
+//!   permitem reduzi-lo (opcional)
+//!
+//! ## Payload da Resposta
+//!
+//! - `embeddings` - Um vetor por texto, na ordem de `input`
+//! - `dimensions` - Número de dimensões dos vetores
+//! - `model` - O modelo que gerou os vetores
+//! - `usage` - Tokens consumidos, no formato de [`Usage`] (`output_tokens` é sempre 0)
+//!
+//! ## Exemplo de Uso
+//!
+//! ```rust,no_run
+//! use mcprs::agent::{AgentRegistry, MCPMessage};
+//! use mcprs::agent_openai::create_openai_agent;
+//! use serde_json::json;
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut registry = AgentRegistry::new();
+//! registry.register_agent(Box::new(create_openai_agent(None)));
+//!
+//! let message = MCPMessage::new(
+//!     "openai:embeddings",
+//!     json!({ "input": ["Rust é rápido", "Rust é seguro"] }),
+//! );
+//! let response = registry.process(message).await?;
+//! println!("Dimensões: {}", response.payload["dimensions"]);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::agent::MCPError;
+use crate::usage::Usage;
+use serde_json::{json, Value};
+
+/// Número máximo de textos em uma requisição de embeddings
+pub const MAX_EMBEDDING_INPUTS: usize = 2048;
+
+/// Requisição de embeddings normalizada a partir de um payload MCP.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmbeddingRequest {
+    /// Textos a converter, na ordem em que os vetores serão retornados
+    pub input: Vec<String>,
+
+    /// Número de dimensões pedido, se informado
+    pub dimensions: Option<u32>,
+}
+
+impl EmbeddingRequest {
+    /// Interpreta o payload de uma requisição de embeddings.
+    ///
+    /// # Erros
+    /// * `MCPError::PayloadValidation` - Se `input` estiver ausente, vazio, com
+    ///   mais de [`MAX_EMBEDDING_INPUTS`] textos ou com itens que não sejam
+    ///   strings, ou se `dimensions` não for um inteiro positivo
+    ///
+    /// # Exemplo
+    ///
+    /// ```
+    /// use mcprs::embeddings::EmbeddingRequest;
+    /// use serde_json::json;
+    ///
+    /// let request = EmbeddingRequest::from_payload(&json!({ "input": "Olá" })).unwrap();
+    /// assert_eq!(request.input, vec!["Olá"]);
+    /// ```
+    pub fn from_payload(payload: &Value) -> Result<Self, MCPError> {
+        let input = match payload.get("input") {
+            Some(Value::String(text)) => vec![text.clone()],
+            Some(Value::Array(items)) => items
+                .iter()
+                .map(|item| item.as_str().map(str::to_string))
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| {
+                    MCPError::PayloadValidation(
+                        "Campo 'input' deve ser uma string ou uma lista de strings".to_string(),
+                    )
+                })?,
+            None | Some(Value::Null) => {
+                return Err(MCPError::PayloadValidation("Missing input".to_string()))
+            }
+            Some(_) => {
+                return Err(MCPError::PayloadValidation(
+                    "Campo 'input' deve ser uma string ou uma lista de strings".to_string(),
+                ))
+            }
+        };
+
+        if input.is_empty() {
+            return Err(MCPError::PayloadValidation(
+                "Campo 'input' não pode ser vazio".to_string(),
+            ));
+        }
+        if input.len() > MAX_EMBEDDING_INPUTS {
+            return Err(MCPError::PayloadValidation(format!(
+                "Campo 'input' aceita no máximo {} textos",
+                MAX_EMBEDDING_INPUTS
+            )));
+        }
+
+        let dimensions = match payload.get("dimensions") {
+            None | Some(Value::Null) => None,
+            Some(value) => Some(
+                value
+                    .as_u64()
+                    .filter(|n| *n > 0)
+                    .and_then(|n| u32::try_from(n).ok())
+                    .ok_or_else(|| {
+                        MCPError::PayloadValidation(
+                            "Campo 'dimensions' deve ser um inteiro positivo".to_string(),
+                        )
+                    })?,
+            ),
+        };
+
+        Ok(Self { input, dimensions })
+    }
+}
+
+/// Vetores de embeddings retornados por um provedor.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Embeddings {
+    /// O modelo que gerou os vetores
+    pub model: String,
+
+    /// Um vetor por texto, na ordem da requisição
+    pub vectors: Vec<Vec<f64>>,
+
+    /// Tokens consumidos, se informados pelo provedor
+    pub usage: Option<Usage>,
+}
+
+impl Embeddings {
+    /// Número de dimensões dos vetores (0 se não houver vetores).
+    pub fn dimensions(&self) -> usize {
+        self.vectors.first().map_or(0, Vec::len)
+    }
+
+    /// Confere se há um vetor por texto e se todos têm a mesma dimensão.
+    ///
+    /// # Retorna
+    /// A descrição da inconsistência, usada nos erros de resposta inválida
+    pub fn check(&self, request: &EmbeddingRequest) -> Result<(), String> {
+        if self.vectors.len() != request.input.len() {
+            return Err(format!(
+                "{} vetores para {} textos",
+                self.vectors.len(),
+                request.input.len()
+            ));
+        }
+        let dimensions = self.dimensions();
+        if self.vectors.iter().any(|v| v.len() != dimensions) {
+            return Err("vetores com dimensões diferentes".to_string());
+        }
+        Ok(())
+    }
+
+    /// Converte para o payload normalizado da resposta.
+    pub fn to_payload(&self) -> Value {
+        json!({
+            "embeddings": self.vectors,
+            "dimensions": self.dimensions(),
+            "model": self.model,
+            "usage": self.usage.map(|usage| usage.to_json()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embedding_request_from_payload() {
+        let request = EmbeddingRequest::from_payload(&json!({
+            "input": ["um", "dois"],
+            "dimensions": 256
+        }))
+        .unwrap();
+        assert_eq!(request.input, vec!["um", "dois"]);
+        assert_eq!(request.dimensions, Some(256));
+
+        for invalid in [
+            json!({}),
+            json!({ "input": [] }),
+            json!({ "input": ["um", 2] }),
+            json!({ "input": 42 }),
+            json!({ "input": "um", "dimensions": 0 }),
+            json!({ "input": vec!["x"; MAX_EMBEDDING_INPUTS + 1] }),
+        ] {
+            assert!(
+                matches!(
+                    EmbeddingRequest::from_payload(&invalid),
+                    Err(MCPError::PayloadValidation(_))
+                ),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_embeddings_payload_and_check() {
+        let request = EmbeddingRequest::from_payload(&json!({ "input": ["a", "b"] })).unwrap();
+        let embeddings = Embeddings {
+            model: "modelo".to_string(),
+            vectors: vec![vec![0.5, -0.25], vec![0.0, 1.0]],
+            usage: Some(Usage::new(4, 0)),
+        };
+
+        assert!(embeddings.check(&request).is_ok());
+        assert_eq!(
+            embeddings.to_payload(),
+            json!({
+                "embeddings": [[0.5, -0.25], [0.0, 1.0]],
+                "dimensions": 2,
+                "model": "modelo",
+                "usage": { "input_tokens": 4, "output_tokens": 0, "total_tokens": 4 }
+            })
+        );
+
+        let ragged = Embeddings {
+            vectors: vec![vec![0.5, -0.25], vec![1.0]],
+            ..embeddings.clone()
+        };
+        assert!(ragged.check(&request).is_err());
+
+        let missing = Embeddings {
+            vectors: vec![vec![0.5, -0.25]],
+            ..embeddings
+        };
+        assert!(missing.check(&request).is_err());
+    }
+}
@@ -6,7 +6,7 @@
 //! ## Funcionalidades Principais
 //!
 //! - **Protocolo Padronizado**: Define um formato comum para mensagens trocadas com diferentes LLMs
-//! - **Sistema de Agentes**: Abstração para integrações com diferentes APIs (OpenAI, DeepSeek, etc.)
+//! - **Sistema de Agentes**: Abstração para integrações com diferentes APIs (OpenAI, DeepSeek, Anthropic, etc.)
 //! - **Servidor e Cliente**: Implementações prontas para uso em aplicações
 //! - **Autenticação**: Sistema de autenticação baseado em tokens
 //! - **Histórico de Conversas**: Gerenciamento de contexto e histórico
@@ -63,29 +63,63 @@
 //! ## Módulos Principais
 //!
 //! - [`agent`]: Define a trait AIAgent e estruturas básicas do protocolo
+//! - [`chat`]: Modelo tipado de requisições de chat compartilhado pelos agentes
+//! - [`protocol`]: Validação do envelope MCP e negociação de versão
 //! - [`server`]: Implementação do servidor HTTP para processar requisições MCP
 //! - [`client`]: Funções para enviar requisições MCP
 //! - [`agent_openai`]: Implementação de agente para a API OpenAI
 //! - [`agent_deepseek`]: Implementação de agente para a API DeepSeek
+//! - [`agent_azure_openai`]: Implementação de agente para deployments do Azure OpenAI
+//! - [`agent_openai_compatible`]: Agente genérico configurável para APIs compatíveis com a OpenAI
+//! - [`agent_anthropic`]: Implementação de agente para a Messages API da Anthropic (Claude)
+//! - [`agent_gemini`]: Implementação de agente para a API Google Gemini
+//! - [`agent_mistral`]: Implementação de agente para a API Mistral
+//! - [`agent_cohere`]: Implementação de agente para a Chat API da Cohere
+//! - [`agent_ollama`]: Implementação de agente para modelos locais via Ollama
 //! - [`auth`]: Sistema de autenticação para o servidor
 //! - [`conversation`]: Gerenciamento de histórico de conversas
+//! - [`conversation_api`]: Tipos de resposta versionados dos endpoints de conversa
+//! - [`embeddings`]: Formato comum das requisições e respostas de embeddings
+//! - [`conversation_store`]: Backends de armazenamento de conversas (memória, JSON-lines, SQLite)
 //! - [`streaming`]: Suporte para respostas em streaming
+//! - [`structured`]: Saída estruturada em JSON com validação por JSON Schema
+//! - [`tools`]: Ferramentas em Rust executadas pelo servidor no ciclo de chamadas do modelo
+//! - [`usage`]: Consumo de tokens normalizado e tabela de preços para estimar custos
 
 pub mod agent;
+pub mod agent_anthropic;
+pub mod agent_azure_openai;
+pub mod agent_cohere;
 pub mod agent_deepseek;
+pub mod agent_gemini;
+pub mod agent_mistral;
+pub mod agent_ollama;
 pub mod agent_openai;
+pub mod agent_openai_compatible;
 pub mod auth;
+pub mod chat;
 pub mod client;
 pub mod conversation;
+pub mod conversation_api;
+pub mod conversation_store;
+pub mod embeddings;
+pub mod protocol;
 pub mod server;
 pub mod streaming;
+pub mod structured;
 pub mod testing;
+pub mod tools;
+pub mod usage;
 
 /// Re-exporta tipos comumente usados para facilitar o uso
 pub use agent::{AIAgent, AgentRegistry, MCPError, MCPMessage};
 pub use auth::{AuthConfig, AuthUser};
-pub use conversation::{Conversation, ConversationManager, ConversationMessage};
+pub use chat::{ChatMessage, ChatRequest, ChatRole};
+pub use conversation::{Conversation, ConversationManager, ConversationMessage, JanitorHandle};
+pub use conversation_store::ConversationStore;
 pub use streaming::{StreamingToken, TokenStream};
+pub use tools::{Tool, ToolExecutor, ToolRegistry};
+pub use usage::{PriceTable, Usage};
 
 // Exportação adicional de funções do servidor
 pub use server::run_http_server;
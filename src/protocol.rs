@@ -0,0 +1,146 @@
+//! # Validação e Versionamento do Protocolo
+//!
+//! Este módulo concentra as regras do envelope MCP: o identificador `magic`,
+//! as versões do protocolo suportadas e a negociação de versão entre cliente
+//! e servidor.
+//!
+//! O servidor valida toda mensagem recebida com [`validate_message`] e
+//! responde na mesma versão usada pelo cliente. Os clientes podem consultar
+//! as versões aceitas em `GET /mcp/info` (veja [`ProtocolInfo`]) e escolher a
+//! mais recente que também suportam com [`negotiate_version`].
+//!
+//! ## Exemplo de Uso
+//!
+//! ```rust
+//! use mcprs::agent::MCPMessage;
+//! use mcprs::protocol::{negotiate_version, validate_message, SUPPORTED_VERSIONS};
+//! use serde_json::json;
+//!
+//! let message = MCPMessage::new("openai:chat", json!({ "user_prompt": "Olá" }));
+//! assert_eq!(validate_message(&message).unwrap(), 1);
+//!
+//! // Um cliente que fala as versões 1 e 2 negocia a maior versão em comum
+//! assert_eq!(negotiate_version(&[1, 2], SUPPORTED_VERSIONS), Some(1));
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use crate::agent::{MCPError, MCPMessage};
+
+/// Identificador do protocolo presente em toda mensagem MCP.
+pub const MAGIC: &str = "MCP0";
+
+/// Versão usada por padrão em novas mensagens.
+pub const CURRENT_VERSION: u8 = 1;
+
+/// Versões do protocolo aceitas pelo servidor, em ordem crescente.
+pub const SUPPORTED_VERSIONS: &[u8] = &[1];
+
+/// Chave de [`MCPMessage::meta`] com o ID de rastreamento distribuído.
+pub const META_TRACE_ID: &str = "trace_id";
+
+/// Chave de [`MCPMessage::meta`] com o ID da conversa associada.
+pub const META_CONVERSATION_ID: &str = "conversation_id";
+
+/// Chave de [`MCPMessage::meta`] com o identificador do usuário autenticado.
+pub const META_USER: &str = "user";
+
+/// Informações do protocolo anunciadas em `GET /mcp/info`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ProtocolInfo {
+    /// Identificador do protocolo
+    pub magic: String,
+
+    /// Versão preferida pelo servidor
+    pub current_version: u8,
+
+    /// Versões aceitas pelo servidor
+    pub supported_versions: Vec<u8>,
+
+    /// Agentes registrados no servidor, em ordem alfabética; omitido quando
+    /// o cliente não pode conhecê-los
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub agents: Vec<String>,
+}
+
+impl ProtocolInfo {
+    /// Cria as informações do protocolo para os agentes informados.
+    pub fn new(mut agents: Vec<String>) -> Self {
+        agents.sort();
+        Self {
+            magic: MAGIC.to_string(),
+            current_version: CURRENT_VERSION,
+            supported_versions: SUPPORTED_VERSIONS.to_vec(),
+            agents,
+        }
+    }
+}
+
+/// Valida o envelope de uma mensagem recebida.
+///
+/// # Retorna
+/// * `Ok(u8)` - A versão negociada, que deve ser usada na resposta
+/// * `Err(MCPError::InvalidMagic)` - Se `magic` não for [`MAGIC`]
+/// * `Err(MCPError::UnsupportedVersion)` - Se a versão não estiver em [`SUPPORTED_VERSIONS`]
+pub fn validate_message(message: &MCPMessage) -> Result<u8, MCPError> {
+    if message.magic != MAGIC {
+        return Err(MCPError::InvalidMagic(message.magic.clone()));
+    }
+
+    if !SUPPORTED_VERSIONS.contains(&message.version) {
+        return Err(MCPError::UnsupportedVersion {
+            requested: message.version,
+            supported: SUPPORTED_VERSIONS.to_vec(),
+        });
+    }
+
+    Ok(message.version)
+}
+
+/// Escolhe a maior versão suportada por ambos os lados.
+///
+/// # Argumentos
+/// * `client_versions` - Versões que o cliente sabe usar
+/// * `server_versions` - Versões anunciadas pelo servidor
+///
+/// # Retorna
+/// A maior versão em comum, ou `None` se não houver nenhuma
+pub fn negotiate_version(client_versions: &[u8], server_versions: &[u8]) -> Option<u8> {
+    client_versions
+        .iter()
+        .filter(|v| server_versions.contains(v))
+        .max()
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_validate_message() {
+        let message = MCPMessage::new("dummy:test", json!({}));
+        assert_eq!(validate_message(&message).unwrap(), CURRENT_VERSION);
+
+        let mut bad_magic = message.clone();
+        bad_magic.magic = "XXXX".to_string();
+        assert!(matches!(
+            validate_message(&bad_magic),
+            Err(MCPError::InvalidMagic(m)) if m == "XXXX"
+        ));
+
+        let mut bad_version = message;
+        bad_version.version = 99;
+        assert!(matches!(
+            validate_message(&bad_version),
+            Err(MCPError::UnsupportedVersion { requested: 99, .. })
+        ));
+    }
+
+    #[test]
+    fn test_negotiate_version() {
+        assert_eq!(negotiate_version(&[1, 2, 3], &[1, 2]), Some(2));
+        assert_eq!(negotiate_version(&[3], &[1, 2]), None);
+    }
+}
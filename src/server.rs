@@ -53,28 +53,34 @@
 //! ```
 
 use axum::{
-    extract::Json,
-    http::StatusCode,
+    extract::{rejection::JsonRejection, Json, Path, Query, State},
+    http::{HeaderMap, HeaderValue, Request, StatusCode},
+    middleware::{self, Next},
     response::{
         sse::{Event, Sse},
         IntoResponse, Response,
     },
-    routing::{get, post},
+    routing::{get, patch, post},
     Extension, Router,
 };
-use futures::Stream;
-use serde_json::json;
+use futures::{Stream, StreamExt};
+use serde_json::{json, Value};
+use std::collections::HashMap;
 use std::convert::Infallible;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tokio_stream::wrappers::ReceiverStream;
-use tracing::{error, info};
+use tracing::{debug, error, info, info_span, Instrument};
 use tracing_subscriber;
 
-use crate::agent::{AgentRegistry, MCPError, MCPMessage};
-use crate::auth::AuthConfig;
-use crate::conversation::ConversationManager;
+use crate::agent::{AgentRegistry, ErrorBody, MCPError, MCPMessage};
+use crate::auth::{bearer_token, AuthConfig, AuthError, AuthUser};
+use crate::chat::{ChatMessage, ChatRequest, ChatRole, ToolCall};
+use crate::conversation::{Conversation, ConversationManager, ConversationScope};
+use crate::conversation_api::{ConversationListResponse, ConversationResponse};
+use crate::protocol::{validate_message, ProtocolInfo, META_CONVERSATION_ID, META_USER};
+use crate::streaming::TokenStream;
 
 /// Estado compartilhado da aplicação no servidor.
 ///
@@ -86,30 +92,48 @@ pub struct AppState {
     registry: Arc<RwLock<AgentRegistry>>,
 
     /// Configuração de autenticação (opcional)
-    #[allow(dead_code)]
     auth_config: Option<AuthConfig>,
 
     /// Gerenciador de conversas (opcional)
     conversation_manager: Option<Arc<ConversationManager>>,
 }
 
-/// Estrutura para representar uma resposta de erro em JSON.
+/// Envelope JSON dos erros de processamento MCP: `{ "error": { "code", "message", ... } }`.
 #[derive(serde::Serialize, serde::Deserialize)]
-struct ErrorResponse {
-    /// Mensagem de erro
-    error: String,
+struct MCPErrorResponse {
+    /// Detalhes do erro
+    error: ErrorBody,
 }
 
 /// Converte um MCPError em uma resposta HTTP.
+///
+/// O status é dado por [`MCPError::status_code`] e o corpo é o envelope
+/// [`MCPErrorResponse`].
 impl IntoResponse for MCPError {
     fn into_response(self) -> Response {
-        let body = Json(ErrorResponse {
-            error: self.to_string(),
+        let body = Json(MCPErrorResponse {
+            error: self.to_error_body(),
         });
-        (StatusCode::BAD_REQUEST, body).into_response()
+        (self.status_code(), body).into_response()
     }
 }
 
+/// Cria um evento SSE do tipo `error` com o mesmo envelope JSON de `/mcp`.
+fn error_event(error: &MCPError) -> Event {
+    let body = MCPErrorResponse {
+        error: error.to_error_body(),
+    };
+    Event::default()
+        .event("error")
+        .json_data(&body)
+        .unwrap_or_else(|_| Event::default().event("error").data(error.to_string()))
+}
+
+/// Converte a rejeição do extrator JSON em um `MCPError::BadRequest`.
+fn json_rejection(rejection: JsonRejection) -> MCPError {
+    MCPError::BadRequest(rejection.body_text())
+}
+
 /// Inicia e executa o servidor HTTP MCP básico.
 ///
 /// Esta é a versão mais simples do servidor, sem autenticação ou
@@ -146,6 +170,7 @@ pub async fn run_http_server(registry: AgentRegistry, addr: SocketAddr) {
     // Configura o roteador com a rota /mcp para requisições POST.
     let app = Router::new()
         .route("/mcp", post(handle_mcp))
+        .route("/mcp/info", get(handle_info))
         .route("/health", get(|| async { "OK" }))
         .with_state(app_state);
 
@@ -162,6 +187,7 @@ pub async fn run_http_server(registry: AgentRegistry, addr: SocketAddr) {
 /// Esta versão do servidor inclui:
 /// - Autenticação via token Bearer
 /// - Gerenciamento de histórico de conversas
+/// - Limpeza automática de conversas expiradas (veja `ConversationManager::spawn_janitor`)
 /// - Suporte para streaming de respostas
 /// - Endpoints adicionais para gerenciar conversações
 ///
@@ -198,21 +224,16 @@ pub async fn run_http_server_with_auth(
     // Inicializa o logging.
     tracing_subscriber::fmt::init();
 
+    // Remove conversas expiradas enquanto o servidor estiver em execução
+    let janitor = conversation_manager.spawn_janitor();
+
     let app_state = AppState {
         registry: Arc::new(RwLock::new(registry)),
-        auth_config: Some(auth_config.clone()),
+        auth_config: Some(auth_config),
         conversation_manager: Some(Arc::new(conversation_manager)),
     };
 
-    // Configura as rotas
-    let app = Router::new()
-        .route("/mcp", post(handle_mcp))
-        .route("/mcp/stream", get(handle_stream_mcp))
-        .route("/conversation", post(create_conversation))
-        .route("/conversation/:id", get(get_conversation))
-        .route("/health", get(|| async { "OK" }))
-        .with_state(app_state)
-        .layer(Extension(auth_config));
+    let app = build_router_with_auth(app_state);
 
     info!("Servidor MCP avançado rodando em {}", addr);
 
@@ -220,6 +241,160 @@ pub async fn run_http_server_with_auth(
         .serve(app.into_make_service())
         .await
         .unwrap();
+
+    janitor.shutdown().await;
+}
+
+/// Monta o roteador do servidor avançado.
+///
+/// Todas as rotas, exceto `/health` e `/mcp/info`, passam pelo middleware [`require_auth`],
+/// que valida o token Bearer contra a `AuthConfig` do estado. `/mcp/info` só lista
+/// os agentes para tokens válidos.
+fn build_router_with_auth(app_state: AppState) -> Router {
+    let protected = Router::new()
+        .route("/mcp", post(handle_mcp))
+        .route(
+            "/mcp/stream",
+            get(handle_stream_mcp).post(handle_stream_mcp),
+        )
+        .route(
+            "/conversation",
+            get(list_conversations).post(create_conversation),
+        )
+        .route(
+            "/conversation/:id",
+            get(get_conversation).delete(delete_conversation),
+        )
+        .route(
+            "/conversation/:id/messages",
+            post(append_conversation_message),
+        )
+        .route(
+            "/conversation/:id/metadata",
+            patch(update_conversation_metadata),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            require_auth,
+        ));
+
+    let mut app = Router::new()
+        .merge(protected)
+        .route("/mcp/info", get(handle_info))
+        .route("/health", get(|| async { "OK" }))
+        .with_state(app_state.clone());
+
+    // Disponibiliza a configuração para os extratores `AuthUser` e `AuthConfig`
+    if let Some(auth_config) = app_state.auth_config {
+        app = app.layer(Extension(auth_config));
+    }
+
+    app
+}
+
+/// Middleware de autenticação por token Bearer.
+///
+/// Quando o estado possui uma `AuthConfig`, o token do cabeçalho
+/// `Authorization` é validado e o [`AuthUser`](crate::auth::AuthUser)
+/// resolvido é inserido nas extensões da requisição, ficando disponível
+/// para os handlers. Tokens ausentes ou desconhecidos resultam em
+/// `401 Unauthorized`.
+async fn require_auth<B>(
+    State(state): State<AppState>,
+    mut request: Request<B>,
+    next: Next<B>,
+) -> Result<Response, AuthError> {
+    if let Some(ref auth_config) = state.auth_config {
+        let token = bearer_token(request.headers())
+            .ok_or_else(|| AuthError::new("Token de autorização ausente ou inválido"))?;
+
+        let user = auth_config
+            .authenticate(token)
+            .inspect_err(|_| error!("Tentativa de acesso com token inválido"))?;
+
+        request.extensions_mut().insert(user);
+    }
+
+    Ok(next.run(request).await)
+}
+
+/// Handler para a rota /mcp/info.
+///
+/// Anuncia o identificador do protocolo e as versões suportadas, permitindo
+/// que o cliente negocie a versão antes de enviar mensagens. Não requer
+/// autenticação; quando o servidor tem uma `AuthConfig`, os agentes
+/// registrados só são listados para requisições com um token válido.
+async fn handle_info(State(state): State<AppState>, headers: HeaderMap) -> Json<ProtocolInfo> {
+    let authorized = match state.auth_config {
+        Some(ref auth_config) => {
+            bearer_token(&headers).is_some_and(|token| auth_config.is_valid_token(token))
+        }
+        None => true,
+    };
+
+    let agents = if authorized {
+        state.registry.read().await.agent_names()
+    } else {
+        Vec::new()
+    };
+    Json(ProtocolInfo::new(agents))
+}
+
+/// Cabeçalho HTTP com o ID da requisição MCP.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Determina o ID de uma requisição MCP.
+///
+/// Usa o `id` da mensagem, se informado; senão o cabeçalho `X-Request-Id`
+/// enviado pelo cliente; senão gera um novo UUID.
+fn resolve_request_id(message: Option<&MCPMessage>, headers: &HeaderMap) -> String {
+    message
+        .and_then(|message| message.id.clone())
+        .or_else(|| {
+            headers
+                .get(REQUEST_ID_HEADER)
+                .and_then(|value| value.to_str().ok())
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
+}
+
+/// Preenche o ID e os metadados de correlação de uma requisição MCP.
+///
+/// O usuário é sempre o autenticado pelo servidor, sobrescrevendo o valor
+/// enviado pelo cliente. O `conversation_id` do payload é copiado para os
+/// metadados, se ainda não estiver lá.
+fn annotate_request(message: &mut MCPMessage, request_id: &str, user: Option<&AuthUser>) {
+    message.id = Some(request_id.to_string());
+
+    match user {
+        Some(user) => {
+            message.meta.insert(META_USER.to_string(), user.subject());
+        }
+        None => {
+            message.meta.remove(META_USER);
+        }
+    }
+
+    if let Some(conversation_id) = message
+        .payload
+        .get("conversation_id")
+        .and_then(Value::as_str)
+    {
+        message
+            .meta
+            .entry(META_CONVERSATION_ID.to_string())
+            .or_insert_with(|| conversation_id.to_string());
+    }
+}
+
+/// Adiciona o cabeçalho `X-Request-Id` à resposta.
+fn with_request_id(mut response: Response, request_id: &str) -> Response {
+    if let Ok(value) = HeaderValue::from_str(request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
 }
 
 /// Handler para a rota /mcp.
@@ -227,177 +402,744 @@ pub async fn run_http_server_with_auth(
 /// Este handler recebe uma requisição POST com uma MCPMessage,
 /// valida-a, e a encaminha para o agente apropriado.
 ///
+/// Toda resposta, inclusive de erro, inclui o cabeçalho `X-Request-Id`
+/// (veja [`resolve_request_id`]). O mesmo ID é repetido no campo `id` da
+/// mensagem de resposta.
+///
+/// Quando o gerenciamento de conversas está habilitado e o payload contém
+/// `conversation_id`, o histórico da conversa é enviado ao agente antes das
+/// novas mensagens, e a pergunta e a resposta são registradas na conversa.
+/// Nesse caso, o cliente deve enviar apenas as mensagens novas.
+///
 /// # Argumentos
 /// * `state` - O estado compartilhado da aplicação
 /// * `payload` - A mensagem MCP recebida no corpo da requisição
 ///
 /// # Retorna
-/// * `Ok(Json<MCPMessage>)` - A resposta do agente
-/// * `Err(MCPError)` - Se ocorrer um erro no processamento
+/// A resposta do agente, ou o envelope de erro se o processamento falhar
 async fn handle_mcp(
     axum::extract::State(state): axum::extract::State<AppState>,
-    Json(payload): Json<MCPMessage>,
+    user: Option<AuthUser>,
+    headers: HeaderMap,
+    payload: Result<Json<MCPMessage>, JsonRejection>,
+) -> Response {
+    let request_id = resolve_request_id(payload.as_ref().ok().map(|p| &p.0), &headers);
+    let span = info_span!("mcp_request", request_id = %request_id);
+
+    let result = process_mcp(state, user, payload, &request_id)
+        .instrument(span)
+        .await;
+    with_request_id(result.into_response(), &request_id)
+}
+
+/// Processa uma requisição da rota /mcp.
+async fn process_mcp(
+    state: AppState,
+    user: Option<AuthUser>,
+    payload: Result<Json<MCPMessage>, JsonRejection>,
+    request_id: &str,
 ) -> Result<Json<MCPMessage>, MCPError> {
-    // Validação do campo magic.
-    if payload.magic != "MCP0" {
-        error!("Magic inválido: {}", payload.magic);
-        return Ok(Json(MCPMessage::new(
-            "error",
-            json!({"message": "Magic inválido"}),
-        )));
-    }
+    let Json(mut payload) = payload.map_err(json_rejection)?;
+    annotate_request(&mut payload, request_id, user.as_ref());
+
+    // Validação do envelope (magic e versão)
+    let version =
+        validate_message(&payload).inspect_err(|e| error!("Mensagem rejeitada: {}", e))?;
+
+    // Carrega o histórico da conversa, se a requisição referenciar uma
+    let conversation = match state.conversation_manager {
+        Some(ref manager) => {
+            let owner = user.as_ref().map(AuthUser::subject);
+            attach_conversation_history(manager, owner.as_deref(), &mut payload)?
+        }
+        None => None,
+    };
 
     // Processa a mensagem utilizando o registro de agentes.
-    let response = {
+    let mut response = {
         let reg = state.registry.read().await;
         reg.process(payload).await?
     };
 
-    Ok(Json(response))
+    // Registra o turno na conversa e devolve seu ID na resposta
+    if let (Some(manager), Some(conversation)) = (&state.conversation_manager, conversation) {
+        record_conversation_turn(manager, conversation, &mut response)?;
+    }
+
+    // Responde na mesma versão usada pelo cliente
+    Ok(Json(response.with_version(version)))
+}
+
+/// Turno de conversa em andamento em uma requisição MCP.
+struct ConversationTurn {
+    /// ID da conversa referenciada pela requisição
+    conversation_id: String,
+
+    /// Novas mensagens (não-sistema) enviadas nesta requisição
+    new_messages: Vec<ChatMessage>,
+
+    /// Mensagens do histórico descartadas pela janela de contexto
+    dropped_messages: usize,
+}
+
+/// Prepara uma requisição que referencia uma conversa via `conversation_id`.
+///
+/// O histórico da conversa é inserido entre as instruções de sistema da
+/// requisição e as novas mensagens, e o payload é reescrito com o campo
+/// `messages` completo. Se o gerenciador tiver uma janela de contexto, apenas
+/// as mensagens mais recentes que cabem no orçamento são reenviadas. Requisições sem `conversation_id` não são alteradas.
+///
+/// Conversas que não pertencem a `owner` são tratadas como inexistentes.
+///
+/// # Retorna
+/// * `Ok(Some(ConversationTurn))` - Se a requisição referenciar uma conversa
+/// * `Ok(None)` - Se não houver `conversation_id` no payload
+/// * `Err(MCPError)` - Se a conversa não existir ou o payload for inválido
+fn attach_conversation_history(
+    manager: &ConversationManager,
+    owner: Option<&str>,
+    message: &mut MCPMessage,
+) -> Result<Option<ConversationTurn>, MCPError> {
+    let Some(conversation_id) = message
+        .payload
+        .get("conversation_id")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+    else {
+        return Ok(None);
+    };
+
+    let conversation = manager
+        .get_conversation(&conversation_id)
+        .filter(|conversation| conversation.is_accessible_by(owner))
+        .ok_or_else(|| MCPError::ConversationNotFound(conversation_id.clone()))?;
+
+    let request = ChatRequest::from_payload(&message.payload)?;
+    let (system_messages, new_messages): (Vec<ChatMessage>, Vec<ChatMessage>) = request
+        .messages
+        .into_iter()
+        .partition(|m| m.role == ChatRole::System);
+
+    // Limita o histórico ao orçamento de tokens, se configurado
+    let (history, dropped_messages) = match manager.context_window() {
+        Some(builder) => {
+            let counter = builder.counter();
+            let reserved = system_messages
+                .iter()
+                .chain(&new_messages)
+                .map(|m| counter.count_message_tokens(m.role.as_str(), &m.content))
+                .sum();
+            let window = builder.build_with_reserved(&conversation.messages, reserved);
+            if window.dropped > 0 {
+                debug!(
+                    "Janela de contexto da conversa {} descartou {} mensagens",
+                    conversation_id, window.dropped
+                );
+            }
+            (window.chat_messages()?, window.dropped)
+        }
+        None => (conversation.chat_history()?, 0),
+    };
+
+    let mut messages = system_messages;
+    messages.extend(history);
+    messages.extend(new_messages.iter().cloned());
+
+    if let Some(payload) = message.payload.as_object_mut() {
+        payload.remove("system_prompt");
+        payload.remove("user_prompt");
+        payload.insert("messages".to_string(), json!(messages));
+    }
+
+    Ok(Some(ConversationTurn {
+        conversation_id,
+        new_messages,
+        dropped_messages,
+    }))
+}
+
+/// Registra as novas mensagens e a resposta do agente na conversa.
+///
+/// A resposta (`payload.answer`, com as chamadas de `payload.tool_calls`) é
+/// armazenada com o papel `assistant`, e os resultados de ferramentas da
+/// requisição mantêm o `tool_call_id`, para que o histórico possa ser
+/// reenviado aos provedores. O `conversation_id` é incluído no payload da
+/// resposta, junto com `dropped_messages` quando a janela de contexto
+/// descartou parte do histórico.
+fn record_conversation_turn(
+    manager: &ConversationManager,
+    turn: ConversationTurn,
+    response: &mut MCPMessage,
+) -> Result<(), MCPError> {
+    let id = &turn.conversation_id;
+
+    for message in &turn.new_messages {
+        manager
+            .add_chat_message_to_conversation(id, message)
+            .map_err(MCPError::InternalAgentError)?;
+    }
+
+    if let Some(reply) = assistant_reply(&response.payload)? {
+        manager
+            .add_chat_message_to_conversation(id, &reply)
+            .map_err(MCPError::InternalAgentError)?;
+    }
+
+    if let Some(payload) = response.payload.as_object_mut() {
+        payload.insert("conversation_id".to_string(), json!(id));
+        if turn.dropped_messages > 0 {
+            payload.insert("dropped_messages".to_string(), json!(turn.dropped_messages));
+        }
+    }
+
+    Ok(())
+}
+
+/// Monta a mensagem do assistente a partir do payload da resposta do agente.
+///
+/// Respostas com saída estruturada trazem o objeto em `data` no lugar de
+/// `answer`; nesse caso, o JSON é registrado como texto.
+///
+/// # Retorna
+/// `None` se a resposta não tiver `answer`, `data` nem `tool_calls`
+fn assistant_reply(payload: &Value) -> Result<Option<ChatMessage>, MCPError> {
+    let answer = match (payload.get("answer"), payload.get("data")) {
+        (Some(Value::String(answer)), _) => Some(answer.clone()),
+        (None, Some(data)) => Some(data.to_string()),
+        _ => None,
+    };
+    let tool_calls: Vec<ToolCall> = match payload.get("tool_calls") {
+        None | Some(Value::Null) => Vec::new(),
+        Some(calls) => serde_json::from_value(calls.clone())
+            .map_err(|e| MCPError::InternalAgentError(format!("'tool_calls' inválido: {}", e)))?,
+    };
+
+    if answer.is_none() && tool_calls.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(ChatMessage::assistant_tool_calls(
+        answer.unwrap_or_default(),
+        tool_calls,
+    )))
 }
 
 /// Handler para o endpoint de streaming /mcp/stream.
 ///
 /// Este handler é semelhante ao `handle_mcp`, mas retorna a resposta
-/// como um stream de eventos (Server-Sent Events).
+/// como um stream de eventos (Server-Sent Events). Cada `StreamingToken`
+/// produzido pelo agente é enviado como um evento SSE próprio, com o token
+/// serializado em JSON no campo `data`, assim que é gerado.
+///
+/// O corpo é validado e o agente é resolvido antes de o stream ser aberto:
+/// falhas nessa etapa são respondidas com o status HTTP de
+/// [`MCPError::status_code`] e o envelope de erro JSON, como em `handle_mcp`.
+/// Erros ocorridos depois de o stream começar são enviados como eventos do
+/// tipo `error`. A resposta inclui o cabeçalho `X-Request-Id`.
+///
+/// Com `conversation_id` no payload, o histórico da conversa é enviado ao
+/// agente como em `handle_mcp`, e o turno (as novas mensagens e o texto
+/// completo da resposta) é registrado quando o stream termina sem erros.
 ///
 /// # Argumentos
 /// * `state` - O estado compartilhado da aplicação
 /// * `payload` - A mensagem MCP recebida no corpo da requisição
 ///
 /// # Retorna
-/// Um stream de eventos SSE com a resposta
+/// Um stream de eventos SSE com os tokens da resposta, ou o envelope de erro
 async fn handle_stream_mcp(
     axum::extract::State(state): axum::extract::State<AppState>,
-    Json(payload): Json<MCPMessage>,
-) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
-    let (tx, rx) = tokio::sync::mpsc::channel(100);
+    user: Option<AuthUser>,
+    headers: HeaderMap,
+    payload: Result<Json<MCPMessage>, JsonRejection>,
+) -> Response {
+    let request_id = resolve_request_id(payload.as_ref().ok().map(|p| &p.0), &headers);
+    let span = info_span!("mcp_stream", request_id = %request_id);
 
-    // Inicia o processamento em uma task separada
-    tokio::spawn(async move {
-        // Validação do campo magic
-        if payload.magic != "MCP0" {
-            let _ = tx
-                .send(Ok(Event::default().data("Error: Invalid magic")))
-                .await;
-            return;
+    let response = match open_mcp_stream(state, user, payload, &request_id)
+        .instrument(span.clone())
+        .await
+    {
+        Ok((token_stream, pending_turn)) => {
+            Sse::new(span.in_scope(|| forward_tokens(token_stream, pending_turn))).into_response()
         }
+        Err(error) => error.into_response(),
+    };
+    with_request_id(response, &request_id)
+}
 
-        // Processa a mensagem e envia resultados para o stream
-        let reg = state.registry.read().await;
-        match reg.process(payload).await {
-            Ok(response) => {
-                let _ = tx
-                    .send(Ok(
-                        Event::default().data(serde_json::to_string(&response).unwrap_or_default())
-                    ))
-                    .await;
+/// Turno de conversa de um stream, registrado quando o stream termina.
+struct PendingTurn {
+    /// Gerenciador onde o turno será registrado
+    manager: Arc<ConversationManager>,
+
+    /// O turno em andamento
+    turn: ConversationTurn,
+}
+
+/// Valida uma requisição da rota /mcp/stream e obtém o stream de tokens do agente.
+///
+/// Se a requisição referenciar uma conversa, o histórico é inserido no
+/// payload e o turno a registrar é retornado junto com o stream.
+async fn open_mcp_stream(
+    state: AppState,
+    user: Option<AuthUser>,
+    payload: Result<Json<MCPMessage>, JsonRejection>,
+    request_id: &str,
+) -> Result<(TokenStream, Option<PendingTurn>), MCPError> {
+    let Json(mut payload) = payload.map_err(json_rejection)?;
+    annotate_request(&mut payload, request_id, user.as_ref());
+
+    // Validação do envelope (magic e versão)
+    validate_message(&payload).inspect_err(|e| error!("Mensagem rejeitada: {}", e))?;
+
+    // Carrega o histórico da conversa, se a requisição referenciar uma
+    let pending_turn = match state.conversation_manager {
+        Some(ref manager) => {
+            let owner = user.as_ref().map(AuthUser::subject);
+            attach_conversation_history(manager, owner.as_deref(), &mut payload)?.map(|turn| {
+                PendingTurn {
+                    manager: Arc::clone(manager),
+                    turn,
+                }
+            })
+        }
+        None => None,
+    };
+
+    let reg = state.registry.read().await;
+    let token_stream = reg.process_stream(payload).await?;
+    Ok((token_stream, pending_turn))
+}
+
+/// Encaminha cada token do agente como um evento SSE independente.
+///
+/// O encaminhamento ocorre em uma task separada, dentro do span atual. Se
+/// houver um turno de conversa pendente, o texto dos tokens é acumulado e
+/// registrado na conversa quando o stream termina sem erros; streams
+/// interrompidos (erro do agente ou cliente desconectado) não são registrados.
+fn forward_tokens(
+    mut token_stream: TokenStream,
+    pending_turn: Option<PendingTurn>,
+) -> impl Stream<Item = Result<Event, Infallible>> {
+    let (tx, rx) = tokio::sync::mpsc::channel(100);
+
+    tokio::spawn(
+        async move {
+            let mut answer = String::new();
+            let mut completed = true;
+
+            while let Some(token_result) = token_stream.next().await {
+                let (event, is_finish) = match token_result {
+                    Ok(token) => {
+                        answer.push_str(&token.content);
+                        (
+                            Event::default().json_data(&token).unwrap_or_else(|e| {
+                                Event::default().event("error").data(e.to_string())
+                            }),
+                            token.is_finish,
+                        )
+                    }
+                    Err(error) => {
+                        completed = false;
+                        (error_event(&error), false)
+                    }
+                };
+
+                if tx.send(Ok(event)).await.is_err() {
+                    completed = false;
+                    break;
+                }
+                if is_finish {
+                    break;
+                }
             }
-            Err(error) => {
-                let _ = tx
-                    .send(Ok(Event::default().data(format!("Error: {}", error))))
-                    .await;
+
+            if let (Some(pending), true) = (pending_turn, completed) {
+                let mut response = MCPMessage::new("stream_response", json!({ "answer": answer }));
+                if let Err(e) =
+                    record_conversation_turn(&pending.manager, pending.turn, &mut response)
+                {
+                    error!("Falha ao registrar o turno do stream: {}", e);
+                }
             }
         }
-    });
+        .in_current_span(),
+    );
+
+    ReceiverStream::new(rx)
+}
+
+/// Número padrão de conversas por página em `GET /conversation`.
+const DEFAULT_PAGE_LIMIT: usize = 20;
+
+/// Número máximo de conversas por página em `GET /conversation`.
+const MAX_PAGE_LIMIT: usize = 100;
+
+/// Corpo opcional de `POST /conversation`.
+#[derive(serde::Deserialize, Default)]
+struct CreateConversationRequest {
+    /// Metadados iniciais da conversa
+    #[serde(default)]
+    metadata: HashMap<String, String>,
+
+    /// Instrução de sistema registrada como primeira mensagem da conversa
+    system_prompt: Option<String>,
+}
+
+/// Parâmetros de paginação de `GET /conversation`.
+#[derive(serde::Deserialize)]
+struct ListConversationsQuery {
+    /// Número de conversas a pular (padrão: 0)
+    offset: Option<usize>,
+
+    /// Tamanho da página (padrão: 20, máximo: 100)
+    limit: Option<usize>,
+}
+
+/// Retorna o gerenciador de conversas ou `MCPError::NotFound` se estiver desabilitado.
+fn conversation_manager(state: &AppState) -> Result<&ConversationManager, MCPError> {
+    state.conversation_manager.as_deref().ok_or_else(|| {
+        MCPError::NotFound("Gerenciamento de conversas não está habilitado".to_string())
+    })
+}
 
-    Sse::new(ReceiverStream::new(rx))
+/// Carrega uma conversa acessível pelo usuário autenticado.
+///
+/// Conversas de outros usuários são tratadas como inexistentes, para não
+/// revelar sua existência.
+fn load_owned_conversation(
+    manager: &ConversationManager,
+    id: &str,
+    user: Option<&AuthUser>,
+) -> Result<Conversation, MCPError> {
+    let owner = user.map(AuthUser::subject);
+    manager
+        .get_conversation(id)
+        .filter(|conversation| conversation.is_accessible_by(owner.as_deref()))
+        .ok_or_else(|| MCPError::ConversationNotFound(id.to_string()))
 }
 
 /// Endpoint para criar uma nova conversa.
 ///
+/// O corpo é opcional e pode conter `metadata` (objeto de strings) e
+/// `system_prompt`, registrado como a primeira mensagem da conversa. Uma
+/// requisição sem `Content-Type: application/json` é tratada como sem corpo.
+/// A conversa pertence ao usuário autenticado.
+///
 /// # Argumentos
 /// * `state` - O estado compartilhado da aplicação
+/// * `user` - O usuário autenticado, se houver
+/// * `body` - Metadados e prompt de sistema iniciais (opcional)
 ///
 /// # Retorna
-/// * No sucesso: Status 201 Created com ID da conversa
-/// * No erro: Status 500 Internal Server Error ou 501 Not Implemented
+/// * No sucesso: Status 201 Created com a [`ConversationResponse`] da nova conversa
+/// * No erro: o status e o envelope JSON do [`MCPError`] (400 para corpo inválido)
 async fn create_conversation(
-    axum::extract::State(state): axum::extract::State<AppState>,
-) -> impl IntoResponse {
-    if let Some(ref conversation_manager) = state.conversation_manager {
-        match conversation_manager.create_conversation() {
-            Ok(conversation) => (
-                StatusCode::CREATED,
-                Json(json!({
-                    "conversation_id": conversation.id,
-                    "created_at": conversation.created_at.elapsed().unwrap_or_default().as_secs()
-                })),
-            ),
-            Err(e) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({ "error": e })),
-            ),
-        }
-    } else {
-        (
-            StatusCode::NOT_IMPLEMENTED,
-            Json(json!({ "error": "Gerenciamento de conversas não está habilitado" })),
-        )
+    State(state): State<AppState>,
+    user: Option<AuthUser>,
+    body: Result<Json<CreateConversationRequest>, JsonRejection>,
+) -> Result<Response, MCPError> {
+    let manager = conversation_manager(&state)?;
+    let request = match body {
+        Ok(Json(request)) => request,
+        Err(JsonRejection::MissingJsonContentType(_)) => CreateConversationRequest::default(),
+        Err(rejection) => return Err(json_rejection(rejection)),
+    };
+
+    let mut conversation = Conversation::new();
+    conversation.owner = user.as_ref().map(AuthUser::subject);
+    conversation.metadata = request.metadata;
+    if let Some(system_prompt) = request.system_prompt {
+        conversation.add_message(ChatRole::System.as_str(), &system_prompt);
     }
+
+    manager
+        .update_conversation(conversation.clone())
+        .map_err(MCPError::InternalAgentError)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ConversationResponse::from(&conversation)),
+    )
+        .into_response())
+}
+
+/// Endpoint para listar as conversas do usuário autenticado.
+///
+/// As conversas são ordenadas da atualizada mais recentemente para a mais
+/// antiga e paginadas pelos parâmetros `offset` e `limit`.
+///
+/// # Retorna
+/// * No sucesso: Status 200 OK com uma [`ConversationListResponse`]
+/// * No erro: o status e o envelope JSON do [`MCPError`]
+async fn list_conversations(
+    State(state): State<AppState>,
+    user: Option<AuthUser>,
+    Query(query): Query<ListConversationsQuery>,
+) -> Result<Response, MCPError> {
+    let manager = conversation_manager(&state)?;
+    let owner = user.as_ref().map(AuthUser::subject);
+    let offset = query.offset.unwrap_or(0);
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_LIMIT)
+        .clamp(1, MAX_PAGE_LIMIT);
+
+    let page = manager
+        .list_conversations(
+            ConversationScope::AccessibleBy(owner.as_deref()),
+            offset,
+            limit,
+        )
+        .map_err(MCPError::InternalAgentError)?;
+
+    Ok(Json(ConversationListResponse::new(&page, offset, limit)).into_response())
 }
 
 /// Endpoint para obter uma conversa existente pelo ID.
 ///
 /// # Argumentos
 /// * `state` - O estado compartilhado da aplicação
+/// * `user` - O usuário autenticado, se houver
 /// * `id` - O ID da conversa a ser recuperada
 ///
 /// # Retorna
-/// * No sucesso: Status 200 OK com dados da conversa
-/// * No erro: Status 404 Not Found ou 501 Not Implemented
+/// * No sucesso: Status 200 OK com a [`ConversationResponse`]
+/// * No erro: o status e o envelope JSON do [`MCPError`] (404 para conversa inexistente)
 async fn get_conversation(
-    axum::extract::State(state): axum::extract::State<AppState>,
-    axum::extract::Path(id): axum::extract::Path<String>,
-) -> impl IntoResponse {
-    if let Some(ref conversation_manager) = state.conversation_manager {
-        match conversation_manager.get_conversation(&id) {
-            Some(conversation) => {
-                let messages: Vec<_> = conversation
-                    .messages
-                    .iter()
-                    .map(|msg| {
-                        json!({
-                            "role": msg.role,
-                            "content": msg.content,
-                            "timestamp": msg.timestamp.elapsed().unwrap_or_default().as_secs()
-                        })
-                    })
-                    .collect();
-
-                (
-                    StatusCode::OK,
-                    Json(json!({
-                        "conversation_id": conversation.id,
-                        "messages": messages,
-                        "metadata": conversation.metadata,
-                        "created_at": conversation.created_at.elapsed().unwrap_or_default().as_secs(),
-                        "updated_at": conversation.updated_at.elapsed().unwrap_or_default().as_secs()
-                    })),
-                )
-            }
-            None => (
-                StatusCode::NOT_FOUND,
-                Json(json!({ "error": "Conversa não encontrada" })),
-            ),
-        }
-    } else {
-        (
-            StatusCode::NOT_IMPLEMENTED,
-            Json(json!({ "error": "Gerenciamento de conversas não está habilitado" })),
-        )
+    State(state): State<AppState>,
+    user: Option<AuthUser>,
+    Path(id): Path<String>,
+) -> Result<Response, MCPError> {
+    let manager = conversation_manager(&state)?;
+    let conversation = load_owned_conversation(manager, &id, user.as_ref())?;
+    Ok(Json(ConversationResponse::from(&conversation)).into_response())
+}
+
+/// Endpoint para adicionar uma mensagem a uma conversa.
+///
+/// O corpo deve ser uma mensagem `{ "role": ..., "content": ... }`; mensagens
+/// com papel `tool` informam também `tool_call_id`, e mensagens do assistente
+/// podem trazer `tool_calls`.
+///
+/// # Retorna
+/// * No sucesso: Status 201 Created com a [`ConversationResponse`] atualizada
+/// * No erro: o status e o envelope JSON do [`MCPError`] (400 para corpo
+///   inválido, 404 para conversa inexistente, 422 para mensagem inconsistente)
+async fn append_conversation_message(
+    State(state): State<AppState>,
+    user: Option<AuthUser>,
+    Path(id): Path<String>,
+    message: Result<Json<ChatMessage>, JsonRejection>,
+) -> Result<Response, MCPError> {
+    let manager = conversation_manager(&state)?;
+    let Json(message) = message.map_err(json_rejection)?;
+    message.validate()?;
+    load_owned_conversation(manager, &id, user.as_ref())?;
+
+    manager
+        .add_chat_message_to_conversation(&id, &message)
+        .map_err(MCPError::InternalAgentError)?;
+
+    let conversation = load_owned_conversation(manager, &id, user.as_ref())?;
+    Ok((
+        StatusCode::CREATED,
+        Json(ConversationResponse::from(&conversation)),
+    )
+        .into_response())
+}
+
+/// Endpoint para remover uma conversa.
+///
+/// # Retorna
+/// * No sucesso: Status 204 No Content
+/// * No erro: o status e o envelope JSON do [`MCPError`] (404 para conversa inexistente)
+async fn delete_conversation(
+    State(state): State<AppState>,
+    user: Option<AuthUser>,
+    Path(id): Path<String>,
+) -> Result<Response, MCPError> {
+    let manager = conversation_manager(&state)?;
+    load_owned_conversation(manager, &id, user.as_ref())?;
+
+    match manager.delete_conversation(&id) {
+        Ok(true) => Ok(StatusCode::NO_CONTENT.into_response()),
+        Ok(false) => Err(MCPError::ConversationNotFound(id)),
+        Err(e) => Err(MCPError::InternalAgentError(e)),
     }
 }
 
+/// Endpoint para atualizar os metadados de uma conversa.
+///
+/// O corpo é um objeto JSON cujas chaves com valor string são inseridas ou
+/// substituídas e cujas chaves com valor `null` são removidas. As demais
+/// chaves são preservadas.
+///
+/// # Retorna
+/// * No sucesso: Status 200 OK com a [`ConversationResponse`] atualizada
+/// * No erro: o status e o envelope JSON do [`MCPError`] (400 para corpo
+///   inválido, 404 para conversa inexistente)
+async fn update_conversation_metadata(
+    State(state): State<AppState>,
+    user: Option<AuthUser>,
+    Path(id): Path<String>,
+    changes: Result<Json<HashMap<String, Option<String>>>, JsonRejection>,
+) -> Result<Response, MCPError> {
+    let manager = conversation_manager(&state)?;
+    let Json(changes) = changes.map_err(json_rejection)?;
+    load_owned_conversation(manager, &id, user.as_ref())?;
+
+    let conversation = manager
+        .update_metadata(&id, changes)
+        .map_err(MCPError::InternalAgentError)?;
+
+    Ok(Json(ConversationResponse::from(&conversation)).into_response())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::agent::DummyAgent;
+    use crate::auth::AuthUser;
     use axum::body::Body;
     use axum::http::{Request, StatusCode};
     use serde_json::json;
     use tower::ServiceExt;
 
+    /// Agente de teste que emite a resposta em vários tokens
+    struct StreamingTestAgent;
+
+    #[async_trait::async_trait]
+    impl crate::agent::AIAgent for StreamingTestAgent {
+        fn name(&self) -> &str {
+            "streamer"
+        }
+
+        async fn process_request(&self, _message: MCPMessage) -> Result<MCPMessage, MCPError> {
+            Ok(MCPMessage::new(
+                "streamer_response",
+                json!({"answer": "Olá mundo"}),
+            ))
+        }
+
+        async fn process_stream(
+            &self,
+            _message: MCPMessage,
+        ) -> Result<crate::streaming::TokenStream, MCPError> {
+            let tokens = ["Olá", " mundo", ""].into_iter().map(|content| {
+                Ok(crate::streaming::StreamingToken {
+                    content: content.to_string(),
+                    is_finish: content.is_empty(),
+                    metadata: None,
+                })
+            });
+            Ok(Box::pin(futures::stream::iter(tokens.collect::<Vec<_>>())))
+        }
+    }
+
+    /// Agente de teste que responde com o número de mensagens recebidas
+    struct HistoryCountAgent;
+
+    #[async_trait::async_trait]
+    impl crate::agent::AIAgent for HistoryCountAgent {
+        fn name(&self) -> &str {
+            "history"
+        }
+
+        async fn process_request(&self, message: MCPMessage) -> Result<MCPMessage, MCPError> {
+            let request = ChatRequest::from_payload(&message.payload)?;
+            let roles: Vec<&str> = request.messages.iter().map(|m| m.role.as_str()).collect();
+            Ok(MCPMessage::new(
+                "history_response",
+                json!({ "answer": format!("{} mensagens: {}", roles.len(), roles.join(",")) }),
+            ))
+        }
+    }
+
+    /// Agente de teste que pede uma ferramenta quando o último turno é do
+    /// usuário e a requisição tem `tools`; caso contrário, lista os papéis recebidos
+    struct ToolCallingAgent;
+
+    #[async_trait::async_trait]
+    impl crate::agent::AIAgent for ToolCallingAgent {
+        fn name(&self) -> &str {
+            "tooling"
+        }
+
+        async fn process_request(&self, message: MCPMessage) -> Result<MCPMessage, MCPError> {
+            let request = ChatRequest::from_payload(&message.payload)?;
+            let last = request.messages.last().map(|m| m.role);
+            if !request.tools.is_empty() && last == Some(ChatRole::User) {
+                return Ok(MCPMessage::new(
+                    "tooling_response",
+                    json!({
+                        "answer": "",
+                        "tool_calls": [{ "id": "call_1", "name": "clima", "arguments": {} }]
+                    }),
+                ));
+            }
+
+            let roles: Vec<&str> = request.messages.iter().map(|m| m.role.as_str()).collect();
+            Ok(MCPMessage::new(
+                "tooling_response",
+                json!({ "answer": roles.join(",") }),
+            ))
+        }
+    }
+
+    /// Agente de teste que responde com um objeto JSON
+    struct JsonAnswerAgent;
+
+    #[async_trait::async_trait]
+    impl crate::agent::AIAgent for JsonAnswerAgent {
+        fn name(&self) -> &str {
+            "json"
+        }
+
+        async fn process_request(&self, message: MCPMessage) -> Result<MCPMessage, MCPError> {
+            let request = ChatRequest::from_payload(&message.payload)?;
+            Ok(MCPMessage::new(
+                "json_response",
+                json!({ "answer": json!({ "mensagens": request.messages.len() }).to_string() }),
+            ))
+        }
+    }
+
+    fn build_auth_test_state() -> AppState {
+        let mut registry = AgentRegistry::new();
+        registry.register_agent(Box::new(DummyAgent {
+            api_key: "test_key".to_string(),
+        }));
+        registry.register_agent(Box::new(StreamingTestAgent));
+        registry.register_agent(Box::new(HistoryCountAgent));
+        registry.register_agent(Box::new(ToolCallingAgent));
+        registry.register_agent(Box::new(JsonAnswerAgent));
+
+        let auth_config = AuthConfig::new();
+        auth_config.add_token("token-valido".to_string());
+        auth_config.add_token("outro-token".to_string());
+
+        AppState {
+            registry: Arc::new(RwLock::new(registry)),
+            auth_config: Some(auth_config),
+            conversation_manager: Some(Arc::new(ConversationManager::new(24))),
+        }
+    }
+
+    fn mcp_request(token: Option<&str>) -> Request<Body> {
+        let message = MCPMessage::new("dummy:test", json!({"test": "value"}));
+        let mut builder = Request::builder()
+            .uri("/mcp")
+            .method("POST")
+            .header("Content-Type", "application/json");
+        if let Some(token) = token {
+            builder = builder.header("Authorization", format!("Bearer {}", token));
+        }
+        builder
+            .body(Body::from(serde_json::to_string(&message).unwrap()))
+            .unwrap()
+    }
+
     async fn build_test_app() -> Router {
         // Criar um registro com um agente dummy para testes
         let mut registry = AgentRegistry::new();
@@ -445,14 +1187,10 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_handle_mcp_invalid_magic() {
-        // Construir app de teste
+    async fn test_handle_mcp_generates_request_id() {
         let app = build_test_app().await;
 
-        // Criar requisição com magic inválido
-        let mut message = MCPMessage::new("dummy:test", json!({"test": "value"}));
-        message.magic = "INVALID".to_string();
-
+        let message = MCPMessage::new("dummy:test", json!({}));
         let request = Request::builder()
             .uri("/mcp")
             .method("POST")
@@ -460,43 +1198,949 @@ mod tests {
             .body(Body::from(serde_json::to_string(&message).unwrap()))
             .unwrap();
 
-        // Enviar requisição e verificar resposta
         let response = app.oneshot(request).await.unwrap();
         assert_eq!(response.status(), StatusCode::OK);
+        let request_id = response.headers()[REQUEST_ID_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert!(uuid::Uuid::parse_str(&request_id).is_ok());
 
-        // Verificar corpo da resposta
         let body_bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
         let response_message: MCPMessage = serde_json::from_slice(&body_bytes).unwrap();
-
-        assert_eq!(response_message.command, "error");
-        assert!(response_message.payload["message"]
-            .as_str()
-            .unwrap()
-            .contains("inválido"));
+        assert_eq!(response_message.id.as_deref(), Some(request_id.as_str()));
+        assert!(response_message.timestamp.is_some());
     }
 
     #[tokio::test]
-    async fn test_handle_mcp_agent_not_found() {
-        // Construir app de teste
-        let app = build_test_app().await;
+    async fn test_handle_mcp_echoes_request_id_and_meta() {
+        let app = build_router_with_auth(build_auth_test_state());
 
-        // Criar requisição para agente inexistente
-        let message = MCPMessage::new("nonexistent:test", json!({"test": "value"}));
+        // O ID da mensagem tem precedência sobre o cabeçalho
+        let message = MCPMessage::new("dummy:test", json!({}))
+            .with_id("req-1")
+            .with_meta(crate::protocol::META_TRACE_ID, "trace-1")
+            .with_meta(META_USER, "forjado");
         let request = Request::builder()
             .uri("/mcp")
             .method("POST")
             .header("Content-Type", "application/json")
+            .header("Authorization", "Bearer token-valido")
+            .header(REQUEST_ID_HEADER, "do-cabecalho")
             .body(Body::from(serde_json::to_string(&message).unwrap()))
             .unwrap();
 
-        // Enviar requisição e verificar resposta
-        let response = app.oneshot(request).await.unwrap();
-        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.headers()[REQUEST_ID_HEADER], "req-1");
+        let body_bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let response_message: MCPMessage = serde_json::from_slice(&body_bytes).unwrap();
+        assert_eq!(response_message.id.as_deref(), Some("req-1"));
+        assert_eq!(response_message.meta["trace_id"], "trace-1");
+        // O usuário é sempre o autenticado pelo servidor
+        let subject = AuthUser {
+            token: "token-valido".to_string(),
+        }
+        .subject();
+        assert_eq!(response_message.meta[META_USER], subject);
+
+        // Sem ID na mensagem, o cabeçalho do cliente é usado, mesmo em erros
+        let message = MCPMessage::new("inexistente:test", json!({}));
+        let request = Request::builder()
+            .uri("/mcp")
+            .method("POST")
+            .header("Content-Type", "application/json")
+            .header("Authorization", "Bearer token-valido")
+            .header(REQUEST_ID_HEADER, "do-cabecalho")
+            .body(Body::from(serde_json::to_string(&message).unwrap()))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(response.headers()[REQUEST_ID_HEADER], "do-cabecalho");
+    }
+
+    #[tokio::test]
+    async fn test_handle_mcp_invalid_magic() {
+        // Construir app de teste
+        let app = build_test_app().await;
+
+        // Criar requisição com magic inválido
+        let mut message = MCPMessage::new("dummy:test", json!({"test": "value"}));
+        message.magic = "INVALID".to_string();
+
+        let request = Request::builder()
+            .uri("/mcp")
+            .method("POST")
+            .header("Content-Type", "application/json")
+            .body(Body::from(serde_json::to_string(&message).unwrap()))
+            .unwrap();
+
+        // Enviar requisição e verificar resposta
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 
         // Verificar corpo da resposta
         let body_bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
-        let error_response: ErrorResponse = serde_json::from_slice(&body_bytes).unwrap();
+        let error_response: MCPErrorResponse = serde_json::from_slice(&body_bytes).unwrap();
+
+        assert_eq!(error_response.error.code, "invalid_magic");
+        assert!(error_response.error.message.contains("inválido"));
+    }
+
+    #[tokio::test]
+    async fn test_handle_mcp_unsupported_version() {
+        let app = build_test_app().await;
+
+        let message = MCPMessage::new("dummy:test", json!({})).with_version(99);
+        let request = Request::builder()
+            .uri("/mcp")
+            .method("POST")
+            .header("Content-Type", "application/json")
+            .body(Body::from(serde_json::to_string(&message).unwrap()))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let body_bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let error_response: MCPErrorResponse = serde_json::from_slice(&body_bytes).unwrap();
+        assert_eq!(error_response.error.code, "unsupported_version");
+    }
+
+    #[tokio::test]
+    async fn test_info_is_public_and_lists_versions() {
+        let app = build_router_with_auth(build_auth_test_state());
+
+        let request = Request::builder()
+            .uri("/mcp/info")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body_bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+        assert!(body.get("agents").is_none());
+        let info: ProtocolInfo = serde_json::from_value(body).unwrap();
+        assert_eq!(info.magic, "MCP0");
+        assert_eq!(info.supported_versions, crate::protocol::SUPPORTED_VERSIONS);
+
+        // Com um token válido, os agentes registrados também são listados
+        let request = Request::builder()
+            .uri("/mcp/info")
+            .header("Authorization", "Bearer token-valido")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let body_bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let info: ProtocolInfo = serde_json::from_slice(&body_bytes).unwrap();
+        assert_eq!(
+            info.agents,
+            vec!["dummy", "history", "json", "streamer", "tooling"]
+        );
+    }
 
-        assert!(error_response.error.contains("não foi encontrado"));
+    #[tokio::test]
+    async fn test_handle_mcp_agent_not_found() {
+        // Construir app de teste
+        let app = build_test_app().await;
+
+        // Criar requisição para agente inexistente
+        let message = MCPMessage::new("nonexistent:test", json!({"test": "value"}));
+        let request = Request::builder()
+            .uri("/mcp")
+            .method("POST")
+            .header("Content-Type", "application/json")
+            .body(Body::from(serde_json::to_string(&message).unwrap()))
+            .unwrap();
+
+        // Enviar requisição e verificar resposta
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // Verificar corpo da resposta
+        let body_bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let error_response: MCPErrorResponse = serde_json::from_slice(&body_bytes).unwrap();
+
+        assert_eq!(error_response.error.code, "agent_not_found");
+        assert!(!error_response.error.retryable);
+        assert!(error_response.error.message.contains("não foi encontrado"));
+    }
+
+    #[tokio::test]
+    async fn test_auth_router_rejects_missing_token() {
+        let app = build_router_with_auth(build_auth_test_state());
+
+        let response = app.oneshot(mcp_request(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let body_bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+        assert_eq!(body["error"]["code"], "unauthorized");
+        assert!(body["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("ausente"));
+    }
+
+    #[tokio::test]
+    async fn test_auth_router_rejects_unknown_token() {
+        let app = build_router_with_auth(build_auth_test_state());
+
+        let response = app
+            .oneshot(mcp_request(Some("token-desconhecido")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let body_bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let envelope: MCPErrorResponse = serde_json::from_slice(&body_bytes).unwrap();
+        assert_eq!(envelope.error.code, "unauthorized");
+        assert!(!envelope.error.retryable);
+        assert!(envelope.error.message.contains("inválido"));
+    }
+
+    #[tokio::test]
+    async fn test_auth_router_accepts_registered_token() {
+        let app = build_router_with_auth(build_auth_test_state());
+
+        let response = app
+            .oneshot(mcp_request(Some("token-valido")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body_bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let response_message: MCPMessage = serde_json::from_slice(&body_bytes).unwrap();
+        assert_eq!(response_message.command, "dummy_response");
+    }
+
+    #[tokio::test]
+    async fn test_auth_router_protects_conversation_routes() {
+        let app = build_router_with_auth(build_auth_test_state());
+
+        let request = Request::builder()
+            .uri("/conversation")
+            .method("POST")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let request = Request::builder()
+            .uri("/conversation")
+            .method("POST")
+            .header("Authorization", "Bearer token-valido")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+
+    #[tokio::test]
+    async fn test_auth_router_health_is_public() {
+        let app = build_router_with_auth(build_auth_test_state());
+
+        let request = Request::builder()
+            .uri("/health")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_auth_user_exposed_to_handlers() {
+        let state = build_auth_test_state();
+        let app = Router::new()
+            .route("/whoami", get(|user: AuthUser| async move { user.token }))
+            .route_layer(middleware::from_fn_with_state(state.clone(), require_auth))
+            .with_state(state);
+
+        let request = Request::builder()
+            .uri("/whoami")
+            .header("Authorization", "Bearer token-valido")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body_bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(&body_bytes[..], b"token-valido");
+    }
+
+    #[tokio::test]
+    async fn test_stream_forwards_each_token_as_event() {
+        let app = build_router_with_auth(build_auth_test_state());
+
+        let message = MCPMessage::new("streamer:chat", json!({"user_prompt": "Oi"}));
+        let request = Request::builder()
+            .uri("/mcp/stream")
+            .method("POST")
+            .header("Content-Type", "application/json")
+            .header("Authorization", "Bearer token-valido")
+            .body(Body::from(serde_json::to_string(&message).unwrap()))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body_bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body = String::from_utf8(body_bytes.to_vec()).unwrap();
+        let tokens: Vec<crate::streaming::StreamingToken> = body
+            .lines()
+            .filter_map(|line| line.strip_prefix("data:"))
+            .map(|data| serde_json::from_str(data).unwrap())
+            .collect();
+
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(tokens[0].content, "Olá");
+        assert_eq!(tokens[1].content, " mundo");
+        assert!(tokens[2].is_finish);
+    }
+
+    #[tokio::test]
+    async fn test_stream_replays_and_records_conversation() {
+        let state = build_auth_test_state();
+        let manager = state.conversation_manager.clone().unwrap();
+        let conversation = manager.create_conversation().unwrap();
+        manager
+            .assign_owner(&conversation.id, &subject_of("token-valido"))
+            .unwrap();
+        manager
+            .add_message_to_conversation(&conversation.id, "user", "Olá")
+            .unwrap();
+        manager
+            .add_message_to_conversation(&conversation.id, "assistant", "Oi!")
+            .unwrap();
+        let app = build_router_with_auth(state);
+
+        let message = MCPMessage::new(
+            "history:chat",
+            json!({ "user_prompt": "Tudo bem?", "conversation_id": conversation.id }),
+        );
+        let request = Request::builder()
+            .uri("/mcp/stream")
+            .method("POST")
+            .header("Content-Type", "application/json")
+            .header("Authorization", "Bearer token-valido")
+            .body(Body::from(serde_json::to_string(&message).unwrap()))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body_bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body = String::from_utf8(body_bytes.to_vec()).unwrap();
+
+        // O histórico chega ao agente antes da nova mensagem
+        assert!(body.contains("3 mensagens: user,assistant,user"));
+
+        // O turno é registrado ao final do stream
+        let stored = manager.get_conversation(&conversation.id).unwrap();
+        let contents: Vec<&str> = stored.messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(
+            contents,
+            vec![
+                "Olá",
+                "Oi!",
+                "Tudo bem?",
+                "3 mensagens: user,assistant,user"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stream_rejects_unknown_agent_before_streaming() {
+        let app = build_router_with_auth(build_auth_test_state());
+
+        let message = MCPMessage::new("nonexistent:chat", json!({}));
+        let request = Request::builder()
+            .uri("/mcp/stream")
+            .method("POST")
+            .header("Content-Type", "application/json")
+            .header("Authorization", "Bearer token-valido")
+            .body(Body::from(serde_json::to_string(&message).unwrap()))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert!(response.headers().contains_key(REQUEST_ID_HEADER));
+
+        // O erro usa o mesmo envelope de /mcp
+        let body_bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let envelope: MCPErrorResponse = serde_json::from_slice(&body_bytes).unwrap();
+        assert_eq!(envelope.error.code, "agent_not_found");
+    }
+
+    #[tokio::test]
+    async fn test_stream_rejects_invalid_messages_with_status() {
+        let app = build_router_with_auth(build_auth_test_state());
+
+        let mut message = MCPMessage::new("streamer:chat", json!({"user_prompt": "Oi"}));
+        message.magic = "XXXX".to_string();
+        let requests = [
+            (serde_json::to_string(&message).unwrap(), "invalid_magic"),
+            ("{ não é json".to_string(), "bad_request"),
+        ];
+
+        for (body, code) in requests {
+            let request = Request::builder()
+                .uri("/mcp/stream")
+                .method("POST")
+                .header("Content-Type", "application/json")
+                .header("Authorization", "Bearer token-valido")
+                .body(Body::from(body))
+                .unwrap();
+
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+            let body_bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            let envelope: MCPErrorResponse = serde_json::from_slice(&body_bytes).unwrap();
+            assert_eq!(envelope.error.code, code);
+        }
+    }
+
+    /// Agente de teste que sempre falha com um limite de taxa do provedor
+    struct RateLimitedAgent;
+
+    #[async_trait::async_trait]
+    impl crate::agent::AIAgent for RateLimitedAgent {
+        fn name(&self) -> &str {
+            "limited"
+        }
+
+        async fn process_request(&self, _message: MCPMessage) -> Result<MCPMessage, MCPError> {
+            Err(MCPError::RateLimited {
+                provider: "openai".to_string(),
+                retry_after_secs: Some(7),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mcp_maps_provider_errors_to_status_and_envelope() {
+        let state = build_auth_test_state();
+        state
+            .registry
+            .write()
+            .await
+            .register_agent(Box::new(RateLimitedAgent));
+        let app = build_router_with_auth(state);
+
+        let message = MCPMessage::new("limited:chat", json!({ "user_prompt": "Olá" }));
+        let (status, body) = post_mcp(app, &message).await;
+
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(
+            body["error"],
+            json!({
+                "code": "rate_limited",
+                "message": "Limite de requisições excedido no provedor 'openai'",
+                "retryable": true,
+                "provider": "openai",
+                "provider_status": 429,
+                "retry_after_secs": 7
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mcp_malformed_body_uses_error_envelope() {
+        let app = build_router_with_auth(build_auth_test_state());
+
+        let request = Request::builder()
+            .uri("/mcp")
+            .method("POST")
+            .header("Content-Type", "application/json")
+            .header("Authorization", "Bearer token-valido")
+            .body(Body::from("{ não é json"))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body_bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let envelope: MCPErrorResponse = serde_json::from_slice(&body_bytes).unwrap();
+        assert_eq!(envelope.error.code, "bad_request");
+    }
+
+    fn subject_of(token: &str) -> String {
+        AuthUser {
+            token: token.to_string(),
+        }
+        .subject()
+    }
+
+    async fn post_mcp(app: Router, message: &MCPMessage) -> (StatusCode, serde_json::Value) {
+        let request = Request::builder()
+            .uri("/mcp")
+            .method("POST")
+            .header("Content-Type", "application/json")
+            .header("Authorization", "Bearer token-valido")
+            .body(Body::from(serde_json::to_string(message).unwrap()))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body_bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (status, serde_json::from_slice(&body_bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_mcp_replays_conversation_history() {
+        let state = build_auth_test_state();
+        let manager = state.conversation_manager.clone().unwrap();
+        let conversation = manager.create_conversation().unwrap();
+        manager
+            .assign_owner(&conversation.id, &subject_of("token-valido"))
+            .unwrap();
+        let app = build_router_with_auth(state);
+
+        // Primeiro turno: apenas a mensagem nova chega ao agente
+        let first = MCPMessage::new(
+            "history:chat",
+            json!({ "user_prompt": "Olá", "conversation_id": conversation.id }),
+        );
+        let (status, body) = post_mcp(app.clone(), &first).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["payload"]["answer"], "1 mensagens: user");
+        assert_eq!(body["payload"]["conversation_id"], conversation.id);
+
+        // Segundo turno: histórico é inserido após o prompt de sistema
+        let second = MCPMessage::new(
+            "history:chat",
+            json!({
+                "system_prompt": "Seja breve.",
+                "user_prompt": "Tudo bem?",
+                "conversation_id": conversation.id
+            }),
+        );
+        let (status, body) = post_mcp(app, &second).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body["payload"]["answer"],
+            "4 mensagens: system,user,assistant,user"
+        );
+
+        // O prompt de sistema da requisição não é persistido
+        let stored = manager.get_conversation(&conversation.id).unwrap();
+        let roles: Vec<&str> = stored.messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, vec!["user", "assistant", "user", "assistant"]);
+        assert_eq!(stored.messages[0].content, "Olá");
+        assert_eq!(stored.messages[1].content, "1 mensagens: user");
+    }
+
+    #[tokio::test]
+    async fn test_mcp_records_tool_calls_in_conversation() {
+        let state = build_auth_test_state();
+        let manager = state.conversation_manager.clone().unwrap();
+        let conversation = manager.create_conversation().unwrap();
+        manager
+            .assign_owner(&conversation.id, &subject_of("token-valido"))
+            .unwrap();
+        let app = build_router_with_auth(state);
+        let tools = json!([{ "name": "clima", "parameters": { "type": "object" } }]);
+
+        // Primeiro turno: o modelo pede uma ferramenta
+        let first = MCPMessage::new(
+            "tooling:chat",
+            json!({ "user_prompt": "Como está o tempo?", "tools": tools, "conversation_id": conversation.id }),
+        );
+        let (status, body) = post_mcp(app.clone(), &first).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["payload"]["tool_calls"][0]["id"], "call_1");
+
+        // Segundo turno: o resultado da ferramenta responde à chamada registrada
+        let second = MCPMessage::new(
+            "tooling:chat",
+            json!({
+                "messages": [{ "role": "tool", "tool_call_id": "call_1", "content": "21 graus" }],
+                "tools": tools,
+                "conversation_id": conversation.id
+            }),
+        );
+        let (status, body) = post_mcp(app.clone(), &second).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["payload"]["answer"], "user,assistant,tool");
+
+        // Terceiro turno: o histórico com ferramentas continua válido
+        let third = MCPMessage::new(
+            "tooling:chat",
+            json!({ "user_prompt": "Obrigado", "conversation_id": conversation.id }),
+        );
+        let (status, body) = post_mcp(app, &third).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body["payload"]["answer"],
+            "user,assistant,tool,assistant,user"
+        );
+
+        let stored = manager.get_conversation(&conversation.id).unwrap();
+        assert_eq!(stored.messages[1].tool_calls[0].name, "clima");
+        assert_eq!(stored.messages[2].tool_call_id.as_deref(), Some("call_1"));
+    }
+
+    #[tokio::test]
+    async fn test_mcp_records_structured_answer_in_conversation() {
+        let state = build_auth_test_state();
+        let manager = state.conversation_manager.clone().unwrap();
+        let conversation = manager.create_conversation().unwrap();
+        manager
+            .assign_owner(&conversation.id, &subject_of("token-valido"))
+            .unwrap();
+        let app = build_router_with_auth(state);
+
+        // A saída estruturada troca `answer` por `data` na resposta
+        let first = MCPMessage::new(
+            "json:chat",
+            json!({
+                "user_prompt": "Quantas mensagens?",
+                "response_format": { "type": "json_object" },
+                "conversation_id": conversation.id
+            }),
+        );
+        let (status, body) = post_mcp(app.clone(), &first).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["payload"]["data"], json!({ "mensagens": 1 }));
+
+        // A resposta do assistente é registrada e reenviada no próximo turno
+        let second = MCPMessage::new(
+            "history:chat",
+            json!({ "user_prompt": "E agora?", "conversation_id": conversation.id }),
+        );
+        let (status, body) = post_mcp(app, &second).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body["payload"]["answer"],
+            "3 mensagens: user,assistant,user"
+        );
+
+        let stored = manager.get_conversation(&conversation.id).unwrap();
+        assert_eq!(stored.messages[1].role, "assistant");
+        assert_eq!(stored.messages[1].content, "{\"mensagens\":1}");
+    }
+
+    #[tokio::test]
+    async fn test_mcp_unknown_conversation() {
+        let app = build_router_with_auth(build_auth_test_state());
+
+        let message = MCPMessage::new(
+            "history:chat",
+            json!({ "user_prompt": "Olá", "conversation_id": "nao-existe" }),
+        );
+        let (status, body) = post_mcp(app, &message).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"]["code"], "conversation_not_found");
+        assert!(body["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("nao-existe"));
+    }
+
+    #[tokio::test]
+    async fn test_mcp_applies_context_window() {
+        let mut state = build_auth_test_state();
+        let manager = ConversationManager::new(24)
+            .with_context_window(crate::conversation::ContextWindowBuilder::new(30));
+        let conversation = manager.create_conversation().unwrap();
+        manager
+            .assign_owner(&conversation.id, &subject_of("token-valido"))
+            .unwrap();
+        for i in 0..10 {
+            manager
+                .add_message_to_conversation(&conversation.id, "user", &format!("Pergunta {}", i))
+                .unwrap();
+        }
+        state.conversation_manager = Some(Arc::new(manager));
+        let app = build_router_with_auth(state);
+
+        let message = MCPMessage::new(
+            "history:chat",
+            json!({ "user_prompt": "Última", "conversation_id": conversation.id }),
+        );
+        let (status, body) = post_mcp(app, &message).await;
+        assert_eq!(status, StatusCode::OK);
+
+        // Cada mensagem custa ~8 tokens pela heurística: cabem duas do histórico
+        assert_eq!(body["payload"]["answer"], "3 mensagens: user,user,user");
+        assert_eq!(body["payload"]["dropped_messages"], 8);
+    }
+
+    async fn send_json(
+        app: Router,
+        method: &str,
+        uri: &str,
+        token: &str,
+        body: Option<serde_json::Value>,
+    ) -> (StatusCode, serde_json::Value) {
+        let builder = Request::builder()
+            .uri(uri)
+            .method(method)
+            .header("Authorization", format!("Bearer {}", token));
+        let request = match body {
+            Some(body) => builder
+                .header("Content-Type", "application/json")
+                .body(Body::from(body.to_string())),
+            None => builder.body(Body::empty()),
+        }
+        .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body_bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        // Corpos vazios ou em texto (rejeições do axum) viram `null`
+        let body = serde_json::from_slice(&body_bytes).unwrap_or(serde_json::Value::Null);
+        (status, body)
+    }
+
+    #[tokio::test]
+    async fn test_create_conversation_with_metadata_and_system_prompt() {
+        let app = build_router_with_auth(build_auth_test_state());
+
+        let (status, body) = send_json(
+            app.clone(),
+            "POST",
+            "/conversation",
+            "token-valido",
+            Some(json!({
+                "metadata": { "idioma": "pt-br" },
+                "system_prompt": "Seja breve."
+            })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let id = body["conversation_id"].as_str().unwrap().to_string();
+
+        let (status, body) = send_json(
+            app.clone(),
+            "GET",
+            &format!("/conversation/{}", id),
+            "token-valido",
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["metadata"]["idioma"], "pt-br");
+        assert_eq!(body["messages"][0]["role"], "system");
+        assert_eq!(body["messages"][0]["content"], "Seja breve.");
+
+        // Resposta versionada, com IDs de mensagem e timestamps RFC 3339
+        let parsed: ConversationResponse = serde_json::from_value(body.clone()).unwrap();
+        assert_eq!(
+            parsed.schema_version,
+            crate::conversation_api::CONVERSATION_SCHEMA_VERSION
+        );
+        assert!(!parsed.messages[0].id.is_empty());
+        let age = chrono::Utc::now() - parsed.summary.created_at;
+        assert!(age.num_seconds().abs() < 60);
+
+        // Corpo vazio continua aceito
+        let (status, _) = send_json(app, "POST", "/conversation", "token-valido", None).await;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+
+    #[tokio::test]
+    async fn test_list_conversations_is_scoped_and_paginated() {
+        let app = build_router_with_auth(build_auth_test_state());
+
+        for _ in 0..3 {
+            send_json(app.clone(), "POST", "/conversation", "token-valido", None).await;
+        }
+        send_json(app.clone(), "POST", "/conversation", "outro-token", None).await;
+
+        let (status, body) = send_json(
+            app.clone(),
+            "GET",
+            "/conversation?offset=1&limit=1",
+            "token-valido",
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["total"], 3);
+        assert_eq!(body["offset"], 1);
+        assert_eq!(body["limit"], 1);
+        assert_eq!(body["conversations"].as_array().unwrap().len(), 1);
+
+        let (_, body) = send_json(app, "GET", "/conversation", "outro-token", None).await;
+        assert_eq!(body["total"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_append_update_and_delete_conversation() {
+        let app = build_router_with_auth(build_auth_test_state());
+        let (_, body) = send_json(app.clone(), "POST", "/conversation", "token-valido", None).await;
+        let id = body["conversation_id"].as_str().unwrap().to_string();
+
+        let (status, body) = send_json(
+            app.clone(),
+            "POST",
+            &format!("/conversation/{}/messages", id),
+            "token-valido",
+            Some(json!({ "role": "user", "content": "Olá" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["message_count"], 1);
+
+        // Papel inválido é rejeitado
+        let (status, _) = send_json(
+            app.clone(),
+            "POST",
+            &format!("/conversation/{}/messages", id),
+            "token-valido",
+            Some(json!({ "role": "robot", "content": "beep" })),
+        )
+        .await;
+        assert!(status.is_client_error());
+
+        let (status, body) = send_json(
+            app.clone(),
+            "PATCH",
+            &format!("/conversation/{}/metadata", id),
+            "token-valido",
+            Some(json!({ "idioma": "pt-br", "inexistente": null })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["metadata"], json!({ "idioma": "pt-br" }));
+
+        let (status, _) = send_json(
+            app.clone(),
+            "DELETE",
+            &format!("/conversation/{}", id),
+            "token-valido",
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+
+        let (status, _) = send_json(
+            app,
+            "GET",
+            &format!("/conversation/{}", id),
+            "token-valido",
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_conversation_routes_use_mcp_error_envelope() {
+        let app = build_router_with_auth(build_auth_test_state());
+        let (_, body) = send_json(app.clone(), "POST", "/conversation", "token-valido", None).await;
+        let id = body["conversation_id"].as_str().unwrap().to_string();
+
+        let (status, body) = send_json(
+            app.clone(),
+            "GET",
+            "/conversation/inexistente",
+            "token-valido",
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"]["code"], "conversation_not_found");
+        assert_eq!(body["error"]["retryable"], false);
+
+        let (status, body) = send_json(
+            app.clone(),
+            "PATCH",
+            &format!("/conversation/{}/metadata", id),
+            "token-valido",
+            Some(json!({ "idioma": 1 })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"]["code"], "bad_request");
+
+        let (status, body) = send_json(
+            app,
+            "POST",
+            &format!("/conversation/{}/messages", id),
+            "token-valido",
+            Some(json!({ "role": "tool", "content": "21 graus" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["error"]["code"], "invalid_payload");
+    }
+
+    #[tokio::test]
+    async fn test_conversations_of_other_users_are_hidden() {
+        let app = build_router_with_auth(build_auth_test_state());
+        let (_, body) = send_json(app.clone(), "POST", "/conversation", "token-valido", None).await;
+        let id = body["conversation_id"].as_str().unwrap().to_string();
+
+        for (method, uri, body) in [
+            ("GET", format!("/conversation/{}", id), None),
+            (
+                "POST",
+                format!("/conversation/{}/messages", id),
+                Some(json!({ "role": "user", "content": "Olá" })),
+            ),
+            (
+                "PATCH",
+                format!("/conversation/{}/metadata", id),
+                Some(json!({ "a": "b" })),
+            ),
+            ("DELETE", format!("/conversation/{}", id), None),
+        ] {
+            let (status, _) = send_json(app.clone(), method, &uri, "outro-token", body).await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{} {}", method, uri);
+        }
+
+        // Também não é possível continuar a conversa pelo /mcp
+        let request = Request::builder()
+            .uri("/mcp")
+            .method("POST")
+            .header("Content-Type", "application/json")
+            .header("Authorization", "Bearer outro-token")
+            .body(Body::from(
+                serde_json::to_string(&MCPMessage::new(
+                    "history:chat",
+                    json!({ "user_prompt": "Olá", "conversation_id": id }),
+                ))
+                .unwrap(),
+            ))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_ne!(response.status(), StatusCode::OK);
+
+        // O dono continua com acesso
+        let (status, _) = send_json(
+            app,
+            "GET",
+            &format!("/conversation/{}", id),
+            "token-valido",
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_unowned_conversations_are_hidden_until_assigned() {
+        let state = build_auth_test_state();
+        let manager = state.conversation_manager.clone().unwrap();
+        let legacy = manager.create_conversation().unwrap();
+        let app = build_router_with_auth(state);
+
+        let uri = format!("/conversation/{}", legacy.id);
+        let (status, _) = send_json(app.clone(), "GET", &uri, "token-valido", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, body) =
+            send_json(app.clone(), "GET", "/conversation", "token-valido", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["total"], 0);
+
+        manager
+            .assign_owner(&legacy.id, &subject_of("token-valido"))
+            .unwrap();
+        let (status, _) = send_json(app.clone(), "GET", &uri, "token-valido", None).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = send_json(app, "DELETE", &uri, "outro-token", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
     }
 }
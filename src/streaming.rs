@@ -39,7 +39,8 @@
 //! # }
 //! ```
 
-use crate::agent::MCPError;
+use crate::agent::{MCPError, MCPMessage};
+use crate::usage::ChatCompletionUsage;
 use futures::{Stream, StreamExt};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -151,56 +152,91 @@ pub async fn process_json_stream<S, T>(stream: S) -> Result<TokenStream, MCPErro
 where
     S: Stream<Item = Result<bytes::Bytes, reqwest::Error>> + Send + 'static,
     T: for<'de> Deserialize<'de> + Send + 'static + Debug,
+{
+    process_json_stream_with::<_, T, _>(stream, |parsed| {
+        Ok(Some(StreamingToken {
+            content: format!("{:?}", parsed),
+            is_finish: false,
+            metadata: None,
+        }))
+    })
+    .await
+}
+
+/// Processa um stream de bytes convertendo cada chunk JSON com uma função própria.
+///
+/// Aceita tanto JSON delimitado por linha quanto eventos SSE (`data: {...}`).
+/// Linhas de controle SSE (`event:`, `id:`, `retry:` e comentários) e o marcador
+/// `data: [DONE]` são ignorados. A função `map` decide como cada chunk vira um
+/// [`StreamingToken`]; retornar `Ok(None)` descarta o chunk (útil para eventos
+/// sem conteúdo) e retornar `Err` propaga o erro no stream. Um token final com
+/// `is_finish = true` é sempre emitido ao término do stream.
+///
+/// # Argumentos
+/// * `stream` - Um stream de bytes (geralmente de uma resposta HTTP)
+/// * `map` - Função que converte cada chunk desserializado em um token
+///
+/// # Exemplo
+///
+/// ```rust
+/// use futures::{stream, StreamExt};
+/// use mcprs::streaming::{process_json_stream_with, StreamingToken};
+/// use serde_json::Value;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let chunks = vec![Ok::<_, reqwest::Error>(bytes::Bytes::from(
+///     "data: {\"text\":\"Olá\"}\n\n",
+/// ))];
+///
+/// let mut tokens = process_json_stream_with::<_, Value, _>(stream::iter(chunks), |chunk| {
+///     Ok(chunk["text"].as_str().map(|text| StreamingToken {
+///         content: text.to_string(),
+///         is_finish: false,
+///         metadata: None,
+///     }))
+/// })
+/// .await?;
+///
+/// assert_eq!(tokens.next().await.unwrap()?.content, "Olá");
+/// # Ok(())
+/// # }
+/// ```
+pub async fn process_json_stream_with<S, T, F>(
+    stream: S,
+    mut map: F,
+) -> Result<TokenStream, MCPError>
+where
+    S: Stream<Item = Result<bytes::Bytes, reqwest::Error>> + Send + 'static,
+    T: for<'de> Deserialize<'de> + Send + 'static,
+    F: FnMut(T) -> Result<Option<StreamingToken>, MCPError> + Send + 'static,
 {
     let (tx, rx) = mpsc::channel(100);
 
     tokio::spawn(async move {
         let mut stream = Box::pin(stream);
-        let mut buffer = String::new();
+        // Bytes ainda não processados; só linhas completas são decodificadas,
+        // para não partir caracteres UTF-8 divididos entre chunks
+        let mut buffer: Vec<u8> = Vec::new();
 
-        while let Some(chunk_result) = stream.next().await {
+        'outer: while let Some(chunk_result) = stream.next().await {
             match chunk_result {
                 Ok(chunk) => {
-                    let chunk_str = String::from_utf8_lossy(&chunk);
-                    buffer.push_str(&chunk_str);
-
-                    // Processar buffer para extrair objetos JSON completos
-                    while let Some(pos) = buffer.find('\n') {
-                        // Converter para String para ter propriedade dos dados
-                        let line = buffer[..pos].trim().to_string();
+                    buffer.extend_from_slice(&chunk);
 
-                        // Agora line é proprietária dos dados, podemos modificar buffer seguramente
-                        buffer = buffer[pos + 1..].to_string();
-
-                        if line.is_empty() || line == "data: [DONE]" {
-                            continue;
-                        }
+                    // Processar cada linha completa do buffer
+                    let mut start = 0;
+                    while let Some(pos) = buffer[start..].iter().position(|&b| b == b'\n') {
+                        let end = start + pos;
+                        let line = String::from_utf8_lossy(&buffer[start..end]);
+                        start = end + 1;
 
-                        // Remover prefixos comuns como "data: "
-                        let json_str = line.strip_prefix("data: ").unwrap_or(&line);
-
-                        match serde_json::from_str::<T>(json_str) {
-                            Ok(parsed) => {
-                                let token = StreamingToken {
-                                    content: format!("{:?}", parsed),
-                                    is_finish: false,
-                                    metadata: None,
-                                };
-
-                                if tx.send(Ok(token)).await.is_err() {
-                                    break;
-                                }
-                            }
-                            Err(e) => {
-                                let _ = tx
-                                    .send(Err(MCPError::InternalAgentError(format!(
-                                        "Erro ao desserializar: {}",
-                                        e
-                                    ))))
-                                    .await;
+                        if let Some(item) = decode_line::<T, F>(line.trim(), &mut map) {
+                            if tx.send(item).await.is_err() {
+                                break 'outer;
                             }
                         }
                     }
+                    buffer.drain(..start);
                 }
                 Err(e) => {
                     let _ = tx
@@ -214,6 +250,12 @@ where
             }
         }
 
+        // Processar uma eventual última linha sem quebra de linha final
+        let line = String::from_utf8_lossy(&buffer);
+        if let Some(item) = decode_line::<T, F>(line.trim(), &mut map) {
+            let _ = tx.send(item).await;
+        }
+
         // Sinalizar que o stream terminou
         let _ = tx
             .send(Ok(StreamingToken {
@@ -227,6 +269,153 @@ where
     Ok(create_token_stream(rx))
 }
 
+/// Decodifica uma linha de um stream JSON/SSE.
+///
+/// Retorna `None` quando a linha deve ser ignorada.
+fn decode_line<T, F>(line: &str, map: &mut F) -> Option<Result<StreamingToken, MCPError>>
+where
+    T: for<'de> Deserialize<'de>,
+    F: FnMut(T) -> Result<Option<StreamingToken>, MCPError>,
+{
+    if line.is_empty()
+        || line.starts_with(':')
+        || line.starts_with("event:")
+        || line.starts_with("id:")
+        || line.starts_with("retry:")
+    {
+        return None;
+    }
+
+    // Remover prefixos comuns como "data: "
+    let json_str = line.strip_prefix("data:").map(str::trim).unwrap_or(line);
+    if json_str == "[DONE]" {
+        return None;
+    }
+
+    match serde_json::from_str::<T>(json_str) {
+        Ok(parsed) => map(parsed).transpose(),
+        Err(e) => Some(Err(MCPError::InternalAgentError(format!(
+            "Erro ao desserializar: {}",
+            e
+        )))),
+    }
+}
+
+/// Cria um stream de tokens a partir de uma resposta MCP completa.
+///
+/// Emite um único token com o conteúdo de `payload.answer` (ou o payload
+/// serializado, se não houver `answer`), com o payload completo em `metadata`,
+/// seguido do token final. É usado como comportamento padrão de
+/// [`AIAgent::process_stream`](crate::agent::AIAgent::process_stream) para
+/// agentes sem suporte nativo a streaming.
+///
+/// # Exemplo
+///
+/// ```rust
+/// use futures::StreamExt;
+/// use mcprs::agent::MCPMessage;
+/// use mcprs::streaming::token_stream_from_message;
+/// use serde_json::json;
+///
+/// # async fn example() {
+/// let message = MCPMessage::new("dummy_response", json!({"answer": "Olá"}));
+/// let mut stream = token_stream_from_message(message);
+///
+/// assert_eq!(stream.next().await.unwrap().unwrap().content, "Olá");
+/// assert!(stream.next().await.unwrap().unwrap().is_finish);
+/// # }
+/// ```
+pub fn token_stream_from_message(message: MCPMessage) -> TokenStream {
+    let content = match message.payload.get("answer").and_then(Value::as_str) {
+        Some(answer) => answer.to_string(),
+        None => message.payload.to_string(),
+    };
+
+    let tokens = vec![
+        Ok(StreamingToken {
+            content,
+            is_finish: false,
+            metadata: Some(message.payload),
+        }),
+        Ok(StreamingToken {
+            content: String::new(),
+            is_finish: true,
+            metadata: None,
+        }),
+    ];
+
+    Box::pin(futures::stream::iter(tokens))
+}
+
+/// Chunk de streaming no formato de Chat Completions da OpenAI.
+#[derive(Deserialize)]
+struct ChatCompletionChunk {
+    #[serde(default)]
+    choices: Vec<ChatCompletionChunkChoice>,
+    usage: Option<ChatCompletionUsage>,
+}
+
+/// Item de escolha em um chunk de streaming de Chat Completions.
+#[derive(Deserialize)]
+struct ChatCompletionChunkChoice {
+    #[serde(default)]
+    index: usize,
+    #[serde(default)]
+    delta: ChatCompletionDelta,
+    finish_reason: Option<String>,
+}
+
+/// Conteúdo incremental de um chunk de streaming de Chat Completions.
+#[derive(Deserialize, Default)]
+struct ChatCompletionDelta {
+    content: Option<String>,
+}
+
+/// Processa um stream SSE no formato de Chat Completions da OpenAI.
+///
+/// Cada evento `data: {...}` com `delta.content` na escolha de `index` 0 vira
+/// um token; deltas de outras escolhas são descartados.
+/// Quando o provedor informa `finish_reason` ou `usage`, eles são incluídos
+/// em `metadata` (com `usage` no formato normalizado de [`Usage`](crate::usage::Usage)).
+/// Usado pelos agentes de APIs compatíveis com a OpenAI (OpenAI, DeepSeek, Mistral).
+///
+/// # Argumentos
+/// * `stream` - Stream de bytes da resposta HTTP com `stream: true`
+pub async fn process_chat_completion_stream<S>(stream: S) -> Result<TokenStream, MCPError>
+where
+    S: Stream<Item = Result<bytes::Bytes, reqwest::Error>> + Send + 'static,
+{
+    process_json_stream_with::<_, ChatCompletionChunk, _>(stream, |chunk| {
+        let choice = chunk.choices.into_iter().find(|choice| choice.index == 0);
+        let (content, finish_reason) = match choice {
+            Some(choice) => (
+                choice.delta.content.unwrap_or_default(),
+                choice.finish_reason,
+            ),
+            None => (String::new(), None),
+        };
+
+        let mut metadata = serde_json::Map::new();
+        if let Some(reason) = finish_reason {
+            metadata.insert("finish_reason".to_string(), Value::String(reason));
+        }
+        if let Some(usage) = chunk.usage {
+            metadata.insert("usage".to_string(), usage.normalized().to_json());
+        }
+
+        if content.is_empty() && metadata.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(StreamingToken {
+            content,
+            is_finish: false,
+            metadata: (!metadata.is_empty()).then_some(Value::Object(metadata)),
+        }))
+    })
+    .await
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -318,4 +507,92 @@ mod tests {
         assert!(tokens[0].contains("Parte 1"));
         assert!(tokens[1].contains("Parte 2"));
     }
+
+    #[tokio::test]
+    async fn test_process_chat_completion_stream() {
+        let chunks = vec![
+            Ok(bytes::Bytes::from(
+                "data: {\"choices\":[{\"delta\":{\"role\":\"assistant\"},\"finish_reason\":null}]}\n\n",
+            )),
+            Ok(bytes::Bytes::from(
+                "data: {\"choices\":[{\"delta\":{\"content\":\"Olá\"},\"finish_reason\":null}]}\n\n",
+            )),
+            Ok(bytes::Bytes::from(
+                "data: {\"choices\":[{\"delta\":{\"content\":\", mundo\"},\"finish_reason\":null}]}\n\n",
+            )),
+            Ok(bytes::Bytes::from(
+                "data: {\"choices\":[{\"delta\":{},\"finish_reason\":\"stop\"}]}\n\n",
+            )),
+            Ok(bytes::Bytes::from("data: [DONE]\n\n")),
+        ];
+
+        let mut token_stream = process_chat_completion_stream(stream::iter(chunks))
+            .await
+            .unwrap();
+
+        let mut tokens = Vec::new();
+        while let Some(token_result) = token_stream.next().await {
+            let token = token_result.unwrap();
+            if token.is_finish {
+                break;
+            }
+            tokens.push(token);
+        }
+
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(tokens[0].content, "Olá");
+        assert_eq!(tokens[1].content, ", mundo");
+        assert_eq!(
+            tokens[2].metadata.as_ref().unwrap()["finish_reason"],
+            "stop"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_process_chat_completion_stream_ignores_other_choices() {
+        let chunks = vec![
+            Ok(bytes::Bytes::from(
+                "data: {\"choices\":[{\"index\":1,\"delta\":{\"content\":\"Oi\"}}]}\n\n",
+            )),
+            Ok(bytes::Bytes::from(
+                "data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Olá\"}}]}\n\n",
+            )),
+        ];
+
+        let mut token_stream = process_chat_completion_stream(stream::iter(chunks))
+            .await
+            .unwrap();
+
+        let token = token_stream.next().await.unwrap().unwrap();
+        assert_eq!(token.content, "Olá");
+        assert!(token_stream.next().await.unwrap().unwrap().is_finish);
+    }
+
+    #[tokio::test]
+    async fn test_process_json_stream_keeps_utf8_split_across_chunks() {
+        let line = "data: {\"text\":\"ação é\"}\n".as_bytes();
+        // Divide no meio do "ç", que ocupa dois bytes
+        let split = line.iter().position(|&b| b == 0xC3).unwrap() + 1;
+        let chunks = vec![
+            Ok(bytes::Bytes::copy_from_slice(&line[..split])),
+            Ok(bytes::Bytes::copy_from_slice(&line[split..])),
+        ];
+
+        let mut token_stream =
+            process_json_stream_with::<_, Value, _>(stream::iter(chunks), |chunk| {
+                Ok(chunk["text"].as_str().map(|text| StreamingToken {
+                    content: text.to_string(),
+                    is_finish: false,
+                    metadata: None,
+                }))
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            token_stream.next().await.unwrap().unwrap().content,
+            "ação é"
+        );
+        assert!(token_stream.next().await.unwrap().unwrap().is_finish);
+    }
 }
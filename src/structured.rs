@@ -0,0 +1,596 @@
+//! # Saída Estruturada (Modo JSON)
+//!
+//! Este módulo interpreta o campo `response_format` do payload e garante que
+//! a resposta do modelo seja um JSON válido, opcionalmente conforme um JSON
+//! Schema.
+//!
+//! ## Formatos Aceitos
+//!
+//! - `{"type": "text"}` - Resposta em texto livre (padrão)
+//! - `{"type": "json_object"}` - A resposta deve ser um objeto JSON
+//! - `{"type": "json_schema", "schema": {...}, "name": "...", "strict": false}` -
+//!   A resposta deve satisfazer o JSON Schema informado. O formato da OpenAI,
+//!   com `name`, `schema` e `strict` dentro de `json_schema`, também é aceito.
+//!
+//! Os modos JSON aceitam ainda `max_retries` (até [`MAX_STRUCTURED_RETRIES`]):
+//! quantas vezes o modelo é chamado de novo, com os erros de validação, se a
+//! resposta não for válida.
+//!
+//! ## Mapeamento por Provedor
+//!
+//! Cada agente usa o modo JSON nativo do provedor quando disponível
+//! (`response_format` nas APIs compatíveis com a OpenAI e na Cohere,
+//! `responseMimeType` no Gemini e `format` no Ollama). Provedores sem modo
+//! nativo recebem as instruções de [`ResponseFormat::instructions`] no prompt
+//! de sistema.
+//!
+//! ## Validação
+//!
+//! Em [`AgentRegistry::process`](crate::agent::AgentRegistry::process), a
+//! resposta dos modos JSON é interpretada e validada: o objeto fica em
+//! `payload.data` no lugar de `answer`, e o `usage` soma todas as tentativas.
+//! Se nenhuma tentativa for válida, o erro é `MCPError::Upstream`, com o
+//! `usage` somado na mensagem. Respostas com `tool_calls` e respostas em
+//! streaming não são validadas.
+//!
+//! ## Exemplo de Uso
+//!
+//! ```rust
+//! use mcprs::structured::ResponseFormat;
+//! use serde_json::json;
+//!
+//! let format = ResponseFormat::from_payload(&json!({
+//!     "response_format": {
+//!         "type": "json_schema",
+//!         "schema": {
+//!             "type": "object",
+//!             "properties": { "ano": { "type": "integer" } },
+//!             "required": ["ano"]
+//!         },
+//!         "max_retries": 2
+//!     }
+//! }))
+//! .unwrap()
+//! .unwrap();
+//!
+//! assert_eq!(format.max_retries(), 2);
+//! assert_eq!(format.parse_answer("{\"ano\": 2015}").unwrap(), json!({ "ano": 2015 }));
+//! assert!(format.parse_answer("{\"ano\": \"2015\"}").is_err());
+//! ```
+
+use crate::agent::{AIAgent, MCPError, MCPMessage};
+use crate::chat::{ChatMessage, ChatRequest};
+use crate::usage::Usage;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tracing::warn;
+
+/// Número máximo de novas tentativas aceito em `max_retries`
+pub const MAX_STRUCTURED_RETRIES: u32 = 5;
+
+/// Nome usado para o schema quando o payload não informa `name`
+const DEFAULT_SCHEMA_NAME: &str = "resposta";
+
+/// Formato de resposta pedido no campo `response_format` do payload.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResponseFormat {
+    /// Texto livre (`{"type": "text"}`)
+    Text,
+
+    /// Um objeto JSON qualquer (`{"type": "json_object"}`)
+    JsonObject {
+        /// Novas tentativas se a resposta não for um objeto JSON
+        max_retries: u32,
+    },
+
+    /// Um JSON que satisfaz o schema informado (`{"type": "json_schema"}`)
+    JsonSchema {
+        /// Nome do schema, enviado aos provedores que o exigem
+        name: String,
+
+        /// O JSON Schema da resposta
+        schema: Value,
+
+        /// Pede ao provedor que siga o schema estritamente, quando suportado
+        strict: bool,
+
+        /// Novas tentativas se a resposta não satisfizer o schema
+        max_retries: u32,
+
+        /// O schema compilado, usado para validar cada resposta
+        validator: SchemaValidator,
+    },
+}
+
+/// Validador de um JSON Schema, compilado uma única vez em
+/// [`ResponseFormat::from_payload`] e compartilhado entre as cópias do formato.
+#[derive(Debug, Clone)]
+pub struct SchemaValidator(Arc<jsonschema::Validator>);
+
+impl PartialEq for SchemaValidator {
+    /// O validador é derivado do schema, que já é comparado no formato.
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl ResponseFormat {
+    /// Interpreta o campo `response_format` do payload.
+    ///
+    /// # Retorna
+    /// * `Ok(None)` - Se o campo não for informado
+    /// * `Ok(Some(ResponseFormat))` - O formato pedido
+    ///
+    /// # Erros
+    /// * `MCPError::PayloadValidation` - Se o campo não for um objeto, se `type`
+    ///   for desconhecido, se o schema for ausente ou inválido ou se
+    ///   `max_retries` passar de [`MAX_STRUCTURED_RETRIES`]
+    pub fn from_payload(payload: &Value) -> Result<Option<Self>, MCPError> {
+        let format = match payload.get("response_format") {
+            None | Some(Value::Null) => return Ok(None),
+            Some(Value::Object(format)) => format,
+            Some(_) => {
+                return Err(MCPError::PayloadValidation(
+                    "Campo 'response_format' deve ser um objeto".to_string(),
+                ))
+            }
+        };
+
+        let max_retries = match format.get("max_retries") {
+            None | Some(Value::Null) => 0,
+            Some(value) => value
+                .as_u64()
+                .filter(|n| *n <= MAX_STRUCTURED_RETRIES as u64)
+                .ok_or_else(|| {
+                    MCPError::PayloadValidation(format!(
+                        "Campo 'response_format.max_retries' deve ser um inteiro entre 0 e {}",
+                        MAX_STRUCTURED_RETRIES
+                    ))
+                })? as u32,
+        };
+
+        match format.get("type").and_then(Value::as_str) {
+            Some("text") => Ok(Some(ResponseFormat::Text)),
+            Some("json_object") => Ok(Some(ResponseFormat::JsonObject { max_retries })),
+            Some("json_schema") => {
+                // Aceita também o formato da OpenAI, com os campos em `json_schema`
+                let spec = format
+                    .get("json_schema")
+                    .and_then(Value::as_object)
+                    .unwrap_or(format);
+
+                let schema = spec
+                    .get("schema")
+                    .filter(|schema| schema.is_object())
+                    .cloned()
+                    .ok_or_else(|| {
+                        MCPError::PayloadValidation(
+                            "Campo 'response_format' do tipo 'json_schema' exige um objeto em 'schema'"
+                                .to_string(),
+                        )
+                    })?;
+                let validator = jsonschema::validator_for(&schema).map_err(|e| {
+                    MCPError::PayloadValidation(format!(
+                        "JSON Schema inválido em 'response_format': {}",
+                        e
+                    ))
+                })?;
+
+                let name = match spec.get("name") {
+                    None | Some(Value::Null) => DEFAULT_SCHEMA_NAME.to_string(),
+                    Some(Value::String(name)) if is_valid_schema_name(name) => name.clone(),
+                    Some(_) => {
+                        return Err(MCPError::PayloadValidation(
+                            "Campo 'response_format.name' deve ter apenas letras, números, '_' e '-' (até 64)"
+                                .to_string(),
+                        ))
+                    }
+                };
+
+                Ok(Some(ResponseFormat::JsonSchema {
+                    name,
+                    schema,
+                    strict: spec.get("strict").and_then(Value::as_bool).unwrap_or(false),
+                    max_retries,
+                    validator: SchemaValidator(Arc::new(validator)),
+                }))
+            }
+            Some(other) => Err(MCPError::PayloadValidation(format!(
+                "Tipo de 'response_format' inválido: '{}' (esperado \"text\", \"json_object\" ou \"json_schema\")",
+                other
+            ))),
+            None => Err(MCPError::PayloadValidation(
+                "Campo 'response_format' deve informar 'type'".to_string(),
+            )),
+        }
+    }
+
+    /// Indica se a resposta deve ser JSON.
+    pub fn is_json(&self) -> bool {
+        !matches!(self, ResponseFormat::Text)
+    }
+
+    /// O JSON Schema da resposta, se houver.
+    pub fn schema(&self) -> Option<&Value> {
+        match self {
+            ResponseFormat::JsonSchema { schema, .. } => Some(schema),
+            _ => None,
+        }
+    }
+
+    /// Quantas vezes o modelo pode ser chamado de novo após uma resposta inválida.
+    pub fn max_retries(&self) -> u32 {
+        match self {
+            ResponseFormat::Text => 0,
+            ResponseFormat::JsonObject { max_retries }
+            | ResponseFormat::JsonSchema { max_retries, .. } => *max_retries,
+        }
+    }
+
+    /// Instruções de sistema para provedores sem modo JSON nativo.
+    ///
+    /// # Retorna
+    /// `None` para [`ResponseFormat::Text`]
+    pub fn instructions(&self) -> Option<String> {
+        match self {
+            ResponseFormat::Text => None,
+            ResponseFormat::JsonObject { .. } => Some(
+                "Responda apenas com um objeto JSON válido, sem texto adicional nem blocos de código."
+                    .to_string(),
+            ),
+            ResponseFormat::JsonSchema { schema, .. } => Some(format!(
+                "Responda apenas com um JSON válido, sem texto adicional nem blocos de código, \
+                 que satisfaça este JSON Schema:\n{}",
+                schema
+            )),
+        }
+    }
+
+    /// Valor de `response_format` no formato de Chat Completions da OpenAI.
+    ///
+    /// # Argumentos
+    /// * `native_schema` - Se o provedor aceita `{"type": "json_schema"}`;
+    ///   caso contrário, o schema é pedido como `{"type": "json_object"}`
+    pub fn openai_value(&self, native_schema: bool) -> Value {
+        match self {
+            ResponseFormat::Text => json!({ "type": "text" }),
+            ResponseFormat::JsonSchema {
+                name,
+                schema,
+                strict,
+                ..
+            } if native_schema => json!({
+                "type": "json_schema",
+                "json_schema": { "name": name, "schema": schema, "strict": strict }
+            }),
+            _ => json!({ "type": "json_object" }),
+        }
+    }
+
+    /// Interpreta e valida a resposta do modelo.
+    ///
+    /// Blocos de código Markdown (` ```json `) em volta do JSON são ignorados.
+    ///
+    /// # Retorna
+    /// * `Ok(Value)` - O JSON da resposta
+    /// * `Err(Vec<String>)` - As mensagens de erro de interpretação ou validação
+    pub fn parse_answer(&self, answer: &str) -> Result<Value, Vec<String>> {
+        let data: Value = serde_json::from_str(strip_code_fence(answer))
+            .map_err(|e| vec![format!("A resposta não é um JSON válido: {}", e)])?;
+
+        match self {
+            ResponseFormat::Text => Ok(data),
+            ResponseFormat::JsonObject { .. } if !data.is_object() => {
+                Err(vec!["A resposta deve ser um objeto JSON".to_string()])
+            }
+            ResponseFormat::JsonObject { .. } => Ok(data),
+            ResponseFormat::JsonSchema { validator, .. } => {
+                let errors: Vec<String> = validator
+                    .0
+                    .iter_errors(&data)
+                    .map(|e| {
+                        let path = e.instance_path.to_string();
+                        if path.is_empty() {
+                            e.to_string()
+                        } else {
+                            format!("{}: {}", path, e)
+                        }
+                    })
+                    .collect();
+
+                if errors.is_empty() {
+                    Ok(data)
+                } else {
+                    Err(errors)
+                }
+            }
+        }
+    }
+}
+
+/// Verifica se o nome do schema segue o formato aceito pelos provedores.
+fn is_valid_schema_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Remove um bloco de código Markdown em volta da resposta, se houver.
+fn strip_code_fence(answer: &str) -> &str {
+    let trimmed = answer.trim();
+    let Some(body) = trimmed.strip_prefix("```") else {
+        return trimmed;
+    };
+    // Descarta o identificador da linguagem na primeira linha (ex: "json")
+    let body = body.split_once('\n').map_or("", |(_, rest)| rest);
+    body.trim_end().strip_suffix("```").unwrap_or(body).trim()
+}
+
+/// Processa uma requisição com saída JSON, validando a resposta do agente.
+///
+/// A cada resposta inválida, enquanto houver tentativas, a resposta e os
+/// erros de validação são acrescentados ao histórico e o agente é chamado de
+/// novo.
+///
+/// # Erros
+/// * `MCPError::Upstream` - Se nenhuma tentativa produzir uma resposta válida
+/// * Qualquer erro retornado pelo agente
+pub(crate) async fn process_structured(
+    agent: &dyn AIAgent,
+    message: MCPMessage,
+    format: &ResponseFormat,
+) -> Result<MCPMessage, MCPError> {
+    let mut payload = message.payload.clone();
+    let mut usage: Option<Usage> = None;
+    let mut attempt = 0;
+
+    loop {
+        let request = MCPMessage {
+            payload: payload.clone(),
+            ..message.clone()
+        };
+        let mut response = agent.process_request(request).await?;
+        if let Some(step_usage) = Usage::from_payload(&response.payload) {
+            *usage.get_or_insert_with(Usage::default) += step_usage;
+        }
+        if let (Some(usage), Some(fields)) = (usage, response.payload.as_object_mut()) {
+            fields.insert("usage".to_string(), usage.to_json());
+        }
+
+        // O modelo pediu ferramentas: ainda não há resposta final para validar
+        let has_tool_calls = response
+            .payload
+            .get("tool_calls")
+            .and_then(Value::as_array)
+            .is_some_and(|calls| !calls.is_empty());
+        if has_tool_calls {
+            return Ok(response);
+        }
+
+        let answer = response
+            .payload
+            .get("answer")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+
+        let errors = match format.parse_answer(&answer) {
+            Ok(data) => {
+                if let Some(fields) = response.payload.as_object_mut() {
+                    fields.remove("answer");
+                    fields.insert("data".to_string(), data);
+                }
+                return Ok(response);
+            }
+            Err(errors) => errors,
+        };
+
+        if attempt >= format.max_retries() {
+            // As tentativas consumiram tokens mesmo sem resposta válida
+            let usage = usage.map(|usage| usage.to_json()).unwrap_or(Value::Null);
+            warn!(
+                "Resposta de '{}' inválida para 'response_format' após {} tentativa(s); uso: {}",
+                agent.name(),
+                attempt + 1,
+                usage
+            );
+            return Err(MCPError::Upstream {
+                provider: agent.name().to_string(),
+                status: None,
+                message: format!(
+                    "Resposta não satisfaz 'response_format' após {} tentativa(s) (uso: {}): {}",
+                    attempt + 1,
+                    usage,
+                    errors.join("; ")
+                ),
+                retryable: true,
+            });
+        }
+
+        attempt += 1;
+        warn!(
+            "Resposta de '{}' inválida para 'response_format' (nova tentativa {}): {}",
+            agent.name(),
+            attempt,
+            errors.join("; ")
+        );
+
+        // Reenvia o histórico com a resposta inválida e os erros encontrados
+        let mut messages = ChatRequest::from_payload(&payload)?.messages;
+        messages.push(ChatMessage::assistant(answer));
+        messages.push(ChatMessage::user(format!(
+            "A resposta anterior não satisfaz o formato pedido:\n- {}\n\
+             Responda novamente apenas com o JSON corrigido.",
+            errors.join("\n- ")
+        )));
+        if let Some(fields) = payload.as_object_mut() {
+            fields.remove("system_prompt");
+            fields.remove("user_prompt");
+            fields.insert("messages".to_string(), json!(messages));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::collections::VecDeque;
+    use std::sync::{Arc, Mutex};
+
+    fn schema_format(max_retries: u32) -> ResponseFormat {
+        ResponseFormat::from_payload(&json!({
+            "response_format": {
+                "type": "json_schema",
+                "schema": {
+                    "type": "object",
+                    "properties": { "ano": { "type": "integer", "minimum": 1900 } },
+                    "required": ["ano"]
+                },
+                "max_retries": max_retries
+            }
+        }))
+        .unwrap()
+        .unwrap()
+    }
+
+    /// Agente que devolve respostas pré-definidas e guarda as requisições.
+    struct ScriptedAgent {
+        answers: Mutex<VecDeque<&'static str>>,
+        requests: Arc<Mutex<Vec<Value>>>,
+    }
+
+    #[async_trait]
+    impl AIAgent for ScriptedAgent {
+        fn name(&self) -> &str {
+            "roteiro"
+        }
+
+        async fn process_request(&self, message: MCPMessage) -> Result<MCPMessage, MCPError> {
+            self.requests.lock().unwrap().push(message.payload);
+            let answer = self.answers.lock().unwrap().pop_front().unwrap();
+            Ok(MCPMessage::new(
+                "roteiro_response",
+                json!({
+                    "answer": answer,
+                    "usage": { "input_tokens": 10, "output_tokens": 5, "total_tokens": 15 }
+                }),
+            ))
+        }
+    }
+
+    fn scripted(answers: Vec<&'static str>) -> (ScriptedAgent, Arc<Mutex<Vec<Value>>>) {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let agent = ScriptedAgent {
+            answers: Mutex::new(answers.into()),
+            requests: requests.clone(),
+        };
+        (agent, requests)
+    }
+
+    #[test]
+    fn test_response_format_parsing() {
+        assert_eq!(ResponseFormat::from_payload(&json!({})).unwrap(), None);
+        assert_eq!(
+            ResponseFormat::from_payload(&json!({ "response_format": { "type": "json_object" } }))
+                .unwrap(),
+            Some(ResponseFormat::JsonObject { max_retries: 0 })
+        );
+
+        // O formato da OpenAI é aceito
+        let format = ResponseFormat::from_payload(&json!({
+            "response_format": {
+                "type": "json_schema",
+                "json_schema": { "name": "ano", "schema": { "type": "object" }, "strict": true }
+            }
+        }))
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            format.openai_value(true),
+            json!({
+                "type": "json_schema",
+                "json_schema": { "name": "ano", "schema": { "type": "object" }, "strict": true }
+            })
+        );
+        assert_eq!(format.openai_value(false), json!({ "type": "json_object" }));
+
+        for invalid in [
+            json!("json"),
+            json!({ "type": "xml" }),
+            json!({ "type": "json_schema" }),
+            json!({ "type": "json_schema", "schema": { "type": "inexistente" } }),
+            json!({ "type": "json_object", "max_retries": 10 }),
+        ] {
+            let result = ResponseFormat::from_payload(&json!({ "response_format": invalid }));
+            assert!(
+                matches!(result, Err(MCPError::PayloadValidation(_))),
+                "{:?}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_answer() {
+        let format = schema_format(0);
+        assert_eq!(
+            format
+                .parse_answer("```json\n{\"ano\": 2015}\n```")
+                .unwrap(),
+            json!({ "ano": 2015 })
+        );
+
+        let errors = format.parse_answer("{\"ano\": 1500}").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("/ano: "));
+        assert!(format.parse_answer("O ano foi 2015").is_err());
+
+        let format = ResponseFormat::JsonObject { max_retries: 0 };
+        assert!(format.parse_answer("[1, 2]").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_process_structured_retries_with_errors() {
+        let (agent, requests) = scripted(vec!["{\"ano\": \"2015\"}", "{\"ano\": 2015}"]);
+        let message = MCPMessage::new(
+            "roteiro:chat",
+            json!({ "system_prompt": "Responda em JSON.", "user_prompt": "Quando saiu o Rust 1.0?" }),
+        );
+
+        let response = process_structured(&agent, message, &schema_format(1))
+            .await
+            .unwrap();
+        assert_eq!(response.payload["data"], json!({ "ano": 2015 }));
+        assert!(response.payload.get("answer").is_none());
+        assert_eq!(
+            response.payload["usage"],
+            json!({ "input_tokens": 20, "output_tokens": 10, "total_tokens": 30 })
+        );
+
+        // A segunda tentativa recebe a resposta inválida e os erros
+        let requests = requests.lock().unwrap();
+        let messages = requests[1]["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[0]["role"], "system");
+        assert_eq!(messages[2]["content"], "{\"ano\": \"2015\"}");
+        assert!(messages[3]["content"].as_str().unwrap().contains("/ano"));
+    }
+
+    #[tokio::test]
+    async fn test_process_structured_fails_after_retries() {
+        let (agent, _) = scripted(vec!["não sei", "{}"]);
+        let message = MCPMessage::new("roteiro:chat", json!({ "user_prompt": "Ano?" }));
+
+        let result = process_structured(&agent, message, &schema_format(1)).await;
+        assert!(matches!(
+            result,
+            Err(MCPError::Upstream { provider, message, .. })
+                if provider == "roteiro"
+                    && message.contains("2 tentativa(s)")
+                    && message.contains("\"total_tokens\":30")
+        ));
+    }
+}
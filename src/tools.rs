@@ -0,0 +1,639 @@
+//! # Ferramentas Executadas pelo Servidor
+//!
+//! Este módulo permite que o próprio `mcprs` hospede ferramentas escritas em
+//! Rust. Uma [`Tool`] descreve seus argumentos com um JSON Schema e é
+//! executada de forma assíncrona; o [`ToolRegistry`] reúne as ferramentas
+//! disponíveis; e o [`ToolExecutor`] envolve um agente e conduz o ciclo
+//! modelo → `tool_calls` → resultados → modelo até obter a resposta final.
+//!
+//! ## Ciclo de Execução
+//!
+//! 1. As definições das ferramentas registradas são enviadas em `tools`
+//! 2. Se a resposta trouxer `tool_calls`, cada chamada é executada e o
+//!    resultado volta ao modelo como uma mensagem com papel `tool`
+//! 3. O ciclo termina quando o modelo responde sem `tool_calls`, ou falha com
+//!    `MCPError::InternalAgentError` ao atingir o limite de iterações
+//!
+//! Todas as mensagens do ciclo são registradas na [`Conversation`], e a
+//! resposta final inclui:
+//! - `tool_trace`: as chamadas executadas (`id`, `name`, `arguments`,
+//!   `result` ou `error` e `iteration`)
+//! - `iterations`: quantas chamadas ao modelo foram feitas
+//! - `usage`: a soma do consumo de tokens de todas as iterações
+//!
+//! Erros de uma ferramenta não interrompem o ciclo: são enviados ao modelo
+//! como `{"error": "..."}` para que ele possa reagir.
+//!
+//! ## Exemplo de Uso
+//!
+//! ```rust,no_run
+//! use async_trait::async_trait;
+//! use mcprs::agent::{AgentRegistry, MCPError, MCPMessage};
+//! use mcprs::agent_openai::create_openai_agent;
+//! use mcprs::tools::{Tool, ToolExecutor, ToolRegistry};
+//! use serde_json::{json, Value};
+//!
+//! struct Clima;
+//!
+//! #[async_trait]
+//! impl Tool for Clima {
+//!     fn name(&self) -> &str {
+//!         "clima"
+//!     }
+//!
+//!     fn description(&self) -> Option<&str> {
+//!         Some("Consulta o clima de uma cidade")
+//!     }
+//!
+//!     fn parameters(&self) -> Value {
+//!         json!({
+//!             "type": "object",
+//!             "properties": { "cidade": { "type": "string" } },
+//!             "required": ["cidade"]
+//!         })
+//!     }
+//!
+//!     async fn call(&self, arguments: Value) -> Result<Value, MCPError> {
+//!         Ok(json!({ "cidade": arguments["cidade"], "temperatura": 21 }))
+//!     }
+//! }
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut tools = ToolRegistry::new();
+//! tools.register_tool(Box::new(Clima));
+//!
+//! // O executor substitui o agente no registro, com o mesmo nome
+//! let executor = ToolExecutor::new(Box::new(create_openai_agent(None)), tools)
+//!     .with_max_iterations(4);
+//! let mut registry = AgentRegistry::new();
+//! registry.register_agent(Box::new(executor));
+//!
+//! let message = MCPMessage::new("openai:chat", json!({ "user_prompt": "Como está Lisboa?" }));
+//! let response = registry.process(message).await?;
+//! println!("Resposta: {}", response.payload["answer"]);
+//! println!("Ferramentas: {}", response.payload["tool_trace"]);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::agent::{AIAgent, MCPError, MCPMessage};
+use crate::chat::{ChatMessage, ChatRequest, ChatRole, ToolCall, ToolDefinition};
+use crate::conversation::Conversation;
+use crate::usage::Usage;
+use async_trait::async_trait;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use tracing::{debug, warn};
+
+/// Limite padrão de chamadas ao modelo em um ciclo de ferramentas
+pub const DEFAULT_MAX_TOOL_ITERATIONS: usize = 8;
+
+/// Ferramenta que pode ser chamada pelo modelo e executada pelo servidor.
+#[async_trait]
+pub trait Tool: Send + Sync {
+    /// Nome da ferramenta (letras, números, `_` e `-`, até 64 caracteres).
+    fn name(&self) -> &str;
+
+    /// Descrição usada pelo modelo para decidir quando chamar a ferramenta.
+    fn description(&self) -> Option<&str> {
+        None
+    }
+
+    /// JSON Schema dos argumentos aceitos pela ferramenta.
+    fn parameters(&self) -> Value;
+
+    /// Executa a ferramenta com os argumentos gerados pelo modelo.
+    ///
+    /// # Argumentos
+    /// * `arguments` - Os argumentos da chamada; se o modelo não gerar JSON
+    ///   válido, o texto original como string
+    ///
+    /// # Retorna
+    /// * `Ok(Value)` - O resultado enviado de volta ao modelo
+    /// * `Err(MCPError)` - Um erro, informado ao modelo como `{"error": "..."}`
+    async fn call(&self, arguments: Value) -> Result<Value, MCPError>;
+
+    /// Definição da ferramenta enviada ao modelo em `tools`.
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: self.name().to_string(),
+            description: self.description().map(str::to_string),
+            parameters: self.parameters(),
+        }
+    }
+}
+
+/// Coleção de ferramentas disponíveis para um [`ToolExecutor`].
+#[derive(Default)]
+pub struct ToolRegistry {
+    /// Mapa de nome da ferramenta para sua implementação
+    tools: BTreeMap<String, Box<dyn Tool>>,
+}
+
+impl ToolRegistry {
+    /// Cria um registro vazio.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registra uma ferramenta, substituindo outra de mesmo nome.
+    pub fn register_tool(&mut self, tool: Box<dyn Tool>) {
+        self.tools.insert(tool.name().to_string(), tool);
+    }
+
+    /// Retorna a ferramenta com o nome informado.
+    pub fn get(&self, name: &str) -> Option<&dyn Tool> {
+        self.tools.get(name).map(Box::as_ref)
+    }
+
+    /// Retorna os nomes das ferramentas registradas, em ordem alfabética.
+    pub fn tool_names(&self) -> Vec<String> {
+        self.tools.keys().cloned().collect()
+    }
+
+    /// Indica se nenhuma ferramenta foi registrada.
+    pub fn is_empty(&self) -> bool {
+        self.tools.is_empty()
+    }
+
+    /// Definições de todas as ferramentas, na ordem de [`tool_names`](Self::tool_names).
+    pub fn definitions(&self) -> Vec<ToolDefinition> {
+        self.tools.values().map(|tool| tool.definition()).collect()
+    }
+
+    /// Executa uma chamada de ferramenta pedida pelo modelo.
+    ///
+    /// # Erros
+    /// * `MCPError::NotFound` - Se a ferramenta não estiver registrada
+    /// * Qualquer erro retornado pela própria ferramenta
+    pub async fn call(&self, call: &ToolCall) -> Result<Value, MCPError> {
+        let tool = self.get(&call.name).ok_or_else(|| {
+            MCPError::NotFound(format!("Ferramenta '{}' não está registrada", call.name))
+        })?;
+        tool.call(call.arguments.clone()).await
+    }
+}
+
+/// Registro de uma chamada de ferramenta executada pelo [`ToolExecutor`].
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ToolInvocation {
+    /// ID da chamada, como informado pelo modelo
+    pub id: String,
+
+    /// Nome da ferramenta
+    pub name: String,
+
+    /// Argumentos gerados pelo modelo
+    pub arguments: Value,
+
+    /// Resultado da ferramenta, se a execução teve sucesso
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+
+    /// Mensagem de erro, se a execução falhou
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+
+    /// Iteração do ciclo (a partir de 1) em que o modelo pediu a chamada
+    pub iteration: usize,
+}
+
+impl ToolInvocation {
+    /// Conteúdo da mensagem `tool` enviada de volta ao modelo.
+    ///
+    /// Resultados textuais são enviados como estão; os demais, em JSON.
+    fn message_content(&self) -> String {
+        match (&self.result, &self.error) {
+            (Some(Value::String(text)), _) => text.clone(),
+            (Some(result), _) => result.to_string(),
+            (None, error) => json!({ "error": error }).to_string(),
+        }
+    }
+}
+
+/// Resultado de um ciclo completo do [`ToolExecutor`].
+#[derive(Debug, Clone)]
+pub struct ToolRun {
+    /// Resposta final do agente, com `tool_trace`, `iterations` e o `usage` acumulado
+    pub response: MCPMessage,
+
+    /// Chamadas de ferramentas executadas, na ordem
+    pub trace: Vec<ToolInvocation>,
+
+    /// Número de chamadas feitas ao modelo
+    pub iterations: usize,
+}
+
+impl ToolRun {
+    /// Texto da resposta final do modelo (`payload.answer`).
+    pub fn answer(&self) -> &str {
+        self.response
+            .payload
+            .get("answer")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+    }
+}
+
+/// Agente que executa as ferramentas pedidas pelo modelo de outro agente.
+///
+/// O executor usa o mesmo nome do agente envolvido, então pode ser
+/// registrado no [`AgentRegistry`](crate::agent::AgentRegistry) no lugar dele.
+/// Como o executor define `tools`, o payload não pode informar esse campo;
+/// `tool_choice` continua aceito e vale apenas para a primeira iteração.
+pub struct ToolExecutor {
+    /// Agente que conversa com o modelo
+    agent: Box<dyn AIAgent>,
+
+    /// Ferramentas disponíveis ao modelo
+    tools: ToolRegistry,
+
+    /// Limite de chamadas ao modelo por requisição
+    max_iterations: usize,
+}
+
+impl ToolExecutor {
+    /// Cria um executor com o limite de [`DEFAULT_MAX_TOOL_ITERATIONS`].
+    ///
+    /// # Argumentos
+    /// * `agent` - Agente com suporte a chamadas de ferramentas
+    /// * `tools` - Ferramentas disponíveis ao modelo
+    pub fn new(agent: Box<dyn AIAgent>, tools: ToolRegistry) -> Self {
+        Self {
+            agent,
+            tools,
+            max_iterations: DEFAULT_MAX_TOOL_ITERATIONS,
+        }
+    }
+
+    /// Define o limite de chamadas ao modelo por requisição (mínimo 1).
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations.max(1);
+        self
+    }
+
+    /// Ferramentas disponíveis ao modelo.
+    pub fn tools(&self) -> &ToolRegistry {
+        &self.tools
+    }
+
+    /// Executa o ciclo de ferramentas registrando cada etapa na conversa.
+    ///
+    /// O histórico da conversa é enviado ao modelo depois das instruções de
+    /// sistema da requisição e seguido pelas novas mensagens, que também
+    /// são registradas. As instruções de sistema não são registradas.
+    ///
+    /// # Argumentos
+    /// * `message` - A requisição de chat, sem o campo `tools`
+    /// * `conversation` - Conversa onde as mensagens do ciclo são registradas
+    ///
+    /// # Erros
+    /// * `MCPError::PayloadValidation` - Se o payload for inválido ou informar `tools`
+    /// * `MCPError::InternalAgentError` - Se o limite de iterações for atingido
+    ///   ou o agente retornar `tool_calls` em formato inválido
+    /// * Qualquer erro retornado pelo agente envolvido
+    pub async fn run(
+        &self,
+        message: MCPMessage,
+        conversation: &mut Conversation,
+    ) -> Result<ToolRun, MCPError> {
+        if message.payload.get("tools").is_some() {
+            return Err(MCPError::PayloadValidation(
+                "Campo 'tools' não é aceito: as ferramentas são definidas pelo servidor"
+                    .to_string(),
+            ));
+        }
+
+        let mut payload = message.payload.clone();
+        if let Some(fields) = payload.as_object_mut() {
+            fields.insert("tools".to_string(), json!(self.tools.definitions()));
+        }
+
+        let request = ChatRequest::from_payload(&payload)?;
+        let (mut messages, new_messages): (Vec<ChatMessage>, Vec<ChatMessage>) = request
+            .messages
+            .into_iter()
+            .partition(|m| m.role == ChatRole::System);
+
+        for new_message in &new_messages {
+            conversation.add_chat_message(new_message);
+        }
+        messages.extend(conversation.chat_history()?);
+
+        if let Some(fields) = payload.as_object_mut() {
+            fields.remove("system_prompt");
+            fields.remove("user_prompt");
+        }
+
+        let mut trace = Vec::new();
+        let mut usage: Option<Usage> = None;
+
+        for iteration in 1..=self.max_iterations {
+            payload["messages"] = json!(messages);
+            let request = MCPMessage {
+                payload: payload.clone(),
+                ..message.clone()
+            };
+
+            let mut response = self.agent.process_request(request).await?;
+            if let Some(step_usage) = Usage::from_payload(&response.payload) {
+                *usage.get_or_insert_with(Usage::default) += step_usage;
+            }
+
+            let tool_calls = tool_calls(&response.payload)?;
+            let answer = response
+                .payload
+                .get("answer")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string();
+
+            if tool_calls.is_empty() {
+                conversation.add_chat_message(&ChatMessage::assistant(answer));
+
+                if let Some(fields) = response.payload.as_object_mut() {
+                    fields.insert("tool_trace".to_string(), json!(trace));
+                    fields.insert("iterations".to_string(), json!(iteration));
+                    if let Some(usage) = usage {
+                        fields.insert("usage".to_string(), usage.to_json());
+                    }
+                }
+
+                return Ok(ToolRun {
+                    response,
+                    trace,
+                    iterations: iteration,
+                });
+            }
+
+            let assistant = ChatMessage::assistant_tool_calls(answer, tool_calls.clone());
+            conversation.add_chat_message(&assistant);
+            messages.push(assistant);
+
+            for call in tool_calls {
+                let invocation = self.invoke(call, iteration).await;
+                let result = ChatMessage::tool(&invocation.id, invocation.message_content());
+                conversation.add_chat_message(&result);
+                messages.push(result);
+                trace.push(invocation);
+            }
+
+            // Uma escolha forçada levaria o modelo a chamar ferramentas indefinidamente
+            if let Some(fields) = payload.as_object_mut() {
+                fields.remove("tool_choice");
+            }
+        }
+
+        Err(MCPError::InternalAgentError(format!(
+            "Limite de {} iterações atingido sem resposta final do modelo",
+            self.max_iterations
+        )))
+    }
+
+    /// Executa uma chamada de ferramenta, registrando o resultado ou o erro.
+    async fn invoke(&self, call: ToolCall, iteration: usize) -> ToolInvocation {
+        debug!("Executando ferramenta '{}' ({})", call.name, call.id);
+        let outcome = self.tools.call(&call).await;
+        if let Err(ref e) = outcome {
+            warn!("Ferramenta '{}' falhou: {}", call.name, e);
+        }
+
+        let (result, error) = match outcome {
+            Ok(result) => (Some(result), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+
+        ToolInvocation {
+            id: call.id,
+            name: call.name,
+            arguments: call.arguments,
+            result,
+            error,
+            iteration,
+        }
+    }
+}
+
+/// Lê as chamadas de ferramentas (`payload.tool_calls`) de uma resposta.
+fn tool_calls(payload: &Value) -> Result<Vec<ToolCall>, MCPError> {
+    match payload.get("tool_calls") {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(calls) => serde_json::from_value(calls.clone()).map_err(|e| {
+            MCPError::InternalAgentError(format!("Campo 'tool_calls' inválido na resposta: {}", e))
+        }),
+    }
+}
+
+#[async_trait]
+impl AIAgent for ToolExecutor {
+    /// Retorna o nome do agente envolvido.
+    fn name(&self) -> &str {
+        self.agent.name()
+    }
+
+    /// Executa o ciclo de ferramentas em uma conversa temporária.
+    ///
+    /// Veja [`ToolExecutor::run`] para o formato da resposta e os erros.
+    async fn process_request(&self, message: MCPMessage) -> Result<MCPMessage, MCPError> {
+        let mut conversation = Conversation::new();
+        Ok(self.run(message, &mut conversation).await?.response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+    use std::sync::{Arc, Mutex};
+
+    /// Ferramenta que soma os números em `a` e `b`.
+    struct Soma;
+
+    #[async_trait]
+    impl Tool for Soma {
+        fn name(&self) -> &str {
+            "soma"
+        }
+
+        fn parameters(&self) -> Value {
+            json!({
+                "type": "object",
+                "properties": { "a": { "type": "number" }, "b": { "type": "number" } }
+            })
+        }
+
+        async fn call(&self, arguments: Value) -> Result<Value, MCPError> {
+            match (arguments["a"].as_i64(), arguments["b"].as_i64()) {
+                (Some(a), Some(b)) => Ok(json!(a + b)),
+                _ => Err(MCPError::BadRequest("argumentos inválidos".to_string())),
+            }
+        }
+    }
+
+    /// Agente que devolve respostas pré-definidas e guarda as requisições.
+    struct ScriptedAgent {
+        responses: Mutex<VecDeque<Value>>,
+        requests: Arc<Mutex<Vec<Value>>>,
+    }
+
+    impl ScriptedAgent {
+        fn new(responses: Vec<Value>) -> Self {
+            Self {
+                responses: Mutex::new(responses.into()),
+                requests: Arc::new(Mutex::new(Vec::new())),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl AIAgent for ScriptedAgent {
+        fn name(&self) -> &str {
+            "roteiro"
+        }
+
+        async fn process_request(&self, message: MCPMessage) -> Result<MCPMessage, MCPError> {
+            self.requests.lock().unwrap().push(message.payload);
+            let payload = self.responses.lock().unwrap().pop_front().unwrap();
+            Ok(MCPMessage::new("roteiro_response", payload))
+        }
+    }
+
+    fn executor(agent: ScriptedAgent) -> ToolExecutor {
+        let mut tools = ToolRegistry::new();
+        tools.register_tool(Box::new(Soma));
+        ToolExecutor::new(Box::new(agent), tools)
+    }
+
+    fn tool_call_response(id: &str, arguments: Value) -> Value {
+        json!({
+            "answer": "",
+            "tool_calls": [{ "id": id, "name": "soma", "arguments": arguments }],
+            "usage": { "input_tokens": 10, "output_tokens": 5, "total_tokens": 15 }
+        })
+    }
+
+    #[test]
+    fn test_tool_registry_definitions() {
+        let mut tools = ToolRegistry::new();
+        assert!(tools.is_empty());
+        tools.register_tool(Box::new(Soma));
+
+        assert_eq!(tools.tool_names(), vec!["soma"]);
+        assert!(tools.get("soma").is_some());
+        assert_eq!(
+            tools.definitions(),
+            vec![ToolDefinition {
+                name: "soma".to_string(),
+                description: None,
+                parameters: Soma.parameters(),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tool_executor_runs_loop_and_records_conversation() {
+        let agent = ScriptedAgent::new(vec![
+            tool_call_response("call_1", json!({ "a": 2, "b": 3 })),
+            tool_call_response("call_2", json!({ "a": "x" })),
+            json!({
+                "answer": "A soma é 5",
+                "usage": { "input_tokens": 30, "output_tokens": 4, "total_tokens": 34 }
+            }),
+        ]);
+        let requests = agent.requests.clone();
+
+        let mut conversation = Conversation::new();
+        let message = MCPMessage::new(
+            "roteiro:chat",
+            json!({
+                "system_prompt": "Use as ferramentas.",
+                "user_prompt": "Quanto é 2 + 3?",
+                "tool_choice": "required"
+            }),
+        );
+        let run = executor(agent)
+            .run(message, &mut conversation)
+            .await
+            .unwrap();
+
+        assert_eq!(run.answer(), "A soma é 5");
+        assert_eq!(run.iterations, 3);
+        assert_eq!(run.trace.len(), 2);
+        assert_eq!(run.trace[0].result, Some(json!(5)));
+        assert_eq!(
+            run.trace[1].error.as_deref(),
+            Some("Requisição inválida: argumentos inválidos")
+        );
+        assert_eq!(run.response.payload["tool_trace"][0]["name"], "soma");
+        assert_eq!(run.response.payload["iterations"], 3);
+        assert_eq!(
+            run.response.payload["usage"],
+            json!({ "input_tokens": 50, "output_tokens": 14, "total_tokens": 64 })
+        );
+
+        // Cada etapa fica na conversa, exceto a instrução de sistema
+        let roles: Vec<&str> = conversation
+            .messages
+            .iter()
+            .map(|m| m.role.as_str())
+            .collect();
+        assert_eq!(
+            roles,
+            vec![
+                "user",
+                "assistant",
+                "tool",
+                "assistant",
+                "tool",
+                "assistant"
+            ]
+        );
+        assert_eq!(conversation.messages[1].tool_calls[0].id, "call_1");
+        assert_eq!(
+            conversation.messages[2].tool_call_id.as_deref(),
+            Some("call_1")
+        );
+        assert_eq!(conversation.messages[2].content, "5");
+
+        // O modelo recebe as ferramentas e os resultados anteriores;
+        // tool_choice vale só para a primeira iteração
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests[0]["tools"][0]["name"], "soma");
+        assert_eq!(requests[0]["tool_choice"], "required");
+        assert!(requests[1].get("tool_choice").is_none());
+        assert_eq!(requests[0]["messages"].as_array().unwrap().len(), 2);
+        assert_eq!(requests[2]["messages"].as_array().unwrap().len(), 6);
+        assert_eq!(
+            requests[2]["messages"][5],
+            json!({
+                "role": "tool",
+                "content": "{\"error\":\"Requisição inválida: argumentos inválidos\"}",
+                "tool_call_id": "call_2"
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tool_executor_limits_iterations() {
+        let agent = ScriptedAgent::new(vec![
+            tool_call_response("call_1", json!({ "a": 1, "b": 1 })),
+            tool_call_response("call_2", json!({ "a": 2, "b": 2 })),
+        ]);
+
+        let executor = executor(agent).with_max_iterations(2);
+        let message = MCPMessage::new("roteiro:chat", json!({ "user_prompt": "Some sem parar" }));
+        let result = executor.process_request(message).await;
+        assert!(
+            matches!(result, Err(MCPError::InternalAgentError(e)) if e.contains("2 iterações"))
+        );
+
+        // O payload não pode definir as próprias ferramentas
+        let message = MCPMessage::new(
+            "roteiro:chat",
+            json!({ "user_prompt": "Olá", "tools": [{ "name": "outra" }] }),
+        );
+        let result = executor.process_request(message).await;
+        assert!(matches!(result, Err(MCPError::PayloadValidation(_))));
+    }
+}
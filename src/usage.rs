@@ -0,0 +1,289 @@
+//! # Uso de Tokens e Custo Estimado
+//!
+//! Este módulo define o formato normalizado de consumo de tokens usado por
+//! todos os agentes e uma tabela de preços configurável para estimar o custo
+//! de cada chamada.
+//!
+//! ## Formato de `usage`
+//!
+//! Toda resposta de chat inclui em `payload.usage`, quando o provedor informa
+//! a contagem de tokens:
+//!
+//! - `input_tokens`: tokens do prompt (incluindo o histórico)
+//! - `output_tokens`: tokens gerados pelo modelo
+//! - `total_tokens`: soma informada pelo provedor, ou calculada se ausente
+//!
+//! ## Custo Estimado
+//!
+//! Com uma [`PriceTable`] registrada em
+//! [`AgentRegistry::set_price_table`](crate::agent::AgentRegistry::set_price_table),
+//! o registro acrescenta `payload.cost` às respostas cujo `model` tenha preço
+//! conhecido. Os preços são em dólares por milhão de tokens, e um modelo
+//! datado como `gpt-4o-2024-08-06` usa o preço do prefixo mais longo
+//! cadastrado (`gpt-4o`).
+//!
+//! ## Exemplo de Uso
+//!
+//! ```rust
+//! use mcprs::usage::{PriceTable, Usage};
+//!
+//! let prices = PriceTable::new()
+//!     .with_price("gpt-4o", 2.50, 10.00)
+//!     .with_price("gpt-4o-mini", 0.15, 0.60);
+//!
+//! let usage = Usage::new(1_000, 500);
+//! assert_eq!(usage.total_tokens, 1_500);
+//!
+//! let cost = prices.estimate("gpt-4o-2024-08-06", &usage).unwrap();
+//! assert!((cost.total - 0.0075).abs() < 1e-9);
+//! ```
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Consumo de tokens de uma chamada, no formato comum a todos os agentes.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Usage {
+    /// Tokens do prompt enviado ao modelo
+    pub input_tokens: u64,
+
+    /// Tokens gerados pelo modelo
+    pub output_tokens: u64,
+
+    /// Total de tokens cobrados
+    pub total_tokens: u64,
+}
+
+impl Usage {
+    /// Cria um registro de uso, calculando o total.
+    pub fn new(input_tokens: u64, output_tokens: u64) -> Self {
+        Self {
+            input_tokens,
+            output_tokens,
+            total_tokens: input_tokens + output_tokens,
+        }
+    }
+
+    /// Substitui o total calculado pelo informado pelo provedor, se houver.
+    pub fn with_total(mut self, total_tokens: Option<u64>) -> Self {
+        if let Some(total) = total_tokens {
+            self.total_tokens = total;
+        }
+        self
+    }
+
+    /// Lê o campo `usage` de um payload de resposta.
+    ///
+    /// # Retorna
+    /// `None` se o payload não tiver `usage` no formato normalizado
+    pub fn from_payload(payload: &Value) -> Option<Self> {
+        payload
+            .get("usage")
+            .and_then(|usage| serde_json::from_value(usage.clone()).ok())
+    }
+
+    /// Converte para o objeto JSON enviado no payload.
+    pub fn to_json(&self) -> Value {
+        serde_json::to_value(self).unwrap_or_default()
+    }
+}
+
+impl std::ops::AddAssign for Usage {
+    /// Acumula o consumo de várias chamadas, como as iterações de um
+    /// [`ToolExecutor`](crate::tools::ToolExecutor).
+    fn add_assign(&mut self, other: Self) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.total_tokens += other.total_tokens;
+    }
+}
+
+/// Contagem de tokens no formato de Chat Completions da OpenAI, usado também
+/// pelos provedores compatíveis e pelos chunks de streaming
+#[derive(Deserialize)]
+pub(crate) struct ChatCompletionUsage {
+    #[serde(default)]
+    prompt_tokens: u64,
+    #[serde(default)]
+    completion_tokens: u64,
+    total_tokens: Option<u64>,
+}
+
+impl ChatCompletionUsage {
+    /// Converte para o formato normalizado de [`Usage`].
+    pub(crate) fn normalized(&self) -> Usage {
+        Usage::new(self.prompt_tokens, self.completion_tokens).with_total(self.total_tokens)
+    }
+}
+
+/// Preço de um modelo, em dólares por milhão de tokens.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ModelPrice {
+    /// Preço por milhão de tokens de entrada
+    pub input_per_million: f64,
+
+    /// Preço por milhão de tokens de saída
+    pub output_per_million: f64,
+}
+
+/// Custo estimado de uma chamada, em dólares.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct Cost {
+    /// Custo dos tokens de entrada
+    pub input: f64,
+
+    /// Custo dos tokens de saída
+    pub output: f64,
+
+    /// Custo total
+    pub total: f64,
+
+    /// Moeda dos valores (sempre "USD")
+    pub currency: &'static str,
+}
+
+impl ModelPrice {
+    /// Calcula o custo de um consumo de tokens com este preço.
+    pub fn cost(&self, usage: &Usage) -> Cost {
+        let input = usage.input_tokens as f64 * self.input_per_million / 1_000_000.0;
+        let output = usage.output_tokens as f64 * self.output_per_million / 1_000_000.0;
+
+        Cost {
+            input,
+            output,
+            total: input + output,
+            currency: "USD",
+        }
+    }
+}
+
+/// Tabela de preços por modelo usada para estimar o custo das chamadas.
+///
+/// A tabela pode ser montada com [`with_price`](Self::with_price) ou lida de
+/// um objeto JSON no formato
+/// `{"gpt-4o": {"input_per_million": 2.5, "output_per_million": 10.0}}`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(transparent)]
+pub struct PriceTable {
+    prices: BTreeMap<String, ModelPrice>,
+}
+
+impl PriceTable {
+    /// Cria uma tabela vazia.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Define o preço de um modelo ou prefixo de modelo.
+    ///
+    /// # Argumentos
+    /// * `model` - Nome do modelo (ex: "gpt-4o")
+    /// * `input_per_million` - Dólares por milhão de tokens de entrada
+    /// * `output_per_million` - Dólares por milhão de tokens de saída
+    pub fn with_price(
+        mut self,
+        model: impl Into<String>,
+        input_per_million: f64,
+        output_per_million: f64,
+    ) -> Self {
+        self.prices.insert(
+            model.into(),
+            ModelPrice {
+                input_per_million,
+                output_per_million,
+            },
+        );
+        self
+    }
+
+    /// Retorna o preço de um modelo.
+    ///
+    /// Usa o preço cadastrado com o nome exato ou, se não houver, o do
+    /// prefixo cadastrado mais longo.
+    pub fn price(&self, model: &str) -> Option<&ModelPrice> {
+        self.prices.get(model).or_else(|| {
+            self.prices
+                .iter()
+                .filter(|(prefix, _)| model.starts_with(prefix.as_str()))
+                .max_by_key(|(prefix, _)| prefix.len())
+                .map(|(_, price)| price)
+        })
+    }
+
+    /// Estima o custo de uma chamada.
+    ///
+    /// # Retorna
+    /// `None` se o modelo não tiver preço cadastrado
+    pub fn estimate(&self, model: &str, usage: &Usage) -> Option<Cost> {
+        self.price(model).map(|price| price.cost(usage))
+    }
+
+    /// Acrescenta `cost` a um payload de resposta com `model` e `usage`.
+    ///
+    /// Payloads sem esses campos, ou de modelos sem preço, não são alterados.
+    pub fn annotate(&self, payload: &mut Value) {
+        let Some(model) = payload.get("model").and_then(Value::as_str) else {
+            return;
+        };
+        let Some(usage) = Usage::from_payload(payload) else {
+            return;
+        };
+
+        if let Some(cost) = self.estimate(model, &usage) {
+            payload["cost"] = serde_json::to_value(cost).unwrap_or_default();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_price_lookup_prefers_longest_prefix() {
+        let prices = PriceTable::new()
+            .with_price("gpt-4o", 2.50, 10.00)
+            .with_price("gpt-4o-mini", 0.15, 0.60);
+
+        assert_eq!(prices.price("gpt-4o").unwrap().input_per_million, 2.50);
+        assert_eq!(
+            prices
+                .price("gpt-4o-mini-2024-07-18")
+                .unwrap()
+                .input_per_million,
+            0.15
+        );
+        assert!(prices.price("claude-3-5-sonnet").is_none());
+    }
+
+    #[test]
+    fn test_annotate_adds_cost() {
+        let prices: PriceTable = serde_json::from_value(json!({
+            "deepseek-chat": { "input_per_million": 0.27, "output_per_million": 1.10 }
+        }))
+        .unwrap();
+
+        let mut payload = json!({
+            "answer": "Olá!",
+            "model": "deepseek-chat",
+            "usage": { "input_tokens": 2_000_000, "output_tokens": 1_000_000, "total_tokens": 3_000_000 }
+        });
+        prices.annotate(&mut payload);
+
+        let cost = &payload["cost"];
+        assert!((cost["input"].as_f64().unwrap() - 0.54).abs() < 1e-9);
+        assert!((cost["output"].as_f64().unwrap() - 1.10).abs() < 1e-9);
+        assert!((cost["total"].as_f64().unwrap() - 1.64).abs() < 1e-9);
+        assert_eq!(cost["currency"], "USD");
+
+        // Sem preço cadastrado, o payload não muda
+        let mut payload = json!({
+            "model": "gpt-4o",
+            "usage": { "input_tokens": 1, "output_tokens": 1, "total_tokens": 2 }
+        });
+        prices.annotate(&mut payload);
+        assert!(payload.get("cost").is_none());
+    }
+}
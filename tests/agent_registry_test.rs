@@ -1,4 +1,5 @@
 use mcprs::agent::{AgentRegistry, DummyAgent, MCPError, MCPMessage};
+use mcprs::usage::PriceTable;
 use serde_json::json;
 
 #[tokio::test]
@@ -41,3 +42,29 @@ async fn test_registry_invalid_command_format() {
     let result = registry.process(msg).await;
     assert!(matches!(result, Err(MCPError::InvalidCommandFormat)));
 }
+
+#[tokio::test]
+async fn test_registry_estimates_cost_with_price_table() {
+    let mut registry = AgentRegistry::new();
+    registry.register_agent(Box::new(DummyAgent {
+        api_key: "dummy_key".to_string(),
+    }));
+    registry.set_price_table(PriceTable::new().with_price("gpt-4o", 2.50, 10.00));
+
+    // O DummyAgent devolve o payload, simulando uma resposta com `model` e `usage`
+    let msg = MCPMessage::new(
+        "dummy:echo",
+        json!({
+            "answer": "Olá!",
+            "model": "gpt-4o-2024-08-06",
+            "usage": { "input_tokens": 1000, "output_tokens": 500, "total_tokens": 1500 }
+        }),
+    );
+    let response = registry.process(msg).await.unwrap();
+
+    let cost = &response.payload["cost"];
+    assert!((cost["input"].as_f64().unwrap() - 0.0025).abs() < 1e-9);
+    assert!((cost["output"].as_f64().unwrap() - 0.005).abs() < 1e-9);
+    assert!((cost["total"].as_f64().unwrap() - 0.0075).abs() < 1e-9);
+    assert_eq!(cost["currency"], "USD");
+}
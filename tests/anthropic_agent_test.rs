@@ -0,0 +1,266 @@
+use mcprs::agent::{AIAgent, MCPError, MCPMessage};
+use mcprs::testing::MockHttpClient;
+use mockall::predicate;
+use serde_json::json;
+
+// Helper para criar uma resposta mockada
+fn create_mock_response(body: serde_json::Value) -> reqwest::Response {
+    reqwest::Response::from(
+        http::Response::builder()
+            .status(200)
+            .body(body.to_string())
+            .unwrap(),
+    )
+}
+
+// Helper para criar o corpo de uma resposta da Messages API
+fn message_response(text: &str) -> serde_json::Value {
+    json!({
+        "id": "msg_123",
+        "type": "message",
+        "role": "assistant",
+        "content": [{ "type": "text", "text": text }],
+        "stop_reason": "end_turn",
+        "usage": { "input_tokens": 10, "output_tokens": 4 }
+    })
+}
+
+#[tokio::test]
+async fn test_anthropic_agent_successful_request() {
+    let mut mock_client = MockHttpClient::new();
+
+    mock_client
+        .expect_post()
+        .with(
+            predicate::eq("https://api.anthropic.com/v1/messages".to_string()),
+            predicate::always(),
+            predicate::always(),
+        )
+        .times(1)
+        .return_once(move |_, _, _| Ok(create_mock_response(message_response("Mock response"))));
+
+    let agent = mcprs::agent_anthropic::create_anthropic_agent(Some(Box::new(mock_client)));
+    let message = MCPMessage::new("anthropic:chat", json!({ "user_prompt": "Test prompt" }));
+
+    let result = agent.process_request(message).await.unwrap();
+    assert_eq!(result.command, "anthropic_response");
+    assert_eq!(
+        result.payload,
+        json!({
+            "answer": "Mock response",
+            "id": "msg_123",
+            "model": "claude-3-5-sonnet-latest",
+            "stop_reason": "end_turn",
+            "usage": { "input_tokens": 10, "output_tokens": 4, "total_tokens": 14 }
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_anthropic_agent_network_error() {
+    // Uma porta sem servidor produz uma falha de transporte real
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+    let transport_error = reqwest::Client::new()
+        .post(format!("http://{}/v1/messages", addr))
+        .send()
+        .await
+        .unwrap_err();
+
+    let mut mock_client = MockHttpClient::new();
+    mock_client
+        .expect_post()
+        .return_once(move |_, _, _| Err(transport_error));
+
+    let agent = mcprs::agent_anthropic::create_anthropic_agent(Some(Box::new(mock_client)));
+    let message = MCPMessage::new("anthropic:chat", json!({ "user_prompt": "Test prompt" }));
+
+    let err = agent.process_request(message).await.unwrap_err();
+    assert!(matches!(
+        err,
+        MCPError::Upstream {
+            ref provider,
+            status: None,
+            ..
+        } if provider == "anthropic"
+    ));
+    assert!(err.is_retryable());
+}
+
+#[tokio::test]
+async fn test_anthropic_agent_overloaded() {
+    let mut mock_client = MockHttpClient::new();
+
+    mock_client.expect_post().return_once(|_, _, _| {
+        Ok(reqwest::Response::from(
+            http::Response::builder()
+                .status(529)
+                .body("Overloaded")
+                .unwrap(),
+        ))
+    });
+
+    let agent = mcprs::agent_anthropic::create_anthropic_agent(Some(Box::new(mock_client)));
+    let message = MCPMessage::new("anthropic:chat", json!({ "user_prompt": "Test prompt" }));
+
+    let result = agent.process_request(message).await;
+    let err = result.unwrap_err();
+    assert!(matches!(
+        err,
+        MCPError::Upstream {
+            status: Some(529),
+            ..
+        }
+    ));
+    assert!(err.is_retryable());
+}
+
+#[tokio::test]
+async fn test_anthropic_agent_api_error() {
+    let mut mock_client = MockHttpClient::new();
+
+    mock_client.expect_post().return_once(|_, _, _| {
+        Ok(reqwest::Response::from(
+            http::Response::builder()
+                .status(400)
+                .body("Bad Request")
+                .unwrap(),
+        ))
+    });
+
+    let agent = mcprs::agent_anthropic::create_anthropic_agent(Some(Box::new(mock_client)));
+    let message = MCPMessage::new("anthropic:chat", json!({ "user_prompt": "Test prompt" }));
+
+    let result = agent.process_request(message).await;
+    let err = result.unwrap_err();
+    assert!(matches!(
+        err,
+        MCPError::Upstream {
+            status: Some(400),
+            ..
+        }
+    ));
+    assert!(!err.is_retryable());
+}
+
+#[tokio::test]
+async fn test_anthropic_agent_create_with_environment() {
+    // Definir variáveis de ambiente temporariamente
+    std::env::set_var("ANTHROPIC_API_KEY", "test-key-from-env");
+
+    // Criar um mock que verifica se a chave e a versão da API são enviadas
+    let mut mock_client = MockHttpClient::new();
+    mock_client
+        .expect_post()
+        .withf(|_, _, headers| {
+            headers
+                .iter()
+                .any(|(k, v)| k == "x-api-key" && v == "test-key-from-env")
+                && headers.iter().any(|(k, _)| k == "anthropic-version")
+        })
+        .return_once(|_, _, _| Ok(create_mock_response(message_response("Resposta de teste"))));
+
+    let agent = mcprs::agent_anthropic::create_anthropic_agent(Some(Box::new(mock_client)));
+    let message = MCPMessage::new(
+        "anthropic:chat",
+        json!({ "user_prompt": "Teste com env var" }),
+    );
+
+    let result = agent.process_request(message).await.unwrap();
+    assert_eq!(result.payload["answer"], "Resposta de teste");
+
+    // Limpar a variável de ambiente após o teste
+    std::env::remove_var("ANTHROPIC_API_KEY");
+}
+
+#[tokio::test]
+async fn test_anthropic_agent_custom_model_and_parameters() {
+    let mut mock_client = MockHttpClient::new();
+
+    // Verificar se o modelo e os parâmetros são enviados na requisição
+    mock_client
+        .expect_post()
+        .withf(|_, body, _| {
+            let parsed: serde_json::Value = serde_json::from_slice(body).unwrap_or_default();
+            parsed["model"] == "claude-3-opus-latest"
+                && parsed["max_tokens"] == 50
+                && parsed["stop_sequences"] == json!(["\n\nHuman:", "FIM"])
+        })
+        .return_once(|_, _, _| Ok(create_mock_response(message_response("Resposta do Opus"))));
+
+    // Criar agente diretamente com modelo personalizado
+    let agent = mcprs::agent_anthropic::AnthropicAgent::new(
+        "chave-teste".to_string(),
+        "claude-3-opus-latest".to_string(),
+        Box::new(mock_client),
+    );
+
+    let message = MCPMessage::new(
+        "anthropic:chat",
+        json!({
+            "user_prompt": "Teste com Opus",
+            "max_tokens": 50,
+            "stop_sequences": ["\n\nHuman:", "FIM"]
+        }),
+    );
+    let result = agent.process_request(message).await.unwrap();
+
+    assert_eq!(result.payload["answer"], "Resposta do Opus");
+}
+
+#[tokio::test]
+async fn test_anthropic_agent_missing_prompt() {
+    let mock_client = MockHttpClient::new();
+    let agent = mcprs::agent_anthropic::create_anthropic_agent(Some(Box::new(mock_client)));
+
+    // Payload sem o campo user_prompt
+    let message = MCPMessage::new(
+        "anthropic:chat",
+        json!({
+            "system_prompt": "Seja breve.",
+            "max_tokens": 100
+        }),
+    );
+
+    let result = agent.process_request(message).await;
+    assert!(
+        matches!(result, Err(MCPError::PayloadValidation(e)) if e.contains("Missing user_prompt"))
+    );
+}
+
+#[tokio::test]
+async fn test_anthropic_agent_multi_turn_payload() {
+    let mut mock_client = MockHttpClient::new();
+
+    // O prompt de sistema vai no campo `system`, fora da lista de mensagens
+    mock_client
+        .expect_post()
+        .withf(|_, body, _| {
+            let parsed: serde_json::Value = serde_json::from_slice(body).unwrap_or_default();
+            parsed["system"] == "Responda em português."
+                && parsed["messages"]
+                    == json!([
+                        { "role": "user", "content": "Qual a capital da França?" },
+                        { "role": "assistant", "content": "Paris." },
+                        { "role": "user", "content": "E da Itália?" }
+                    ])
+        })
+        .return_once(|_, _, _| Ok(create_mock_response(message_response("Roma."))));
+
+    let agent = mcprs::agent_anthropic::create_anthropic_agent(Some(Box::new(mock_client)));
+    let message = MCPMessage::new(
+        "anthropic:chat",
+        json!({
+            "system_prompt": "Responda em português.",
+            "messages": [
+                { "role": "user", "content": "Qual a capital da França?" },
+                { "role": "assistant", "content": "Paris." }
+            ],
+            "user_prompt": "E da Itália?"
+        }),
+    );
+
+    let result = agent.process_request(message).await.unwrap();
+    assert_eq!(result.payload["answer"], "Roma.");
+}
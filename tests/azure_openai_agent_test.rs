@@ -0,0 +1,104 @@
+use mcprs::agent::{AIAgent, MCPError, MCPMessage};
+use mcprs::agent_azure_openai::create_azure_openai_agent;
+use mcprs::testing::MockHttpClient;
+use serde_json::json;
+
+// Helper para criar uma resposta mockada
+fn create_mock_response(body: serde_json::Value) -> reqwest::Response {
+    reqwest::Response::from(
+        http::Response::builder()
+            .status(200)
+            .body(body.to_string())
+            .unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn test_azure_openai_agent_create_with_environment() {
+    // Sem endpoint e deployments o agente não é criado
+    std::env::remove_var("AZURE_OPENAI_ENDPOINT");
+    std::env::remove_var("AZURE_OPENAI_DEPLOYMENTS");
+    assert!(create_azure_openai_agent(None).is_none());
+
+    // Definir variáveis de ambiente temporariamente
+    std::env::set_var("AZURE_OPENAI_ENDPOINT", "https://empresa.openai.azure.com/");
+    std::env::set_var("AZURE_OPENAI_DEPLOYMENTS", "gpt-4o-prod, gpt-4o-mini");
+    std::env::set_var("AZURE_OPENAI_API_KEY", "test-key-from-env");
+    std::env::set_var("AZURE_OPENAI_API_VERSION", "2024-02-01");
+
+    // O primeiro deployment é o padrão e a versão da API é a configurada
+    let mut mock_client = MockHttpClient::new();
+    mock_client
+        .expect_post()
+        .withf(|url, _, headers| {
+            url == "https://empresa.openai.azure.com/openai/deployments/gpt-4o-prod/chat/completions?api-version=2024-02-01"
+                && headers
+                    .iter()
+                    .any(|(k, v)| k == "api-key" && v == "test-key-from-env")
+        })
+        .return_once(|_, _, _| {
+            Ok(create_mock_response(json!({
+                "choices": [{ "message": { "content": "Resposta de teste" }, "finish_reason": "stop" }],
+                "usage": { "prompt_tokens": 5, "completion_tokens": 3 }
+            })))
+        });
+
+    let agent = create_azure_openai_agent(Some(Box::new(mock_client))).unwrap();
+    assert_eq!(agent.deployments, vec!["gpt-4o-prod", "gpt-4o-mini"]);
+
+    let message = MCPMessage::new("azure:chat", json!({ "user_prompt": "Teste com env var" }));
+    let result = agent.process_request(message).await.unwrap();
+    assert_eq!(
+        result.payload,
+        json!({
+            "answer": "Resposta de teste",
+            "finish_reason": "stop",
+            "usage": { "input_tokens": 5, "output_tokens": 3, "total_tokens": 8 },
+            "deployment": "gpt-4o-prod"
+        })
+    );
+
+    // Limpar as variáveis de ambiente após o teste
+    for var in [
+        "AZURE_OPENAI_ENDPOINT",
+        "AZURE_OPENAI_DEPLOYMENTS",
+        "AZURE_OPENAI_API_KEY",
+        "AZURE_OPENAI_API_VERSION",
+    ] {
+        std::env::remove_var(var);
+    }
+}
+
+#[tokio::test]
+async fn test_azure_openai_agent_content_filter_error() {
+    let mut mock_client = MockHttpClient::new();
+
+    mock_client.expect_post().return_once(|_, _, _| {
+        Ok(reqwest::Response::from(
+            http::Response::builder()
+                .status(400)
+                .body(
+                    r#"{"error":{"code":"content_filter","message":"The response was filtered"}}"#,
+                )
+                .unwrap(),
+        ))
+    });
+
+    let agent = mcprs::agent_azure_openai::AzureOpenAIAgent::new(
+        "https://empresa.openai.azure.com",
+        mcprs::agent_azure_openai::AzureCredential::ApiKey("chave".to_string()),
+        Box::new(mock_client),
+    )
+    .with_deployment("gpt-4o-prod");
+
+    let message = MCPMessage::new("azure:chat", json!({ "user_prompt": "Test prompt" }));
+    let err = agent.process_request(message).await.unwrap_err();
+    assert!(matches!(
+        err,
+        MCPError::Upstream {
+            status: Some(400),
+            ..
+        }
+    ));
+    assert_eq!(err.provider(), Some("azure"));
+}
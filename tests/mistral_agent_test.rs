@@ -0,0 +1,79 @@
+use mcprs::agent::{AIAgent, MCPError, MCPMessage};
+use mcprs::testing::MockHttpClient;
+use serde_json::json;
+
+// Helper para criar uma resposta mockada
+fn create_mock_response(body: serde_json::Value) -> reqwest::Response {
+    reqwest::Response::from(
+        http::Response::builder()
+            .status(200)
+            .body(body.to_string())
+            .unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn test_mistral_agent_create_with_environment() {
+    // Definir variáveis de ambiente temporariamente
+    std::env::set_var("MISTRAL_API_KEY", "test-key-from-env");
+    std::env::set_var("MISTRAL_MODEL", "mistral-large-latest");
+
+    // Criar um mock que verifica a chave e o modelo
+    let mut mock_client = MockHttpClient::new();
+    mock_client
+        .expect_post()
+        .withf(|_, body, headers| {
+            let parsed: serde_json::Value = serde_json::from_slice(body).unwrap_or_default();
+            parsed["model"] == "mistral-large-latest"
+                && headers
+                    .iter()
+                    .any(|(k, v)| k == "Authorization" && v == "Bearer test-key-from-env")
+        })
+        .return_once(|_, _, _| {
+            Ok(create_mock_response(json!({
+                "choices": [{ "message": { "content": "Resposta de teste" } }],
+                "usage": { "prompt_tokens": 4, "completion_tokens": 3 }
+            })))
+        });
+
+    let agent = mcprs::agent_mistral::create_mistral_agent(Some(Box::new(mock_client)));
+    let message = MCPMessage::new(
+        "mistral:chat",
+        json!({ "user_prompt": "Teste com env var" }),
+    );
+
+    let result = agent.process_request(message).await.unwrap();
+    assert_eq!(result.payload["answer"], "Resposta de teste");
+    assert_eq!(result.payload["usage"]["output_tokens"], 3);
+
+    // Limpar as variáveis de ambiente após o teste
+    std::env::remove_var("MISTRAL_API_KEY");
+    std::env::remove_var("MISTRAL_MODEL");
+}
+
+#[tokio::test]
+async fn test_mistral_agent_unauthorized() {
+    let mut mock_client = MockHttpClient::new();
+
+    mock_client.expect_post().return_once(|_, _, _| {
+        Ok(reqwest::Response::from(
+            http::Response::builder()
+                .status(401)
+                .body("Unauthorized")
+                .unwrap(),
+        ))
+    });
+
+    let agent = mcprs::agent_mistral::create_mistral_agent(Some(Box::new(mock_client)));
+    let message = MCPMessage::new("mistral:chat", json!({ "user_prompt": "Test prompt" }));
+
+    let err = agent.process_request(message).await.unwrap_err();
+    assert!(matches!(
+        err,
+        MCPError::Upstream {
+            status: Some(401),
+            ..
+        }
+    ));
+    assert_eq!(err.provider(), Some("mistral"));
+}
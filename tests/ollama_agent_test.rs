@@ -0,0 +1,270 @@
+use axum::{
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{get, post},
+    Json, Router,
+};
+use futures::StreamExt;
+use mcprs::agent::{AIAgent, MCPError, MCPMessage};
+use mcprs::agent_ollama::OllamaAgent;
+use mcprs::testing::ReqwestClient;
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+
+// Servidor local que imita a API do Ollama
+async fn start_stand_in_server() -> SocketAddr {
+    let app = Router::new()
+        .route("/api/chat", post(handle_chat))
+        .route("/api/generate", post(handle_generate))
+        .route("/api/embed", post(handle_embed))
+        .route("/api/tags", get(handle_tags));
+
+    let server =
+        axum::Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(app.into_make_service());
+    let addr = server.local_addr();
+    tokio::spawn(server);
+    addr
+}
+
+async fn handle_chat(Json(body): Json<Value>) -> axum::response::Response {
+    if body["model"] != "llama3.2" {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "model not found" })),
+        )
+            .into_response();
+    }
+
+    // Responde com o número de mensagens recebidas e o conteúdo da última
+    let messages = body["messages"].as_array().unwrap();
+    let last = messages.last().unwrap()["content"].as_str().unwrap();
+    let answer = format!("{} mensagens, última: {}", messages.len(), last);
+
+    if body["stream"] == true {
+        let lines = [
+            json!({ "message": { "role": "assistant", "content": "Olá" }, "done": false }),
+            json!({ "message": { "role": "assistant", "content": ", mundo" }, "done": false }),
+            json!({
+                "message": { "role": "assistant", "content": "" },
+                "done": true,
+                "done_reason": "stop",
+                "prompt_eval_count": 7,
+                "eval_count": 2
+            }),
+        ];
+        let body: String = lines.iter().map(|line| format!("{}\n", line)).collect();
+        return ([("Content-Type", "application/x-ndjson")], body).into_response();
+    }
+
+    Json(json!({
+        "model": "llama3.2",
+        "message": { "role": "assistant", "content": answer },
+        "done": true,
+        "done_reason": "stop",
+        "prompt_eval_count": 12,
+        "eval_count": 5
+    }))
+    .into_response()
+}
+
+async fn handle_generate(Json(body): Json<Value>) -> Json<Value> {
+    let system = body["system"].as_str().unwrap_or("-");
+    let prompt = body["prompt"].as_str().unwrap();
+
+    Json(json!({
+        "model": body["model"],
+        "response": format!("[{}] {}", system, prompt),
+        "done": true,
+        "done_reason": "stop",
+        "prompt_eval_count": 3,
+        "eval_count": 4
+    }))
+}
+
+async fn handle_embed(Json(body): Json<Value>) -> Json<Value> {
+    // Um vetor por texto: [tamanho do texto, posição]
+    let embeddings: Vec<Value> = body["input"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .enumerate()
+        .map(|(i, text)| json!([text.as_str().unwrap().len() as f64, i as f64]))
+        .collect();
+
+    Json(json!({
+        "model": body["model"],
+        "embeddings": embeddings,
+        "prompt_eval_count": 6
+    }))
+}
+
+async fn handle_tags() -> Json<Value> {
+    Json(json!({
+        "models": [
+            { "name": "llama3.2:latest", "size": 2019393189u64, "modified_at": "2024-10-01T10:00:00Z", "digest": "abc" },
+            { "name": "mistral:latest", "size": 4113301824u64, "modified_at": "2024-09-01T10:00:00Z", "digest": "def" }
+        ]
+    }))
+}
+
+fn create_agent(addr: SocketAddr, model: &str) -> OllamaAgent {
+    OllamaAgent::new(
+        format!("http://{}", addr),
+        model.to_string(),
+        Box::new(ReqwestClient::new()),
+    )
+}
+
+#[tokio::test]
+async fn test_ollama_agent_chat() {
+    let addr = start_stand_in_server().await;
+    let agent = create_agent(addr, "llama3.2");
+
+    let message = MCPMessage::new(
+        "ollama:chat",
+        json!({
+            "system_prompt": "Seja breve.",
+            "messages": [
+                { "role": "user", "content": "Qual a capital da França?" },
+                { "role": "assistant", "content": "Paris." }
+            ],
+            "user_prompt": "E da Itália?"
+        }),
+    );
+
+    let result = agent.process_request(message).await.unwrap();
+    assert_eq!(result.command, "ollama_response");
+    assert_eq!(
+        result.payload,
+        json!({
+            "answer": "4 mensagens, última: E da Itália?",
+            "model": "llama3.2",
+            "done_reason": "stop",
+            "usage": { "input_tokens": 12, "output_tokens": 5, "total_tokens": 17 }
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_ollama_agent_generate() {
+    let addr = start_stand_in_server().await;
+    let agent = create_agent(addr, "llama3.2");
+
+    let message = MCPMessage::new(
+        "ollama:generate",
+        json!({ "system_prompt": "Seja breve.", "user_prompt": "O que é Rust?" }),
+    );
+
+    let result = agent.process_request(message).await.unwrap();
+    assert_eq!(result.payload["answer"], "[Seja breve.] O que é Rust?");
+    assert_eq!(result.payload["usage"]["output_tokens"], 4);
+}
+
+#[tokio::test]
+async fn test_ollama_agent_stream() {
+    let addr = start_stand_in_server().await;
+    let agent = create_agent(addr, "llama3.2");
+
+    let message = MCPMessage::new("ollama:chat", json!({ "user_prompt": "Olá" }));
+    let mut stream = agent.process_stream(message).await.unwrap();
+
+    let mut content = String::new();
+    let mut metadata = None;
+    while let Some(token) = stream.next().await {
+        let token = token.unwrap();
+        if token.is_finish {
+            break;
+        }
+        content.push_str(&token.content);
+        if token.metadata.is_some() {
+            metadata = token.metadata;
+        }
+    }
+
+    assert_eq!(content, "Olá, mundo");
+    assert_eq!(
+        metadata.unwrap(),
+        json!({
+            "done_reason": "stop",
+            "usage": { "input_tokens": 7, "output_tokens": 2, "total_tokens": 9 }
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_ollama_agent_list_models() {
+    let addr = start_stand_in_server().await;
+    let agent = create_agent(addr, "llama3.2");
+
+    let models = agent.list_models().await.unwrap();
+    let names: Vec<&str> = models.iter().map(|m| m.name.as_str()).collect();
+    assert_eq!(names, vec!["llama3.2:latest", "mistral:latest"]);
+
+    // A listagem também é exposta pelo comando `ollama:models`
+    let result = agent
+        .process_request(MCPMessage::new("ollama:models", json!({})))
+        .await
+        .unwrap();
+    assert_eq!(result.payload["models"][1]["name"], "mistral:latest");
+}
+
+#[tokio::test]
+async fn test_ollama_agent_embeddings() {
+    let addr = start_stand_in_server().await;
+    let agent = create_agent(addr, "llama3.2");
+
+    let message = MCPMessage::new("ollama:embeddings", json!({ "input": ["um", "dois"] }));
+    let result = agent.process_request(message).await.unwrap();
+    assert_eq!(result.command, "ollama_response");
+    assert_eq!(
+        result.payload,
+        json!({
+            "embeddings": [[2.0, 0.0], [4.0, 1.0]],
+            "dimensions": 2,
+            "model": "nomic-embed-text",
+            "usage": { "input_tokens": 6, "output_tokens": 0, "total_tokens": 6 }
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_ollama_agent_stream_rejects_embeddings() {
+    let addr = start_stand_in_server().await;
+    let agent = create_agent(addr, "llama3.2");
+
+    let message = MCPMessage::new("ollama:embeddings", json!({ "input": "um" }));
+    let result = agent.process_stream(message).await;
+    assert!(matches!(result, Err(MCPError::PayloadValidation(e)) if e.contains("embeddings")));
+}
+
+#[tokio::test]
+async fn test_ollama_agent_unknown_model() {
+    let addr = start_stand_in_server().await;
+    let agent = create_agent(addr, "desconhecido");
+
+    let message = MCPMessage::new("ollama:chat", json!({ "user_prompt": "Olá" }));
+    let err = agent.process_request(message).await.unwrap_err();
+
+    assert!(matches!(
+        err,
+        MCPError::Upstream {
+            status: Some(404),
+            ..
+        }
+    ));
+}
+
+#[tokio::test]
+async fn test_ollama_agent_server_unavailable() {
+    // Reserva uma porta livre e a libera, para que nenhum servidor escute nela
+    let addr = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let agent = create_agent(addr, "llama3.2");
+
+    let message = MCPMessage::new("ollama:chat", json!({ "user_prompt": "Olá" }));
+    let err = agent.process_request(message).await.unwrap_err();
+    assert!(matches!(err, MCPError::Upstream { status: None, .. }));
+    assert!(err.is_retryable());
+}
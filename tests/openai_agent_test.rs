@@ -41,7 +41,10 @@ async fn test_openai_agent_successful_request() {
 
     let result = agent.process_request(message).await.unwrap();
     assert_eq!(result.command, "openai_response");
-    assert_eq!(result.payload, json!({ "answer": "Mock response" }));
+    assert_eq!(
+        result.payload,
+        json!({ "answer": "Mock response", "model": "gpt-3.5-turbo" })
+    );
 }
 
 #[tokio::test]
@@ -61,7 +64,15 @@ async fn test_openai_agent_network_error() {
     let message = MCPMessage::new("openai:chat", json!({ "user_prompt": "Test prompt" }));
 
     let result = agent.process_request(message).await;
-    assert!(matches!(result, Err(MCPError::InternalAgentError(_))));
+    let err = result.unwrap_err();
+    assert!(matches!(
+        err,
+        MCPError::Upstream {
+            status: Some(500),
+            ..
+        }
+    ));
+    assert!(err.is_retryable());
 }
 
 #[tokio::test]
@@ -81,7 +92,15 @@ async fn test_openai_agent_api_error() {
     let message = MCPMessage::new("openai:chat", json!({ "user_prompt": "Test prompt" }));
 
     let result = agent.process_request(message).await;
-    assert!(matches!(result, Err(MCPError::InternalAgentError(_))));
+    let err = result.unwrap_err();
+    assert!(matches!(
+        err,
+        MCPError::Upstream {
+            status: Some(400),
+            ..
+        }
+    ));
+    assert!(!err.is_retryable());
 }
 
 #[tokio::test]
@@ -156,6 +175,64 @@ async fn test_openai_agent_custom_model() {
     assert_eq!(result.payload["answer"], "Resposta do GPT-4");
 }
 
+#[tokio::test]
+async fn test_openai_agent_model_override() {
+    let mut mock_client = MockHttpClient::new();
+
+    // O modelo do payload substitui o padrão quando está na lista de permitidos
+    mock_client
+        .expect_post()
+        .withf(|_, body, _| {
+            let parsed: serde_json::Value = serde_json::from_slice(body).unwrap_or_default();
+            parsed["model"] == "gpt-4o"
+        })
+        .times(1)
+        .return_once(|_, _, _| {
+            Ok(create_mock_response(json!({
+                "model": "gpt-4o-2024-08-06",
+                "choices": [{
+                    "message": { "role": "assistant", "content": "Resposta do GPT-4o" }
+                }]
+            })))
+        });
+
+    let agent = mcprs::agent_openai::OpenAIAgent::new(
+        "chave-teste".to_string(),
+        "gpt-4o-mini".to_string(),
+        Box::new(mock_client),
+    )
+    .with_allowed_models(["gpt-4o"]);
+
+    let message = MCPMessage::new(
+        "openai:chat",
+        json!({ "user_prompt": "Olá", "model": "gpt-4o" }),
+    );
+    let result = agent.process_request(message).await.unwrap();
+
+    assert_eq!(result.payload["answer"], "Resposta do GPT-4o");
+    assert_eq!(result.payload["model"], "gpt-4o-2024-08-06");
+}
+
+#[tokio::test]
+async fn test_openai_agent_rejects_model_outside_allow_list() {
+    // Nenhuma requisição deve ser enviada
+    let mock_client = MockHttpClient::new();
+    let agent = mcprs::agent_openai::OpenAIAgent::new(
+        "chave-teste".to_string(),
+        "gpt-4o-mini".to_string(),
+        Box::new(mock_client),
+    )
+    .with_allowed_models(["gpt-4o"]);
+
+    let message = MCPMessage::new(
+        "openai:chat",
+        json!({ "user_prompt": "Olá", "model": "o1-preview" }),
+    );
+    let result = agent.process_request(message).await;
+
+    assert!(matches!(result, Err(MCPError::PayloadValidation(e)) if e.contains("o1-preview")));
+}
+
 #[tokio::test]
 async fn test_openai_agent_missing_prompt() {
     let mock_client = MockHttpClient::new();
@@ -172,6 +249,480 @@ async fn test_openai_agent_missing_prompt() {
 
     let result = agent.process_request(message).await;
     assert!(
-        matches!(result, Err(MCPError::InternalAgentError(e)) if e.contains("Missing user_prompt"))
+        matches!(result, Err(MCPError::PayloadValidation(e)) if e.contains("Missing user_prompt"))
+    );
+}
+
+#[tokio::test]
+async fn test_openai_agent_multi_turn_payload() {
+    let mut mock_client = MockHttpClient::new();
+
+    // Verificar se o histórico e o prompt de sistema chegam na ordem correta
+    mock_client
+        .expect_post()
+        .withf(|_, body, _| {
+            let parsed: serde_json::Value = serde_json::from_slice(body).unwrap_or_default();
+            parsed["messages"]
+                == json!([
+                    { "role": "system", "content": "Responda em português." },
+                    { "role": "user", "content": "Qual a capital da França?" },
+                    { "role": "assistant", "content": "Paris." },
+                    { "role": "user", "content": "E da Itália?" }
+                ])
+        })
+        .return_once(|_, _, _| {
+            Ok(create_mock_response(json!({
+                "choices": [{
+                    "message": {
+                        "role": "assistant",
+                        "content": "Roma."
+                    }
+                }]
+            })))
+        });
+
+    let agent = mcprs::agent_openai::create_openai_agent(Some(Box::new(mock_client)));
+    let message = MCPMessage::new(
+        "openai:chat",
+        json!({
+            "system_prompt": "Responda em português.",
+            "messages": [
+                { "role": "user", "content": "Qual a capital da França?" },
+                { "role": "assistant", "content": "Paris." }
+            ],
+            "user_prompt": "E da Itália?"
+        }),
+    );
+
+    let result = agent.process_request(message).await.unwrap();
+    assert_eq!(result.payload["answer"], "Roma.");
+}
+
+#[tokio::test]
+async fn test_openai_agent_forwards_generation_params() {
+    let mut mock_client = MockHttpClient::new();
+
+    // Todos os parâmetros de geração chegam à API com os nomes da OpenAI
+    mock_client
+        .expect_post()
+        .withf(|_, body, _| {
+            let parsed: serde_json::Value = serde_json::from_slice(body).unwrap_or_default();
+            parsed["temperature"] == 0.2
+                && parsed["top_p"] == 0.9
+                && parsed["max_tokens"] == 50
+                && parsed["stop"] == json!(["\n\n"])
+                && parsed["seed"] == 123
+                && parsed["presence_penalty"] == 0.5
+                && parsed["frequency_penalty"] == -0.5
+                && parsed["n"] == 2
+                && parsed["logit_bias"] == json!({ "50256": -100.0 })
+                && parsed["user"] == "usuario-42"
+        })
+        .times(1)
+        .return_once(|_, _, _| {
+            Ok(create_mock_response(json!({
+                "choices": [{ "message": { "role": "assistant", "content": "ok" } }]
+            })))
+        });
+
+    let agent = mcprs::agent_openai::create_openai_agent(Some(Box::new(mock_client)));
+    let message = MCPMessage::new(
+        "openai:chat",
+        json!({
+            "user_prompt": "Olá",
+            "temperature": 0.2,
+            "top_p": 0.9,
+            "max_tokens": 50,
+            "stop": "\n\n",
+            "seed": 123,
+            "presence_penalty": 0.5,
+            "frequency_penalty": -0.5,
+            "n": 2,
+            "logit_bias": { "50256": -100 },
+            "user": "usuario-42"
+        }),
+    );
+
+    let result = agent.process_request(message).await.unwrap();
+    assert_eq!(result.payload["answer"], "ok");
+}
+
+#[tokio::test]
+async fn test_openai_agent_invalid_generation_param() {
+    let mock_client = MockHttpClient::new();
+    let agent = mcprs::agent_openai::create_openai_agent(Some(Box::new(mock_client)));
+
+    let message = MCPMessage::new(
+        "openai:chat",
+        json!({ "user_prompt": "Olá", "temperature": 5 }),
+    );
+
+    let result = agent.process_request(message).await;
+    assert!(matches!(result, Err(MCPError::PayloadValidation(e)) if e.contains("temperature")));
+}
+
+#[tokio::test]
+async fn test_openai_agent_tool_calls() {
+    let mut mock_client = MockHttpClient::new();
+
+    // As ferramentas são enviadas como funções no formato da OpenAI
+    mock_client
+        .expect_post()
+        .withf(|_, body, _| {
+            let parsed: serde_json::Value = serde_json::from_slice(body).unwrap_or_default();
+            parsed["tools"]
+                == json!([{
+                    "type": "function",
+                    "function": {
+                        "name": "clima",
+                        "description": "Consulta o clima de uma cidade",
+                        "parameters": {
+                            "type": "object",
+                            "properties": { "cidade": { "type": "string" } },
+                            "required": ["cidade"]
+                        }
+                    }
+                }])
+                && parsed["tool_choice"] == "required"
+        })
+        .times(1)
+        .return_once(|_, _, _| {
+            Ok(create_mock_response(json!({
+                "choices": [{
+                    "message": {
+                        "role": "assistant",
+                        "content": null,
+                        "tool_calls": [{
+                            "id": "call_abc",
+                            "type": "function",
+                            "function": { "name": "clima", "arguments": "{\"cidade\":\"Lisboa\"}" }
+                        }]
+                    },
+                    "finish_reason": "tool_calls"
+                }]
+            })))
+        });
+
+    let agent = mcprs::agent_openai::create_openai_agent(Some(Box::new(mock_client)));
+    let message = MCPMessage::new(
+        "openai:chat",
+        json!({
+            "user_prompt": "Qual o clima em Lisboa?",
+            "tools": [{
+                "name": "clima",
+                "description": "Consulta o clima de uma cidade",
+                "parameters": {
+                    "type": "object",
+                    "properties": { "cidade": { "type": "string" } },
+                    "required": ["cidade"]
+                }
+            }],
+            "tool_choice": "required"
+        }),
+    );
+
+    let result = agent.process_request(message).await.unwrap();
+    assert_eq!(result.payload["answer"], "");
+    assert_eq!(result.payload["finish_reason"], "tool_calls");
+    assert_eq!(
+        result.payload["tool_calls"],
+        json!([{ "id": "call_abc", "name": "clima", "arguments": { "cidade": "Lisboa" } }])
+    );
+}
+
+#[tokio::test]
+async fn test_openai_agent_sends_tool_results() {
+    let mut mock_client = MockHttpClient::new();
+
+    // As chamadas e resultados do histórico voltam no formato da OpenAI
+    mock_client
+        .expect_post()
+        .withf(|_, body, _| {
+            let parsed: serde_json::Value = serde_json::from_slice(body).unwrap_or_default();
+            parsed["messages"]
+                == json!([
+                    { "role": "user", "content": "Qual o clima em Lisboa?" },
+                    {
+                        "role": "assistant",
+                        "content": null,
+                        "tool_calls": [{
+                            "id": "call_abc",
+                            "type": "function",
+                            "function": { "name": "clima", "arguments": "{\"cidade\":\"Lisboa\"}" }
+                        }]
+                    },
+                    { "role": "tool", "content": "{\"temp\":21}", "tool_call_id": "call_abc" }
+                ])
+        })
+        .times(1)
+        .return_once(|_, _, _| {
+            Ok(create_mock_response(json!({
+                "choices": [{
+                    "message": { "role": "assistant", "content": "Fazem 21 °C em Lisboa." },
+                    "finish_reason": "stop"
+                }]
+            })))
+        });
+
+    let agent = mcprs::agent_openai::create_openai_agent(Some(Box::new(mock_client)));
+    let message = MCPMessage::new(
+        "openai:chat",
+        json!({
+            "messages": [
+                { "role": "user", "content": "Qual o clima em Lisboa?" },
+                {
+                    "role": "assistant",
+                    "content": "",
+                    "tool_calls": [{ "id": "call_abc", "name": "clima", "arguments": { "cidade": "Lisboa" } }]
+                },
+                { "role": "tool", "tool_call_id": "call_abc", "content": "{\"temp\":21}" }
+            ],
+            "tools": [{ "name": "clima" }]
+        }),
+    );
+
+    let result = agent.process_request(message).await.unwrap();
+    assert_eq!(result.payload["answer"], "Fazem 21 °C em Lisboa.");
+    assert!(result.payload.get("tool_calls").is_none());
+}
+
+/// Ferramenta de teste que devolve a temperatura de uma cidade.
+struct Clima;
+
+#[async_trait::async_trait]
+impl mcprs::tools::Tool for Clima {
+    fn name(&self) -> &str {
+        "clima"
+    }
+
+    fn parameters(&self) -> serde_json::Value {
+        json!({ "type": "object", "properties": { "cidade": { "type": "string" } } })
+    }
+
+    async fn call(&self, arguments: serde_json::Value) -> Result<serde_json::Value, MCPError> {
+        Ok(json!({ "cidade": arguments["cidade"], "temperatura": 21 }))
+    }
+}
+
+#[tokio::test]
+async fn test_openai_agent_with_tool_executor() {
+    let mut mock_client = MockHttpClient::new();
+    let mut sequence = mockall::Sequence::new();
+
+    // Primeira chamada: o modelo pede a ferramenta registrada no servidor
+    mock_client
+        .expect_post()
+        .withf(|_, body, _| {
+            let parsed: serde_json::Value = serde_json::from_slice(body).unwrap_or_default();
+            parsed["tools"][0]["function"]["name"] == "clima"
+                && parsed["messages"].as_array().map(Vec::len) == Some(1)
+        })
+        .times(1)
+        .in_sequence(&mut sequence)
+        .return_once(|_, _, _| {
+            Ok(create_mock_response(json!({
+                "choices": [{
+                    "message": {
+                        "role": "assistant",
+                        "content": null,
+                        "tool_calls": [{
+                            "id": "call_abc",
+                            "type": "function",
+                            "function": { "name": "clima", "arguments": "{\"cidade\":\"Lisboa\"}" }
+                        }]
+                    },
+                    "finish_reason": "tool_calls"
+                }],
+                "usage": { "prompt_tokens": 20, "completion_tokens": 10, "total_tokens": 30 }
+            })))
+        });
+
+    // Segunda chamada: o resultado da ferramenta volta ao modelo
+    mock_client
+        .expect_post()
+        .withf(|_, body, _| {
+            let parsed: serde_json::Value = serde_json::from_slice(body).unwrap_or_default();
+            parsed["messages"][2]
+                == json!({
+                    "role": "tool",
+                    "content": "{\"cidade\":\"Lisboa\",\"temperatura\":21}",
+                    "tool_call_id": "call_abc"
+                })
+        })
+        .times(1)
+        .in_sequence(&mut sequence)
+        .return_once(|_, _, _| {
+            Ok(create_mock_response(json!({
+                "choices": [{
+                    "message": { "role": "assistant", "content": "Faz 21 graus em Lisboa." },
+                    "finish_reason": "stop"
+                }],
+                "usage": { "prompt_tokens": 40, "completion_tokens": 8, "total_tokens": 48 }
+            })))
+        });
+
+    let mut tools = mcprs::tools::ToolRegistry::new();
+    tools.register_tool(Box::new(Clima));
+    let agent = mcprs::agent_openai::create_openai_agent(Some(Box::new(mock_client)));
+    let executor = mcprs::tools::ToolExecutor::new(Box::new(agent), tools);
+
+    let mut conversation = mcprs::conversation::Conversation::new();
+    let message = MCPMessage::new("openai:chat", json!({ "user_prompt": "Como está Lisboa?" }));
+    let run = executor.run(message, &mut conversation).await.unwrap();
+
+    assert_eq!(run.answer(), "Faz 21 graus em Lisboa.");
+    assert_eq!(run.iterations, 2);
+    assert_eq!(
+        run.response.payload["tool_trace"],
+        json!([{
+            "id": "call_abc",
+            "name": "clima",
+            "arguments": { "cidade": "Lisboa" },
+            "result": { "cidade": "Lisboa", "temperatura": 21 },
+            "iteration": 1
+        }])
+    );
+    assert_eq!(
+        run.response.payload["usage"],
+        json!({ "input_tokens": 60, "output_tokens": 18, "total_tokens": 78 })
+    );
+    assert_eq!(conversation.messages.len(), 4);
+}
+
+#[tokio::test]
+async fn test_openai_agent_structured_output_via_registry() {
+    let mut mock_client = MockHttpClient::new();
+
+    // O schema é enviado no formato `json_schema` da OpenAI
+    mock_client
+        .expect_post()
+        .withf(|_, body, _| {
+            let parsed: serde_json::Value = serde_json::from_slice(body).unwrap_or_default();
+            parsed["response_format"]
+                == json!({
+                    "type": "json_schema",
+                    "json_schema": {
+                        "name": "lancamento",
+                        "schema": {
+                            "type": "object",
+                            "properties": { "ano": { "type": "integer" } },
+                            "required": ["ano"]
+                        },
+                        "strict": false
+                    }
+                })
+        })
+        .times(1)
+        .return_once(|_, _, _| {
+            Ok(create_mock_response(json!({
+                "choices": [{
+                    "message": { "role": "assistant", "content": "{\"ano\": 2015}" },
+                    "finish_reason": "stop"
+                }]
+            })))
+        });
+
+    let mut registry = mcprs::agent::AgentRegistry::new();
+    registry.register_agent(Box::new(mcprs::agent_openai::OpenAIAgent::new(
+        "test_key".to_string(),
+        "gpt-4o-mini".to_string(),
+        Box::new(mock_client),
+    )));
+
+    let message = MCPMessage::new(
+        "openai:chat",
+        json!({
+            "user_prompt": "Quando o Rust 1.0 foi lançado?",
+            "response_format": {
+                "type": "json_schema",
+                "name": "lancamento",
+                "schema": {
+                    "type": "object",
+                    "properties": { "ano": { "type": "integer" } },
+                    "required": ["ano"]
+                }
+            }
+        }),
     );
+
+    let result = registry.process(message).await.unwrap();
+    assert_eq!(result.payload["data"], json!({ "ano": 2015 }));
+    assert!(result.payload.get("answer").is_none());
+}
+
+#[tokio::test]
+async fn test_openai_agent_embeddings() {
+    let mut mock_client = MockHttpClient::new();
+
+    mock_client
+        .expect_post()
+        .withf(|url, body, _| {
+            let parsed: serde_json::Value = serde_json::from_slice(body).unwrap_or_default();
+            url == "https://api.openai.com/v1/embeddings"
+                && parsed
+                    == json!({
+                        "model": "text-embedding-3-small",
+                        "input": ["Rust é rápido", "Rust é seguro"],
+                        "dimensions": 3,
+                        "encoding_format": "float"
+                    })
+        })
+        .times(1)
+        .return_once(|_, _, _| {
+            // Os vetores podem chegar fora da ordem de `input`
+            Ok(create_mock_response(json!({
+                "object": "list",
+                "data": [
+                    { "object": "embedding", "index": 1, "embedding": [0.0, 1.0, 0.0] },
+                    { "object": "embedding", "index": 0, "embedding": [0.5, -0.5, 0.25] }
+                ],
+                "model": "text-embedding-3-small",
+                "usage": { "prompt_tokens": 8, "total_tokens": 8 }
+            })))
+        });
+
+    let agent = mcprs::agent_openai::create_openai_agent(Some(Box::new(mock_client)));
+    let message = MCPMessage::new(
+        "openai:embeddings",
+        json!({ "input": ["Rust é rápido", "Rust é seguro"], "dimensions": 3 }),
+    );
+
+    let result = agent.process_request(message).await.unwrap();
+    assert_eq!(result.command, "openai_response");
+    assert_eq!(
+        result.payload,
+        json!({
+            "embeddings": [[0.5, -0.5, 0.25], [0.0, 1.0, 0.0]],
+            "dimensions": 3,
+            "model": "text-embedding-3-small",
+            "usage": { "input_tokens": 8, "output_tokens": 0, "total_tokens": 8 }
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_openai_agent_stream_rejects_embeddings() {
+    // O comando não chega ao endpoint de chat
+    let agent = mcprs::agent_openai::create_openai_agent(Some(Box::new(MockHttpClient::new())));
+    let message = MCPMessage::new("openai:embeddings", json!({ "input": "Olá" }));
+
+    let result = agent.process_stream(message).await;
+    assert!(matches!(result, Err(MCPError::PayloadValidation(e)) if e.contains("streaming")));
+}
+
+#[tokio::test]
+async fn test_openai_agent_embeddings_count_mismatch() {
+    let mut mock_client = MockHttpClient::new();
+
+    mock_client.expect_post().times(1).return_once(|_, _, _| {
+        Ok(create_mock_response(json!({
+            "data": [{ "index": 0, "embedding": [0.1, 0.2] }]
+        })))
+    });
+
+    let agent = mcprs::agent_openai::create_openai_agent(Some(Box::new(mock_client)));
+    let message = MCPMessage::new("openai:embeddings", json!({ "input": ["um", "dois"] }));
+
+    let err = agent.process_request(message).await.unwrap_err();
+    assert!(matches!(err, MCPError::Upstream { status: None, .. }));
 }
@@ -1,7 +1,9 @@
 use futures::stream;
 use futures::StreamExt;
 use mcprs::agent::MCPError;
-use mcprs::streaming::{create_token_stream, process_json_stream, StreamingToken};
+use mcprs::streaming::{
+    create_token_stream, process_json_stream, process_json_stream_with, StreamingToken,
+};
 use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc;
 
@@ -185,3 +187,42 @@ async fn test_process_json_stream_with_errors() {
     assert!(token3.is_ok());
     assert!(token3.unwrap().is_finish);
 }
+
+#[tokio::test]
+async fn test_process_json_stream_with_custom_mapping() {
+    // Eventos SSE com linhas de controle que devem ser ignoradas
+    let chunks = vec![
+        Ok(bytes::Bytes::from("event: delta\n")),
+        Ok(bytes::Bytes::from(r#"data: {"text":"Olá","index":1}"#)),
+        Ok(bytes::Bytes::from("\n\n: comentário\n")),
+        Ok(bytes::Bytes::from(r#"data: {"text":"","index":2}"#)),
+        Ok(bytes::Bytes::from("\n\n")),
+        Ok(bytes::Bytes::from(r#"data: {"text":" mundo","index":3}"#)),
+    ];
+
+    let mut token_stream =
+        process_json_stream_with::<_, TestJsonChunk, _>(stream::iter(chunks), |chunk| {
+            if chunk.text.is_empty() {
+                return Ok(None);
+            }
+            Ok(Some(StreamingToken {
+                content: chunk.text,
+                is_finish: false,
+                metadata: None,
+            }))
+        })
+        .await
+        .unwrap();
+
+    let mut tokens = Vec::new();
+    while let Some(token_result) = token_stream.next().await {
+        let token = token_result.unwrap();
+        if token.is_finish {
+            break;
+        }
+        tokens.push(token.content);
+    }
+
+    // O último chunk não tem quebra de linha e ainda assim deve ser processado
+    assert_eq!(tokens, vec!["Olá", " mundo"]);
+}